@@ -0,0 +1,17 @@
+//! Legacy `.lzma` input through the alone decoder and the xz-core pipeline.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lzma_safe::Stream;
+use xz_core::config::DecodeMode;
+
+use xz_rs_fuzz::{decompress, drain, MEMLIMIT};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(decoder) = Stream::default().alone_decoder(MEMLIMIT) {
+        drain(decoder, data);
+    }
+
+    decompress(data, DecodeMode::Lzma);
+});
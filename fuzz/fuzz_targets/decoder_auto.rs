@@ -0,0 +1,18 @@
+//! Input of any format through the auto decoder and the xz-core pipeline.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lzma_safe::decoder::options::Flags;
+use lzma_safe::Stream;
+use xz_core::config::DecodeMode;
+
+use xz_rs_fuzz::{decompress, drain, MEMLIMIT};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(decoder) = Stream::default().auto_decoder(MEMLIMIT, Flags::CONCATENATED) {
+        drain(decoder, data);
+    }
+
+    decompress(data, DecodeMode::Auto);
+});
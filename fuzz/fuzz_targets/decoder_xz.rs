@@ -0,0 +1,19 @@
+//! `.xz` input through the stream decoder and the xz-core pipeline.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lzma_safe::decoder::options::Flags;
+use lzma_safe::Stream;
+use xz_core::config::DecodeMode;
+
+use xz_rs_fuzz::{decompress, drain, MEMLIMIT};
+
+fuzz_target!(|data: &[u8]| {
+    let flags = Flags::CONCATENATED | Flags::UNSUPPORTED_CHECK;
+    if let Ok(decoder) = Stream::default().decoder(MEMLIMIT, flags) {
+        drain(decoder, data);
+    }
+
+    decompress(data, DecodeMode::Xz);
+});
@@ -0,0 +1,20 @@
+//! Whole `.xz` files through the seeking file info parser.
+
+#![no_main]
+
+use std::io::Cursor;
+use std::num::NonZeroU64;
+
+use libfuzzer_sys::fuzz_target;
+use xz_core::file_info::extract_file_info;
+
+use xz_rs_fuzz::MEMLIMIT;
+
+fuzz_target!(|data: &[u8]| {
+    let memlimit = NonZeroU64::new(MEMLIMIT);
+    let Ok(info) = extract_file_info(&mut Cursor::new(data), memlimit) else {
+        return;
+    };
+
+    std::hint::black_box((info.streams(), info.blocks(), info.checks()));
+});
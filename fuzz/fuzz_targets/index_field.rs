@@ -0,0 +1,52 @@
+//! Raw Index fields through the streaming and single-call Index decoders.
+//!
+//! A decoded Index must survive re-encoding unchanged.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lzma_safe::{Action, Index, Stream};
+
+use xz_rs_fuzz::{chunk_size, MEMLIMIT};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(mut decoder) = Stream::default().index_decoder(MEMLIMIT) {
+        let mut chunks = data.chunks(chunk_size(data)).peekable();
+        while let Some(chunk) = chunks.next() {
+            let action = if chunks.peek().is_none() {
+                Action::Finish
+            } else {
+                Action::Run
+            };
+            if decoder.process(chunk, action).is_err() || decoder.is_finished() {
+                break;
+            }
+        }
+        if let Some(index) = decoder.index() {
+            walk(index);
+        }
+    }
+
+    let Ok(index) = Index::decode_xz_index_field(data, MEMLIMIT) else {
+        return;
+    };
+    walk(&index);
+
+    let encoded = index
+        .encode_xz_index_field()
+        .expect("decoded Index re-encodes");
+    let decoded =
+        Index::decode_xz_index_field(&encoded, MEMLIMIT).expect("re-encoded Index decodes");
+    assert_eq!(decoded.block_count(), index.block_count());
+    assert_eq!(decoded.uncompressed_size(), index.uncompressed_size());
+});
+
+/// Visits every Stream and Block record.
+fn walk(index: &Index) {
+    for entry in index.iter() {
+        std::hint::black_box(entry);
+    }
+    for entry in index.iter_blocks() {
+        std::hint::black_box(entry);
+    }
+}
@@ -0,0 +1,33 @@
+#!/usr/bin/env bash
+# Seed corpus/<target>/ with the upstream test vectors, and corpus/index_field/ with
+# the Index fields cut out of them, so fuzzing starts from structurally valid input.
+set -euo pipefail
+
+FUZZ_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+VECTORS="${FUZZ_DIR}/../xz-cli/tests/test_cli/vectors"
+CORPUS="${FUZZ_DIR}/corpus"
+
+seed() { # target, files...
+  local target="$1"
+  shift
+  mkdir -p "${CORPUS}/${target}"
+  cp "$@" "${CORPUS}/${target}/"
+}
+
+seed decoder_xz "${VECTORS}"/xz/*.xz
+seed decoder_alone "${VECTORS}"/lzma/*.lzma
+seed decoder_auto "${VECTORS}"/xz/*.xz "${VECTORS}"/lzma/*.lzma "${VECTORS}"/lz/*.lz
+seed file_info "${VECTORS}"/xz/*.xz
+
+# The Index field precedes the 12-byte Stream Footer, whose bytes 4..8 hold its
+# Backward Size as (size / 4 - 1) in little endian.
+mkdir -p "${CORPUS}/index_field"
+for file in "${VECTORS}"/xz/good-*.xz; do
+  tail -c 2 "${file}" | cmp -s - <(printf 'YZ') || continue
+  read -r b0 b1 b2 b3 < <(tail -c 8 "${file}" | head -c 4 | od -An -tu1)
+  index_size=$(( ((b0 | b1 << 8 | b2 << 16 | b3 << 24) + 1) * 4 ))
+  tail -c "$(( index_size + 12 ))" "${file}" | head -c "${index_size}" \
+    > "${CORPUS}/index_field/$(basename "${file}" .xz).index"
+done
+
+echo "Seeded ${CORPUS}."
@@ -0,0 +1,93 @@
+//! Helpers shared by the fuzz targets.
+//!
+//! Arbitrary input is expected to fail decoding; the targets only look for panics,
+//! memory errors and hangs in the wrapper logic around liblzma.
+
+use std::io::{self, Write};
+use std::num::NonZeroU64;
+
+use lzma_safe::{Action, Decoder};
+use xz_core::config::DecodeMode;
+use xz_core::options::DecompressionOptions;
+
+/// Memory limit for every decoder, well below libFuzzer's default RSS limit.
+pub const MEMLIMIT: u64 = 64 * 1024 * 1024;
+
+/// Output decoded per input before giving up, so small decompression bombs stay fast.
+const MAX_OUTPUT: u64 = 16 * 1024 * 1024;
+
+/// Size of the output buffer handed to [`Decoder::process`].
+const OUTPUT_SIZE: usize = 4096;
+
+/// Input chunk size for `data`.
+///
+/// It varies with the input length so chunk boundaries land everywhere without spending
+/// input bytes on choosing them, which keeps the seeds valid files.
+pub fn chunk_size(data: &[u8]) -> usize {
+    1 + data.len() % 512
+}
+
+/// Feeds `data` to `decoder` in chunks and discards the output.
+pub fn drain(mut decoder: Decoder, data: &[u8]) {
+    let mut output = vec![0_u8; OUTPUT_SIZE];
+    let mut chunks = data.chunks(chunk_size(data)).peekable();
+
+    loop {
+        let input = chunks.next().unwrap_or_default();
+        let action = if chunks.peek().is_none() {
+            Action::Finish
+        } else {
+            Action::Run
+        };
+
+        let mut offset = 0;
+        loop {
+            let Ok((read, written)) = decoder.process(&input[offset..], &mut output, action) else {
+                return;
+            };
+            offset += read;
+
+            if decoder.is_finished() || decoder.total_out() > MAX_OUTPUT {
+                return;
+            }
+            if read == 0 && written == 0 {
+                break;
+            }
+            if offset == input.len() && action == Action::Run {
+                break;
+            }
+        }
+
+        if action == Action::Finish {
+            return;
+        }
+    }
+}
+
+/// Runs `data` through the xz-core synchronous pipeline in `mode`.
+pub fn decompress(data: &[u8], mode: DecodeMode) {
+    let options = DecompressionOptions::default()
+        .with_mode(mode)
+        .with_memlimit(NonZeroU64::new(MEMLIMIT).expect("non-zero limit"));
+    let _ = xz_core::pipeline::decompress(data, LimitedSink::default(), &options);
+}
+
+/// Writer that discards its input and fails once [`MAX_OUTPUT`] bytes were written.
+#[derive(Default)]
+struct LimitedSink {
+    written: u64,
+}
+
+impl Write for LimitedSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written += buf.len() as u64;
+        if self.written > MAX_OUTPUT {
+            return Err(io::Error::other("output limit reached"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
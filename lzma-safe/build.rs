@@ -0,0 +1,61 @@
+//! Build script for lzma-safe
+//!
+//! Forwards what the liblzma linked by liblzma-sys supports as `cfg` flags, so the
+//! wrappers only reference functions that exist in that library.
+
+use std::env;
+
+/// Version-gated `cfg` flags and the first stable release (in `LZMA_VERSION` format)
+/// that provides them.
+const VERSION_CFGS: &[(&str, u32)] = &[
+    ("lzma_5_4", 50_040_002),
+    ("lzma_5_6", 50_060_002),
+    ("lzma_5_8", 50_080_002),
+    // 5.4.0 stabilised MicroLZMA, the lzip decoder and the multi-threaded decoder.
+    ("has_microlzma", 50_040_002),
+    ("has_lzip_decoder", 50_040_002),
+    ("has_mt_decoder", 50_040_002),
+];
+
+/// Capability `cfg` flags and the name liblzma-sys probes them under in a system
+/// library; a probe result overrides the version.
+const PROBED_CFGS: &[(&str, &str)] = &[
+    ("has_microlzma", "microlzma"),
+    ("has_lzip_decoder", "lzip_decoder"),
+    ("has_mt_decoder", "mt_decoder"),
+];
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=DEP_LZMA_THREADS");
+    println!("cargo:rerun-if-env-changed=DEP_LZMA_VERSION_NUMBER");
+    println!("cargo:rerun-if-env-changed=DEP_LZMA_CAPABILITIES");
+    println!("cargo:rustc-check-cfg=cfg(liblzma_threads)");
+    for (cfg, _) in VERSION_CFGS {
+        println!("cargo:rustc-check-cfg=cfg({cfg})");
+    }
+
+    let threads = env::var_os("DEP_LZMA_THREADS").is_some();
+    if threads {
+        println!("cargo:rustc-cfg=liblzma_threads");
+    }
+
+    // Without version information, assume a current liblzma rather than hiding
+    // functionality that is most likely there.
+    let version = env::var("DEP_LZMA_VERSION_NUMBER")
+        .ok()
+        .and_then(|number| number.parse::<u32>().ok())
+        .unwrap_or(u32::MAX);
+
+    let capabilities = env::var("DEP_LZMA_CAPABILITIES").ok();
+    for &(cfg, since) in VERSION_CFGS {
+        let probed = PROBED_CFGS.iter().find(|(name, _)| *name == cfg);
+        let available = match (&capabilities, probed) {
+            (Some(found), Some((_, capability))) => found.split(',').any(|c| c == *capability),
+            _ => version >= since,
+        };
+        if !available || (cfg == "has_mt_decoder" && !threads) {
+            continue;
+        }
+        println!("cargo:rustc-cfg={cfg}");
+    }
+}
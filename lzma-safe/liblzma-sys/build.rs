@@ -1,16 +1,19 @@
+#![allow(unused_assignments, unused_variables)] // LOCAL-HACK
 //! Build script for liblzma-sys
 //!
 //! This build script handles both system and vendored builds of liblzma,
 //! with security checks for known vulnerabilities and support for patches.
 
+use std::collections::BTreeSet;
 use std::env;
 use std::ffi::OsStr;
+use std::fmt::Write as _;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 
-/// Feature macros required for liblzma functionality
+/// Feature macros shared by the encoder and decoder halves of liblzma
 ///
 /// These macros ensure that the corresponding source files expose
 /// the functionality required by the safe wrapper (easy presets, filters,
@@ -18,37 +21,23 @@ use std::process::Command;
 /// like `lzma_easy_encoder` would report `LZMA_OPTIONS_ERROR` because the
 /// encoder paths remain disabled.
 const LIBLZMA_FEATURE_MACROS: &[&str] = &[
-    // Integrity checks
+    // CRC32 is used by the .xz headers themselves, so it can't be disabled
     "HAVE_CHECK_CRC32",
-    "HAVE_CHECK_CRC64",
-    "HAVE_CHECK_SHA256",
-    // Decoder support
+];
+
+/// Feature macros enabled by the `decoder` feature
+const LIBLZMA_DECODER_MACROS: &[&str] = &[
     "HAVE_DECODERS",
     "HAVE_DECODER_LZMA1",
     "HAVE_DECODER_LZMA2",
-    "HAVE_DECODER_DELTA",
-    "HAVE_DECODER_ARM",
-    "HAVE_DECODER_ARM64",
-    "HAVE_DECODER_ARMTHUMB",
-    "HAVE_DECODER_IA64",
-    "HAVE_DECODER_POWERPC",
-    "HAVE_DECODER_SPARC",
-    "HAVE_DECODER_X86",
-    "HAVE_DECODER_RISCV",
     "HAVE_LZIP_DECODER",
-    // Encoder support (required by the safe API)
+];
+
+/// Feature macros enabled by the `encoder` feature
+const LIBLZMA_ENCODER_MACROS: &[&str] = &[
     "HAVE_ENCODERS",
     "HAVE_ENCODER_LZMA1",
     "HAVE_ENCODER_LZMA2",
-    "HAVE_ENCODER_DELTA",
-    "HAVE_ENCODER_ARM",
-    "HAVE_ENCODER_ARM64",
-    "HAVE_ENCODER_ARMTHUMB",
-    "HAVE_ENCODER_IA64",
-    "HAVE_ENCODER_POWERPC",
-    "HAVE_ENCODER_SPARC",
-    "HAVE_ENCODER_X86",
-    "HAVE_ENCODER_RISCV",
     // Match finders used by the default presets
     "HAVE_MF_BT2",
     "HAVE_MF_BT3",
@@ -57,6 +46,91 @@ const LIBLZMA_FEATURE_MACROS: &[&str] = &[
     "HAVE_MF_HC4",
 ];
 
+/// Optional filters: cargo feature, name in the `HAVE_ENCODER_*`/`HAVE_DECODER_*` macros,
+/// and the sources only that filter needs
+const OPTIONAL_FILTERS: &[(&str, &str, &[&str])] = &[
+    (
+        "filter-delta",
+        "DELTA",
+        &["delta_common.c", "delta_encoder.c", "delta_decoder.c"],
+    ),
+    ("filter-x86", "X86", &["x86.c"]),
+    ("filter-powerpc", "POWERPC", &["powerpc.c"]),
+    ("filter-ia64", "IA64", &["ia64.c"]),
+    ("filter-arm", "ARM", &["arm.c"]),
+    ("filter-armthumb", "ARMTHUMB", &["armthumb.c"]),
+    ("filter-arm64", "ARM64", &["arm64.c"]),
+    ("filter-sparc", "SPARC", &["sparc.c"]),
+    ("filter-riscv", "RISCV", &["riscv.c"]),
+];
+
+/// Optional integrity checks: cargo feature, name in the `HAVE_CHECK_*` macro, and the
+/// sources only that check needs
+const OPTIONAL_CHECKS: &[(&str, &str, &[&str])] = &[
+    (
+        "check-crc64",
+        "CRC64",
+        &["crc64_fast.c", "crc64_table.c", "crc64_small.c"],
+    ),
+    ("check-sha256", "SHA256", &["sha256.c"]),
+];
+
+/// Sources that need liblzma's threading layer (`mythread.h`)
+const THREADED_SOURCES: &[&str] = &["outqueue.c", "stream_encoder_mt.c", "stream_decoder_mt.c"];
+
+/// Table-driven sources that the `small` feature replaces or drops
+const FAST_SOURCES: &[&str] = &[
+    "crc32_fast.c",
+    "crc32_table.c",
+    "crc64_fast.c",
+    "crc64_table.c",
+    "fastpos_table.c",
+];
+
+/// Size-optimized replacements used with the `small` feature
+const SMALL_SOURCES: &[&str] = &["crc32_small.c", "crc64_small.c"];
+
+/// Optional functions probed in a system liblzma: the capability exported to dependents
+/// and the symbol that provides it
+const PROBED_CAPABILITIES: &[(&str, &str)] = &[
+    ("threads", "lzma_stream_encoder_mt"),
+    ("mt_decoder", "lzma_stream_decoder_mt"),
+    ("microlzma", "lzma_microlzma_encoder"),
+    ("lzip_decoder", "lzma_lzip_decoder"),
+];
+
+/// Oldest system liblzma accepted unless `LIBLZMA_SYS_MIN_VERSION` says otherwise
+const DEFAULT_MIN_VERSION: &str = "5.2.0";
+
+/// Encoder-only sources whose names don't contain `_encoder`
+const ENCODER_ONLY_SOURCES: &[&str] = &["fastpos_table.c", "price_table.c"];
+
+/// Decoder-only sources whose names don't contain `_decoder`
+const DECODER_ONLY_SOURCES: &[&str] = &["file_info.c", "index_hash.c"];
+
+/// Public functions that only exist when liblzma is built with encoders
+#[cfg(feature = "bindgen")]
+const ENCODER_FUNCTIONS: &[&str] = &[
+    "lzma_.*_encoder(_.*)?",
+    "lzma_.*_encode",
+    "lzma_.*_buffer_bound",
+    "lzma_block_header_size",
+    "lzma_filter_flags_size",
+    "lzma_filters_update",
+    "lzma_mf_is_supported",
+    "lzma_mode_is_supported",
+    "lzma_mt_block_size",
+    "lzma_properties_size",
+];
+
+/// Public functions that only exist when liblzma is built with decoders
+#[cfg(feature = "bindgen")]
+const DECODER_FUNCTIONS: &[&str] = &[
+    "lzma_.*_decoder(_.*)?",
+    "lzma_.*_decode",
+    "lzma_index_hash_.*",
+];
+
 /// Represents the stability level of a liblzma version
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Stability {
@@ -201,14 +275,114 @@ struct VersionInfo {
     git_commit: Option<String>,
 }
 
+/// How the final binary links liblzma
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LinkMode {
+    /// Link a system liblzma dynamically, or the vendored build statically
+    Auto,
+    /// Link liblzma statically, whether from the system or the vendored build
+    Static,
+    /// Only link a system liblzma, dynamically
+    Dynamic,
+}
+
+impl LinkMode {
+    /// Read the link mode from `LIBLZMA_SYS_STATIC` or the `static`/`dynamic` features
+    fn detect() -> Result<Self, String> {
+        if let Some(value) = env::var_os("LIBLZMA_SYS_STATIC") {
+            return match value.to_str() {
+                Some("1" | "true" | "yes") => Ok(Self::Static),
+                Some("0" | "false" | "no") => Ok(Self::Dynamic),
+                _ => Err(format!(
+                    "LIBLZMA_SYS_STATIC must be 1 or 0, got {}",
+                    value.to_string_lossy()
+                )),
+            };
+        }
+
+        match (cfg!(feature = "static"), cfg!(feature = "dynamic")) {
+            (true, true) => {
+                Err("the `static` and `dynamic` features are mutually exclusive".into())
+            }
+            (true, false) => Ok(Self::Static),
+            (false, true) => Ok(Self::Dynamic),
+            (false, false) => Ok(Self::Auto),
+        }
+    }
+}
+
+/// Sandbox mechanism available to the tools, as picked by upstream's `--enable-sandbox`
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Sandbox {
+    Landlock,
+    Pledge,
+    Capsicum,
+}
+
+impl Sandbox {
+    /// Every mechanism and the target OS it exists on
+    const ALL: [(Self, &'static str); 3] = [
+        (Self::Landlock, "linux"),
+        (Self::Pledge, "openbsd"),
+        (Self::Capsicum, "freebsd"),
+    ];
+
+    /// Read the mechanism from `LIBLZMA_SYS_SANDBOX`, defaulting to the target's own
+    fn detect() -> Result<Option<Self>, String> {
+        let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+        let native = Self::ALL
+            .iter()
+            .find(|(_, os)| *os == target_os)
+            .map(|&(sandbox, _)| sandbox);
+
+        let requested = env::var("LIBLZMA_SYS_SANDBOX").unwrap_or_default();
+        let sandbox = match requested.as_str() {
+            "" | "auto" => return Ok(native),
+            "no" => return Ok(None),
+            name => Self::ALL
+                .iter()
+                .map(|&(sandbox, _)| sandbox)
+                .find(|sandbox| sandbox.name() == name)
+                .ok_or_else(|| {
+                    format!(
+                        "LIBLZMA_SYS_SANDBOX must be auto, no, landlock, pledge or capsicum, \
+                         got {name}"
+                    )
+                })?,
+        };
+
+        if native != Some(sandbox) {
+            return Err(format!(
+                "the {} sandbox is not available on {target_os}",
+                sandbox.name()
+            ));
+        }
+        Ok(Some(sandbox))
+    }
+
+    /// Name used in `LIBLZMA_SYS_SANDBOX` and the exported `cfg`
+    fn name(self) -> &'static str {
+        match self {
+            Self::Landlock => "landlock",
+            Self::Pledge => "pledge",
+            Self::Capsicum => "capsicum",
+        }
+    }
+}
+
 /// Information about a system-installed liblzma
 struct SystemLibrary {
     include_paths: Vec<String>,
+    version: Option<Version>,
+    /// Library directories and names to link capability probes against
+    link_paths: Vec<PathBuf>,
+    libs: Vec<String>,
 }
 
 /// Information about a vendored liblzma build
 struct VendoredBuild {
     include_paths: Vec<String>,
+    version: Version,
 }
 
 /// Information about a known vulnerable commit
@@ -239,6 +413,23 @@ fn main() {
     println!("cargo:rerun-if-changed=xz/src/liblzma/api/lzma/version.h");
     println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_ALLOW_UNSAFE");
     println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_FORCE_LOCAL");
+    println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_STATIC");
+    println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_INCLUDE_DIR");
+    println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_NO_VENDOR");
+    println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_MIN_VERSION");
+    println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_SANITIZE");
+    println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_SANDBOX");
+    println!("cargo:rerun-if-env-changed=LIBLZMA_SYS_SYMBOL_PREFIX");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rustc-check-cfg=cfg(liblzma_vendored)");
+    println!("cargo:rustc-check-cfg=cfg(liblzma_threads)");
+    println!("cargo:rustc-check-cfg=cfg(liblzma_symbol_prefix)");
+    println!("cargo:rustc-check-cfg=cfg(liblzma_sandbox, values(\"landlock\", \"pledge\", \"capsicum\"))");
+
+    if !cfg!(feature = "encoder") && !cfg!(feature = "decoder") {
+        panic!("liblzma-sys needs at least one of the `encoder` and `decoder` features");
+    }
 
     if let Err(err) = run() {
         panic!("{err}");
@@ -251,19 +442,49 @@ fn run() -> Result<(), String> {
     let manifest_dir =
         PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set by Cargo"));
     let allow_unsafe = env::var_os("LIBLZMA_SYS_ALLOW_UNSAFE").is_some();
-    let force_local = env::var_os("LIBLZMA_SYS_FORCE_LOCAL").is_some();
+    let sanitizers = requested_sanitizers();
+    let symbol_prefix = requested_symbol_prefix()?;
+    let force_local = env::var_os("LIBLZMA_SYS_FORCE_LOCAL").is_some()
+        || !sanitizers.is_empty()
+        || symbol_prefix.is_some();
+    let link_mode = LinkMode::detect()?;
+    let sandbox = Sandbox::detect()?;
+    let no_vendor = env::var_os("LIBLZMA_SYS_NO_VENDOR").is_some();
+    let lib_dir = env::var_os("LIBLZMA_SYS_LIB_DIR").map(PathBuf::from);
+    let min_version = Version::parse(
+        &env::var("LIBLZMA_SYS_MIN_VERSION").unwrap_or_else(|_| DEFAULT_MIN_VERSION.into()),
+    )
+    .map_err(|err| format!("LIBLZMA_SYS_MIN_VERSION: {err}"))?;
 
     let patches = PatchSet::discover(Path::new("patches"))?;
+    if (link_mode == LinkMode::Dynamic || no_vendor) && (force_local || !patches.is_empty()) {
+        return Err(
+            "the vendored build is disabled, but patches, sanitizers, a symbol prefix or \
+             LIBLZMA_SYS_FORCE_LOCAL force it"
+                .into(),
+        );
+    }
 
     let mut include_paths = Vec::new();
     let mut use_system_headers = false;
-
-    // Try system liblzma first, unless patches are present or forced local build
-    if patches.is_empty() && !force_local {
-        match try_system_liblzma(allow_unsafe)? {
+    let mut version = None;
+    let mut capabilities = None;
+
+    // An explicit library directory replaces pkg-config entirely
+    if let Some(lib_dir) = lib_dir.filter(|_| patches.is_empty() && !force_local) {
+        let system = use_liblzma_dir(&lib_dir, allow_unsafe, link_mode, min_version)?;
+        capabilities = probe_capabilities(&out_dir, &system);
+        include_paths = system.include_paths;
+        use_system_headers = true;
+        version = system.version;
+    } else if patches.is_empty() && !force_local {
+        // Try system liblzma first, unless patches are present or forced local build
+        match try_system_liblzma(allow_unsafe, link_mode == LinkMode::Static, min_version)? {
             Some(system) => {
+                capabilities = probe_capabilities(&out_dir, &system);
                 include_paths = system.include_paths;
                 use_system_headers = true;
+                version = system.version;
             }
             None => {
                 println!("cargo:warning=pkg-config did not yield a safe liblzma; trying vendored sources");
@@ -271,26 +492,107 @@ fn run() -> Result<(), String> {
         }
     } else if !patches.is_empty() {
         println!("cargo:warning=patches detected; forcing vendored liblzma build");
+    } else if !sanitizers.is_empty() {
+        println!("cargo:warning=sanitizers requested; forcing vendored liblzma build");
+    } else if symbol_prefix.is_some() {
+        println!("cargo:warning=symbol prefix requested; forcing vendored liblzma build");
     }
 
     // Fall back to vendored build if system library wasn't suitable
     if include_paths.is_empty() {
-        let vendored = build_vendored_liblzma(&out_dir, &manifest_dir, &patches, allow_unsafe)?;
+        if link_mode == LinkMode::Dynamic || no_vendor {
+            return Err(
+                "no usable system liblzma was found and the vendored build is disabled".into(),
+            );
+        }
+        let vendored = build_vendored_liblzma(
+            &out_dir,
+            &manifest_dir,
+            &patches,
+            &sanitizers,
+            symbol_prefix.as_deref(),
+            allow_unsafe,
+        )?;
         include_paths = vendored.include_paths;
         use_system_headers = false;
+        version = Some(vendored.version);
+    }
+
+    // Read by dependents' build scripts as `DEP_LZMA_VERSION_NUMBER`, in the same
+    // format as `LZMA_VERSION`
+    if let Some(version) = version {
+        println!("cargo:version_number={}", version.ordinal());
+    }
+
+    // Let the crate report which liblzma it ended up with. A system liblzma's
+    // multi-threaded API is used if it exports one and the `threads` feature is on.
+    if !use_system_headers {
+        println!("cargo:rustc-cfg=liblzma_vendored");
+    }
+    let threads = if use_system_headers {
+        cfg!(feature = "threads")
+            && capabilities
+                .as_ref()
+                .is_none_or(|found: &Vec<&str>| found.contains(&"threads"))
+    } else {
+        vendored_threads()
+    };
+    if threads {
+        println!("cargo:rustc-cfg=liblzma_threads");
+        // Read by dependents' build scripts as `DEP_LZMA_THREADS`
+        println!("cargo:threads=1");
+    }
+
+    // Read by dependents' build scripts as `DEP_LZMA_CAPABILITIES`, which takes
+    // precedence over the version for the functions that were probed
+    if let Some(found) = &capabilities {
+        println!("cargo:capabilities={}", found.join(","));
+    }
+
+    // The sandbox is up to the tools, not liblzma; exporting it keeps them in line
+    // with what the vendored build was configured for. Read by dependents' build
+    // scripts as `DEP_LZMA_SANDBOX`.
+    if let Some(sandbox) = sandbox {
+        println!("cargo:rustc-cfg=liblzma_sandbox=\"{}\"", sandbox.name());
+        println!("cargo:sandbox={}", sandbox.name());
+    }
+
+    // Rust code has to link against the renamed functions too. Read by dependents'
+    // build scripts as `DEP_LZMA_SYMBOL_PREFIX`.
+    if let Some(prefix) = &symbol_prefix {
+        println!("cargo:rustc-cfg=liblzma_symbol_prefix");
+        println!("cargo:symbol_prefix={prefix}");
+        #[cfg(not(feature = "bindgen"))]
+        write_prefixed_bindings(&out_dir, &manifest_dir, prefix)?;
     }
 
     // Generate bindings if the feature is enabled
     #[cfg(feature = "bindgen")]
-    generate_bindings(&out_dir, &include_paths, use_system_headers);
+    generate_bindings(
+        &out_dir,
+        &include_paths,
+        use_system_headers,
+        symbol_prefix.as_deref(),
+    );
 
     Ok(())
 }
 
 /// Attempt to use system-installed liblzma via pkg-config
 #[cfg(feature = "pkg-config")]
-fn try_system_liblzma(allow_unsafe: bool) -> Result<Option<SystemLibrary>, String> {
-    let library = match pkg_config::Config::new().probe("liblzma") {
+fn try_system_liblzma(
+    allow_unsafe: bool,
+    link_static: bool,
+    min_version: Version,
+) -> Result<Option<SystemLibrary>, String> {
+    // pkg-config links libraries from system directories dynamically even in static
+    // mode, so static link lines are emitted below instead.
+    let library = match pkg_config::Config::new()
+        .atleast_version(&min_version.display())
+        .statik(link_static)
+        .cargo_metadata(!link_static)
+        .probe("liblzma")
+    {
         Ok(info) => info,
         Err(err) => {
             println!("cargo:warning=pkg-config probe for liblzma failed: {err}");
@@ -299,17 +601,23 @@ fn try_system_liblzma(allow_unsafe: bool) -> Result<Option<SystemLibrary>, Strin
     };
 
     // Verify the system library version is safe
-    match library.version.as_str() {
+    let version = match library.version.as_str() {
         version_str if !version_str.is_empty() => {
             let version = Version::parse(version_str)?;
             if let Err(err) = ensure_version_safe(&version, allow_unsafe, "system liblzma") {
                 println!("cargo:warning={err}; falling back to vendored liblzma");
                 return Ok(None);
             }
+            Some(version)
         }
         _ => {
             println!("cargo:warning=pkg-config returned liblzma without version information; skipping CVE check");
+            None
         }
+    };
+
+    if link_static {
+        emit_static_link(&library);
     }
 
     // Export include paths for cargo
@@ -323,16 +631,168 @@ fn try_system_liblzma(allow_unsafe: bool) -> Result<Option<SystemLibrary>, Strin
         .map(|p| p.display().to_string())
         .collect();
 
-    Ok(Some(SystemLibrary { include_paths }))
+    Ok(Some(SystemLibrary {
+        include_paths,
+        version,
+        link_paths: library.link_paths,
+        libs: library.libs,
+    }))
+}
+
+/// Link the liblzma in `lib_dir`, with headers from `LIBLZMA_SYS_INCLUDE_DIR`
+///
+/// The include directory defaults to `include` next to `lib_dir`. The version is read
+/// from the headers since there is no pkg-config metadata to ask.
+fn use_liblzma_dir(
+    lib_dir: &Path,
+    allow_unsafe: bool,
+    link_mode: LinkMode,
+    min_version: Version,
+) -> Result<SystemLibrary, String> {
+    let include_dir = env::var_os("LIBLZMA_SYS_INCLUDE_DIR")
+        .map_or_else(|| lib_dir.join("../include"), PathBuf::from);
+
+    let header_path = include_dir.join("lzma/version.h");
+    let header = fs::read_to_string(&header_path).map_err(|err| {
+        format!(
+            "unable to read liblzma version header at {}: {err}; set LIBLZMA_SYS_INCLUDE_DIR",
+            header_path.display()
+        )
+    })?;
+    let version = parse_version_header(&header)?;
+    ensure_version_safe(&version, allow_unsafe, "liblzma in LIBLZMA_SYS_LIB_DIR")?;
+    if version.ordinal() < min_version.ordinal() {
+        return Err(format!(
+            "liblzma in LIBLZMA_SYS_LIB_DIR is {}, older than the required {}",
+            version.display(),
+            min_version.display()
+        ));
+    }
+
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    if link_mode == LinkMode::Static {
+        println!("cargo:rustc-link-lib=static=lzma");
+        // A static liblzma leaves its threading library for the final link
+        if needs_libpthread() {
+            println!("cargo:rustc-link-lib=pthread");
+        }
+    } else {
+        println!("cargo:rustc-link-lib=dylib=lzma");
+    }
+    println!("cargo:include={}", include_dir.display());
+
+    Ok(SystemLibrary {
+        include_paths: vec![include_dir.display().to_string()],
+        version: Some(version),
+        link_paths: vec![lib_dir.to_path_buf()],
+        libs: vec!["lzma".into()],
+    })
+}
+
+/// Link a probed system liblzma statically, and its dependencies as pkg-config reports
+#[cfg(feature = "pkg-config")]
+fn emit_static_link(library: &pkg_config::Library) {
+    for path in &library.link_paths {
+        println!("cargo:rustc-link-search=native={}", path.display());
+    }
+    for lib in &library.libs {
+        if lib == "lzma" {
+            // Not bundled, so the linker finds liblzma.a in its default paths as well
+            println!("cargo:rustc-link-lib=static:-bundle=lzma");
+        } else {
+            println!("cargo:rustc-link-lib={lib}");
+        }
+    }
 }
 
 /// Stub implementation when pkg-config feature is disabled
 #[cfg(not(feature = "pkg-config"))]
-fn try_system_liblzma(_allow_unsafe: bool) -> Result<Option<SystemLibrary>, String> {
+fn try_system_liblzma(
+    _allow_unsafe: bool,
+    _link_static: bool,
+    _min_version: Version,
+) -> Result<Option<SystemLibrary>, String> {
     println!("cargo:warning=pkg-config feature disabled; skipping system liblzma detection");
     Ok(None)
 }
 
+/// Check which optional functions a system liblzma exports
+///
+/// Distributions build liblzma with different options, so the version alone doesn't
+/// say whether, say, the threaded coders exist. Returns `None` if probe programs can't
+/// be linked at all, e.g. when cross compiling without a target linker, in which case
+/// dependents fall back to the version.
+fn probe_capabilities(out_dir: &Path, library: &SystemLibrary) -> Option<Vec<&'static str>> {
+    let probe_dir = out_dir.join("probes");
+    fs::create_dir_all(&probe_dir).ok()?;
+    let links = |symbol: &str| links_symbol(&probe_dir, library, symbol);
+
+    if !links("lzma_code") {
+        println!("cargo:warning=unable to link against the system liblzma to probe its functions; assuming them from its version");
+        return None;
+    }
+
+    Some(
+        PROBED_CAPABILITIES
+            .iter()
+            .filter(|(_, symbol)| links(symbol))
+            .map(|&(capability, _)| capability)
+            .collect(),
+    )
+}
+
+/// Whether a program referencing `symbol` links against `library`
+fn links_symbol(probe_dir: &Path, library: &SystemLibrary, symbol: &str) -> bool {
+    let source = probe_dir.join(format!("{symbol}.c"));
+    let program = probe_dir.join(symbol);
+
+    // Declared without the real prototype, like autoconf's AC_CHECK_FUNC: only the
+    // link matters.
+    let code = format!("char {symbol}(void);\nint main(void) {{ return (int)(long)&{symbol}; }}\n");
+    if fs::write(&source, code).is_err() {
+        return false;
+    }
+
+    let Ok(compiler) = cc::Build::new()
+        .cargo_metadata(false)
+        .warnings(false)
+        .try_get_compiler()
+    else {
+        return false;
+    };
+    let mut command = compiler.to_command();
+    if compiler.is_like_msvc() {
+        command
+            .arg(&source)
+            .arg(format!("/Fe{}", program.display()))
+            .arg(format!(
+                "/Fo{}",
+                probe_dir.join(format!("{symbol}.obj")).display()
+            ))
+            .arg("/link");
+        for path in &library.link_paths {
+            command.arg(format!("/LIBPATH:{}", path.display()));
+        }
+        for lib in &library.libs {
+            command.arg(format!("{lib}.lib"));
+        }
+    } else {
+        command.arg(&source).arg("-o").arg(&program);
+        for path in &library.link_paths {
+            command.arg(format!("-L{}", path.display()));
+        }
+        for lib in &library.libs {
+            command.arg(format!("-l{lib}"));
+        }
+    }
+
+    command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
 /// Apply patches and validate version information
 fn prepare_vendored_sources(
     manifest_dir: &Path,
@@ -375,11 +835,39 @@ fn get_sizeof_size_t() -> String {
 /// Configure `cc::Build` with feature macros and basic settings
 fn configure_build_features(build: &mut cc::Build) {
     // Enable encoder/decoder support since we bypass liblzma's configure step
-    for flag in LIBLZMA_FEATURE_MACROS {
+    let mut macros = LIBLZMA_FEATURE_MACROS.to_vec();
+    if cfg!(feature = "decoder") {
+        macros.extend_from_slice(LIBLZMA_DECODER_MACROS);
+    }
+    if cfg!(feature = "encoder") {
+        macros.extend_from_slice(LIBLZMA_ENCODER_MACROS);
+    }
+    for flag in macros {
         build.define(flag, "1");
     }
 
+    for (feature, name, _) in OPTIONAL_FILTERS {
+        if !feature_enabled(feature) {
+            continue;
+        }
+        if cfg!(feature = "decoder") {
+            build.define(&format!("HAVE_DECODER_{name}"), "1");
+        }
+        if cfg!(feature = "encoder") {
+            build.define(&format!("HAVE_ENCODER_{name}"), "1");
+        }
+    }
+    for (feature, name, _) in OPTIONAL_CHECKS {
+        if feature_enabled(feature) {
+            build.define(&format!("HAVE_CHECK_{name}"), "1");
+        }
+    }
+
     build.define("ASSUME_RAM", "128");
+
+    if cfg!(feature = "small") {
+        build.define("HAVE_SMALL", "1");
+    }
 }
 
 /// Add source files to the build
@@ -391,11 +879,13 @@ fn add_source_files(build: &mut cc::Build, manifest_dir: &Path) -> Result<(), St
 
     let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
     let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
-    if target_arch == "x86" && target_env != "msvc" {
+    // The assembly CRCs are table-driven like the C code they replace
+    if target_arch == "x86" && target_env != "msvc" && !cfg!(feature = "small") {
         let check_dir = liblzma_src.join("check");
-        build
-            .file(check_dir.join("crc32_x86.S"))
-            .file(check_dir.join("crc64_x86.S"));
+        build.file(check_dir.join("crc32_x86.S"));
+        if feature_enabled("check-crc64") {
+            build.file(check_dir.join("crc64_x86.S"));
+        }
     }
 
     let xz_common = manifest_dir.join("xz/src/common");
@@ -406,15 +896,79 @@ fn add_source_files(build: &mut cc::Build, manifest_dir: &Path) -> Result<(), St
     Ok(())
 }
 
+/// Whether the vendored build enables liblzma's threading support for the target
+///
+/// Without the `threads` feature, and on other targets such as wasm32, liblzma is
+/// built single-threaded and without the multithreaded coders.
+fn vendored_threads() -> bool {
+    cfg!(feature = "threads")
+        && env::var("CARGO_CFG_TARGET_FAMILY")
+            .is_ok_and(|family| family == "unix" || family == "windows")
+}
+
+/// Whether the target keeps pthreads in a separate library
+///
+/// Android's bionic has them in libc and ships no libpthread to link against.
+fn needs_libpthread() -> bool {
+    env::var("CARGO_CFG_TARGET_FAMILY").is_ok_and(|family| family == "unix")
+        && env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os != "android")
+}
+
 /// Configure target-specific settings
 fn configure_target_specific(build: &mut cc::Build) {
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    configure_tuklib(build, &target_os);
+
+    if !vendored_threads() {
+        return;
+    }
+
     let target_family = env::var("CARGO_CFG_TARGET_FAMILY").unwrap_or_default();
+    if target_family == "windows" {
+        // Condition variables and one-time init need Vista; MinGW uses them too
+        build.define("MYTHREAD_VISTA", "1");
+        return;
+    }
 
-    if target_family == "unix" {
-        build.define("MYTHREAD_POSIX", "1");
-        build.flag_if_supported("-pthread");
+    build.define("MYTHREAD_POSIX", "1");
+    build.flag_if_supported("-pthread");
+    if needs_libpthread() {
         println!("cargo:rustc-link-lib=pthread");
     }
+
+    // Keep encoder/decoder timeouts immune to wall clock changes. glibc, musl and
+    // bionic all have these; macOS lacks `pthread_condattr_setclock`.
+    if matches!(target_os.as_str(), "linux" | "android") {
+        build
+            .define("HAVE_CLOCK_GETTIME", "1")
+            .define("HAVE_CLOCK_MONOTONIC", "1")
+            .define("HAVE_PTHREAD_CONDATTR_SETCLOCK", "1");
+    }
+}
+
+/// Pick the tuklib backends that count CPU cores and physical memory
+///
+/// tuklib detects Windows by itself. Elsewhere, without a backend, `lzma_cputhreads()`
+/// and `lzma_physmem()` report 0, which callers already treat as unknown.
+fn configure_tuklib(build: &mut cc::Build, target_os: &str) {
+    match target_os {
+        "linux" | "android" => {
+            build
+                .define("TUKLIB_CPUCORES_SCHED_GETAFFINITY", "1")
+                .define("TUKLIB_PHYSMEM_SYSCONF", "1");
+        }
+        "macos" | "ios" | "freebsd" | "netbsd" | "openbsd" | "dragonfly" => {
+            build
+                .define("TUKLIB_CPUCORES_SYSCTL", "1")
+                .define("TUKLIB_PHYSMEM_SYSCTL", "1");
+        }
+        "solaris" | "illumos" => {
+            build
+                .define("TUKLIB_CPUCORES_SYSCONF", "1")
+                .define("TUKLIB_PHYSMEM_SYSCONF", "1");
+        }
+        _ => {}
+    }
 }
 
 /// Configure architecture-specific feature to enable optimizations
@@ -422,31 +976,63 @@ fn configure_cpu_features(build: &mut cc::Build) {
     let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
     let target_env = env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
 
-    if target_env != "msvc" {
+    // GCC/Clang only; with MSVC tuklib_integer.h uses `_byteswap_*` instead
+    let gnu_builtins = target_env != "msvc";
+    if gnu_builtins {
         build.define("HAVE_FUNC_ATTRIBUTE_CONSTRUCTOR", "1");
     }
 
-    match target_arch.as_str() {
-        "x86_64" | "x86" => {
-            build.define("TUKLIB_FAST_UNALIGNED_ACCESS", "1");
+    if matches!(
+        target_arch.as_str(),
+        "x86_64" | "x86" | "aarch64" | "loongarch64"
+    ) {
+        build.define("TUKLIB_FAST_UNALIGNED_ACCESS", "1");
+        if gnu_builtins {
             build.define("HAVE___BUILTIN_BSWAPXX", "1");
             build.define("HAVE___BUILTIN_ASSUME_ALIGNED", "1");
-            build.define("HAVE_USABLE_CLMUL", "1");
+        }
+    }
+
+    if cfg!(feature = "small") {
+        // Upstream's small build only has the bitwise CRCs
+        return;
+    }
+
+    if target_arch == "x86" && target_env != "msvc" {
+        build.define("HAVE_CRC_X86_ASM", "1");
+    }
+
+    if cfg!(feature = "hw-crc") {
+        configure_hardware_crc(build, &target_arch, &target_env);
+    }
+}
 
-            if target_arch == "x86" && target_env != "msvc" {
-                build.define("HAVE_CRC_X86_ASM", "1");
+/// Enable the CLMUL, ARM64 and LoongArch CRC code paths
+///
+/// liblzma picks the accelerated implementation at run time, so it also needs a way
+/// to query the CPU; without one it silently keeps using the portable tables.
+fn configure_hardware_crc(build: &mut cc::Build, target_arch: &str, target_env: &str) {
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+
+    match target_arch {
+        "x86_64" | "x86" => {
+            build.define("HAVE_USABLE_CLMUL", "1");
+            // MSVC uses `__cpuid` from <intrin.h> instead
+            if target_env != "msvc" {
+                build.define("HAVE_CPUID_H", "1");
             }
         }
         "aarch64" => {
-            build.define("TUKLIB_FAST_UNALIGNED_ACCESS", "1");
-            build.define("HAVE___BUILTIN_BSWAPXX", "1");
-            build.define("HAVE___BUILTIN_ASSUME_ALIGNED", "1");
             build.define("HAVE_ARM64_CRC32", "1");
+            match target_os.as_str() {
+                "linux" | "android" => build.define("HAVE_GETAUXVAL", "1"),
+                "freebsd" => build.define("HAVE_ELF_AUX_INFO", "1"),
+                "macos" | "ios" => build.define("HAVE_SYSCTLBYNAME", "1"),
+                // Windows uses `IsProcessorFeaturePresent`
+                _ => build,
+            };
         }
         "loongarch64" => {
-            build.define("TUKLIB_FAST_UNALIGNED_ACCESS", "1");
-            build.define("HAVE___BUILTIN_BSWAPXX", "1");
-            build.define("HAVE___BUILTIN_ASSUME_ALIGNED", "1");
             build.define("HAVE_LOONGARCH_CRC32", "1");
         }
         _ => {}
@@ -480,8 +1066,217 @@ fn configure_package_info(build: &mut cc::Build, version_info: &VersionInfo, siz
         .define("HAVE__BOOL", "1")
         .define("HAVE_STDINT_H", "1")
         .define("_GNU_SOURCE", "1")
-        .flag_if_supported("-std=c99")
         .warnings(false);
+
+    // cl.exe has no C99 mode flag and only warns about unknown options
+    if env::var("CARGO_CFG_TARGET_ENV").is_ok_and(|target_env| target_env != "msvc") {
+        build.flag_if_supported("-std=c99");
+    }
+}
+
+/// Sanitizers to instrument the vendored C code with
+///
+/// `LIBLZMA_SYS_SANITIZE` takes a comma-separated list such as `address,undefined`.
+/// Without it, the sanitizers Rust code is built with (`-Zsanitizer`, as used by
+/// `cargo fuzz`) are applied to the C code as well.
+fn requested_sanitizers() -> Vec<String> {
+    env::var("LIBLZMA_SYS_SANITIZE")
+        .or_else(|_| env::var("CARGO_CFG_SANITIZE"))
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Compile the vendored sources with sanitizer instrumentation
+fn configure_sanitizers(build: &mut cc::Build, sanitizers: &[String]) {
+    if sanitizers.is_empty() {
+        return;
+    }
+
+    if env::var("CARGO_CFG_TARGET_ENV").is_ok_and(|target_env| target_env == "msvc") {
+        // MSVC only has AddressSanitizer
+        if sanitizers.iter().any(|name| name == "address") {
+            build.flag("/fsanitize=address");
+        }
+        return;
+    }
+
+    build
+        .flag(format!("-fsanitize={}", sanitizers.join(",")))
+        .flag("-fno-omit-frame-pointer")
+        .flag("-g");
+
+    // Rust doesn't link a UBSan runtime, so undefined behaviour traps instead of
+    // being reported, which fuzzers pick up as a crash.
+    if sanitizers.iter().any(|name| name == "undefined") {
+        build.flag("-fsanitize-trap=undefined");
+    }
+}
+
+/// Prefix for the symbols of the vendored build, from `LIBLZMA_SYS_SYMBOL_PREFIX`
+fn requested_symbol_prefix() -> Result<Option<String>, String> {
+    let prefix = env::var("LIBLZMA_SYS_SYMBOL_PREFIX").unwrap_or_default();
+    if prefix.is_empty() {
+        return Ok(None);
+    }
+
+    let mut chars = prefix.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!(
+            "LIBLZMA_SYS_SYMBOL_PREFIX must be a C identifier, got {prefix}"
+        ));
+    }
+    Ok(Some(prefix))
+}
+
+/// Rename every external liblzma symbol to start with `prefix`
+///
+/// A header included ahead of every source maps each name liblzma's headers declare
+/// `extern` to its prefixed form, so definitions and references are renamed alike and
+/// the library can share a process with another liblzma.
+fn configure_symbol_prefix(
+    build: &mut cc::Build,
+    out_dir: &Path,
+    manifest_dir: &Path,
+    prefix: &str,
+) -> Result<(), String> {
+    let mut headers = Vec::new();
+    gather_files(&manifest_dir.join("xz/src/liblzma"), "h", &mut headers)?;
+
+    let mut symbols = BTreeSet::new();
+    for header in &headers {
+        let content = fs::read_to_string(header)
+            .map_err(|err| format!("failed to read {}: {err}", header.display()))?;
+        symbols.extend(
+            content
+                .lines()
+                .filter_map(extern_symbol)
+                .map(str::to_string),
+        );
+    }
+
+    let mut defines = String::from("/* Generated by liblzma-sys build.rs */\n");
+    for symbol in &symbols {
+        let _ = writeln!(defines, "#define {symbol} {prefix}{symbol}");
+    }
+    let header = out_dir.join("lzma_symbol_prefix.h");
+    fs::write(&header, defines)
+        .map_err(|err| format!("failed to write {}: {err}", header.display()))?;
+
+    if env::var("CARGO_CFG_TARGET_ENV").is_ok_and(|target_env| target_env == "msvc") {
+        build.flag(format!("/FI{}", header.display()));
+    } else {
+        build.flag("-include").flag(&header);
+    }
+
+    // tuklib names its symbols through a prefix macro of its own
+    build.define("TUKLIB_SYMBOL_PREFIX", format!("{prefix}lzma_").as_str());
+    Ok(())
+}
+
+/// Name of the function or variable an `extern` declaration in a liblzma header
+/// declares, such as `lzma_code` in `extern LZMA_API(lzma_ret) lzma_code(`
+fn extern_symbol(line: &str) -> Option<&str> {
+    let line = line.trim_start().strip_prefix("extern ")?;
+    let mut rest = line;
+    while let Some(start) = rest.find("lzma_") {
+        let candidate = &rest[start..];
+        let len = candidate
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(candidate.len());
+        let (name, after) = candidate.split_at(len);
+
+        // Skip the return type and `lzma_attr_*` annotation macros
+        let declares = after.trim_start().starts_with(['(', '[', ';']);
+        let preceded_by_identifier = rest[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        if declares && !preceded_by_identifier && !name.starts_with("lzma_attr") {
+            return Some(name);
+        }
+        rest = after;
+    }
+    None
+}
+
+/// Copy the pre-generated bindings into `OUT_DIR`, linking each function under its
+/// prefixed name
+#[cfg(not(feature = "bindgen"))]
+fn write_prefixed_bindings(
+    out_dir: &Path,
+    manifest_dir: &Path,
+    prefix: &str,
+) -> Result<(), String> {
+    let source = manifest_dir.join("src/lzma_bindings.rs");
+    println!("cargo:rerun-if-changed={}", source.display());
+    let bindings = fs::read_to_string(&source)
+        .map_err(|err| format!("failed to read {}: {err}", source.display()))?;
+
+    let mut prefixed = String::with_capacity(bindings.len());
+    for line in bindings.lines() {
+        let trimmed = line.trim_start();
+        if let Some(signature) = trimmed.strip_prefix("pub fn lzma_") {
+            let indent = &line[..line.len() - trimmed.len()];
+            let name = signature.split('(').next().unwrap_or_default();
+            let _ = writeln!(prefixed, "{indent}#[link_name = \"{prefix}lzma_{name}\"]");
+        }
+        prefixed.push_str(line);
+        prefixed.push('\n');
+    }
+
+    let target = out_dir.join("lzma_bindings.rs");
+    fs::write(&target, prefixed)
+        .map_err(|err| format!("failed to write {}: {err}", target.display()))
+}
+
+/// Keep the vendored objects independent of where and when they were built
+///
+/// With GCC and Clang, paths of the crate and `OUT_DIR` are remapped so `__FILE__`
+/// (used by `assert`) and debug info don't embed the build machine's directories.
+/// liblzma doesn't use `__DATE__`/`__TIME__`; `-Wdate-time` keeps patches from adding
+/// them unnoticed, and GCC and Clang take them from `SOURCE_DATE_EPOCH`, which the
+/// compiler inherits.
+fn configure_reproducibility(
+    build: &mut cc::Build,
+    manifest_dir: &Path,
+    out_dir: &Path,
+) -> Result<(), String> {
+    if let Ok(epoch) = env::var("SOURCE_DATE_EPOCH") {
+        if epoch.parse::<u64>().is_err() {
+            return Err(format!(
+                "SOURCE_DATE_EPOCH must be a Unix timestamp, got {epoch}"
+            ));
+        }
+    }
+
+    if env::var("CARGO_CFG_TARGET_ENV").is_ok_and(|target_env| target_env == "msvc") {
+        // Objects without embedded timestamps
+        build.flag("/Brepro");
+        return Ok(());
+    }
+
+    for (dir, replacement) in [(manifest_dir, "liblzma-sys"), (out_dir, "out")] {
+        // `-ffile-prefix-map` combines both, but needs GCC 8 or Clang 10
+        build
+            .flag_if_supported(format!(
+                "-fdebug-prefix-map={}={replacement}",
+                dir.display()
+            ))
+            .flag_if_supported(format!(
+                "-fmacro-prefix-map={}={replacement}",
+                dir.display()
+            ));
+    }
+    build.flag_if_supported("-Wdate-time");
+    Ok(())
 }
 
 /// Build liblzma from vendored sources
@@ -489,6 +1284,8 @@ fn build_vendored_liblzma(
     out_dir: &Path,
     manifest_dir: &Path,
     patches: &PatchSet,
+    sanitizers: &[String],
+    symbol_prefix: Option<&str>,
     allow_unsafe: bool,
 ) -> Result<VendoredBuild, String> {
     let version_info = prepare_vendored_sources(manifest_dir, patches, allow_unsafe)?;
@@ -504,6 +1301,11 @@ fn build_vendored_liblzma(
     configure_cpu_features(&mut build);
     add_include_directories(&mut build, manifest_dir);
     configure_package_info(&mut build, &version_info, &sizeof_size_t);
+    configure_sanitizers(&mut build, sanitizers);
+    configure_reproducibility(&mut build, manifest_dir, out_dir)?;
+    if let Some(prefix) = symbol_prefix {
+        configure_symbol_prefix(&mut build, out_dir, manifest_dir, prefix)?;
+    }
 
     match build.try_compile("lzma") {
         Ok(()) => {}
@@ -515,6 +1317,7 @@ fn build_vendored_liblzma(
             .join("xz/src/liblzma/api")
             .to_string_lossy()
             .to_string()],
+        version: version_info.version,
     })
 }
 
@@ -547,12 +1350,7 @@ fn read_vendored_version(manifest_dir: &Path) -> Result<VersionInfo, String> {
         message
     })?;
 
-    let version = Version::new(
-        parse_define_u32(&header, "LZMA_VERSION_MAJOR")?,
-        parse_define_u32(&header, "LZMA_VERSION_MINOR")?,
-        parse_define_u32(&header, "LZMA_VERSION_PATCH")?,
-        Stability::from_token(&parse_define_token(&header, "LZMA_VERSION_STABILITY")?)?,
-    );
+    let version = parse_version_header(&header)?;
 
     let commit_suffix = parse_define_string(&header, "LZMA_VERSION_COMMIT");
     let xz_dir = Path::new(&manifest_dir).join("xz");
@@ -565,6 +1363,16 @@ fn read_vendored_version(manifest_dir: &Path) -> Result<VersionInfo, String> {
     })
 }
 
+/// Parse the release version from the contents of `lzma/version.h`
+fn parse_version_header(header: &str) -> Result<Version, String> {
+    Ok(Version::new(
+        parse_define_u32(header, "LZMA_VERSION_MAJOR")?,
+        parse_define_u32(header, "LZMA_VERSION_MINOR")?,
+        parse_define_u32(header, "LZMA_VERSION_PATCH")?,
+        Stability::from_token(&parse_define_token(header, "LZMA_VERSION_STABILITY")?)?,
+    ))
+}
+
 /// Parse a numeric #define from C header content
 fn parse_define_u32(content: &str, name: &str) -> Result<u32, String> {
     let needle = format!("#define {name} ");
@@ -769,7 +1577,7 @@ fn is_patch_file(path: &Path) -> bool {
 
 fn collect_liblzma_sources(base: &Path) -> Result<Vec<PathBuf>, String> {
     let mut files = Vec::new();
-    gather_c_sources(base, &mut files)?;
+    gather_files(base, "c", &mut files)?;
 
     files.retain(|path| {
         let name = path
@@ -783,16 +1591,49 @@ fn collect_liblzma_sources(base: &Path) -> Result<Vec<PathBuf>, String> {
                 | "crc_clmul_consts_gen.c"
                 | "fastpos_tablegen.c"
                 | "price_tablegen.c"
-                | "crc32_small.c"
-                | "crc64_small.c"
-        )
+        ) && source_enabled(name)
+            && (vendored_threads() || !THREADED_SOURCES.contains(&name))
     });
 
+    // `read_dir` order varies between file systems; a fixed order keeps the archive
+    // identical across machines
     files.sort();
     Ok(files)
 }
 
-fn gather_c_sources(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
+/// Whether a cargo feature of this crate is enabled
+fn feature_enabled(feature: &str) -> bool {
+    let var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+    env::var_os(var).is_some()
+}
+
+/// Whether a liblzma source file is needed with the enabled coder, filter, check and
+/// `small` features
+fn source_enabled(name: &str) -> bool {
+    // Presets are read by `lzma_easy_decoder_memusage` as well.
+    if name == "lzma_encoder_presets.c" {
+        return true;
+    }
+
+    let small = cfg!(feature = "small");
+    if (small && FAST_SOURCES.contains(&name)) || (!small && SMALL_SOURCES.contains(&name)) {
+        return false;
+    }
+
+    let optional = OPTIONAL_FILTERS.iter().chain(OPTIONAL_CHECKS);
+    for (feature, _, sources) in optional {
+        if sources.contains(&name) && !feature_enabled(feature) {
+            return false;
+        }
+    }
+
+    let encoder_only = name.contains("_encoder") || ENCODER_ONLY_SOURCES.contains(&name);
+    let decoder_only = name.contains("_decoder") || DECODER_ONLY_SOURCES.contains(&name);
+    (!encoder_only || cfg!(feature = "encoder")) && (!decoder_only || cfg!(feature = "decoder"))
+}
+
+/// Recursively collect the files in `dir` with the given extension
+fn gather_files(dir: &Path, extension: &str, out: &mut Vec<PathBuf>) -> Result<(), String> {
     for entry in
         fs::read_dir(dir).map_err(|err| format!("failed to read {}: {err}", dir.display()))?
     {
@@ -800,8 +1641,8 @@ fn gather_c_sources(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
             entry.map_err(|err| format!("failed to access entry in {}: {err}", dir.display()))?;
         let path = entry.path();
         if path.is_dir() {
-            gather_c_sources(&path, out)?;
-        } else if path.extension().and_then(|s| s.to_str()) == Some("c") {
+            gather_files(&path, extension, out)?;
+        } else if path.extension().and_then(|s| s.to_str()) == Some(extension) {
             out.push(path);
         }
     }
@@ -811,7 +1652,12 @@ fn gather_c_sources(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
 
 /// Generate Rust bindings for liblzma using bindgen
 #[cfg(feature = "bindgen")]
-fn generate_bindings(out_dir: &Path, include_paths: &[String], use_system_headers: bool) {
+fn generate_bindings(
+    out_dir: &Path,
+    include_paths: &[String],
+    use_system_headers: bool,
+    symbol_prefix: Option<&str>,
+) {
     let mut builder = bindgen::Builder::default()
         .header("lzma.h")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
@@ -829,6 +1675,22 @@ fn generate_bindings(out_dir: &Path, include_paths: &[String], use_system_header
         // from C comments that contain text interpreted as Rust code
         .generate_comments(false);
 
+    // Only expose the coders that were built
+    if !cfg!(feature = "encoder") {
+        for pattern in ENCODER_FUNCTIONS {
+            builder = builder.blocklist_function(pattern);
+        }
+    }
+    if !cfg!(feature = "decoder") {
+        for pattern in DECODER_FUNCTIONS {
+            builder = builder.blocklist_function(pattern);
+        }
+    }
+
+    if let Some(prefix) = symbol_prefix {
+        builder = builder.parse_callbacks(Box::new(SymbolPrefix(prefix.to_string())));
+    }
+
     // Add system header flag if using pkg-config
     if use_system_headers {
         builder = builder.clang_arg("-DPKG_CONFIG");
@@ -847,3 +1709,18 @@ fn generate_bindings(out_dir: &Path, include_paths: &[String], use_system_header
         .write_to_file(out_dir.join("bindings.rs"))
         .expect("Couldn't write bindings.rs to OUT_DIR");
 }
+
+/// Links every generated function under its prefixed symbol name
+#[cfg(feature = "bindgen")]
+#[derive(Debug)]
+struct SymbolPrefix(String);
+
+#[cfg(feature = "bindgen")]
+impl bindgen::callbacks::ParseCallbacks for SymbolPrefix {
+    fn generated_link_name_override(
+        &self,
+        item_info: bindgen::callbacks::ItemInfo<'_>,
+    ) -> Option<String> {
+        Some(format!("{}{}", self.0, item_info.name))
+    }
+}
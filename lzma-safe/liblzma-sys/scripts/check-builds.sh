@@ -20,9 +20,15 @@ run_case "forced vendored build" \
   env LIBLZMA_SYS_FORCE_LOCAL=1 cargo check
 
 run_case "bindgen without pkg-config" \
-  cargo check --no-default-features --features bindgen
+  cargo check --no-default-features --features bindgen,encoder,decoder
 
 run_case "pre-generated bindings (no bindgen)" \
-  cargo check --no-default-features
+  cargo check --no-default-features --features encoder,decoder
+
+run_case "decoder-only vendored build" \
+  env LIBLZMA_SYS_FORCE_LOCAL=1 cargo check --no-default-features --features decoder
+
+run_case "size-optimized vendored build" \
+  env LIBLZMA_SYS_FORCE_LOCAL=1 cargo check --features small
 
 echo "All build permutations completed."
@@ -14,8 +14,30 @@ include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
 // When the `bindgen` feature is disabled, use pre-generated bindings.
 // This is typically used for pre-generated bindings or when bindgen is not available.
-#[cfg(not(feature = "bindgen"))]
+#[cfg(all(not(feature = "bindgen"), not(liblzma_symbol_prefix)))]
 include!("lzma_bindings.rs");
 
+// With a symbol prefix, the build script writes a copy that links the renamed symbols.
+#[cfg(all(not(feature = "bindgen"), liblzma_symbol_prefix))]
+include!(concat!(env!("OUT_DIR"), "/lzma_bindings.rs"));
+
 /// Size of the legacy `LZMA_Alone` header in bytes.
 pub const LZMA_ALONE_HEADER_SIZE: usize = 1 + 4 + 8;
+
+/// Whether liblzma was built from the bundled sources rather than found via pkg-config.
+pub const LIBLZMA_VENDORED: bool = cfg!(liblzma_vendored);
+
+/// Whether the linked liblzma was built with multi-threading support.
+pub const LIBLZMA_THREADS: bool = cfg!(liblzma_threads);
+
+/// Sandbox mechanism the tools can rely on for this target: `"landlock"`, `"pledge"` or
+/// `"capsicum"`. `None` if the target has none or `LIBLZMA_SYS_SANDBOX=no` was set.
+pub const LIBLZMA_SANDBOX: Option<&str> = if cfg!(liblzma_sandbox = "landlock") {
+    Some("landlock")
+} else if cfg!(liblzma_sandbox = "pledge") {
+    Some("pledge")
+} else if cfg!(liblzma_sandbox = "capsicum") {
+    Some("capsicum")
+} else {
+    None
+};
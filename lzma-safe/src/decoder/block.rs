@@ -0,0 +1,314 @@
+//! Single XZ Block decoder.
+//!
+//! Decodes one Block (header, compressed data, padding and check) without the surrounding
+//! Stream Header, Index or Stream Footer. Combined with the offsets stored in an XZ Index this
+//! allows decoding to start at any Block boundary instead of the beginning of the file.
+
+use crate::encoder::options::IntegrityCheck;
+use crate::{Action, Error, Result, Stream};
+
+/// Number of entries in a filter array passed to `lzma_block_header_decode`.
+const FILTERS_LEN: usize = liblzma_sys::LZMA_FILTERS_MAX as usize + 1;
+
+/// `LZMA_VLI_UNKNOWN`: marks absent sizes and terminates filter arrays.
+const LZMA_VLI_UNKNOWN: u64 = u64::MAX;
+
+/// Information stored in an XZ Block Header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHeader {
+    /// Size of the Block Header in bytes.
+    pub header_size: u32,
+    /// Compressed Size field, if present in the header.
+    pub compressed_size: Option<u64>,
+    /// Uncompressed Size field, if present in the header.
+    pub uncompressed_size: Option<u64>,
+    /// Filter IDs of the chain, in encoding order.
+    pub filter_ids: Vec<u64>,
+    /// Filter chain and decoder options in xz command line syntax (e.g. `--lzma2=dict=8MiB`).
+    pub filters: String,
+    /// Memory needed to decode the Block (in bytes).
+    pub memusage: u64,
+}
+
+impl BlockHeader {
+    /// Decodes the Block Header at the start of `header` without initialising a decoder.
+    ///
+    /// # Parameters
+    ///
+    /// * `header` - Buffer starting with the complete Block Header.
+    /// * `check` - Integrity check of the Stream containing the Block (from its Stream Flags).
+    ///
+    /// # Returns
+    ///
+    /// Returns the sizes, filter chain and memory usage recorded in the header.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::DataError`] if `header` starts with the Index Indicator.
+    /// Returns [`crate::Error::BufError`] if `header` is shorter than the encoded header size.
+    /// Returns [`crate::Error::OptionsError`] if the header uses unsupported options.
+    /// Returns [`crate::Error::MemError`] if memory allocation fails.
+    pub fn decode(header: &[u8], check: IntegrityCheck) -> Result<Self> {
+        let mut stream = Stream::default();
+        let (block, mut filters) = decode_header(header, check, &mut stream)?;
+
+        let filter_ids = filters
+            .iter()
+            .map(|filter| filter.id)
+            .take_while(|&id| id != LZMA_VLI_UNKNOWN)
+            .collect();
+        let memusage = crate::ffi::lzma_raw_decoder_memusage(&filters[..]);
+        let described = crate::ffi::lzma_str_from_filters(&filters[..]);
+        crate::ffi::lzma_filters_free(&mut filters[..], &mut stream);
+
+        Ok(Self {
+            header_size: block.header_size,
+            compressed_size: known_vli(block.compressed_size),
+            uncompressed_size: known_vli(block.uncompressed_size),
+            filter_ids,
+            filters: described?,
+            memusage,
+        })
+    }
+}
+
+/// Streaming decoder for a single XZ Block.
+pub struct BlockDecoder {
+    memlimit: u64,
+    stream: Option<Stream>,
+    total_in: u64,
+    total_out: u64,
+    /// Block options; liblzma keeps a pointer to this until decoding finishes, so it is boxed
+    /// to keep its address stable when the decoder is moved.
+    block: Box<liblzma_sys::lzma_block>,
+    /// Filter array referenced by `block.filters`.
+    _filters: Box<[liblzma_sys::lzma_filter; FILTERS_LEN]>,
+}
+
+impl BlockDecoder {
+    /// Returns the size of a Block Header from its first byte.
+    ///
+    /// # Parameters
+    ///
+    /// * `first_byte` - The first byte of the Block Header.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` when the byte is the Index Indicator (`0x00`), meaning no further
+    /// Blocks follow in the current Stream.
+    pub fn header_size(first_byte: u8) -> Option<usize> {
+        if first_byte == 0 {
+            None
+        } else {
+            Some((usize::from(first_byte) + 1) * 4)
+        }
+    }
+
+    /// Creates a decoder for the Block whose header is at the start of `header`.
+    ///
+    /// # Parameters
+    ///
+    /// * `header` - Buffer starting with the complete Block Header. Bytes after the header
+    ///   are ignored and must be passed to [`process`](Self::process).
+    /// * `check` - Integrity check of the Stream containing the Block (from its Stream Flags).
+    /// * `ignore_check` - Skip verification of the Block's integrity check.
+    /// * `memlimit` - Maximum memory usage for decoding (in bytes).
+    /// * `stream` - An initialized [`Stream`] for LZMA operations.
+    ///
+    /// # Returns
+    ///
+    /// Returns a new [`BlockDecoder`] positioned right after the Block Header.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::DataError`] if `header` starts with the Index Indicator.
+    /// Returns [`crate::Error::BufError`] if `header` is shorter than the encoded header size.
+    /// Returns [`crate::Error::OptionsError`] if the header uses unsupported options.
+    /// Returns [`crate::Error::MemLimitError`] if the filter chain needs more than `memlimit`.
+    /// Returns [`crate::Error::MemError`] if memory allocation fails.
+    pub fn new(
+        header: &[u8],
+        check: IntegrityCheck,
+        ignore_check: bool,
+        memlimit: u64,
+        mut stream: Stream,
+    ) -> Result<Self> {
+        let (mut block, mut filters) = decode_header(header, check, &mut stream)?;
+        // `lzma_block_header_decode` always resets this flag, so it must be set afterwards.
+        block.ignore_check = u8::from(ignore_check);
+
+        let result = if crate::ffi::lzma_raw_decoder_memusage(&filters[..]) > memlimit {
+            Err(Error::MemLimitError)
+        } else {
+            crate::ffi::lzma_block_decoder(&mut block, &mut stream)
+        };
+        // Filter options are only needed to initialise the decoder.
+        crate::ffi::lzma_filters_free(&mut filters[..], &mut stream);
+        result?;
+
+        Ok(Self {
+            memlimit,
+            stream: Some(stream),
+            total_in: 0,
+            total_out: 0,
+            block,
+            _filters: filters,
+        })
+    }
+
+    /// Decompresses Block input.
+    ///
+    /// Feeds the bytes following the Block Header into the underlying liblzma stream and
+    /// writes decompressed output into `output`. Decoding completes once the Block's check
+    /// field has been consumed, after which [`is_finished()`](Self::is_finished) returns
+    /// `true`; any input beyond the end of the Block is left unconsumed.
+    ///
+    /// # Parameters
+    ///
+    /// * `input` - Buffer containing Block bytes to decode.
+    /// * `output` - Buffer to write decompressed data into.
+    /// * `action` - Decoding action (e.g. [`Action::Run`], [`Action::Finish`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::BufError`] if no progress is possible.
+    /// Returns [`crate::Error::DataError`] if the Block is corrupted or its sizes do not match
+    /// the header.
+    /// Returns [`crate::Error::MemError`] if memory allocation fails.
+    /// Returns [`crate::Error::ProgError`] if the decoder is misused (e.g. calling after finish).
+    ///
+    /// # Returns
+    ///
+    /// Returns a tuple `(bytes_read, bytes_written)` on success. End-of-block is reported as
+    /// `Ok`, not as an error.
+    pub fn process(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        action: Action,
+    ) -> Result<(usize, usize)> {
+        let Some(mut stream) = self.stream.take() else {
+            return Err(Error::ProgError);
+        };
+
+        if !input.is_empty() {
+            stream.set_next_input(input);
+        } else if action == Action::Finish && stream.avail_in() == 0 {
+            stream.set_next_input(&[]);
+        }
+        stream.set_next_out(output);
+
+        let input_before = stream.avail_in();
+        let output_before = stream.avail_out();
+
+        let mut result = crate::ffi::lzma_code(&mut stream, action);
+        let bytes_read = input_before - stream.avail_in();
+        let bytes_written = output_before - stream.avail_out();
+
+        if matches!(result, Err(Error::BufError)) && (bytes_read != 0 || bytes_written != 0) {
+            result = Ok(());
+        }
+
+        self.total_in = stream.total_in();
+        self.total_out = stream.total_out();
+
+        match result {
+            Ok(()) => {
+                self.stream = Some(stream);
+                Ok((bytes_read, bytes_written))
+            }
+            Err(Error::StreamEnd) => {
+                stream.finish();
+                Ok((bytes_read, bytes_written))
+            }
+            Err(err) => {
+                self.stream = Some(stream);
+                Err(err)
+            }
+        }
+    }
+
+    /// Whether the whole Block has been decoded.
+    pub fn is_finished(&self) -> bool {
+        self.stream.is_none()
+    }
+
+    /// Total number of bytes consumed from the input side, excluding the Block Header.
+    pub fn total_in(&self) -> u64 {
+        self.total_in
+    }
+
+    /// Total number of bytes produced by the decoder.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    /// Memory limit passed to the decoder.
+    pub fn memlimit(&self) -> u64 {
+        self.memlimit
+    }
+
+    /// Size of the decoded Block Header in bytes.
+    pub fn block_header_size(&self) -> u32 {
+        self.block.header_size
+    }
+
+    /// Uncompressed size recorded in the Block Header, or once decoding finishes, the actual
+    /// uncompressed size. `None` while unknown.
+    pub fn uncompressed_size(&self) -> Option<u64> {
+        known_vli(self.block.uncompressed_size)
+    }
+
+    /// Compressed size recorded in the Block Header, or once decoding finishes, the actual
+    /// compressed size. `None` while unknown.
+    pub fn compressed_size(&self) -> Option<u64> {
+        known_vli(self.block.compressed_size)
+    }
+}
+
+/// Decode a Block Header into boxed block options and the filter array they reference.
+#[allow(clippy::type_complexity)]
+fn decode_header(
+    header: &[u8],
+    check: IntegrityCheck,
+    stream: &mut Stream,
+) -> Result<(
+    Box<liblzma_sys::lzma_block>,
+    Box<[liblzma_sys::lzma_filter; FILTERS_LEN]>,
+)> {
+    let first = *header.first().ok_or(Error::BufError)?;
+    let header_size = BlockDecoder::header_size(first).ok_or(Error::DataError)?;
+    if header.len() < header_size {
+        return Err(Error::BufError);
+    }
+
+    // SAFETY: `lzma_filter` and `lzma_block` are plain C structs; all-zero initialization
+    // is valid and liblzma overwrites the fields it needs.
+    let mut filters: Box<[liblzma_sys::lzma_filter; FILTERS_LEN]> =
+        Box::new(unsafe { std::mem::zeroed() });
+    let mut block: Box<liblzma_sys::lzma_block> = Box::new(unsafe { std::mem::zeroed() });
+    block.version = 1;
+    block.header_size = u32::try_from(header_size).map_err(|_| Error::DataError)?;
+    block.check = check.into();
+    block.filters = filters.as_mut_ptr();
+
+    crate::ffi::lzma_block_header_decode(&mut block, stream, header)?;
+    Ok((block, filters))
+}
+
+/// Map `LZMA_VLI_UNKNOWN` to `None`.
+fn known_vli(value: u64) -> Option<u64> {
+    (value != LZMA_VLI_UNKNOWN).then_some(value)
+}
+
+impl Drop for BlockDecoder {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            stream.finish();
+        }
+    }
+}
+
+// SAFETY: Like the other stream wrappers, this type owns an independent `lzma_stream`; the
+// boxed block options and filter array are only referenced by that stream.
+unsafe impl Send for BlockDecoder {}
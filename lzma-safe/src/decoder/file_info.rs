@@ -213,7 +213,7 @@ impl Drop for FileInfoDecoder {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "encoder", lzma_5_4))]
 mod tests {
     use crate::{Action, Error, Stream};
 
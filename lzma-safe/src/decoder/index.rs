@@ -168,14 +168,16 @@ impl Drop for IndexDecoder {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "encoder"))]
 mod tests {
     use crate::{Action, Error, Stream};
 
     /// Extra output space for XZ stream header, index, and footer in test compression.
+    #[cfg(lzma_5_4)]
     const COMPRESS_OUTPUT_SLACK: usize = 2048;
 
     /// Helper function to compress the data to a XZ stream.
+    #[cfg(lzma_5_4)]
     fn compress_to_xz_stream(data: &[u8]) -> Vec<u8> {
         use crate::encoder::options::{Compression, IntegrityCheck};
 
@@ -194,6 +196,7 @@ mod tests {
     }
 
     /// Helper function to extract the index field from the compressed data.
+    #[cfg(lzma_5_4)]
     fn extract_index_field(data: &[u8]) -> Vec<u8> {
         let compressed = compress_to_xz_stream(data);
         let mut decoder = Stream::default()
@@ -230,6 +233,7 @@ mod tests {
     }
 
     /// Helper function to finish the index decoder by processing the index field.
+    #[cfg(lzma_5_4)]
     fn finish_index_decoder(index_field: &[u8]) -> super::IndexDecoder {
         let mut decoder = Stream::default().index_decoder(u64::MAX).unwrap();
         let mut pos = 0;
@@ -343,6 +347,7 @@ mod tests {
     }
 
     /// Test `IndexDecoder` preserves `total_in` after successful finish.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_decoder_preserves_total_in_after_finish() {
         let index_field = extract_index_field(b"metadata decoder index total_in");
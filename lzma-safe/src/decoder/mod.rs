@@ -2,13 +2,15 @@
 
 use crate::{Action, Result, Stream};
 
+mod block;
 mod file_info;
 mod index;
 pub mod options;
 mod raw;
-#[cfg(test)]
+#[cfg(all(test, feature = "encoder"))]
 mod tests;
 
+pub use block::{BlockDecoder, BlockHeader};
 pub use file_info::FileInfoDecoder;
 pub use index::IndexDecoder;
 pub use options::Options;
@@ -137,6 +139,41 @@ impl Decoder {
         })
     }
 
+    /// Creates a new `.lz` (lzip) decoder.
+    ///
+    /// # Parameters
+    ///
+    /// * `memlimit` - Maximum memory usage for decoding (in bytes).
+    /// * `flags` - Decoder behavior flags (see [`options::Flags`]).
+    /// * `stream` - An initialized [`Stream`] for LZMA operations.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::OptionsError`] if the flags are unsupported.
+    /// Returns [`crate::Error::MemError`] if memory allocation fails.
+    /// Returns [`crate::Error::ProgError`] if the decoder is misused.
+    ///
+    /// # Returns
+    ///
+    /// Returns the new decoder if successful.
+    pub fn new_lzip(memlimit: u64, flags: options::Flags, mut stream: Stream) -> Result<Self> {
+        let options = Options {
+            memlimit,
+            flags,
+            ..Default::default()
+        };
+
+        // Initialize the lzip decoder (.lz format versions 0 and 1).
+        crate::ffi::lzma_lzip_decoder(options.memlimit, options.flags, &mut stream)?;
+
+        Ok(Decoder {
+            options,
+            stream: Some(stream),
+            total_in: 0,
+            total_out: 0,
+        })
+    }
+
     /// Creates a new multi-threaded LZMA decoder with the specified options.
     ///
     /// # Parameters
@@ -146,7 +183,8 @@ impl Decoder {
     ///
     /// # Errors
     ///
-    /// Returns [`crate::Error::OptionsError`] if the decoder options are invalid.
+    /// Returns [`crate::Error::OptionsError`] if the decoder options are invalid, or if
+    /// liblzma was built without threading support (see [`crate::Version::has_threads`]).
     /// Returns [`crate::Error::MemError`] if memory allocation fails.
     /// Returns [`crate::Error::MemLimitError`] if the memory limit is exceeded.
     /// Returns [`crate::Error::FormatError`] if the input format is not recognized.
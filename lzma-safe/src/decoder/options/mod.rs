@@ -62,6 +62,7 @@ impl Options {
     }
 
     /// Convert to the raw `lzma_mt` structure expected by liblzma.
+    #[cfg(has_mt_decoder)]
     pub(crate) fn to_lzma_options(&self) -> liblzma_sys::lzma_mt {
         // SAFETY: lzma_mt is a POD struct; zeroed then filled with required fields.
         let mut options = unsafe { std::mem::zeroed::<liblzma_sys::lzma_mt>() };
@@ -77,7 +78,7 @@ impl Options {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, has_mt_decoder))]
 mod tests {
     use super::*;
 
@@ -1,4 +1,5 @@
 use crate::decoder::options::Flags;
+#[cfg(has_mt_decoder)]
 use crate::decoder::Options;
 use crate::encoder::options::{Compression, IntegrityCheck};
 use crate::{Action, Error, Stream};
@@ -122,6 +123,7 @@ fn decoder_alone_stream_support() {
 }
 
 /// Test decoder configuration and multithreading.
+#[cfg(has_mt_decoder)]
 #[test]
 fn decoder_configuration_accessors_and_mt() {
     let flags = Flags::NO_CHECK | Flags::IGNORE_CHECK;
@@ -416,3 +418,72 @@ fn decoder_partial_processing() {
     assert_eq!(output, TEST_DATA_PRIMARY);
     assert_eq!(total_written, TEST_DATA_PRIMARY.len());
 }
+
+/// Test decoding the first Block of a stream in isolation.
+#[test]
+fn block_decoder_decodes_first_block() {
+    let compressed = compress_xz(TEST_DATA_PRIMARY);
+    let block = &compressed[crate::stream::HEADER_SIZE..];
+    let header_size = BlockDecoder::header_size(block[0]).unwrap();
+
+    let mut decoder = BlockDecoder::new(
+        block,
+        IntegrityCheck::Crc32,
+        false,
+        u64::MAX,
+        Stream::default(),
+    )
+    .unwrap();
+    assert_eq!(decoder.block_header_size() as usize, header_size);
+
+    let mut output = vec![0u8; TEST_DATA_PRIMARY.len() * 2];
+    let (_, written) = decoder
+        .process(&block[header_size..], &mut output, Action::Run)
+        .unwrap();
+
+    assert!(decoder.is_finished());
+    assert_eq!(&output[..written], TEST_DATA_PRIMARY);
+    assert_eq!(
+        decoder.uncompressed_size(),
+        Some(TEST_DATA_PRIMARY.len() as u64)
+    );
+}
+
+/// Test that the Index Indicator and a tight memory limit are rejected.
+#[test]
+fn block_decoder_rejects_index_indicator_and_memlimit() {
+    assert_eq!(BlockDecoder::header_size(0), None);
+    let result = BlockDecoder::new(
+        &[0u8; 8],
+        IntegrityCheck::Crc32,
+        false,
+        u64::MAX,
+        Stream::default(),
+    );
+    assert!(matches!(result, Err(Error::DataError)));
+
+    let compressed = compress_xz(TEST_DATA_PRIMARY);
+    let block = &compressed[crate::stream::HEADER_SIZE..];
+    let result = BlockDecoder::new(block, IntegrityCheck::Crc32, false, 1024, Stream::default());
+    assert!(matches!(result, Err(Error::MemLimitError)));
+}
+
+/// Test inspecting a Block Header without decoding the Block.
+#[cfg(lzma_5_4)]
+#[test]
+fn block_header_decode_reports_filters_and_memusage() {
+    let compressed = compress_xz(TEST_DATA_PRIMARY);
+    let block = &compressed[crate::stream::HEADER_SIZE..];
+
+    let header = BlockHeader::decode(block, IntegrityCheck::Crc32).unwrap();
+    assert_eq!(
+        header.header_size as usize,
+        BlockDecoder::header_size(block[0]).unwrap()
+    );
+    assert_eq!(header.filter_ids, vec![0x21]);
+    assert!(header.filters.starts_with("--lzma2=dict="));
+    assert!(header.memusage > 0);
+
+    let result = BlockHeader::decode(&[0u8; 8], IntegrityCheck::Crc32);
+    assert!(matches!(result, Err(Error::DataError)));
+}
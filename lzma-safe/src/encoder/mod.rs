@@ -1,18 +1,24 @@
 //! High-level encoder built on top of `liblzma`.
 
+#[cfg(feature = "encoder")]
 use crate::{Action, Result, Stream};
 
+#[cfg(feature = "encoder")]
 mod alone;
 pub mod options;
+#[cfg(feature = "encoder")]
 mod raw;
-#[cfg(test)]
+#[cfg(all(test, feature = "encoder"))]
 mod tests;
 
+#[cfg(feature = "encoder")]
 pub use alone::AloneEncoder;
 pub use options::Options;
+#[cfg(feature = "encoder")]
 pub use raw::RawEncoder;
 
 /// Safe wrapper around an `lzma_stream` configured for compression.
+#[cfg(feature = "encoder")]
 pub struct Encoder {
     /// Encoder configuration options (compression level, check, threads, etc.).
     options: Options,
@@ -26,6 +32,7 @@ pub struct Encoder {
     _prepared_filters: Option<options::RawFilters>,
 }
 
+#[cfg(feature = "encoder")]
 impl Encoder {
     /// Creates a new single-threaded encoder with the given compression level and integrity check.
     ///
@@ -68,7 +75,10 @@ impl Encoder {
         })
     }
 
-    /// Creates a new multi-threaded encoder with the specified options.
+    /// Creates a new single-threaded encoder with the preset or filter chain of `options`.
+    ///
+    /// Unlike [`Encoder::new`], this accepts a custom filter chain. The thread count, block
+    /// size and timeout of `options` are ignored.
     ///
     /// # Parameters
     ///
@@ -79,6 +89,45 @@ impl Encoder {
     ///
     /// Returns [`crate::Error::OptionsError`] if the encoder options are invalid.
     /// Returns [`crate::Error::MemError`] if memory allocation fails.
+    /// Returns [`crate::Error::UnsupportedCheck`] if the integrity check type is not supported.
+    ///
+    /// # Returns
+    ///
+    /// Returns the new encoder if successful.
+    pub fn new_single_threaded(options: Options, mut stream: Stream) -> Result<Self> {
+        if options.filters.is_empty() {
+            return Self::new(options.level, options.check, stream);
+        }
+
+        let prepared = options.raw_filters();
+        crate::ffi::lzma_stream_encoder(&prepared, options.check, &mut stream)?;
+
+        Ok(Encoder {
+            options: Options {
+                threads: 1,
+                block_size: 0,
+                timeout: 0,
+                ..options
+            },
+            stream: Some(stream),
+            total_in: 0,
+            total_out: 0,
+            _prepared_filters: Some(prepared),
+        })
+    }
+
+    /// Creates a new multi-threaded encoder with the specified options.
+    ///
+    /// # Parameters
+    ///
+    /// * `options` - Encoder configuration (see [`Options`]).
+    /// * `stream` - An initialized [`Stream`] for LZMA operations.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::OptionsError`] if the encoder options are invalid, or if
+    /// liblzma was built without threading support (see [`crate::Version::has_threads`]).
+    /// Returns [`crate::Error::MemError`] if memory allocation fails.
     /// Returns [`crate::Error::MemLimitError`] if the memory limit is exceeded.
     /// Returns [`crate::Error::UnsupportedCheck`] if the integrity check type is not supported.
     /// Returns [`crate::Error::ProgError`] if the encoder is misused.
@@ -124,6 +173,39 @@ impl Encoder {
         output: &mut [u8],
         action: Action,
     ) -> Result<(usize, usize)> {
+        self.code(input, output, action)
+            .map(|(bytes_read, bytes_written, _)| (bytes_read, bytes_written))
+    }
+
+    /// Ends the current Block so that the data so far can be decoded independently.
+    ///
+    /// Call repeatedly with fresh output space until it reports completion. The multithreaded
+    /// encoder also waits for all queued input (`LZMA_FULL_BARRIER`); the single-threaded
+    /// encoder treats it as `LZMA_FULL_FLUSH`.
+    ///
+    /// # Parameters
+    ///
+    /// * `output` - Output buffer for compressed data.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Encoder::process`].
+    ///
+    /// # Returns
+    ///
+    /// Returns `(bytes_written, done)`, where `done` is `true` once the Block has been closed.
+    pub fn finish_block(&mut self, output: &mut [u8]) -> Result<(usize, bool)> {
+        self.code(&[], output, Action::FullBarrier)
+            .map(|(_, bytes_written, done)| (bytes_written, done))
+    }
+
+    /// Runs `lzma_code` and reports whether liblzma returned `LZMA_STREAM_END`.
+    fn code(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+        action: Action,
+    ) -> Result<(usize, usize, bool)> {
         // If the stream is already finished, prevent further processing.
         let Some(mut stream) = self.stream.take() else {
             if action == Action::Finish {
@@ -131,7 +213,7 @@ impl Encoder {
                 return Err(crate::Error::ProgError);
             }
             // For other actions, allow querying but no more data will be processed.
-            return Ok((0, 0));
+            return Ok((0, 0, false));
         };
 
         // Provide new input only when data is available; otherwise preserve any
@@ -158,12 +240,17 @@ impl Encoder {
             Ok(()) => {
                 // Encoding succeeded, keep the stream for further use.
                 self.stream = Some(stream);
-                Ok((bytes_read, bytes_written))
+                Ok((bytes_read, bytes_written, false))
+            }
+            Err(crate::Error::StreamEnd) if action != Action::Finish => {
+                // A flush has completed; the stream stays open for more input.
+                self.stream = Some(stream);
+                Ok((bytes_read, bytes_written, true))
             }
             Err(crate::Error::StreamEnd) => {
                 // The stream has ended; mark as finished and return the last processed bytes.
                 stream.finish();
-                Ok((bytes_read, bytes_written))
+                Ok((bytes_read, bytes_written, true))
             }
             Err(err) => {
                 // On error, retain the stream for possible recovery or inspection.
@@ -204,6 +291,7 @@ impl Encoder {
     }
 }
 
+#[cfg(feature = "encoder")]
 impl Drop for Encoder {
     /// Ensures the underlying stream is finalized and resources are released.
     fn drop(&mut self) {
@@ -215,4 +303,5 @@ impl Drop for Encoder {
 
 // SAFETY: Encoder is Send because the underlying lzma_stream is not shared across threads.
 // liblzma streams are not thread-safe for concurrent access, so Sync is not implemented.
+#[cfg(feature = "encoder")]
 unsafe impl Send for Encoder {}
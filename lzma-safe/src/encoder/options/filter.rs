@@ -420,6 +420,7 @@ fn create_lzma_filter(
 /// # Returns
 ///
 /// A tuple containing the `lzma_filter` entry and the owned options.
+#[cfg(feature = "encoder")]
 fn create_delta_filter(
     user_options: Option<&DeltaOptions>,
 ) -> (liblzma_sys::lzma_filter, OwnedFilterOptions) {
@@ -468,6 +469,7 @@ fn create_delta_filter(
 /// # Returns
 ///
 /// A tuple containing the `lzma_filter` entry and the owned options.
+#[cfg(feature = "encoder")]
 fn create_bcj_filter(
     filter_type: FilterType,
     user_options: Option<&BcjOptions>,
@@ -518,6 +520,7 @@ fn create_bcj_filter(
 /// # Safety
 ///
 /// The returned pointers are valid as long as the returned `RawFilters` is alive.
+#[cfg(feature = "encoder")]
 pub(crate) fn prepare_filters(configs: &[FilterConfig]) -> RawFilters {
     // Preallocate space for the filter chain and owned option buffers.
     let mut filters = Vec::with_capacity(configs.len() + 1);
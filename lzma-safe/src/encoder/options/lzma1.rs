@@ -147,12 +147,34 @@ impl Lzma1Options {
         self
     }
 
+    /// Dictionary size in bytes.
+    #[must_use]
+    pub fn dict_size(&self) -> u32 {
+        self.raw.dict_size
+    }
+
+    /// Memory needed by the LZMA1 encoder with these options, in bytes.
+    ///
+    /// Returns `None` if liblzma rejects the options.
+    #[must_use]
+    #[cfg(feature = "encoder")]
+    pub fn encoder_memusage(&self) -> Option<u64> {
+        let filters = super::prepare_lzma1_filters(self, super::FilterType::Lzma1);
+        crate::ffi::lzma_raw_encoder_memusage(&filters)
+    }
+
     /// Borrow the raw liblzma options.
     pub(crate) fn as_raw(&self) -> &liblzma_sys::lzma_options_lzma {
         &self.raw
     }
 }
 
+// SAFETY: The pointers in the raw options (the preset dictionary and the reserved fields)
+// are always null: they start zeroed, `lzma_lzma_preset` leaves them so, and no method sets
+// them. The rest is plain data, so the options can be moved and shared across threads.
+unsafe impl Send for Lzma1Options {}
+unsafe impl Sync for Lzma1Options {}
+
 impl Default for Lzma1Options {
     fn default() -> Self {
         // Prefer a deterministic default and mirror the default preset used elsewhere.
@@ -210,5 +232,15 @@ mod tests {
         assert_eq!(raw.nice_len, 32);
         assert_eq!(raw.mf, liblzma_sys::lzma_match_finder_LZMA_MF_HC4);
         assert_eq!(raw.depth, 64);
+        assert_eq!(opts.dict_size(), 1 << 20);
+    }
+
+    /// Test that a smaller dictionary needs less encoder memory.
+    #[test]
+    #[cfg(feature = "encoder")]
+    fn encoder_memusage_follows_dict_size() {
+        let large = Lzma1Options::from_preset(Compression::Level9).unwrap();
+        let small = large.clone().with_dict_size(1 << 20);
+        assert!(small.encoder_memusage().unwrap() < large.encoder_memusage().unwrap());
     }
 }
@@ -13,7 +13,8 @@ pub use filter::{
 pub use lzma1::{Lzma1Options, MatchFinder, Mode};
 pub use present::Compression;
 
-/// Options forwarded to `lzma_stream_encoder_mt`.
+/// Options forwarded to `lzma_stream_encoder_mt`, or to `lzma_stream_encoder` for a single
+/// thread.
 pub struct Options {
     /// Compression preset; ignored when `filters` is non-empty.
     pub level: Compression,
@@ -110,7 +111,37 @@ impl Options {
         self
     }
 
+    /// Memory needed by the multithreaded encoder with these options, in bytes.
+    ///
+    /// Returns `None` if liblzma rejects the options.
+    #[must_use]
+    #[cfg(feature = "encoder")]
+    pub fn memusage(&self) -> Option<u64> {
+        crate::ffi::lzma_stream_encoder_mt_memusage(self)
+    }
+
+    /// Memory needed by the single-threaded encoder with these options, in bytes.
+    ///
+    /// Only the preset or the filter chain matter; see [`crate::Encoder::new_single_threaded`].
+    /// Returns `None` if liblzma rejects the options.
+    #[must_use]
+    #[cfg(feature = "encoder")]
+    pub fn single_threaded_memusage(&self) -> Option<u64> {
+        if self.filters.is_empty() {
+            self.level.encoder_memusage()
+        } else {
+            crate::ffi::lzma_raw_encoder_memusage(&self.raw_filters())
+        }
+    }
+
+    /// Convert the filter chain to the form liblzma expects.
+    #[cfg(feature = "encoder")]
+    pub(crate) fn raw_filters(&self) -> RawFilters {
+        filter::prepare_filters(&self.filters)
+    }
+
     /// Convert to the raw `lzma_mt` structure and keep filter buffers alive if needed.
+    #[cfg(all(feature = "encoder", liblzma_threads))]
     pub(crate) fn to_lzma_options(&self) -> (liblzma_sys::lzma_mt, Option<filter::RawFilters>) {
         // SAFETY: lzma_mt is a POD struct; zeroed then filled with required fields.
         let mut options = unsafe { std::mem::zeroed::<liblzma_sys::lzma_mt>() };
@@ -133,7 +164,7 @@ impl Options {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "encoder", liblzma_threads))]
 mod tests {
     use super::*;
 
@@ -183,4 +214,18 @@ mod tests {
         assert!(!raw.filters[0].options.is_null());
         assert!(!raw.filters[1].options.is_null());
     }
+
+    /// Every extra worker thread needs its own encoder and buffers.
+    #[test]
+    fn memusage_grows_with_threads() {
+        let options = Options::default()
+            .with_level(Compression::Level1)
+            .with_threads(1);
+        let one = options.memusage().unwrap();
+        let options = Options::default()
+            .with_level(Compression::Level1)
+            .with_threads(4);
+        let four = options.memusage().unwrap();
+        assert!(four > one);
+    }
 }
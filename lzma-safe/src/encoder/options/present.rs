@@ -88,6 +88,15 @@ impl Compression {
             }
         }
     }
+
+    /// Memory needed by the single-threaded encoder for this preset, in bytes.
+    ///
+    /// Returns `None` if liblzma doesn't support the preset.
+    #[must_use]
+    #[cfg(feature = "encoder")]
+    pub fn encoder_memusage(self) -> Option<u64> {
+        crate::ffi::lzma_easy_encoder_memusage(self)
+    }
 }
 
 #[cfg(test)]
@@ -112,4 +121,14 @@ mod tests {
         // Values above 9 should be clamped to 9
         assert_eq!(Compression::Extreme(15).to_preset(), 9 | extreme_flag);
     }
+
+    /// Higher presets use bigger dictionaries and therefore more memory.
+    #[test]
+    #[cfg(feature = "encoder")]
+    fn encoder_memusage_grows_with_preset() {
+        let low = Compression::Level0.encoder_memusage().unwrap();
+        let high = Compression::Level9.encoder_memusage().unwrap();
+        assert!(low > 0);
+        assert!(high > low);
+    }
 }
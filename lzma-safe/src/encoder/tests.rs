@@ -1,5 +1,8 @@
 use crate::decoder::options::Flags;
-use crate::encoder::options::{Compression, IntegrityCheck, Lzma1Options, Options};
+use crate::encoder::options::{
+    Compression, FilterConfig, FilterOptions, FilterType, IntegrityCheck, Lzma1Options,
+    LzmaOptions, Options,
+};
 use crate::{Action, Error, Stream};
 
 use super::*;
@@ -160,7 +163,40 @@ fn encoder_tracks_totals_and_finish_behavior() {
     assert_eq!(post_written, 0);
 }
 
+/// Test the single-threaded encoder takes a filter chain and reports its memory usage.
+#[test]
+fn single_threaded_encoder_uses_filter_chain() {
+    let lzma2 = LzmaOptions::from(&Lzma1Options::from_preset(Compression::Level1).unwrap());
+    let options = Options::default()
+        .with_check(IntegrityCheck::Crc64)
+        .with_filters(vec![
+            FilterConfig {
+                filter_type: FilterType::Delta,
+                options: None,
+            },
+            FilterConfig {
+                filter_type: FilterType::Lzma2,
+                options: Some(FilterOptions::Lzma(lzma2)),
+            },
+        ]);
+    let usage = options.single_threaded_memusage().unwrap();
+    assert!(usage < Options::default().single_threaded_memusage().unwrap());
+
+    let mut encoder = Encoder::new_single_threaded(options, Stream::default()).unwrap();
+    let compressed = encode_all(&mut encoder, TEST_DATA);
+    assert_eq!(encoder.threads(), 1);
+    assert_eq!(encoder.check(), IntegrityCheck::Crc64);
+
+    let mut decoder = Stream::default().decoder(u64::MAX, Flags::empty()).unwrap();
+    let mut output = vec![0u8; TEST_DATA.len() * 2];
+    let (_, written) = decoder
+        .process(&compressed, &mut output, Action::Finish)
+        .unwrap();
+    assert_eq!(&output[..written], TEST_DATA);
+}
+
 /// Test multithreaded encoder produces valid compressed stream.
+#[cfg(liblzma_threads)]
 #[test]
 fn multithreaded_encoder_produces_valid_stream() {
     let options = Options {
@@ -189,7 +225,16 @@ fn multithreaded_encoder_produces_valid_stream() {
     assert_eq!(&output[..written], TEST_DATA);
 }
 
+/// Test the multithreaded encoder is reported as unsupported without threads.
+#[cfg(not(liblzma_threads))]
+#[test]
+fn multithreaded_encoder_requires_threads() {
+    let result = Encoder::new_mt(Options::default(), Stream::default());
+    assert!(matches!(result, Err(Error::OptionsError)));
+}
+
 /// Test zero threads are promoted to one for multithreaded encoder.
+#[cfg(liblzma_threads)]
 #[test]
 fn multithreaded_encoder_from_stream_promotes_zero_threads() {
     let mut encoder = Stream::default()
@@ -403,6 +448,7 @@ fn encoder_handles_large_data() {
 }
 
 /// Test encoder options builder methods.
+#[cfg(liblzma_threads)]
 #[test]
 fn encoder_options_builder_methods() {
     let options = Options::default()
@@ -473,3 +519,37 @@ fn encoder_state_consistency() {
     assert_eq!(encoder.total_out(), (written1 + written2 + written3) as u64);
     assert!(encoder.is_finished());
 }
+
+/// Test that ending a Block keeps the stream open for more input.
+#[test]
+fn encoder_finish_block_keeps_stream_open() {
+    let mut encoder = Stream::default()
+        .easy_encoder(Compression::Level1, IntegrityCheck::Crc32)
+        .unwrap();
+    let mut output = vec![0u8; 4096];
+    let mut compressed = Vec::new();
+
+    let (half, _) = TEST_DATA.split_at(TEST_DATA.len() / 2);
+    let (read, written) = encoder.process(half, &mut output, Action::Run).unwrap();
+    assert_eq!(read, half.len());
+    compressed.extend_from_slice(&output[..written]);
+
+    loop {
+        let (written, done) = encoder.finish_block(&mut output).unwrap();
+        compressed.extend_from_slice(&output[..written]);
+        if done {
+            break;
+        }
+    }
+    assert!(!encoder.is_finished());
+
+    compressed.extend(encode_all(&mut encoder, &TEST_DATA[half.len()..]));
+    assert!(encoder.is_finished());
+
+    let mut decoder = Stream::default().decoder(u64::MAX, Flags::empty()).unwrap();
+    let mut decompressed = vec![0u8; TEST_DATA.len() * 2];
+    let (_, decompressed_len) = decoder
+        .process(&compressed, &mut decompressed, Action::Finish)
+        .unwrap();
+    assert_eq!(&decompressed[..decompressed_len], TEST_DATA);
+}
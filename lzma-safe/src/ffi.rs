@@ -21,6 +21,7 @@ pub(crate) fn lzma_end(mut stream: Stream) {
 }
 
 /// Initialise a single-threaded encoder via `lzma_easy_encoder`.
+#[cfg(feature = "encoder")]
 pub(crate) fn lzma_easy_encoder(
     level: encoder::options::Compression,
     check: encoder::options::IntegrityCheck,
@@ -34,7 +35,23 @@ pub(crate) fn lzma_easy_encoder(
     result_from_lzma_ret(ret, ())
 }
 
+/// Initialise a single-threaded `.xz` encoder with a filter chain via `lzma_stream_encoder`.
+#[cfg(feature = "encoder")]
+pub(crate) fn lzma_stream_encoder(
+    filters: &encoder::options::RawFilters,
+    check: encoder::options::IntegrityCheck,
+    stream: &mut Stream,
+) -> Result<()> {
+    // SAFETY: The stream is valid and not already initialized, and the filter chain is
+    // terminated and kept alive by `filters` for the duration of this call.
+    let ret = unsafe {
+        liblzma_sys::lzma_stream_encoder(stream.lzma_stream(), filters.as_ptr(), check.into())
+    };
+    result_from_lzma_ret(ret, ())
+}
+
 /// Initialise a multithreaded encoder via `lzma_stream_encoder_mt`.
+#[cfg(all(feature = "encoder", liblzma_threads))]
 pub(crate) fn lzma_stream_encoder_mt(
     config: &encoder::Options,
     stream: &mut Stream,
@@ -47,6 +64,15 @@ pub(crate) fn lzma_stream_encoder_mt(
     result_from_lzma_ret(ret, raw_filters)
 }
 
+/// A liblzma built without threads has no multithreaded encoder.
+#[cfg(all(feature = "encoder", not(liblzma_threads)))]
+pub(crate) fn lzma_stream_encoder_mt(
+    _config: &encoder::Options,
+    _stream: &mut Stream,
+) -> Result<Option<encoder::options::RawFilters>> {
+    Err(Error::OptionsError)
+}
+
 /// Initialise an XZ decoder with `lzma_stream_decoder`.
 pub(crate) fn lzma_stream_decoder(
     memlimit: u64,
@@ -62,6 +88,7 @@ pub(crate) fn lzma_stream_decoder(
 }
 
 /// Initialise a multithreaded decoder with `lzma_stream_decoder_mt`.
+#[cfg(has_mt_decoder)]
 pub(crate) fn lzma_stream_decoder_mt(
     options: &decoder::Options,
     stream: &mut Stream,
@@ -74,6 +101,15 @@ pub(crate) fn lzma_stream_decoder_mt(
     result_from_lzma_ret(ret, ())
 }
 
+/// liblzma has no multithreaded decoder without threads or before 5.4.
+#[cfg(not(has_mt_decoder))]
+pub(crate) fn lzma_stream_decoder_mt(
+    _options: &decoder::Options,
+    _stream: &mut Stream,
+) -> Result<()> {
+    Err(Error::OptionsError)
+}
+
 /// Initialise an auto-detecting decoder via `lzma_auto_decoder`.
 pub(crate) fn lzma_auto_decoder(
     memlimit: u64,
@@ -95,6 +131,31 @@ pub(crate) fn lzma_alone_decoder(memlimit: u64, stream: &mut Stream) -> Result<(
     result_from_lzma_ret(ret, ())
 }
 
+/// Initialise a `.lz` (lzip) decoder via `lzma_lzip_decoder`.
+#[cfg(has_lzip_decoder)]
+pub(crate) fn lzma_lzip_decoder(
+    memlimit: u64,
+    flags: decoder::options::Flags,
+    stream: &mut Stream,
+) -> Result<()> {
+    // SAFETY: The stream is valid and not already initialized.
+    // The flags are validated by the type system.
+    let ret = unsafe {
+        liblzma_sys::lzma_lzip_decoder(stream.lzma_stream(), memlimit, flags.to_liblzma_flags())
+    };
+    result_from_lzma_ret(ret, ())
+}
+
+/// liblzma can't decode `.lz` files before 5.4.
+#[cfg(not(has_lzip_decoder))]
+pub(crate) fn lzma_lzip_decoder(
+    _memlimit: u64,
+    _flags: decoder::options::Flags,
+    _stream: &mut Stream,
+) -> Result<()> {
+    Err(Error::OptionsError)
+}
+
 /// Populate `lzma_options_lzma` from an `xz(1)`-compatible preset via `lzma_lzma_preset`.
 pub(crate) fn lzma_lzma_preset(
     options: &mut liblzma_sys::lzma_options_lzma,
@@ -109,6 +170,7 @@ pub(crate) fn lzma_lzma_preset(
 }
 
 /// Initialise a legacy `.lzma` encoder via `lzma_alone_encoder`.
+#[cfg(feature = "encoder")]
 pub(crate) fn lzma_alone_encoder(
     options: &liblzma_sys::lzma_options_lzma,
     stream: &mut Stream,
@@ -121,6 +183,7 @@ pub(crate) fn lzma_alone_encoder(
 }
 
 /// Initialise a raw encoder via `lzma_raw_encoder`.
+#[cfg(feature = "encoder")]
 pub(crate) fn lzma_raw_encoder(
     filters: &encoder::options::RawFilters,
     stream: &mut Stream,
@@ -155,6 +218,7 @@ pub(crate) fn lzma_index_decoder(
 /// Initialise a file info decoder with `lzma_file_info_decoder`.
 ///
 /// The combined index will be made available through the `index_ptr` after decoding completes.
+#[cfg(lzma_5_4)]
 pub(crate) fn lzma_file_info_decoder(
     stream: &mut Stream,
     index_ptr: *mut *mut liblzma_sys::lzma_index,
@@ -169,6 +233,17 @@ pub(crate) fn lzma_file_info_decoder(
     result_from_lzma_ret(ret, ())
 }
 
+/// liblzma has no file info decoder before 5.4.
+#[cfg(not(lzma_5_4))]
+pub(crate) fn lzma_file_info_decoder(
+    _stream: &mut Stream,
+    _index_ptr: *mut *mut liblzma_sys::lzma_index,
+    _memlimit: u64,
+    _file_size: u64,
+) -> Result<()> {
+    Err(Error::OptionsError)
+}
+
 /// Initializes an `lzma_index_iter` for traversing an index.
 pub(crate) fn lzma_index_iter_init(iter: &mut liblzma_sys::lzma_index_iter, index: &Index) {
     // SAFETY: Both `iter` and `index` are valid and properly initialized.
@@ -330,6 +405,7 @@ pub(crate) fn decode_xz_index_field(
 }
 
 /// Encode an [`Index`] into the raw XZ Index field bytes stored in a Stream.
+#[cfg(feature = "encoder")]
 pub(crate) fn encode_xz_index_field(index: &Index) -> Result<Vec<u8>> {
     // SAFETY: `index.as_ptr()` is a valid liblzma index pointer for shared access.
     let encoded_size =
@@ -397,6 +473,44 @@ pub(crate) fn lzma_index_cat(
     result_from_lzma_ret(ret, ())
 }
 
+/// Estimate encoder memory usage for a given compression preset.
+///
+/// Returns `None` if liblzma doesn't support the preset.
+#[cfg(feature = "encoder")]
+pub(crate) fn lzma_easy_encoder_memusage(level: encoder::options::Compression) -> Option<u64> {
+    // SAFETY: The compression level is validated by the type system and converted safely.
+    let usage = unsafe { liblzma_sys::lzma_easy_encoder_memusage(level.to_preset()) };
+    (usage != u64::MAX).then_some(usage)
+}
+
+/// Estimate encoder memory usage for a filter chain.
+///
+/// Returns `None` if liblzma rejects the filter chain.
+#[cfg(feature = "encoder")]
+pub(crate) fn lzma_raw_encoder_memusage(filters: &encoder::options::RawFilters) -> Option<u64> {
+    // SAFETY: The filter chain is terminated and kept alive by `filters`.
+    let usage = unsafe { liblzma_sys::lzma_raw_encoder_memusage(filters.as_ptr()) };
+    (usage != u64::MAX).then_some(usage)
+}
+
+/// Estimate memory usage of the multithreaded encoder for the given options.
+///
+/// Returns `None` if liblzma rejects the options.
+#[cfg(all(feature = "encoder", liblzma_threads))]
+pub(crate) fn lzma_stream_encoder_mt_memusage(config: &encoder::Options) -> Option<u64> {
+    let (mt, _raw_filters) = config.to_lzma_options();
+
+    // SAFETY: `mt` and its filter chain memory are valid for the duration of this call.
+    let usage = unsafe { liblzma_sys::lzma_stream_encoder_mt_memusage(&raw const mt) };
+    (usage != u64::MAX).then_some(usage)
+}
+
+/// A liblzma built without threads can't estimate the multithreaded encoder.
+#[cfg(all(feature = "encoder", not(liblzma_threads)))]
+pub(crate) fn lzma_stream_encoder_mt_memusage(_config: &encoder::Options) -> Option<u64> {
+    None
+}
+
 /// Estimate decoder memory usage for a given compression preset.
 #[allow(dead_code)]
 pub(crate) fn lzma_easy_decoder_memusage(level: encoder::options::Compression) -> u64 {
@@ -418,3 +532,116 @@ pub(crate) fn lzma_check_is_supported(check_id: u32) -> bool {
     // pointer; it only inspects the passed check ID.
     unsafe { liblzma_sys::lzma_check_is_supported(check_id) != 0 }
 }
+
+/// Decode an XZ Block Header into `block` with `lzma_block_header_decode`.
+///
+/// `block.header_size`, `block.check` and `block.filters` must be set by the caller, with
+/// `filters` pointing to an array of at least `LZMA_FILTERS_MAX + 1` elements.
+pub(crate) fn lzma_block_header_decode(
+    block: &mut liblzma_sys::lzma_block,
+    stream: &mut Stream,
+    header: &[u8],
+) -> Result<()> {
+    debug_assert!(header.len() >= block.header_size as usize);
+    // SAFETY: `block` is a valid, caller-initialised `lzma_block` whose `filters` pointer
+    // refers to an array large enough for any filter chain, and `header` holds at least
+    // `block.header_size` bytes.
+    let ret = unsafe {
+        liblzma_sys::lzma_block_header_decode(
+            ptr::from_mut(block),
+            stream.lzma_stream().allocator,
+            header.as_ptr(),
+        )
+    };
+    result_from_lzma_ret(ret, ())
+}
+
+/// Initialise a single Block decoder with `lzma_block_decoder`.
+///
+/// liblzma keeps a pointer to `block` until decoding finishes, so the caller must keep it
+/// alive (and pinned in memory) for the lifetime of the stream.
+pub(crate) fn lzma_block_decoder(
+    block: &mut liblzma_sys::lzma_block,
+    stream: &mut Stream,
+) -> Result<()> {
+    // SAFETY: The stream is valid and not already initialized; `block` was filled in by
+    // `lzma_block_header_decode`.
+    let ret =
+        unsafe { liblzma_sys::lzma_block_decoder(stream.lzma_stream(), ptr::from_mut(block)) };
+    result_from_lzma_ret(ret, ())
+}
+
+/// Estimate decoder memory usage for a decoded filter chain.
+pub(crate) fn lzma_raw_decoder_memusage(filters: &[liblzma_sys::lzma_filter]) -> u64 {
+    // SAFETY: `filters` is terminated with `LZMA_VLI_UNKNOWN` by `lzma_block_header_decode`.
+    unsafe { liblzma_sys::lzma_raw_decoder_memusage(filters.as_ptr()) }
+}
+
+/// Free filter-specific options allocated by `lzma_block_header_decode`.
+#[cfg(lzma_5_4)]
+pub(crate) fn lzma_filters_free(filters: &mut [liblzma_sys::lzma_filter], stream: &mut Stream) {
+    // SAFETY: `filters` is a terminated filter array whose options were allocated with the
+    // stream's allocator; liblzma resets every freed entry to `LZMA_VLI_UNKNOWN`.
+    unsafe { liblzma_sys::lzma_filters_free(filters.as_mut_ptr(), stream.lzma_stream().allocator) };
+}
+
+/// Free filter-specific options the way `lzma_filters_free` does, which liblzma only
+/// provides since 5.4.
+#[cfg(not(lzma_5_4))]
+pub(crate) fn lzma_filters_free(filters: &mut [liblzma_sys::lzma_filter], stream: &mut Stream) {
+    let allocator = stream.lzma_stream().allocator;
+    for filter in filters.iter_mut() {
+        if filter.id == u64::MAX {
+            break;
+        }
+        // SAFETY: The options were allocated by liblzma with this allocator, or with
+        // `malloc()` when it is NULL, and are not referenced after being freed.
+        unsafe {
+            match allocator.as_ref().and_then(|allocator| allocator.free) {
+                Some(free) => free((*allocator).opaque, filter.options),
+                None => libc::free(filter.options),
+            }
+        }
+        filter.id = u64::MAX;
+        filter.options = ptr::null_mut();
+    }
+}
+
+/// `LZMA_STR_DECODER`: include the options needed by the decoder.
+#[cfg(lzma_5_4)]
+const LZMA_STR_DECODER: u32 = 0x20;
+/// `LZMA_STR_GETOPT_LONG`: use xz's `--filter=options` command line syntax.
+#[cfg(lzma_5_4)]
+const LZMA_STR_GETOPT_LONG: u32 = 0x40;
+
+/// Describe a decoded filter chain in xz command line syntax with `lzma_str_from_filters`.
+#[cfg(lzma_5_4)]
+pub(crate) fn lzma_str_from_filters(filters: &[liblzma_sys::lzma_filter]) -> Result<String> {
+    let mut raw: *mut std::os::raw::c_char = ptr::null_mut();
+    // SAFETY: `filters` is terminated with `LZMA_VLI_UNKNOWN`; a NULL allocator makes liblzma
+    // allocate the string with `malloc()`.
+    let ret = unsafe {
+        liblzma_sys::lzma_str_from_filters(
+            &mut raw,
+            filters.as_ptr(),
+            LZMA_STR_DECODER | LZMA_STR_GETOPT_LONG,
+            ptr::null(),
+        )
+    };
+    result_from_lzma_ret(ret, ())?;
+
+    // SAFETY: On success `raw` points to a NUL-terminated string allocated with `malloc()`,
+    // which is copied before being released with `free()`.
+    let described = unsafe {
+        let described = std::ffi::CStr::from_ptr(raw).to_string_lossy().into_owned();
+        libc::free(raw.cast());
+        described
+    };
+    Ok(described)
+}
+
+/// liblzma can't describe filter chains before 5.4.
+#[cfg(not(lzma_5_4))]
+pub(crate) fn lzma_str_from_filters(_filters: &[liblzma_sys::lzma_filter]) -> Result<String> {
+    Err(Error::OptionsError)
+}
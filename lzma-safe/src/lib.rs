@@ -40,7 +40,8 @@ pub mod stream;
 mod error;
 mod ffi;
 
-pub use decoder::{Decoder, FileInfoDecoder, IndexDecoder, RawDecoder};
+pub use decoder::{BlockDecoder, BlockHeader, Decoder, FileInfoDecoder, IndexDecoder, RawDecoder};
+#[cfg(feature = "encoder")]
 pub use encoder::{AloneEncoder, Encoder, RawEncoder};
 pub use error::{Error, Result};
 pub use stream::{BlockInfo, Index, IndexEntry, IndexIterMode, IndexIterator, Stream, StreamInfo};
@@ -65,6 +66,51 @@ impl Version {
         // SAFETY: liblzma_version_number() is always safe to call and returns a valid u32.
         unsafe { liblzma_sys::lzma_version_number() }
     }
+
+    /// Returns `true` if liblzma was built from the bundled sources, `false` if the
+    /// system library was linked.
+    pub fn is_vendored() -> bool {
+        liblzma_sys::LIBLZMA_VENDORED
+    }
+
+    /// Returns `true` if the linked liblzma supports multi-threaded coding.
+    pub fn has_threads() -> bool {
+        liblzma_sys::LIBLZMA_THREADS
+    }
+
+    /// Returns the sandbox mechanism the tools may use (`"landlock"`, `"pledge"` or
+    /// `"capsicum"`), or `None` if the target has none or it was disabled at build time.
+    pub fn sandbox() -> Option<&'static str> {
+        liblzma_sys::LIBLZMA_SANDBOX
+    }
+
+    /// Returns `true` if the linked liblzma has the multi-threaded decoder (5.4 and later,
+    /// built with threads).
+    pub fn has_mt_decoder() -> bool {
+        cfg!(has_mt_decoder)
+    }
+
+    /// Returns `true` if the linked liblzma can decode `.lz` files (5.4 and later).
+    pub fn has_lzip_decoder() -> bool {
+        cfg!(has_lzip_decoder)
+    }
+}
+
+/// Hardware information detected by liblzma.
+pub struct Hardware;
+
+impl Hardware {
+    /// Returns the total amount of physical memory (RAM) in bytes, or `0` if unknown.
+    pub fn physical_memory() -> u64 {
+        // SAFETY: lzma_physmem() takes no arguments and is always safe to call.
+        unsafe { liblzma_sys::lzma_physmem() }
+    }
+
+    /// Returns the number of processor threads, or `0` if unknown.
+    pub fn cpu_threads() -> u32 {
+        // SAFETY: lzma_cputhreads() takes no arguments and is always safe to call.
+        unsafe { liblzma_sys::lzma_cputhreads() }
+    }
 }
 
 /// High-level equivalent of `lzma_action` used by [`Encoder`] and [`Decoder`].
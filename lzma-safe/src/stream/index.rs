@@ -98,6 +98,7 @@ impl Index {
     /// # Errors
     ///
     /// Returns an error if liblzma fails to encode the current index.
+    #[cfg(feature = "encoder")]
     pub fn encode_xz_index_field(&self) -> Result<Vec<u8>> {
         ffi::encode_xz_index_field(self)
     }
@@ -532,13 +533,16 @@ pub struct BlockInfo {
     pub unpadded_size: u64,
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "encoder"))]
 mod tests {
     use std::sync::Arc;
 
+    #[cfg(lzma_5_4)]
     use crate::encoder::options::{Compression, IntegrityCheck};
     use crate::stream::Allocator;
-    use crate::{Action, Error, Stream};
+    use crate::Error;
+    #[cfg(lzma_5_4)]
+    use crate::{Action, Stream};
 
     use super::*;
 
@@ -564,6 +568,7 @@ mod tests {
     }
 
     /// Helper function to create a `FileInfoDecoder` from already compressed `.xz` data.
+    #[cfg(lzma_5_4)]
     fn create_test_decoder_from_compressed(compressed: &[u8]) -> Option<crate::FileInfoDecoder> {
         // Create a decoder to extract the index and file info.
         let mut decoder = Stream::default()
@@ -584,10 +589,7 @@ mod tests {
                 }
                 Err(crate::Error::SeekNeeded) => {
                     // Handle random-access request by updating position.
-                    #[allow(clippy::cast_possible_truncation)]
-                    {
-                        pos = decoder.seek_pos() as usize;
-                    }
+                    pos = usize::try_from(decoder.seek_pos()).ok()?;
                     pending_action = Action::Run; // Always resume with Run after seek.
                 }
                 Err(crate::Error::StreamEnd) => break,
@@ -605,6 +607,7 @@ mod tests {
     }
 
     /// Helper function to create a standalone `.xz` stream from input bytes.
+    #[cfg(lzma_5_4)]
     fn compress_to_xz_stream(data: &[u8]) -> Option<Vec<u8>> {
         // Compress input data.
         let mut encoder = Stream::default()
@@ -637,6 +640,7 @@ mod tests {
     ///
     /// Returns `Some(FileInfoDecoder)` with a finished and valid decoder if successful,
     /// or `None` on error.
+    #[cfg(lzma_5_4)]
     fn create_test_decoder(data: &[u8]) -> Option<crate::FileInfoDecoder> {
         let compressed = compress_to_xz_stream(data)?;
         create_test_decoder_from_compressed(&compressed)
@@ -661,6 +665,7 @@ mod tests {
     }
 
     /// Test basic Index creation and accessors.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_basic_accessors() {
         let test_data = b"Lazzy dog jumps over the lazy fox".repeat(100);
@@ -679,6 +684,7 @@ mod tests {
     }
 
     /// Test `Index::checks()` returns non-zero value.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_checks_non_zero() {
         let test_data = b"Lazzy dog jumps over the lazy fox";
@@ -690,6 +696,7 @@ mod tests {
     }
 
     /// Test `Index` can round-trip through raw XZ Index field bytes.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_xz_index_field_roundtrip() {
         let test_data = b"Lazzy dog jumps over the lazy fox".repeat(100);
@@ -713,6 +720,7 @@ mod tests {
     }
 
     /// Test `IndexIterator` with Stream mode using Iterator trait.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_iterator_streams_trait() {
         let test_data = b"Lazzy dog jumps over the lazy fox".repeat(50);
@@ -734,6 +742,7 @@ mod tests {
     }
 
     /// Test `IndexIterator` with Block mode using Iterator trait.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_iterator_blocks_trait() {
         let test_data = b"Lazzy dog jumps over the lazy fox".repeat(50);
@@ -755,6 +764,7 @@ mod tests {
     }
 
     /// Test `IndexIterator` with `NonEmptyBlock` mode using Iterator trait.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_iterator_non_empty_blocks_trait() {
         let test_data = b"Lazzy dog jumps over the lazy fox".repeat(50);
@@ -774,6 +784,7 @@ mod tests {
     }
 
     /// Test `IndexIterator` with Any mode using Iterator trait.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_iterator_any_trait() {
         let test_data = b"Lazzy dog jumps over the lazy fox".repeat(50);
@@ -785,6 +796,7 @@ mod tests {
     }
 
     /// Test `IndexIterator` with Any mode on concatenated streams.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_iterator_any_trait_with_concatenated_streams() {
         let first = compress_to_xz_stream(&b"first stream payload".repeat(32)).unwrap();
@@ -808,6 +820,7 @@ mod tests {
     }
 
     /// Test that iterator returns None when no more entries (Iterator trait).
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_iterator_returns_none_at_end() {
         let test_data = b"Lazzy dog jumps over the lazy fox";
@@ -824,6 +837,7 @@ mod tests {
     }
 
     /// Test `StreamInfo` fields are populated correctly using Iterator trait.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_iterator_stream_info_fields() {
         let test_data = b"Lazzy dog jumps over the lazy fox".repeat(100);
@@ -847,6 +861,7 @@ mod tests {
     }
 
     /// Test `BlockInfo` fields are populated correctly using Iterator trait.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_iterator_block_info_fields() {
         let test_data = b"Lazzy dog jumps over the lazy fox".repeat(100);
@@ -874,6 +889,7 @@ mod tests {
     }
 
     /// Test creating multiple iterators using Iterator trait.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_multiple_iterators_trait() {
         let test_data = b"Lazzy dog jumps over the lazy fox";
@@ -891,6 +907,7 @@ mod tests {
     }
 
     /// Test that `StreamFlags` are properly extracted and parsed.
+    #[cfg(lzma_5_4)]
     #[test]
     fn index_stream_flags() {
         let test_data = b"Lazzy dog jumps over the lazy fox".repeat(10);
@@ -5,7 +5,7 @@ use std::sync::Arc;
 
 mod allocator;
 mod index;
-#[cfg(test)]
+#[cfg(all(test, feature = "encoder"))]
 mod tests;
 
 pub use allocator::{Allocator, LzmaAllocator, StdAllocator};
@@ -14,8 +14,9 @@ pub use index::{
 };
 
 use crate::decoder;
-use crate::encoder;
-use crate::{Decoder, Encoder, FileInfoDecoder, IndexDecoder, Result};
+#[cfg(feature = "encoder")]
+use crate::{encoder, Encoder};
+use crate::{Decoder, FileInfoDecoder, IndexDecoder, Result};
 
 /// Size of the XZ stream header in bytes (12 bytes).
 pub const HEADER_SIZE: usize = liblzma_sys::LZMA_STREAM_HEADER_SIZE as usize;
@@ -101,6 +102,7 @@ impl Stream {
     /// # Returns
     ///
     /// Returns an [`Encoder`] on success.
+    #[cfg(feature = "encoder")]
     pub fn easy_encoder(
         self,
         level: encoder::options::Compression,
@@ -128,6 +130,7 @@ impl Stream {
     /// # Returns
     ///
     /// Returns an [`Encoder`] on success.
+    #[cfg(feature = "encoder")]
     pub fn multithreaded_encoder(
         self,
         level: encoder::options::Compression,
@@ -151,6 +154,7 @@ impl Stream {
     /// # Errors
     ///
     /// Returns an error if the options are not supported by the linked liblzma.
+    #[cfg(feature = "encoder")]
     pub fn alone_encoder(
         self,
         options: encoder::options::Lzma1Options,
@@ -261,6 +265,27 @@ impl Stream {
         Decoder::new_alone(memlimit, self)
     }
 
+    /// Create a decoder for the `.lz` (lzip) format.
+    ///
+    /// # Parameters
+    ///
+    /// * `memlimit` - Maximum memory usage for decompression.
+    /// * `flags` - Decoder flags; use [`decoder::options::Flags::CONCATENATED`] for
+    ///   regular `.lz` files.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::OptionsError`] if the flags are unsupported.
+    /// Returns [`crate::Error::MemError`] if memory allocation fails.
+    /// Returns [`crate::Error::ProgError`] if the decoder is misused.
+    ///
+    /// # Returns
+    ///
+    /// Returns a [`Decoder`] on success.
+    pub fn lzip_decoder(self, memlimit: u64, flags: decoder::options::Flags) -> Result<Decoder> {
+        Decoder::new_lzip(memlimit, flags, self)
+    }
+
     /// Create an index decoder for extracting metadata from XZ Index blocks.
     ///
     /// This decoder extracts information about streams, blocks, and other metadata
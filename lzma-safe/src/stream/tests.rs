@@ -39,6 +39,7 @@ fn next_buffer_helpers_update_lengths() {
 }
 
 /// Test multithreaded decoder sets thread count correctly.
+#[cfg(has_mt_decoder)]
 #[test]
 fn multithreaded_decoder_from_stream_sets_threads() {
     let decoder = Stream::default()
@@ -174,7 +175,18 @@ fn alone_decoder_creation_from_stream() {
     assert!(decoder.is_ok());
 }
 
+/// Test lzip decoder creation from stream.
+#[cfg(has_lzip_decoder)]
+#[test]
+fn lzip_decoder_creation_from_stream() {
+    let stream = Stream::default();
+    let decoder = stream.lzip_decoder(u64::MAX, Flags::CONCATENATED);
+
+    assert!(decoder.is_ok());
+}
+
 /// Test multithreaded encoder creation with different thread counts.
+#[cfg(liblzma_threads)]
 #[test]
 fn multithreaded_encoder_creation() {
     // Test with explicit thread count
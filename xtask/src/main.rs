@@ -0,0 +1,198 @@
+//! Repository maintenance tasks, run with `cargo xtask <command>`.
+//!
+//! - `regen-bindings` regenerates the pre-generated liblzma bindings that
+//!   `liblzma-sys` uses when its `bindgen` feature is off. With `--verify` nothing is
+//!   written; the command fails if a checked-in file drifted from the headers instead.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: cargo xtask regen-bindings [--verify] [--target <triple>]";
+
+/// A checked-in bindings file and the target it is generated for
+struct BindingVariant {
+    /// Target triple passed to clang
+    target: &'static str,
+    /// Path relative to the `liblzma-sys` crate
+    file: &'static str,
+}
+
+/// Pre-generated bindings kept in the tree
+///
+/// liblzma's API only uses fixed-width integers and `size_t`, which bindgen maps to
+/// `usize`, so the Linux variant serves every target.
+const BINDING_VARIANTS: &[BindingVariant] = &[BindingVariant {
+    target: "x86_64-unknown-linux-gnu",
+    file: "src/lzma_bindings.rs",
+}];
+
+/// First line of bindgen's output, which names the bindgen version
+const GENERATOR_LINE_PREFIX: &str = "/* automatically generated by rust-bindgen";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("regen-bindings") => regen_bindings(&args[1..]),
+        Some(command) => Err(format!("unknown command `{command}`\n{USAGE}")),
+        None => Err(USAGE.to_string()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Regenerate every selected bindings variant, or check that it is up to date
+fn regen_bindings(args: &[String]) -> Result<(), String> {
+    let mut verify = false;
+    let mut only_target = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--verify" => verify = true,
+            "--target" => {
+                let target = args.next().ok_or("`--target` needs a target triple")?;
+                only_target = Some(target.as_str());
+            }
+            other => return Err(format!("unexpected argument `{other}`\n{USAGE}")),
+        }
+    }
+
+    let variants: Vec<&BindingVariant> = BINDING_VARIANTS
+        .iter()
+        .filter(|variant| only_target.is_none_or(|target| target == variant.target))
+        .collect();
+    if variants.is_empty() {
+        let known: Vec<&str> = BINDING_VARIANTS.iter().map(|v| v.target).collect();
+        return Err(format!(
+            "no pre-generated bindings for that target; known targets: {}",
+            known.join(", ")
+        ));
+    }
+
+    let sys_dir = liblzma_sys_dir();
+    let mut drifted = Vec::new();
+    for variant in variants {
+        let path = sys_dir.join(variant.file);
+        let generated = generate_bindings(&sys_dir, variant.target)?;
+
+        if !verify {
+            fs::write(&path, generated)
+                .map_err(|err| format!("couldn't write {}: {err}", path.display()))?;
+            println!("Updated {} ({})", variant.file, variant.target);
+            continue;
+        }
+
+        let current = fs::read_to_string(&path)
+            .map_err(|err| format!("couldn't read {}: {err}", path.display()))?;
+        match first_difference(&current, &generated) {
+            None => println!("{} is up to date ({})", variant.file, variant.target),
+            Some(line) => {
+                eprintln!(
+                    "{} differs from the headers at line {line} ({})",
+                    variant.file, variant.target
+                );
+                drifted.push(variant.file);
+            }
+        }
+    }
+
+    if drifted.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} out of date; run `cargo xtask regen-bindings`",
+            drifted.join(", ")
+        ))
+    }
+}
+
+/// Directory of the `liblzma-sys` crate
+fn liblzma_sys_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../lzma-safe/liblzma-sys")
+}
+
+/// Run bindgen on the `lzma.h` shim for `target`
+fn generate_bindings(sys_dir: &Path, target: &str) -> Result<String, String> {
+    let mut builder = bindgen::Builder::default()
+        .header(sys_dir.join("lzma.h").to_string_lossy())
+        .allowlist_function("lzma_.*")
+        .allowlist_type("lzma_.*")
+        .allowlist_var("LZMA_.*")
+        .blocklist_type("max_align_t")
+        .size_t_is_usize(true)
+        .layout_tests(false)
+        .clang_arg(format!("--target={target}"));
+    for arg in header_clang_args(sys_dir)? {
+        builder = builder.clang_arg(arg);
+    }
+
+    let bindings = builder
+        .generate()
+        .map_err(|err| format!("bindgen failed for {target}: {err}"))?;
+    Ok(bindings.to_string())
+}
+
+/// Clang arguments that point the shim at the liblzma headers to use
+///
+/// `LZMA_INCLUDE_DIR` selects an installed copy; otherwise the vendored headers are
+/// used, which the shim includes relative to itself.
+fn header_clang_args(sys_dir: &Path) -> Result<Vec<String>, String> {
+    if let Some(dir) = env::var_os("LZMA_INCLUDE_DIR") {
+        let dir = PathBuf::from(dir);
+        return Ok(vec!["-DPKG_CONFIG".into(), format!("-I{}", dir.display())]);
+    }
+
+    if sys_dir.join("xz/src/liblzma/api/lzma.h").is_file() {
+        Ok(Vec::new())
+    } else {
+        Err("liblzma headers not found; check out the xz submodule or set LZMA_INCLUDE_DIR".into())
+    }
+}
+
+/// Line number of the first difference between two bindings files, ignoring the
+/// bindgen version banner
+fn first_difference(current: &str, generated: &str) -> Option<usize> {
+    let strip = |text: &str| {
+        text.lines()
+            .filter(|line| !line.starts_with(GENERATOR_LINE_PREFIX))
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+    };
+    let current = strip(current);
+    let generated = strip(generated);
+
+    let common = current.len().min(generated.len());
+    (0..common)
+        .find(|&index| current[index] != generated[index])
+        .or((current.len() != generated.len()).then_some(common))
+        .map(|index| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bindings that only differ in the bindgen version are up to date.
+    #[test]
+    fn generator_banner_is_ignored() {
+        let current = "/* automatically generated by rust-bindgen 0.72.1 */\n\npub type a = u32;\n";
+        let generated =
+            "/* automatically generated by rust-bindgen 0.70.1 */\n\npub type a = u32;\n";
+        assert_eq!(first_difference(current, generated), None);
+    }
+
+    /// Changed and missing lines are reported by their line number.
+    #[test]
+    fn reports_first_differing_line() {
+        assert_eq!(first_difference("a\nb\nc\n", "a\nx\nc\n"), Some(2));
+        assert_eq!(first_difference("a\nb\n", "a\nb\nc\n"), Some(3));
+        assert_eq!(first_difference("a\nb\nc\n", "a\n"), Some(2));
+    }
+}
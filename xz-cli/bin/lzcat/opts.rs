@@ -4,7 +4,10 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-use xz_cli::{parse_memory_limit, CliConfig, OperationMode};
+use xz_cli::{
+    argfiles::FileListArgs, parse_memory_limit, CliConfig, ListSizes, LogArgs, OperationMode,
+    DEFAULT_BUFFER_SIZE,
+};
 
 /// LZMA decompression and concatenation utility.
 ///
@@ -23,14 +26,24 @@ pub struct LzCatOpts {
     #[arg(value_name = "FILE")]
     files: Vec<PathBuf>,
 
-    /// Verbose mode
-    #[arg(short = 'v', long = "verbose", conflicts_with = "quiet")]
-    verbose: bool,
+    #[command(flatten)]
+    pub file_list: FileListArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    /// Verbose mode. Use twice for even more detail.
+    #[arg(short = 'v', long = "verbose", conflicts_with = "quiet", action = clap::ArgAction::Count)]
+    verbose: u8,
 
     /// Quiet mode (suppress warnings). Use twice to suppress errors too.
     #[arg(short = 'q', long = "quiet", conflicts_with = "verbose", action = clap::ArgAction::Count)]
     quiet: u8,
 
+    /// Don't set the exit status to 2 when only warnings occurred.
+    #[arg(short = 'Q', long = "no-warn")]
+    no_warn: bool,
+
     /// Use at most this many threads (ignored for .lzma; kept for CLI compatibility)
     #[arg(short = 'T', long = "threads", value_name = "NUM")]
     threads: Option<usize>,
@@ -63,13 +76,15 @@ impl LzCatOpts {
             force: false,
             keep: true,
             stdout: true,
-            verbose: self.verbose,
+            verbose: self.verbose > 0,
+            very_verbose: self.verbose > 1,
             quiet: self.quiet,
-            no_warn: false,
+            no_warn: self.no_warn,
             level: None,
             threads: self.threads,
             compression_memory_limit: None,
             memory_limit: self.memory,
+            mt_memory_limit: self.memory,
             extreme: false,
             format: xz_core::config::DecodeMode::Lzma,
             check: xz_core::options::IntegrityCheck::None,
@@ -77,11 +92,20 @@ impl LzCatOpts {
             lzma2: None,
             filters: None,
             robot: false,
+            list_sizes: ListSizes::default(),
             suffix: None,
             single_stream: self.single_stream,
             ignore_check: false,
             no_adjust: false,
             sparse: false,
+            synchronous: false,
+            block_size: None,
+            block_list: None,
+            flush_timeout: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            mmap: false,
+            io_hints: true,
+            log_level: self.log.log_level,
         }
     }
 
@@ -100,8 +124,11 @@ mod tests {
     fn config_uses_lzma_decode_mode() {
         let opts = LzCatOpts {
             files: vec![PathBuf::from("input.lzma")],
-            verbose: false,
+            file_list: FileListArgs::default(),
+            log: LogArgs::default(),
+            verbose: 0,
             quiet: 0,
+            no_warn: false,
             threads: Some(4),
             memory: Some(1024),
             single_stream: false,
@@ -10,14 +10,21 @@ mod opts;
 
 use opts::LzmaOpts;
 
-use xz_cli::{format_diagnostic_for_stderr, run_cli};
+use xz_cli::{exit_with_error, format_diagnostic_for_stderr, resolve_input_files, run_tool};
 
 const PROGRAM_NAME: &str = "lzma";
 
 fn main() {
     let opts = LzmaOpts::parse();
-    let config = opts.config();
-    let report = run_cli(opts.files(), &config, PROGRAM_NAME);
+    let config = opts
+        .config()
+        .unwrap_or_else(|err| exit_with_error(err.into(), PROGRAM_NAME, opts.quiet));
+    let Some(files) =
+        resolve_input_files(&opts.file_list, opts.files(), PROGRAM_NAME, config.quiet)
+    else {
+        return;
+    };
+    let report = run_tool(&files, &config, PROGRAM_NAME);
     for diagnostic in &report.diagnostics {
         if let Some(msg) = format_diagnostic_for_stderr(config.quiet, diagnostic) {
             eprintln!("{msg}");
@@ -4,7 +4,10 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-use xz_cli::{parse_memory_limit, CliConfig, OperationMode};
+use xz_cli::{
+    argfiles::FileListArgs, parse_lzma1_options, parse_memory_limit, CliConfig, ListSizes, LogArgs,
+    OperationMode, DEFAULT_BUFFER_SIZE,
+};
 
 /// LZMA compression utility.
 ///
@@ -23,6 +26,12 @@ pub struct LzmaOpts {
     #[arg(value_name = "FILE")]
     pub files: Vec<PathBuf>,
 
+    #[command(flatten)]
+    pub file_list: FileListArgs,
+
+    #[command(flatten)]
+    pub log: LogArgs,
+
     /// Force compression
     #[arg(short = 'z', long = "compress", conflicts_with_all = ["decompress", "test"])]
     pub compress: bool,
@@ -52,14 +61,18 @@ pub struct LzmaOpts {
     #[arg(short = 'k', long = "keep")]
     pub keep: bool,
 
-    /// Verbose mode
-    #[arg(short = 'v', long = "verbose", conflicts_with = "quiet")]
-    pub verbose: bool,
+    /// Verbose mode. Use twice for even more detail.
+    #[arg(short = 'v', long = "verbose", conflicts_with = "quiet", action = clap::ArgAction::Count)]
+    pub verbose: u8,
 
     /// Quiet mode (suppress warnings). Use twice to suppress errors too.
     #[arg(short = 'q', long = "quiet", conflicts_with = "verbose", action = clap::ArgAction::Count)]
     pub quiet: u8,
 
+    /// Don't set the exit status to 2 when only warnings occurred.
+    #[arg(short = 'Q', long = "no-warn")]
+    pub no_warn: bool,
+
     /// Compression preset level 0..9
     #[arg(short = '0', group = "level")]
     pub level_0: bool,
@@ -119,6 +132,11 @@ pub struct LzmaOpts {
     /// Don't create sparse files when decompressing.
     #[arg(long = "no-sparse")]
     pub no_sparse: bool,
+
+    /// Synchronize the output file and its directory to the storage device before
+    /// removing the input file.
+    #[arg(long = "synchronous")]
+    pub synchronous: bool,
 }
 
 impl LzmaOpts {
@@ -155,19 +173,28 @@ impl LzmaOpts {
     }
 
     /// Build CLI configuration from the parsed options.
-    pub fn config(&self) -> CliConfig {
-        CliConfig {
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the `--lzma1` option string is malformed.
+    pub fn config(&self) -> Result<CliConfig, xz_cli::Error> {
+        if let Some(raw) = &self.lzma1 {
+            parse_lzma1_options(raw)?;
+        }
+        Ok(CliConfig {
             mode: self.operation_mode(),
             force: self.force,
             keep: self.keep,
             stdout: self.stdout,
-            verbose: self.verbose,
+            verbose: self.verbose > 0,
+            very_verbose: self.verbose > 1,
             quiet: self.quiet,
-            no_warn: false,
+            no_warn: self.no_warn,
             level: self.compression_level(),
             threads: self.threads,
             compression_memory_limit: self.memory,
             memory_limit: self.memory,
+            mt_memory_limit: self.memory,
             extreme: self.extreme,
             format: xz_core::config::DecodeMode::Lzma,
             check: xz_core::options::IntegrityCheck::None,
@@ -175,12 +202,21 @@ impl LzmaOpts {
             lzma2: None,
             filters: None,
             robot: false,
+            list_sizes: ListSizes::default(),
             suffix: self.suffix.clone(),
             single_stream: self.single_stream,
             ignore_check: self.ignore_check,
             no_adjust: false,
             sparse: !self.no_sparse,
-        }
+            synchronous: self.synchronous,
+            block_size: None,
+            block_list: None,
+            flush_timeout: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            mmap: false,
+            io_hints: true,
+            log_level: self.log.log_level,
+        })
     }
 
     /// Files supplied on the command line
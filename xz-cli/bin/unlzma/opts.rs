@@ -4,7 +4,10 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-use xz_cli::{parse_memory_limit, CliConfig, OperationMode};
+use xz_cli::{
+    argfiles::FileListArgs, parse_memory_limit, CliConfig, ListSizes, LogArgs, OperationMode,
+    DEFAULT_BUFFER_SIZE,
+};
 
 /// LZMA decompression utility.
 ///
@@ -23,6 +26,12 @@ pub struct UnlzmaOpts {
     #[arg(value_name = "FILE")]
     files: Vec<PathBuf>,
 
+    #[command(flatten)]
+    pub file_list: FileListArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
     /// Write to standard output and don't delete input files
     #[arg(short = 'c', long = "stdout", alias = "to-stdout")]
     stdout: bool,
@@ -35,14 +44,18 @@ pub struct UnlzmaOpts {
     #[arg(short = 'k', long = "keep")]
     keep: bool,
 
-    /// Verbose mode
-    #[arg(short = 'v', long = "verbose")]
-    verbose: bool,
+    /// Verbose mode. Use twice for even more detail.
+    #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+    verbose: u8,
 
     /// Quiet mode (suppress warnings). Use twice to suppress errors too.
     #[arg(short = 'q', long = "quiet", conflicts_with = "verbose", action = clap::ArgAction::Count)]
     pub quiet: u8,
 
+    /// Don't set the exit status to 2 when only warnings occurred.
+    #[arg(short = 'Q', long = "no-warn")]
+    pub no_warn: bool,
+
     /// Test compressed file integrity
     #[arg(short = 't', long = "test")]
     test: bool,
@@ -65,9 +78,18 @@ pub struct UnlzmaOpts {
     #[arg(short = 'S', long = "suffix", value_name = "SUFFIX")]
     suffix: Option<String>,
 
+    /// Decompress only the first stream, ignore remaining input
+    #[arg(long = "single-stream")]
+    single_stream: bool,
+
     /// Don't create sparse files when decompressing.
     #[arg(long = "no-sparse")]
     no_sparse: bool,
+
+    /// Synchronize the output file and its directory to the storage device before
+    /// removing the input file.
+    #[arg(long = "synchronous")]
+    synchronous: bool,
 }
 
 impl UnlzmaOpts {
@@ -89,13 +111,15 @@ impl UnlzmaOpts {
             force: self.force,
             keep: self.keep,
             stdout: self.stdout,
-            verbose: self.verbose,
+            verbose: self.verbose > 0,
+            very_verbose: self.verbose > 1,
             quiet: self.quiet,
-            no_warn: false,
+            no_warn: self.no_warn,
             level: None,
             threads: self.threads,
             compression_memory_limit: None,
             memory_limit: self.memory,
+            mt_memory_limit: self.memory,
             extreme: false,
             format: xz_core::config::DecodeMode::Lzma,
             check: xz_core::options::IntegrityCheck::None,
@@ -103,11 +127,20 @@ impl UnlzmaOpts {
             lzma2: None,
             filters: None,
             robot: false,
+            list_sizes: ListSizes::default(),
             suffix: self.suffix.clone(),
-            single_stream: false,
+            single_stream: self.single_stream,
             ignore_check: false,
             no_adjust: false,
             sparse: !self.no_sparse,
+            synchronous: self.synchronous,
+            block_size: None,
+            block_list: None,
+            flush_timeout: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            mmap: false,
+            io_hints: true,
+            log_level: self.log.log_level,
         }
     }
 
@@ -4,7 +4,10 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-use xz_cli::{parse_memory_limit, CliConfig, OperationMode};
+use xz_cli::{
+    argfiles::FileListArgs, parse_memory_limit, CliConfig, ListSizes, LogArgs, OperationMode,
+    DEFAULT_BUFFER_SIZE,
+};
 
 /// XZ decompression utility
 ///
@@ -24,6 +27,12 @@ pub struct UnxzOpts {
     #[arg(value_name = "FILE")]
     files: Vec<PathBuf>,
 
+    #[command(flatten)]
+    pub file_list: FileListArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
     /// Write to standard output and don't delete input files
     #[arg(short = 'c', long = "stdout", alias = "to-stdout")]
     stdout: bool,
@@ -36,14 +45,18 @@ pub struct UnxzOpts {
     #[arg(short = 'k', long = "keep")]
     keep: bool,
 
-    /// Verbose mode
-    #[arg(short = 'v', long = "verbose")]
-    verbose: bool,
+    /// Verbose mode. Use twice for even more detail.
+    #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+    verbose: u8,
 
     /// Quiet mode (suppress warnings). Use twice to suppress errors too.
     #[arg(short = 'q', long = "quiet", conflicts_with = "verbose", action = clap::ArgAction::Count)]
     pub quiet: u8,
 
+    /// Don't set the exit status to 2 when only warnings occurred.
+    #[arg(short = 'Q', long = "no-warn")]
+    pub no_warn: bool,
+
     /// Test compressed file integrity
     #[arg(short = 't', long = "test")]
     test: bool,
@@ -62,6 +75,14 @@ pub struct UnxzOpts {
     )]
     memory: Option<u64>,
 
+    /// Decompress only the first stream, ignore remaining input
+    #[arg(long = "single-stream")]
+    single_stream: bool,
+
+    /// Don't verify the integrity check when decompressing
+    #[arg(long = "ignore-check")]
+    ignore_check: bool,
+
     /// Don't create sparse files when decompressing.
     ///
     /// Upstream `xz` attempts to create sparse output files by turning long runs
@@ -69,6 +90,11 @@ pub struct UnxzOpts {
     /// instead.
     #[arg(long = "no-sparse")]
     no_sparse: bool,
+
+    /// Synchronize the output file and its directory to the storage device before
+    /// removing the input file.
+    #[arg(long = "synchronous")]
+    synchronous: bool,
 }
 
 impl UnxzOpts {
@@ -90,13 +116,15 @@ impl UnxzOpts {
             force: self.force,
             keep: self.keep,
             stdout: self.stdout,
-            verbose: self.verbose,
+            verbose: self.verbose > 0,
+            very_verbose: self.verbose > 1,
             quiet: self.quiet,
-            no_warn: false,
+            no_warn: self.no_warn,
             level: None,
             threads: self.threads,
             compression_memory_limit: None,
             memory_limit: self.memory,
+            mt_memory_limit: self.memory,
             extreme: false,
             format: xz_core::config::DecodeMode::Auto,
             check: xz_core::options::IntegrityCheck::Crc64,
@@ -104,11 +132,20 @@ impl UnxzOpts {
             lzma2: None,
             filters: None,
             robot: false,
+            list_sizes: ListSizes::default(),
             suffix: None,
-            single_stream: false,
-            ignore_check: false,
+            single_stream: self.single_stream,
+            ignore_check: self.ignore_check,
             no_adjust: false,
             sparse: !self.no_sparse,
+            synchronous: self.synchronous,
+            block_size: None,
+            block_list: None,
+            flush_timeout: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            mmap: false,
+            io_hints: true,
+            log_level: self.log.log_level,
         }
     }
 
@@ -126,15 +163,21 @@ mod tests {
     fn config_reflects_test_mode() {
         let opts = UnxzOpts {
             files: vec![PathBuf::from("test.xz")],
+            file_list: FileListArgs::default(),
+            log: LogArgs::default(),
             stdout: false,
             force: true,
             keep: false,
-            verbose: true,
+            verbose: 1,
             quiet: 0,
+            no_warn: false,
             test: true,
             threads: Some(8),
             memory: Some(1024),
+            single_stream: false,
+            ignore_check: false,
             no_sparse: false,
+            synchronous: false,
         };
 
         let config = opts.config();
@@ -153,7 +196,7 @@ mod tests {
         assert_eq!(opts.files(), [PathBuf::from("file.xz")]);
         assert!(opts.stdout);
         assert!(opts.keep);
-        assert!(opts.verbose);
+        assert_eq!(opts.verbose, 1);
         assert_eq!(opts.threads, Some(4));
         assert_eq!(opts.memory, Some(1024 * 1024));
     }
@@ -3,46 +3,51 @@
 //! A modern Rust implementation of the xz compression utility, compatible with
 //! the original xz but with improved performance and user experience.
 
-use std::path::PathBuf;
 use std::process;
 
 mod opts;
 
+use clap::CommandFactory;
 use opts::XzOpts;
 
-use xz_cli::{argfiles, Diagnostic, DiagnosticCause, Error, IoErrorNoCode, Result};
-use xz_cli::{format_diagnostic_for_stderr, run_cli};
+use xz_cli::{
+    exit_with_error, format_diagnostic_for_stderr, print_info_memory, print_robot_version,
+    print_version, resolve_input_files, run_tool,
+};
+use xz_cli::{Error, Result};
 
 const PROGRAM_NAME: &str = "xz";
 
 fn main() {
     let opts = XzOpts::parse();
 
-    let config = match opts.config() {
-        Ok(config) => config,
-        Err(err) => {
-            // Match upstream `xz`: `-qq` suppresses runtime error messages but does
-            // not suppress clap's own argument parsing errors.
-            if opts.quiet < 2 {
-                eprintln!("{PROGRAM_NAME}: {err}");
-            }
+    let config = opts.config().unwrap_or_else(|err| {
+        // Match upstream `xz`: `-qq` suppresses runtime error messages but does
+        // not suppress clap's own argument parsing errors.
+        let cause = Error::InvalidOption {
+            message: err.to_string(),
+        };
+        exit_with_error(cause.into(), PROGRAM_NAME, opts.quiet)
+    });
 
-            process::exit(1);
+    if opts.version || opts.info_memory {
+        let result = if opts.version {
+            show_version(&opts)
+        } else {
+            print_info_memory(&config)
+        };
+        if let Err(err) = result {
+            exit_with_error(err, PROGRAM_NAME, config.quiet);
         }
-    };
+        return;
+    }
 
-    let files = match resolve_input_files(&opts) {
-        Ok(files) => files,
-        Err(err) => {
-            let diagnostic = Diagnostic::new(err, PROGRAM_NAME, None);
-            if let Some(msg) = format_diagnostic_for_stderr(config.quiet, &diagnostic) {
-                eprintln!("{msg}");
-            }
-            process::exit(1);
-        }
+    let Some(files) = resolve_input_files(&opts.file_list, &opts.files, PROGRAM_NAME, config.quiet)
+    else {
+        return;
     };
 
-    let report = run_cli(&files, &config, PROGRAM_NAME);
+    let report = run_tool(&files, &config, PROGRAM_NAME);
     for diagnostic in &report.diagnostics {
         if let Some(msg) = format_diagnostic_for_stderr(config.quiet, diagnostic) {
             eprintln!("{msg}");
@@ -54,28 +59,12 @@ fn main() {
     }
 }
 
-fn resolve_input_files(opts: &XzOpts) -> Result<Vec<PathBuf>> {
-    let mut files = opts.files.clone();
-
-    if let Some(path) = opts.files_from_file.as_deref() {
-        let extra =
-            argfiles::read_files(Some(path), argfiles::Delimiter::Line).map_err(|source| {
-                DiagnosticCause::Error(Error::OpenInput {
-                    source: IoErrorNoCode::new(source),
-                })
-            })?;
-        files.extend(extra);
-    }
-
-    if let Some(path) = opts.files0_from_file.as_deref() {
-        let extra =
-            argfiles::read_files(Some(path), argfiles::Delimiter::Nul).map_err(|source| {
-                DiagnosticCause::Error(Error::OpenInput {
-                    source: IoErrorNoCode::new(source),
-                })
-            })?;
-        files.extend(extra);
+/// Print `--version` output, in the machine-readable format with `--robot`.
+fn show_version(opts: &XzOpts) -> Result<()> {
+    if opts.robot {
+        return print_robot_version();
     }
 
-    Ok(files)
+    let command = XzOpts::command();
+    print_version(PROGRAM_NAME, command.get_version().unwrap_or_default())
 }
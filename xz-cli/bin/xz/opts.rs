@@ -1,12 +1,27 @@
 //! Command line argument parsing for xz utility
 
 use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 
-use xz_cli::{parse_memory_limit, CliConfig, OperationMode};
+use xz_cli::{
+    argfiles::FileListArgs, env_options, parse_block_list, parse_block_size, parse_lzma1_options,
+    parse_memory_limit, user_config, CliConfig, ListSizes, LogArgs, OperationMode,
+    DEFAULT_BUFFER_SIZE,
+};
 use xz_core::{config::DecodeMode, options::IntegrityCheck};
 
+/// Parsed `--block-list` sizes; an alias so that clap treats the list as a single value.
+type BlockList = Vec<u64>;
+
+/// Ids of the preset flags; a later preset overrides an earlier one (e.g. `XZ_OPT=-9 xz -6`).
+const PRESET_IDS: [&str; 10] = [
+    "level_0", "level_1", "level_2", "level_3", "level_4", "level_5", "level_6", "level_7",
+    "level_8", "level_9",
+];
+
 /// Modern XZ compression utility
 ///
 /// A modern Rust implementation of the xz compression utility, compatible with
@@ -15,6 +30,8 @@ use xz_core::{config::DecodeMode, options::IntegrityCheck};
 #[command(
     name = "xz",
     version = "0.1.1",
+    disable_version_flag = true,
+    args_override_self = true,
     about = "Compress or decompress .xz files",
     long_about = "xz is a general-purpose data compression tool with command line syntax \
                  similar to gzip and bzip2. The native file format is the .xz format."
@@ -26,7 +43,11 @@ pub struct XzOpts {
     pub files: Vec<PathBuf>,
 
     /// Force compression
-    #[arg(short = 'z', long = "compress", conflicts_with_all = ["decompress", "test", "list"])]
+    #[arg(
+        short = 'z',
+        long = "compress",
+        overrides_with_all = ["decompress", "test", "list", "recompress"]
+    )]
     pub compress: bool,
 
     /// Force decompression
@@ -34,18 +55,33 @@ pub struct XzOpts {
         short = 'd',
         long = "decompress",
         alias = "uncompress",
-        conflicts_with_all = ["compress", "test", "list"]
+        overrides_with_all = ["compress", "test", "list", "recompress"]
     )]
     pub decompress: bool,
 
     /// Test compressed file integrity
-    #[arg(short = 't', long = "test", conflicts_with_all = ["compress", "decompress", "list"])]
+    #[arg(
+        short = 't',
+        long = "test",
+        overrides_with_all = ["compress", "decompress", "list", "recompress"]
+    )]
     pub test: bool,
 
     /// List information about compressed files
-    #[arg(short = 'l', long = "list", conflicts_with_all = ["compress", "decompress", "test"])]
+    #[arg(
+        short = 'l',
+        long = "list",
+        overrides_with_all = ["compress", "decompress", "test", "recompress"]
+    )]
     pub list: bool,
 
+    /// Rewrite .xz and .lzma files in place with the given compression settings
+    #[arg(
+        long = "recompress",
+        overrides_with_all = ["compress", "decompress", "test", "list"]
+    )]
+    pub recompress: bool,
+
     /// Write to standard output and don't delete input files
     #[arg(short = 'c', long = "stdout", alias = "to-stdout")]
     pub stdout: bool,
@@ -58,56 +94,56 @@ pub struct XzOpts {
     #[arg(short = 'k', long = "keep")]
     pub keep: bool,
 
-    /// Verbose mode
-    #[arg(short = 'v', long = "verbose", conflicts_with = "quiet")]
-    pub verbose: bool,
+    /// Verbose mode. Use twice for even more detail.
+    #[arg(short = 'v', long = "verbose", conflicts_with = "quiet", action = clap::ArgAction::Count)]
+    pub verbose: u8,
 
     /// Quiet mode (suppress warnings). Use twice to suppress errors too.
     #[arg(short = 'q', long = "quiet", conflicts_with = "verbose", action = clap::ArgAction::Count)]
     pub quiet: u8,
 
-    /// Don't warn about unsupported check types.
+    /// Don't set the exit status to 2 when only warnings occurred.
     #[arg(short = 'Q', long = "no-warn")]
     pub no_warn: bool,
 
     /// Compression preset level 0 (no compression, fastest)
-    #[arg(short = '0', group = "level")]
+    #[arg(short = '0', overrides_with_all = PRESET_IDS)]
     pub level_0: bool,
 
     /// Compression preset level 1 (fastest)
-    #[arg(short = '1', group = "level")]
+    #[arg(short = '1', overrides_with_all = PRESET_IDS)]
     pub level_1: bool,
 
     /// Compression preset level 2
-    #[arg(short = '2', group = "level")]
+    #[arg(short = '2', overrides_with_all = PRESET_IDS)]
     pub level_2: bool,
 
     /// Compression preset level 3
-    #[arg(short = '3', group = "level")]
+    #[arg(short = '3', overrides_with_all = PRESET_IDS)]
     pub level_3: bool,
 
     /// Compression preset level 4
-    #[arg(short = '4', group = "level")]
+    #[arg(short = '4', overrides_with_all = PRESET_IDS)]
     pub level_4: bool,
 
     /// Compression preset level 5
-    #[arg(short = '5', group = "level")]
+    #[arg(short = '5', overrides_with_all = PRESET_IDS)]
     pub level_5: bool,
 
     /// Compression preset level 6 (default)
-    #[arg(short = '6', group = "level")]
+    #[arg(short = '6', overrides_with_all = PRESET_IDS)]
     pub level_6: bool,
 
     /// Compression preset level 7
-    #[arg(short = '7', group = "level")]
+    #[arg(short = '7', overrides_with_all = PRESET_IDS)]
     pub level_7: bool,
 
     /// Compression preset level 8
-    #[arg(short = '8', group = "level")]
+    #[arg(short = '8', overrides_with_all = PRESET_IDS)]
     pub level_8: bool,
 
     /// Compression preset level 9 (best)
-    #[arg(short = '9', group = "level")]
+    #[arg(short = '9', overrides_with_all = PRESET_IDS)]
     pub level_9: bool,
 
     /// Use at most this many threads
@@ -140,6 +176,14 @@ pub struct XzOpts {
     )]
     pub memlimit_decompress: Option<u64>,
 
+    /// Memory usage limit for multi-threaded decompression
+    #[arg(
+        long = "memlimit-mt-decompress",
+        value_name = "LIMIT",
+        value_parser = parse_memory_limit
+    )]
+    pub memlimit_mt_decompress: Option<u64>,
+
     /// Disable automatic memory limit adjustment
     #[arg(long = "no-adjust")]
     pub no_adjust: bool,
@@ -174,30 +218,73 @@ pub struct XzOpts {
     #[arg(long = "filters", value_name = "CHAIN", conflicts_with_all = ["lzma1", "lzma2"])]
     pub filters: Option<String>,
 
-    /// Read filenames from file (one per line)
+    /// Filter chain for x86 executables, same as `--filters="x86 lzma2"`
     #[arg(
-        long = "files",
-        value_name = "FILE",
-        num_args = 0..=1,
-        default_missing_value = "-",
-        conflicts_with = "files0_from_file"
+        long = "preset-exe-x86",
+        overrides_with_all = ["preset_exe_arm64", "preset_delta4"],
+        conflicts_with_all = ["filters", "lzma1", "lzma2"]
     )]
-    pub files_from_file: Option<PathBuf>,
+    pub preset_exe_x86: bool,
 
-    /// Read filenames from file (null-terminated)
+    /// Filter chain for ARM64 executables, same as `--filters="arm64 lzma2"`
     #[arg(
-        long = "files0",
-        value_name = "FILE",
-        num_args = 0..=1,
-        default_missing_value = "-",
-        conflicts_with = "files_from_file"
+        long = "preset-exe-arm64",
+        overrides_with_all = ["preset_exe_x86", "preset_delta4"],
+        conflicts_with_all = ["filters", "lzma1", "lzma2"]
     )]
-    pub files0_from_file: Option<PathBuf>,
+    pub preset_exe_arm64: bool,
+
+    /// Filter chain for data made of 4-byte samples, such as 32-bit audio or RGBA images,
+    /// same as `--filters="delta:dist=4 lzma2"`
+    #[arg(
+        long = "preset-delta4",
+        overrides_with_all = ["preset_exe_x86", "preset_exe_arm64"],
+        conflicts_with_all = ["filters", "lzma1", "lzma2"]
+    )]
+    pub preset_delta4: bool,
+
+    /// Start a new `.xz` Block after every SIZE bytes of input
+    #[arg(long = "block-size", value_name = "SIZE", value_parser = parse_block_size)]
+    pub block_size: Option<u64>,
+
+    /// Start a new `.xz` Block after the given comma-separated uncompressed sizes
+    #[arg(long = "block-list", value_name = "SIZES", value_parser = parse_block_list)]
+    pub block_list: Option<BlockList>,
+
+    /// When compressing, flush the output if no input arrives for MS milliseconds (0 disables)
+    #[arg(long = "flush-timeout", value_name = "MS")]
+    pub flush_timeout: Option<u64>,
+
+    #[command(flatten)]
+    pub file_list: FileListArgs,
+
+    #[command(flatten)]
+    pub log: LogArgs,
 
     /// Machine-readable output
     #[arg(long = "robot")]
     pub robot: bool,
 
+    /// How --list prints sizes: `default`, `human` (scaled units) or `bytes` (exact, grouped)
+    #[arg(long = "list-sizes", value_name = "STYLE")]
+    pub list_sizes: Option<String>,
+
+    /// Number of decimals in the sizes printed by --list-sizes=human (default 1)
+    #[arg(
+        long = "list-precision",
+        value_name = "N",
+        value_parser = clap::value_parser!(u8).range(0..=6)
+    )]
+    pub list_precision: Option<u8>,
+
+    /// Display the amount of RAM and the memory usage limits, and exit
+    #[arg(long = "info-memory")]
+    pub info_memory: bool,
+
+    /// Display the version number and exit
+    #[arg(short = 'V', long = "version")]
+    pub version: bool,
+
     /// Use custom suffix on compressed files
     #[arg(short = 'S', long = "suffix", value_name = "SUFFIX")]
     pub suffix: Option<String>,
@@ -218,15 +305,47 @@ pub struct XzOpts {
     #[arg(long = "no-sparse")]
     pub no_sparse: bool,
 
+    /// Synchronize the output file and its directory to the storage device before
+    /// removing the input file.
+    #[arg(long = "synchronous")]
+    pub synchronous: bool,
+
+    /// Map input files into memory instead of reading them, which avoids read calls
+    /// and copies for large files. A file truncated while it is read terminates xz.
+    #[arg(long = "mmap")]
+    pub mmap: bool,
+
+    /// Don't give the kernel page cache hints for input files. By default, xz tells the
+    /// kernel that input files are read sequentially and drops the pages already read.
+    #[arg(long = "no-io-hints")]
+    pub no_io_hints: bool,
+
     /// Display long help and exit
     #[arg(short = 'H', long = "long-help", action = clap::ArgAction::Help)]
     _long_help: Option<bool>,
 }
 
 impl XzOpts {
-    /// Parse command line arguments
+    /// Parse the configuration file, `XZ_DEFAULTS`, `XZ_OPT` and the command line
+    /// arguments, in that order
+    ///
+    /// Options given later override earlier ones. Errors in the configuration file or the
+    /// environment variables are fatal, like upstream `xz` does for the latter.
     pub fn parse() -> Self {
-        Parser::parse()
+        let defaults = user_config::load_default_options().unwrap_or_else(|err| {
+            eprintln!("xz: {err}");
+            process::exit(1);
+        });
+        match env_options::args_with_env_options(&Self::command()) {
+            Ok(mut args) => {
+                args.splice(1..1, defaults);
+                Parser::parse_from(args)
+            }
+            Err(err) => {
+                eprintln!("xz: {err}");
+                process::exit(1);
+            }
+        }
     }
 
     /// Determine operation mode based on flags
@@ -237,6 +356,8 @@ impl XzOpts {
             OperationMode::Test
         } else if self.list {
             OperationMode::List
+        } else if self.recompress {
+            OperationMode::Recompress
         } else if self.compress {
             OperationMode::Compress
         } else {
@@ -250,21 +371,33 @@ impl XzOpts {
         match self.format.as_deref() {
             Some("xz") => Ok(DecodeMode::Xz),
             Some("lzma") => Ok(DecodeMode::Lzma),
+            Some("lzip") => Ok(DecodeMode::Lzip),
             Some("raw") => Ok(DecodeMode::Raw),
             Some("auto") | None => Ok(DecodeMode::Auto),
             Some(invalid) => Err(format!("{invalid}: Unknown file format type").into()),
         }
     }
 
+    /// Parse the `--list-sizes` style and its `--list-precision`
+    pub fn list_sizes(&self) -> Result<ListSizes, Box<dyn std::error::Error>> {
+        match (self.list_sizes.as_deref(), self.list_precision) {
+            (Some("human"), precision) => Ok(ListSizes::Human {
+                precision: precision.unwrap_or(1),
+            }),
+            (_, Some(_)) => Err("--list-precision requires --list-sizes=human".into()),
+            (Some("default") | None, None) => Ok(ListSizes::Upstream),
+            (Some("bytes"), None) => Ok(ListSizes::Bytes),
+            (Some(invalid), None) => Err(format!("{invalid}: Unknown size style").into()),
+        }
+    }
+
     /// Parse the check type from the check string
     pub fn check_type_for_format(
         &self,
         format: DecodeMode,
     ) -> Result<IntegrityCheck, Box<dyn std::error::Error>> {
         match (format, self.check.as_deref()) {
-            (DecodeMode::Lzma | DecodeMode::Raw, Some("none") | None) => {
-                Ok(IntegrityCheck::None)
-            }
+            (DecodeMode::Lzma | DecodeMode::Raw, Some("none") | None) => Ok(IntegrityCheck::None),
             (DecodeMode::Lzma, Some(other)) => {
                 Err(format!("{other}: Integrity checks are not supported in .lzma format").into())
             }
@@ -299,35 +432,81 @@ impl XzOpts {
         .find_map(|&(flag, level)| flag.then_some(level))
     }
 
+    /// Filter chain from `--filters` or one of the `--preset-*` flags.
+    fn filter_chain(&self) -> Option<String> {
+        if self.filters.is_some() {
+            return self.filters.clone();
+        }
+        [
+            (self.preset_exe_x86, "x86 lzma2"),
+            (self.preset_exe_arm64, "arm64 lzma2"),
+            (self.preset_delta4, "delta:dist=4 lzma2"),
+        ]
+        .iter()
+        .find_map(|&(flag, chain)| flag.then(|| chain.to_string()))
+    }
+
     /// Build CLI configuration from the parsed options
     pub fn config(&self) -> Result<CliConfig, Box<dyn std::error::Error>> {
         let format = self.file_format()?;
-        let memory_limit = self.memlimit_decompress.or(self.memory);
-        let compression_memory_limit = self.memlimit_compress.or(self.memory);
+        // `-M` sets all three limits; `0` and `max` mean no limit.
+        let limit = |specific: Option<u64>| specific.or(self.memory).filter(|&limit| limit != 0);
+        let memory_limit = limit(self.memlimit_decompress);
+        let compression_memory_limit = limit(self.memlimit_compress);
+        let mt_memory_limit = limit(self.memlimit_mt_decompress);
+        if format == DecodeMode::Lzip && self.operation_mode() == OperationMode::Compress {
+            return Err("Compression of lzip files (.lz) is not supported".into());
+        }
+        // Upstream has no machine-readable format for coding files yet.
+        if self.robot
+            && self.operation_mode() != OperationMode::List
+            && !self.version
+            && !self.info_memory
+        {
+            return Err("Compression and decompression with --robot are not supported yet.".into());
+        }
+        // Report malformed option strings before any file is touched, like upstream.
+        for raw in [&self.lzma1, &self.lzma2].into_iter().flatten() {
+            parse_lzma1_options(raw)?;
+        }
         Ok(CliConfig {
             mode: self.operation_mode(),
             force: self.force,
             keep: self.keep,
             stdout: self.stdout,
-            verbose: self.verbose,
+            verbose: self.verbose > 0,
+            very_verbose: self.verbose > 1,
             quiet: self.quiet,
             no_warn: self.no_warn,
             level: self.compression_level().map(u32::from),
             threads: self.threads,
             compression_memory_limit,
             memory_limit,
+            mt_memory_limit,
             extreme: self.extreme,
             format,
             check: self.check_type_for_format(format)?,
             lzma1: self.lzma1.clone(),
             lzma2: self.lzma2.clone(),
-            filters: self.filters.clone(),
+            filters: self.filter_chain(),
             robot: self.robot,
+            list_sizes: self.list_sizes()?,
             suffix: self.suffix.clone(),
             single_stream: self.single_stream,
             ignore_check: self.ignore_check,
             no_adjust: self.no_adjust,
             sparse: !self.no_sparse,
+            synchronous: self.synchronous,
+            block_size: self.block_size.filter(|&size| size != 0),
+            block_list: self.block_list.clone(),
+            flush_timeout: self
+                .flush_timeout
+                .filter(|&ms| ms != 0)
+                .map(Duration::from_millis),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            mmap: self.mmap,
+            io_hints: !self.no_io_hints,
+            log_level: self.log.log_level,
         })
     }
 }
@@ -344,10 +523,11 @@ mod tests {
             decompress: false,
             test: false,
             list: false,
+            recompress: false,
             stdout: false,
             force: false,
             keep: false,
-            verbose: false,
+            verbose: 0,
             quiet: 0,
             no_warn: false,
             level_0: false,
@@ -364,6 +544,7 @@ mod tests {
             memlimit_compress: None,
             memory: None,
             memlimit_decompress: None,
+            memlimit_mt_decompress: None,
             no_adjust: false,
             extreme: false,
             format: None,
@@ -371,13 +552,26 @@ mod tests {
             lzma1: None,
             lzma2: None,
             filters: None,
-            files_from_file: None,
-            files0_from_file: None,
+            preset_exe_x86: false,
+            preset_exe_arm64: false,
+            preset_delta4: false,
+            block_size: None,
+            block_list: None,
+            flush_timeout: None,
+            file_list: FileListArgs::default(),
+            log: LogArgs::default(),
             robot: false,
+            list_sizes: None,
+            list_precision: None,
+            info_memory: false,
+            version: false,
             suffix: None,
             single_stream: false,
             ignore_check: false,
             no_sparse: false,
+            synchronous: false,
+            mmap: false,
+            no_io_hints: false,
             _long_help: None,
         }
     }
@@ -402,6 +596,11 @@ mod tests {
         let config = opts.config().unwrap();
         assert_eq!(config.mode, OperationMode::Test);
         assert!(!config.stdout);
+
+        let opts = XzOpts::try_parse_from(["xz", "-d", "--recompress", "file.xz"]).unwrap();
+        assert_eq!(opts.operation_mode(), OperationMode::Recompress);
+        let opts = XzOpts::try_parse_from(["xz", "--recompress", "-t", "file.xz"]).unwrap();
+        assert_eq!(opts.operation_mode(), OperationMode::Test);
     }
 
     /// Test compression level detection from preset flags
@@ -485,6 +684,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn filter_presets_expand_to_chains() {
+        let opts = XzOpts::try_parse_from(["xz", "--preset-exe-x86", "file"]).unwrap();
+        assert_eq!(opts.config().unwrap().filters.as_deref(), Some("x86 lzma2"));
+
+        // Like the levels, a later preset overrides an earlier one.
+        let opts = XzOpts::try_parse_from(["xz", "--preset-exe-arm64", "--preset-delta4", "file"])
+            .unwrap();
+        assert_eq!(
+            opts.config().unwrap().filters.as_deref(),
+            Some("delta:dist=4 lzma2")
+        );
+
+        assert!(
+            XzOpts::try_parse_from(["xz", "--preset-delta4", "--filters=lzma2", "file"]).is_err()
+        );
+    }
+
     #[test]
     fn generic_memlimit_populates_compress_and_decompress_limits() {
         let opts = XzOpts::try_parse_from(["xz", "--memlimit", "1MiB", "file.txt"])
@@ -510,4 +727,88 @@ mod tests {
             .unwrap_or_else(|e| panic!("failed to build config: {e}"));
         assert_eq!(config.lzma2.as_deref(), Some("preset=0"));
     }
+
+    /// Test malformed `--lzma1`/`--lzma2` strings are rejected when building the config.
+    #[test]
+    fn config_rejects_invalid_lzma_options() {
+        let opts = XzOpts::try_parse_from(["xz", "--lzma2=pb=5", "file.txt"])
+            .unwrap_or_else(|e| panic!("failed to parse --lzma2: {e}"));
+        let err = opts.config().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Value of the option 'pb' must be in the range [0, 4]"
+        );
+
+        let opts = XzOpts::try_parse_from(["xz", "--format=lzma", "--lzma1=dict", "file.txt"])
+            .unwrap_or_else(|e| panic!("failed to parse --lzma1: {e}"));
+        let err = opts.config().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "dict: Options must be 'name=value' pairs separated with commas"
+        );
+    }
+
+    /// Test that `--robot` is refused outside `--list`, `--version` and `--info-memory`.
+    #[test]
+    fn config_rejects_robot_coding() {
+        let config = |args: &[&str]| {
+            XzOpts::try_parse_from(["xz", "--robot"].iter().chain(args))
+                .unwrap_or_else(|e| panic!("failed to parse {args:?}: {e}"))
+                .config()
+        };
+
+        for args in [&["file"][..], &["-d", "file.xz"], &["-t", "file.xz"]] {
+            assert_eq!(
+                config(args).unwrap_err().to_string(),
+                "Compression and decompression with --robot are not supported yet."
+            );
+        }
+        assert!(config(&["-l", "file.xz"]).is_ok());
+        assert!(config(&["--version"]).is_ok());
+        assert!(config(&["--info-memory"]).is_ok());
+    }
+
+    /// Test `--list-sizes` and `--list-precision` parsing.
+    #[test]
+    fn list_sizes_parses_style_and_precision() {
+        let sizes = |args: &[&str]| {
+            XzOpts::try_parse_from(["xz", "-l"].iter().chain(args))
+                .unwrap_or_else(|e| panic!("failed to parse {args:?}: {e}"))
+                .list_sizes()
+        };
+
+        assert_eq!(sizes(&[]).unwrap(), ListSizes::Upstream);
+        assert_eq!(sizes(&["--list-sizes=bytes"]).unwrap(), ListSizes::Bytes);
+        assert_eq!(
+            sizes(&["--list-sizes=human"]).unwrap(),
+            ListSizes::Human { precision: 1 }
+        );
+        assert_eq!(
+            sizes(&["--list-sizes=human", "--list-precision=3"]).unwrap(),
+            ListSizes::Human { precision: 3 }
+        );
+        assert!(sizes(&["--list-precision=2"]).is_err());
+        assert_eq!(
+            sizes(&["--list-sizes=huge"]).unwrap_err().to_string(),
+            "huge: Unknown size style"
+        );
+        assert!(XzOpts::try_parse_from(["xz", "--list-precision=7"]).is_err());
+    }
+
+    #[test]
+    fn later_options_override_environment_options() {
+        let args = env_options::merge_env_options(
+            &XzOpts::command(),
+            ["xz", "-6", "-z", "-T2", "file.txt"],
+            Some(std::ffi::OsStr::new("-9 -T4")),
+            Some(std::ffi::OsStr::new("-d")),
+        )
+        .unwrap_or_else(|e| panic!("failed to merge environment options: {e}"));
+        let opts = XzOpts::try_parse_from(args)
+            .unwrap_or_else(|e| panic!("failed to parse merged options: {e}"));
+
+        assert_eq!(opts.compression_level(), Some(6));
+        assert_eq!(opts.operation_mode(), OperationMode::Compress);
+        assert_eq!(opts.threads, Some(2));
+    }
 }
@@ -10,14 +10,19 @@ mod opts;
 
 use opts::XzCatOpts;
 
-use xz_cli::{format_diagnostic_for_stderr, run_cli};
+use xz_cli::{format_diagnostic_for_stderr, resolve_input_files, run_tool};
 
 const PROGRAM_NAME: &str = "xzcat";
 
 fn main() {
     let opts = XzCatOpts::parse();
     let config = opts.config();
-    let report = run_cli(opts.files(), &config, PROGRAM_NAME);
+    let Some(files) =
+        resolve_input_files(&opts.file_list, opts.files(), PROGRAM_NAME, config.quiet)
+    else {
+        return;
+    };
+    let report = run_tool(&files, &config, PROGRAM_NAME);
     for diagnostic in &report.diagnostics {
         if let Some(msg) = format_diagnostic_for_stderr(config.quiet, diagnostic) {
             eprintln!("{msg}");
@@ -4,7 +4,10 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-use xz_cli::{parse_memory_limit, CliConfig, OperationMode};
+use xz_cli::{
+    argfiles::FileListArgs, parse_memory_limit, CliConfig, ListSizes, LogArgs, OperationMode,
+    DEFAULT_BUFFER_SIZE,
+};
 
 /// XZ decompression and concatenation utility
 ///
@@ -23,14 +26,24 @@ pub struct XzCatOpts {
     #[arg(value_name = "FILE")]
     files: Vec<PathBuf>,
 
-    /// Verbose mode
-    #[arg(short = 'v', long = "verbose", conflicts_with = "quiet")]
-    verbose: bool,
+    #[command(flatten)]
+    pub file_list: FileListArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
+    /// Verbose mode. Use twice for even more detail.
+    #[arg(short = 'v', long = "verbose", conflicts_with = "quiet", action = clap::ArgAction::Count)]
+    verbose: u8,
 
     /// Quiet mode (suppress warnings). Use twice to suppress errors too.
     #[arg(short = 'q', long = "quiet", conflicts_with = "verbose", action = clap::ArgAction::Count)]
     quiet: u8,
 
+    /// Don't set the exit status to 2 when only warnings occurred.
+    #[arg(short = 'Q', long = "no-warn")]
+    no_warn: bool,
+
     /// Use at most this many threads
     #[arg(short = 'T', long = "threads", value_name = "NUM")]
     threads: Option<usize>,
@@ -48,6 +61,10 @@ pub struct XzCatOpts {
     /// Decompress only the first stream, ignore remaining input
     #[arg(long = "single-stream")]
     single_stream: bool,
+
+    /// Don't verify the integrity check when decompressing
+    #[arg(long = "ignore-check")]
+    ignore_check: bool,
 }
 
 impl XzCatOpts {
@@ -63,13 +80,15 @@ impl XzCatOpts {
             force: false,
             keep: true,
             stdout: true,
-            verbose: self.verbose,
+            verbose: self.verbose > 0,
+            very_verbose: self.verbose > 1,
             quiet: self.quiet,
-            no_warn: false,
+            no_warn: self.no_warn,
             level: None,
             threads: self.threads,
             compression_memory_limit: None,
             memory_limit: self.memory,
+            mt_memory_limit: self.memory,
             extreme: false,
             format: xz_core::config::DecodeMode::Auto,
             check: xz_core::options::IntegrityCheck::Crc64,
@@ -77,11 +96,20 @@ impl XzCatOpts {
             lzma2: None,
             filters: None,
             robot: false,
+            list_sizes: ListSizes::default(),
             suffix: None,
             single_stream: self.single_stream,
-            ignore_check: false,
+            ignore_check: self.ignore_check,
             no_adjust: false,
             sparse: false,
+            synchronous: false,
+            block_size: None,
+            block_list: None,
+            flush_timeout: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            mmap: false,
+            io_hints: true,
+            log_level: self.log.log_level,
         }
     }
 
@@ -99,11 +127,15 @@ mod tests {
     fn config_sets_cat_mode_and_stdout() {
         let opts = XzCatOpts {
             files: vec![PathBuf::from("input.xz")],
-            verbose: true,
+            file_list: FileListArgs::default(),
+            log: LogArgs::default(),
+            verbose: 1,
             quiet: 0,
+            no_warn: false,
             threads: Some(4),
             memory: Some(1024),
             single_stream: false,
+            ignore_check: false,
         };
 
         let config = opts.config();
@@ -121,7 +153,7 @@ mod tests {
             .unwrap();
 
         assert_eq!(opts.files(), [PathBuf::from("input.xz")]);
-        assert!(opts.verbose);
+        assert_eq!(opts.verbose, 1);
         assert_eq!(opts.threads, Some(2));
         assert_eq!(opts.memory, Some(512 * 1024));
     }
@@ -0,0 +1,275 @@
+//! In-process byte comparison of two (decompressed) input streams.
+//!
+//! Compressed operands are decompressed on worker threads and streamed into the comparison
+//! through bounded channels, so no temporary files are written.
+
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+
+use xz_cli::{decompress_file, has_compressed_content, open_input, CliConfig, OperationMode};
+
+/// Size of the chunks compared at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of decompressed chunks buffered between a worker thread and the comparison.
+const CHANNEL_DEPTH: usize = 4;
+
+/// Which operand a comparison result refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// FILE1.
+    First,
+    /// FILE2.
+    Second,
+}
+
+/// Result of comparing two streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// Both streams have identical contents.
+    Equal,
+    /// The streams differ at `byte` (1-based), which is on line `line` (1-based).
+    Differ {
+        /// Position of the first differing byte.
+        byte: u64,
+        /// Line containing the first differing byte.
+        line: u64,
+    },
+    /// One stream is a proper prefix of the other.
+    Eof {
+        /// The shorter stream.
+        shorter: Side,
+        /// Length of the shorter stream.
+        bytes: u64,
+        /// Number of newlines in the shorter stream.
+        newlines: u64,
+        /// Whether the shorter stream ends with a newline.
+        ends_with_newline: bool,
+    },
+}
+
+impl Comparison {
+    /// Exit status used by `cmp` for this result.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Comparison::Equal => 0,
+            Comparison::Differ { .. } | Comparison::Eof { .. } => 1,
+        }
+    }
+}
+
+/// Compare two streams byte by byte, stopping at the first difference.
+///
+/// # Errors
+///
+/// Returns an error if reading either stream fails.
+pub fn compare_streams(mut first: impl Read, mut second: impl Read) -> io::Result<Comparison> {
+    let mut buf1 = vec![0u8; CHUNK_SIZE];
+    let mut buf2 = vec![0u8; CHUNK_SIZE];
+    let mut offset = 0u64;
+    let mut newlines = 0u64;
+    let mut last = None;
+
+    loop {
+        let len1 = read_full(&mut first, &mut buf1)?;
+        let len2 = read_full(&mut second, &mut buf2)?;
+        let common = len1.min(len2);
+
+        if let Some(pos) = buf1[..common]
+            .iter()
+            .zip(&buf2[..common])
+            .position(|(a, b)| a != b)
+        {
+            let line = newlines + count_newlines(&buf1[..pos]) + 1;
+            return Ok(Comparison::Differ {
+                byte: offset + pos as u64 + 1,
+                line,
+            });
+        }
+
+        newlines += count_newlines(&buf1[..common]);
+        offset += common as u64;
+        if common > 0 {
+            last = Some(buf1[common - 1]);
+        }
+
+        if len1 != len2 {
+            let shorter = if len1 < len2 {
+                Side::First
+            } else {
+                Side::Second
+            };
+            return Ok(Comparison::Eof {
+                shorter,
+                bytes: offset,
+                newlines,
+                ends_with_newline: last == Some(b'\n'),
+            });
+        }
+        if len1 == 0 {
+            return Ok(Comparison::Equal);
+        }
+    }
+}
+
+/// Fill `buf` as far as possible, returning fewer bytes only at end of input.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+fn count_newlines(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|&&b| b == b'\n').count() as u64
+}
+
+/// Open `path` for comparison, decompressing it on a worker thread when needed.
+///
+/// Named files are decompressed when their magic bytes show a supported format, whatever
+/// their name; standard input (`-`) is decompressed when it looks compressed and passed
+/// through unchanged otherwise.
+///
+/// # Errors
+///
+/// Returns an error if a plain input file cannot be opened. Decompression errors are reported
+/// when the returned reader is read.
+pub fn open_for_compare(path: &Path) -> Result<Box<dyn Read>, String> {
+    let is_stdin = path == Path::new("-");
+    if !is_stdin && !has_compressed_content(path) {
+        return open_input(path).map_err(|err| format!("{}: {err}", path.display()));
+    }
+
+    let (sender, receiver) = mpsc::sync_channel(CHANNEL_DEPTH);
+    let path = path.to_path_buf();
+    thread::spawn(move || {
+        let config = CliConfig {
+            mode: OperationMode::Decompress,
+            stdout: true,
+            ..CliConfig::default()
+        };
+        let writer = ChannelWriter {
+            sender: sender.clone(),
+        };
+        let result =
+            open_input(&path).and_then(|input| decompress_file(input, writer, &config, is_stdin));
+        if let Err(err) = result {
+            // The receiver may already be gone if a difference was found.
+            let _ = sender.send(Err(format!("{}: {err}", path.display())));
+        }
+    });
+
+    Ok(Box::new(ChannelReader {
+        receiver,
+        chunk: Vec::new(),
+        pos: 0,
+    }))
+}
+
+/// Writer that forwards decompressed chunks to a [`ChannelReader`].
+struct ChannelWriter {
+    sender: SyncSender<Result<Vec<u8>, String>>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender
+            .send(Ok(buf.to_vec()))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reader over chunks produced by a decompression worker thread.
+struct ChannelReader {
+    receiver: Receiver<Result<Vec<u8>, String>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Ok(Err(message)) => return Err(io::Error::other(message)),
+                // The worker finished and dropped its sender: end of input.
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Identical inputs compare equal.
+    #[test]
+    fn compare_streams_equal() {
+        let result = compare_streams(&b"abc\ndef\n"[..], &b"abc\ndef\n"[..]).unwrap();
+        assert_eq!(result, Comparison::Equal);
+        assert_eq!(result.exit_code(), 0);
+    }
+
+    /// The first differing byte and its line are reported 1-based.
+    #[test]
+    fn compare_streams_reports_first_difference() {
+        let result = compare_streams(&b"abc\ndef\n"[..], &b"abc\ndXf\n"[..]).unwrap();
+        assert_eq!(result, Comparison::Differ { byte: 6, line: 2 });
+        assert_eq!(result.exit_code(), 1);
+    }
+
+    /// A proper prefix is reported as EOF on the shorter input.
+    #[test]
+    fn compare_streams_reports_eof_on_shorter() {
+        let result = compare_streams(&b"abc\ndef"[..], &b"abc\n"[..]).unwrap();
+        assert_eq!(
+            result,
+            Comparison::Eof {
+                shorter: Side::Second,
+                bytes: 4,
+                newlines: 1,
+                ends_with_newline: true,
+            }
+        );
+    }
+
+    /// Differences beyond the first chunk keep counting bytes and lines.
+    #[test]
+    fn compare_streams_across_chunks() {
+        let mut first = b"x\n".repeat(CHUNK_SIZE);
+        let second = first.clone();
+        let last = first.len() - 2;
+        first[last] = b'y';
+
+        let result = compare_streams(&first[..], &second[..]).unwrap();
+        assert_eq!(
+            result,
+            Comparison::Differ {
+                byte: last as u64 + 1,
+                line: CHUNK_SIZE as u64,
+            }
+        );
+    }
+}
@@ -13,12 +13,15 @@ use std::process::{self, Command, Stdio};
 
 use tempfile::NamedTempFile;
 
-use xz_cli::{decompress_file, has_compression_extension, open_input, CliConfig, OperationMode};
+use xz_cli::{decompress_file, has_compressed_content, open_input, CliConfig, OperationMode};
 
 const PROGRAM_NAME: &str = "xzcmp";
 
+mod compare;
 mod opts;
 
+use compare::{Comparison, Side};
+
 fn main() {
     match run() {
         Ok(code) => process::exit(code),
@@ -54,6 +57,12 @@ fn run() -> Result<i32, String> {
 
     let (file1, file2) = opts::resolve_operands(&parsed.operands)?;
 
+    if let Some(silent) = parsed.builtin_mode() {
+        return compare_builtin(&file1, &file2, silent);
+    }
+
+    // Options the built-in comparison does not understand are handled by the external
+    // `cmp`, which needs the decompressed contents as files.
     // Prepare (possibly decompressed) file paths for cmp.
     let config = CliConfig {
         mode: OperationMode::Decompress,
@@ -65,7 +74,12 @@ fn run() -> Result<i32, String> {
     let mut temps: Vec<NamedTempFile> = Vec::new();
 
     let path1 = materialize_for_cmp(&file1, &config, &mut temps)?;
-    let path2 = materialize_for_cmp(&file2, &config, &mut temps)?;
+    // Standard input can only be read once, so `-` twice compares it with itself.
+    let path2 = if file2 == Path::new("-") && file1 == file2 {
+        path1.clone()
+    } else {
+        materialize_for_cmp(&file2, &config, &mut temps)?
+    };
 
     let status = run_cmp(&parsed.cmp_program, &parsed.cmp_args, &path1, &path2)
         .map_err(|e| e.to_string())?;
@@ -73,6 +87,55 @@ fn run() -> Result<i32, String> {
     Ok(status.code().unwrap_or(2))
 }
 
+/// Compare `file1` and `file2` in-process, reporting like `cmp`.
+///
+/// # Returns
+///
+/// Returns `0` if the contents are identical and `1` if they differ.
+fn compare_builtin(file1: &Path, file2: &Path, silent: bool) -> Result<i32, String> {
+    if file1 == Path::new("-") && file2 == Path::new("-") {
+        return Ok(0);
+    }
+
+    let first = compare::open_for_compare(file1)?;
+    let second = compare::open_for_compare(file2)?;
+    let result = compare::compare_streams(first, second).map_err(|err| err.to_string())?;
+
+    if !silent {
+        match result {
+            Comparison::Equal => {}
+            Comparison::Differ { byte, line } => {
+                println!(
+                    "{} {} differ: char {byte}, line {line}",
+                    file1.display(),
+                    file2.display()
+                );
+            }
+            Comparison::Eof {
+                shorter,
+                bytes,
+                newlines,
+                ends_with_newline,
+            } => {
+                let name = match shorter {
+                    Side::First => file1.display(),
+                    Side::Second => file2.display(),
+                };
+                if bytes == 0 {
+                    eprintln!("{PROGRAM_NAME}: EOF on {name} which is empty");
+                } else if ends_with_newline {
+                    eprintln!("{PROGRAM_NAME}: EOF on {name} after byte {bytes}, line {newlines}");
+                } else {
+                    let line = newlines + 1;
+                    eprintln!("{PROGRAM_NAME}: EOF on {name} after byte {bytes}, in line {line}");
+                }
+            }
+        }
+    }
+
+    Ok(result.exit_code())
+}
+
 /// Print usage text to stdout.
 fn print_usage(cmp_program: &OsStr) {
     let cmp_display = cmp_program.to_string_lossy();
@@ -92,21 +155,17 @@ fn print_version() {
 
 /// Prepare a path suitable for `cmp`.
 ///
-/// If the input looks like a supported compressed file, it is decompressed into a temporary
-/// file and the temporary file path is returned. Otherwise the original path is returned.
+/// If the input starts like a supported compressed file, whatever its name, it is
+/// decompressed into a temporary file and the temporary file path is returned. Standard
+/// input (`-`) is always copied to a temporary file, decompressed if it looks compressed,
+/// as `cmp` can't read it after it was inspected. Otherwise the original path is returned.
 fn materialize_for_cmp(
     path: &Path,
     config: &CliConfig,
     temps: &mut Vec<NamedTempFile>,
 ) -> Result<PathBuf, String> {
-    if path == Path::new("-") {
-        // Supporting '-' is best-effort: allow it only for one side, and pass it to cmp.
-        // Decompression from stdin is supported by the decompressor, but cmp consumes stdin
-        // too; mixing both reliably is tricky, so we don't attempt it here.
-        return Ok(PathBuf::from("-"));
-    }
-
-    if !has_compression_extension(path) {
+    let is_stdin = path == Path::new("-");
+    if !is_stdin && !has_compressed_content(path) {
         return Ok(path.to_path_buf());
     }
 
@@ -114,12 +173,14 @@ fn materialize_for_cmp(
 
     let tmp = NamedTempFile::new().map_err(|e| e.to_string())?;
     {
-        // `xzcmp` always reads from named paths here; stdin is passed through
-        // directly to `cmp` without going through the decompressor.
-        let stdin_input = false;
+        // Like `xz -dcf`, standard input that isn't compressed is copied unchanged.
+        let config = CliConfig {
+            stdout: is_stdin,
+            ..config.clone()
+        };
 
         let mut out = File::create(tmp.path()).map_err(|e| e.to_string())?;
-        decompress_file(&mut input, &mut out, config, stdin_input).map_err(|e| e.to_string())?;
+        decompress_file(&mut input, &mut out, &config, is_stdin).map_err(|e| e.to_string())?;
     }
 
     let out_path = tmp.path().to_path_buf();
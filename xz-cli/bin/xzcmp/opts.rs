@@ -11,6 +11,8 @@ use xz_cli::has_compression_extension;
 pub struct ParsedArgs {
     /// `cmp` binary to execute (defaults to `cmp`, can be overridden via `CMP`).
     pub cmp_program: OsString,
+    /// Whether `cmp_program` was set through `CMP`.
+    pub custom_cmp: bool,
     /// Options forwarded to the underlying `cmp` invocation.
     pub cmp_args: Vec<OsString>,
     /// FILE1 [FILE2] operands as provided by the user.
@@ -21,12 +23,37 @@ pub struct ParsedArgs {
     pub show_version: bool,
 }
 
+impl ParsedArgs {
+    /// Whether the comparison can run in-process instead of through `cmp`.
+    ///
+    /// The built-in comparison is used unless `CMP` selects another program or options
+    /// other than `-s`/`--quiet`/`--silent` are given.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some(silent)` when the built-in comparison applies.
+    pub fn builtin_mode(&self) -> Option<bool> {
+        if self.custom_cmp {
+            return None;
+        }
+        let mut silent = false;
+        for arg in &self.cmp_args {
+            match arg.to_str() {
+                Some("-s" | "--quiet" | "--silent") => silent = true,
+                _ => return None,
+            }
+        }
+        Some(silent)
+    }
+}
+
 /// Parse `xzcmp` CLI arguments.
 ///
 /// This intentionally does *not* validate options: unknown flags are forwarded to `cmp`
 /// to match upstream behavior.
 pub fn parse_args(args: &[OsString]) -> ParsedArgs {
-    let cmp_program = env::var_os("CMP").unwrap_or_else(|| OsString::from("cmp"));
+    let custom_cmp = env::var_os("CMP");
+    let cmp_program = custom_cmp.clone().unwrap_or_else(|| OsString::from("cmp"));
 
     let mut cmp_args = Vec::new();
     let mut operands = Vec::new();
@@ -69,6 +96,7 @@ pub fn parse_args(args: &[OsString]) -> ParsedArgs {
 
     ParsedArgs {
         cmp_program,
+        custom_cmp: custom_cmp.is_some(),
         cmp_args,
         operands,
         show_help,
@@ -159,6 +187,24 @@ mod tests {
         assert!(parsed.operands == vec![PathBuf::from("a"), PathBuf::from("b")]);
     }
 
+    /// Only the silent option keeps the in-process comparison.
+    #[test]
+    fn builtin_mode_accepts_only_silent_options() {
+        let mut parsed = parse_args(&[OsString::from("a"), OsString::from("b")]);
+        parsed.custom_cmp = false;
+        assert_eq!(parsed.builtin_mode(), Some(false));
+
+        parsed.cmp_args = vec![OsString::from("--silent")];
+        assert_eq!(parsed.builtin_mode(), Some(true));
+
+        parsed.cmp_args = vec![OsString::from("-l")];
+        assert_eq!(parsed.builtin_mode(), None);
+
+        parsed.cmp_args.clear();
+        parsed.custom_cmp = true;
+        assert_eq!(parsed.builtin_mode(), None);
+    }
+
     /// `.xz` suffix should be stripped when inferring the second operand.
     #[test]
     fn infer_second_operand_strips_xz_extension() {
@@ -4,7 +4,12 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
-use xz_cli::{parse_memory_limit, CliConfig, OperationMode};
+use xz_cli::{
+    argfiles::FileListArgs, parse_memory_limit, CliConfig, ListSizes, LogArgs, OperationMode,
+};
+
+/// Buffer size for reading the input and writing the output, like upstream's `BUFSIZ`
+const BUFFER_SIZE: usize = 8 * 1024;
 
 /// Small .xz decompressor
 ///
@@ -26,6 +31,12 @@ pub struct XzDecOpts {
     #[arg(value_name = "FILE")]
     files: Vec<PathBuf>,
 
+    #[command(flatten)]
+    pub file_list: FileListArgs,
+
+    #[command(flatten)]
+    log: LogArgs,
+
     /// Ignored for xz(1) compatibility. xzdec supports only decompression.
     #[arg(short = 'd', long = "decompress", alias = "uncompress")]
     decompress: bool,
@@ -41,8 +52,8 @@ pub struct XzDecOpts {
     /// Memory usage limit for decompression
     #[arg(
         short = 'M',
-        long = "memory",
-        alias = "memlimit",
+        long = "memlimit",
+        visible_aliases = ["memory", "memlimit-decompress"],
         value_name = "LIMIT",
         value_parser = parse_memory_limit
     )]
@@ -71,25 +82,38 @@ impl XzDecOpts {
             keep: true,
             stdout: true,
             verbose: false,
+            very_verbose: false,
             quiet: self.quiet,
             no_warn: true,
             level: None,
-            threads: None,
+            // The single-threaded decoder keeps xzdec small and predictable.
+            threads: Some(1),
             compression_memory_limit: None,
             memory_limit: self.memory,
+            mt_memory_limit: None,
             extreme: false,
-            format: xz_core::config::DecodeMode::Auto,
+            // Like upstream, only `.xz` is supported; `.lzma` needs lzmadec.
+            format: xz_core::config::DecodeMode::Xz,
             check: xz_core::options::IntegrityCheck::Crc64,
             lzma1: None,
             lzma2: None,
             filters: None,
             robot: false,
+            list_sizes: ListSizes::default(),
             suffix: None,
             single_stream: false,
             ignore_check: false,
             no_adjust: false,
             // Always writes to stdout; sparse output is not applicable.
             sparse: false,
+            synchronous: false,
+            block_size: None,
+            block_list: None,
+            flush_timeout: None,
+            buffer_size: BUFFER_SIZE,
+            mmap: false,
+            io_hints: true,
+            log_level: self.log.log_level,
         }
     }
 
@@ -114,6 +138,8 @@ mod tests {
     fn config_sets_cat_mode_and_stdout() {
         let opts = XzDecOpts {
             files: vec![PathBuf::from("input.xz")],
+            file_list: FileListArgs::default(),
+            log: LogArgs::default(),
             decompress: false,
             keep: false,
             stdout: false,
@@ -130,6 +156,38 @@ mod tests {
         assert_eq!(config.memory_limit, Some(1024));
     }
 
+    /// Test that only `.xz` is decoded, single-threaded and with small buffers
+    #[test]
+    fn config_uses_lean_xz_decoder() {
+        let opts = XzDecOpts::try_parse_from(["xzdec", "input.xz"]).unwrap();
+
+        let config = opts.config();
+        assert_eq!(config.format, xz_core::config::DecodeMode::Xz);
+        assert_eq!(config.threads, Some(1));
+        assert_eq!(config.mt_memory_limit, None);
+        assert_eq!(config.buffer_size, BUFFER_SIZE);
+    }
+
+    /// Test that compression options are rejected
+    #[test]
+    fn compression_options_are_rejected() {
+        for flag in [
+            "-z",
+            "--compress",
+            "-9",
+            "-e",
+            "-T2",
+            "--format=lzma",
+            "-t",
+            "-l",
+        ] {
+            assert!(
+                XzDecOpts::try_parse_from(["xzdec", flag, "input.xz"]).is_err(),
+                "{flag} should be rejected"
+            );
+        }
+    }
+
     /// Test memory limit parsing
     #[test]
     fn parse_from_args_reads_memory_limit() {
@@ -12,7 +12,7 @@ use std::process::{self, Command, Stdio};
 
 use tempfile::NamedTempFile;
 
-use xz_cli::{decompress_file, has_compression_extension, open_input, CliConfig, OperationMode};
+use xz_cli::{decompress_file, has_compressed_content, open_input, CliConfig, OperationMode};
 
 const PROGRAM_NAME: &str = "xzdiff";
 
@@ -64,7 +64,12 @@ fn run() -> Result<i32, String> {
     let mut temps: Vec<NamedTempFile> = Vec::new();
 
     let path1 = materialize_for_diff(&file1, &config, &mut temps)?;
-    let path2 = materialize_for_diff(&file2, &config, &mut temps)?;
+    // Standard input can only be read once, so `-` twice compares it with itself.
+    let path2 = if file2 == Path::new("-") && file1 == file2 {
+        path1.clone()
+    } else {
+        materialize_for_diff(&file2, &config, &mut temps)?
+    };
 
     let status = run_diff(&parsed.diff_program, &parsed.diff_args, &path1, &path2)
         .map_err(|e| e.to_string())?;
@@ -91,21 +96,17 @@ fn print_version() {
 
 /// Prepare a path suitable for `diff`.
 ///
-/// If the input looks like a supported compressed file, it is decompressed into a temporary
-/// file and the temporary file path is returned. Otherwise the original path is returned.
+/// If the input starts like a supported compressed file, whatever its name, it is
+/// decompressed into a temporary file and the temporary file path is returned. Standard
+/// input (`-`) is always copied to a temporary file, decompressed if it looks compressed,
+/// as `diff` can't read it after it was inspected. Otherwise the original path is returned.
 fn materialize_for_diff(
     path: &Path,
     config: &CliConfig,
     temps: &mut Vec<NamedTempFile>,
 ) -> Result<PathBuf, String> {
-    if path == Path::new("-") {
-        // Supporting '-' is best-effort: allow it only for one side, and pass it to diff.
-        // Decompression from stdin is supported by the decompressor, but diff consumes stdin
-        // too; mixing both reliably is tricky, so we don't attempt it here.
-        return Ok(PathBuf::from("-"));
-    }
-
-    if !has_compression_extension(path) {
+    let is_stdin = path == Path::new("-");
+    if !is_stdin && !has_compressed_content(path) {
         return Ok(path.to_path_buf());
     }
 
@@ -113,12 +114,14 @@ fn materialize_for_diff(
 
     let tmp = NamedTempFile::new().map_err(|e| e.to_string())?;
     {
-        // `xzdiff` always reads from named paths here; stdin is passed through
-        // directly to `diff` without going through the decompressor.
-        let stdin_input = false;
+        // Like `xz -dcf`, standard input that isn't compressed is copied unchanged.
+        let config = CliConfig {
+            stdout: is_stdin,
+            ..config.clone()
+        };
 
         let mut out = File::create(tmp.path()).map_err(|e| e.to_string())?;
-        decompress_file(&mut input, &mut out, config, stdin_input).map_err(|e| e.to_string())?;
+        decompress_file(&mut input, &mut out, &config, is_stdin).map_err(|e| e.to_string())?;
     }
 
     let out_path = tmp.path().to_path_buf();
@@ -0,0 +1,11 @@
+//! XZ-compressed file grep utility in fixed-string (`grep -F`) mode
+//!
+//! Shares the `xzgrep` implementation, which selects the matching mode from the
+//! name it was invoked as, like the upstream script family.
+
+#[path = "../xzgrep/main.rs"]
+mod xzgrep;
+
+fn main() {
+    xzgrep::main();
+}
@@ -20,16 +20,30 @@ const PROGRAM_NAME: &str = "xzgrep";
 
 mod opts;
 
-fn main() {
+pub(crate) fn main() {
     match run() {
         Ok(code) => process::exit(code),
         Err(err) => {
-            eprintln!("{PROGRAM_NAME}: {err}");
+            eprintln!("{}: {err}", program_name());
             process::exit(2);
         }
     }
 }
 
+/// Returns the name the program was invoked as, like upstream's `${0##*/}`, so
+/// `xzegrep` and `xzfgrep` report errors under their own name.
+fn program_name() -> String {
+    env::args_os()
+        .next()
+        .as_deref()
+        .map(Path::new)
+        .and_then(Path::file_name)
+        .map_or_else(
+            || PROGRAM_NAME.to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+}
+
 /// Execute the `xzgrep` command.
 ///
 /// # Returns
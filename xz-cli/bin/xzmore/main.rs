@@ -6,6 +6,7 @@
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::fs::File;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process::{self, Command, Stdio};
 
@@ -73,16 +74,57 @@ fn run() -> Result<i32, String> {
         pager_inputs.push(path);
     }
 
-    let mut cmd = Command::new(&parsed.pager_program);
-    cmd.args(&parsed.pager_args);
-    cmd.arg("--");
-    cmd.args(&pager_inputs);
-    cmd.stdin(Stdio::inherit());
-    cmd.stdout(Stdio::inherit());
-    cmd.stderr(Stdio::inherit());
+    run_pager(&parsed, &pager_inputs)
+}
+
+/// Run the pager on `inputs` and return its exit code.
+///
+/// A `PAGER` that doesn't exist falls back to [`opts::DEFAULT_PAGER`], and without any
+/// pager the inputs are written to stdout. The pager inherits the environment, so it
+/// reads its own settings such as `LESS` or `MORE` as usual.
+fn run_pager(parsed: &opts::ParsedArgs, inputs: &[PathBuf]) -> Result<i32, String> {
+    let mut candidates = vec![(
+        parsed.pager_program.as_os_str(),
+        parsed.pager_env_args.as_slice(),
+    )];
+    if parsed.pager_program != OsStr::new(opts::DEFAULT_PAGER) {
+        candidates.push((OsStr::new(opts::DEFAULT_PAGER), &[]));
+    }
 
-    let status = cmd.status().map_err(|err| err.to_string())?;
-    Ok(status.code().unwrap_or(2))
+    for (program, env_args) in candidates {
+        let mut cmd = Command::new(program);
+        cmd.args(env_args);
+        cmd.args(&parsed.pager_args);
+        cmd.arg("--");
+        cmd.args(inputs);
+        cmd.stdin(Stdio::inherit());
+        cmd.stdout(Stdio::inherit());
+        cmd.stderr(Stdio::inherit());
+
+        match cmd.status() {
+            Ok(status) => return Ok(status.code().unwrap_or(2)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(format!("{}: {err}", program.to_string_lossy())),
+        }
+    }
+
+    print_inputs(inputs)
+}
+
+/// Write `inputs` to stdout, for when no pager is available.
+fn print_inputs(inputs: &[PathBuf]) -> Result<i32, String> {
+    let mut stdout = io::stdout().lock();
+    for input in inputs {
+        let mut reader = open_input(input).map_err(|err| err.to_string())?;
+        match io::copy(&mut reader, &mut stdout) {
+            Ok(_) => {}
+            // The reader of the output went away, like a pager that was quit.
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(0),
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+    stdout.flush().map_err(|err| err.to_string())?;
+    Ok(0)
 }
 
 /// Print usage text to stdout.
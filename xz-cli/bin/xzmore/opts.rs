@@ -4,12 +4,17 @@ use std::env;
 use std::ffi::{OsStr, OsString};
 use std::path::PathBuf;
 
+/// Pager used when `PAGER` is unset or empty, or names a program that doesn't exist.
+pub const DEFAULT_PAGER: &str = "more";
+
 /// Parsed command-line arguments for `xzmore`.
 #[derive(Debug, Clone)]
 pub struct ParsedArgs {
     /// Pager binary to execute (defaults to `more`, can be overridden via `PAGER`).
     pub pager_program: OsString,
-    /// Options forwarded to the underlying pager invocation.
+    /// Options that followed the program name in `PAGER`, e.g. `-R` in `less -R`.
+    pub pager_env_args: Vec<OsString>,
+    /// Options forwarded to the underlying pager invocation, including `+cmd` ones.
     pub pager_args: Vec<OsString>,
     /// Input file operands as provided by the user.
     pub files: Vec<PathBuf>,
@@ -22,9 +27,10 @@ pub struct ParsedArgs {
 /// Parse `xzmore` CLI arguments.
 ///
 /// This intentionally does *not* validate options: unknown flags are forwarded
-/// to the pager to match the behavior of wrapper tools like `xzgrep`.
+/// to the pager to match the behavior of wrapper tools like `xzgrep`. Like options,
+/// `+cmd` arguments such as `+/pattern` are pager commands rather than files.
 pub fn parse_args(args: &[OsString]) -> ParsedArgs {
-    let pager_program = env::var_os("PAGER").unwrap_or_else(|| OsString::from("more"));
+    let (pager_program, pager_env_args) = split_pager(env::var_os("PAGER"));
 
     let mut pager_args = Vec::new();
     let mut files = Vec::new();
@@ -52,7 +58,7 @@ pub fn parse_args(args: &[OsString]) -> ParsedArgs {
         }
 
         // Stop option parsing at the first non-option, but treat "-" as a file operand.
-        if text.starts_with('-') && arg != OsStr::new("-") {
+        if (text.starts_with('-') && arg != OsStr::new("-")) || text.starts_with('+') {
             pager_args.push(arg);
             it.next();
             continue;
@@ -67,6 +73,7 @@ pub fn parse_args(args: &[OsString]) -> ParsedArgs {
 
     ParsedArgs {
         pager_program,
+        pager_env_args,
         pager_args,
         files,
         show_help,
@@ -74,6 +81,19 @@ pub fn parse_args(args: &[OsString]) -> ParsedArgs {
     }
 }
 
+/// Splits the value of `PAGER` into the program and its options.
+///
+/// Like the upstream scripts, which `eval` the variable, the value may carry options
+/// separated by whitespace. An unset or blank value selects [`DEFAULT_PAGER`].
+fn split_pager(value: Option<OsString>) -> (OsString, Vec<OsString>) {
+    let value = value.map(|value| value.to_string_lossy().into_owned());
+    let mut words = value.iter().flat_map(|value| value.split_whitespace());
+    match words.next() {
+        Some(program) => (program.into(), words.map(OsString::from).collect()),
+        None => (DEFAULT_PAGER.into(), Vec::new()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,4 +122,25 @@ mod tests {
         assert!(parsed.pager_args == vec![OsString::from("-F")]);
         assert!(parsed.files == vec![PathBuf::from("a.xz"), PathBuf::from("b.txt")]);
     }
+
+    /// An initial `+cmd` command goes to the pager like an option.
+    #[test]
+    fn parse_args_forwards_plus_commands() {
+        let args = vec![OsString::from("+/needle"), OsString::from("a.xz")];
+        let parsed = parse_args(&args);
+
+        assert!(parsed.pager_args == vec![OsString::from("+/needle")]);
+        assert!(parsed.files == vec![PathBuf::from("a.xz")]);
+    }
+
+    /// `PAGER` may carry options, and a blank value selects the default pager.
+    #[test]
+    fn split_pager_handles_options_and_blank_values() {
+        let (program, args) = split_pager(Some(OsString::from(" less  -R -S ")));
+        assert_eq!(program, OsString::from("less"));
+        assert_eq!(args, vec![OsString::from("-R"), OsString::from("-S")]);
+
+        assert_eq!(split_pager(Some(OsString::from("  "))).0, DEFAULT_PAGER);
+        assert_eq!(split_pager(None).0, DEFAULT_PAGER);
+    }
 }
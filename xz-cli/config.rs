@@ -1,8 +1,12 @@
 //! Configuration types and constants for XZ CLI operations.
 
+use std::time::Duration;
+
 use xz_core::config::DecodeMode;
 use xz_core::options::IntegrityCheck;
 
+use crate::logging::LogLevel;
+
 /// Default buffer size for file I/O operations
 pub const DEFAULT_BUFFER_SIZE: usize = 512 * 1024;
 
@@ -12,6 +16,9 @@ pub const XZ_EXTENSION: &str = "xz";
 /// File extension for LZMA compressed files
 pub const LZMA_EXTENSION: &str = "lzma";
 
+/// File extension for lzip compressed files
+pub const LZIP_EXTENSION: &str = "lz";
+
 /// Represents different modes of operation for CLI utilities
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OperationMode {
@@ -25,6 +32,33 @@ pub enum OperationMode {
     List,
     /// Test integrity without extracting
     Test,
+    /// Decompress compressed files and compress them again in place
+    Recompress,
+}
+
+impl OperationMode {
+    /// Returns `true` if the output is compressed data.
+    ///
+    /// Ratios then compare the output to the input; for [`OperationMode::Recompress`]
+    /// that is the new size relative to the old one.
+    pub fn is_compressing(self) -> bool {
+        matches!(self, OperationMode::Compress | OperationMode::Recompress)
+    }
+}
+
+/// How `--list` prints byte counts; robot output always uses plain bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListSizes {
+    /// Like upstream `xz`: bytes up to 9999, then `KiB` to `TiB` with one decimal
+    #[default]
+    Upstream,
+    /// The largest binary unit that keeps the value at 1 or above, with `precision` decimals
+    Human {
+        /// Number of decimals printed after scaling
+        precision: u8,
+    },
+    /// Exact byte counts with thousands separators
+    Bytes,
 }
 
 /// Configuration for CLI operations
@@ -41,9 +75,11 @@ pub struct CliConfig {
     pub stdout: bool,
     /// Verbose output
     pub verbose: bool,
+    /// Extra verbose output (`-vv`)
+    pub very_verbose: bool,
     /// Quiet mode level (0 = normal, 1 = suppress warnings, 2+ = suppress errors too)
     pub quiet: u8,
-    /// Suppress warnings and don't let them affect the exit status.
+    /// Don't let warnings affect the exit status; they are still printed.
     pub no_warn: bool,
     /// Compression level (0-9)
     pub level: Option<u32>,
@@ -53,6 +89,8 @@ pub struct CliConfig {
     pub compression_memory_limit: Option<u64>,
     /// Memory limit for decompression
     pub memory_limit: Option<u64>,
+    /// Memory limit that reduces the thread count of multi-threaded decompression
+    pub mt_memory_limit: Option<u64>,
     /// Use extreme compression
     pub extreme: bool,
     /// File format to use
@@ -65,8 +103,10 @@ pub struct CliConfig {
     pub lzma2: Option<String>,
     /// Optional filter chain string (from `--filters=CHAIN`)
     pub filters: Option<String>,
-    /// Machine-readable output
+    /// Machine-readable output for `--list`, `--version` and `--info-memory`
     pub robot: bool,
+    /// How `--list` prints byte counts
+    pub list_sizes: ListSizes,
     /// Custom suffix for compressed files
     pub suffix: Option<String>,
     /// Decompress only the first stream
@@ -77,6 +117,22 @@ pub struct CliConfig {
     pub no_adjust: bool,
     /// Enable sparse output when decompressing to a regular file
     pub sparse: bool,
+    /// Flush output files to the storage device before removing the input
+    pub synchronous: bool,
+    /// Maximum uncompressed size of a Block in `.xz` output (from `--block-size`)
+    pub block_size: Option<u64>,
+    /// Uncompressed sizes of the Blocks in `.xz` output (from `--block-list`)
+    pub block_list: Option<Vec<u64>>,
+    /// Flush the output when compressing stdin and no input arrives for this long
+    pub flush_timeout: Option<Duration>,
+    /// Capacity of the buffers used to read the input and write the output
+    pub buffer_size: usize,
+    /// Map regular input files into memory instead of reading them
+    pub mmap: bool,
+    /// Tell the kernel how input files are read so they don't fill the page cache
+    pub io_hints: bool,
+    /// Level of the structured event log on stderr, or `None` to disable it
+    pub log_level: Option<LogLevel>,
 }
 
 impl Default for CliConfig {
@@ -87,12 +143,14 @@ impl Default for CliConfig {
             keep: false,
             stdout: false,
             verbose: false,
+            very_verbose: false,
             quiet: 0,
             no_warn: false,
             level: None,
             threads: None,
             compression_memory_limit: None,
             memory_limit: None,
+            mt_memory_limit: None,
             extreme: false,
             format: DecodeMode::Auto,
             check: IntegrityCheck::Crc64,
@@ -100,11 +158,20 @@ impl Default for CliConfig {
             lzma2: None,
             filters: None,
             robot: false,
+            list_sizes: ListSizes::Upstream,
             suffix: None,
             single_stream: false,
             ignore_check: false,
             no_adjust: false,
             sparse: true,
+            synchronous: false,
+            block_size: None,
+            block_list: None,
+            flush_timeout: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            mmap: false,
+            io_hints: true,
+            log_level: None,
         }
     }
 }
@@ -4,6 +4,9 @@ use std::io;
 use std::path::{Path, PathBuf};
 
 use thiserror::Error;
+use xz_core::options::FilterType;
+
+use crate::i18n::tr;
 
 /// Formats `std::io::Error` similar to `strerror(3)` output, without the trailing
 /// `"(os error N)"` suffix that Rust includes by default.
@@ -61,6 +64,7 @@ pub enum Severity {
 /// - `0`: success (no warnings/errors)
 /// - `1`: at least one real error occurred
 /// - `2`: only warnings occurred (no real errors)
+/// - `128 + signal`: interrupted by a signal
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ExitStatus {
     /// No warnings or errors.
@@ -70,6 +74,8 @@ pub enum ExitStatus {
     Warning,
     /// One or more real errors (takes precedence over warnings).
     Error,
+    /// Interrupted by the given signal (takes precedence over everything else).
+    Signal(i32),
 }
 
 impl ExitStatus {
@@ -79,6 +85,7 @@ impl ExitStatus {
             ExitStatus::Ok => 0,
             ExitStatus::Error => 1,
             ExitStatus::Warning => 2,
+            ExitStatus::Signal(signal) => 128 + signal,
         }
     }
 
@@ -112,6 +119,15 @@ impl Report {
         self.status.observe_cli_error(&cause);
         self.diagnostics.push(Diagnostic::new(cause, program, file));
     }
+
+    /// Records a note about the settings, which like upstream doesn't affect the status.
+    pub fn note(&mut self, warning: Warning, program: &str) {
+        self.diagnostics.push(Diagnostic::new(
+            DiagnosticCause::Warning(warning),
+            program,
+            None,
+        ));
+    }
 }
 
 /// A structured CLI diagnostic that preserves the underlying failure and context.
@@ -124,6 +140,9 @@ pub struct Diagnostic {
     pub program: String,
     /// Input file path, or `None` for stdin.
     pub file: Option<PathBuf>,
+    /// Whether the diagnostic is about the invocation rather than any input, so that no
+    /// file is named.
+    pub general: bool,
     /// Underlying diagnostic cause produced by processing.
     pub cause: DiagnosticCause,
 }
@@ -145,20 +164,101 @@ impl Diagnostic {
         Self {
             program: program.to_string(),
             file: file.map(PathBuf::from),
+            general: false,
             cause,
         }
     }
+
+    /// Creates a [`Diagnostic`] about the invocation itself, such as a bad option or an
+    /// unreadable list of files, which names no file.
+    ///
+    /// # Parameters
+    ///
+    /// * `cause` - The underlying diagnostic cause to wrap
+    /// * `program` - Program name to include in error messages
+    pub fn general(cause: DiagnosticCause, program: &str) -> Self {
+        Self {
+            general: true,
+            ..Self::new(cause, program, None)
+        }
+    }
 }
 
 impl std::fmt::Display for Diagnostic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.file.as_deref() {
-            Some(file) => write!(f, "{}: {}: {}", self.program, file.display(), self.cause),
-            None => write!(f, "{}: (stdin): {}", self.program, self.cause),
+        // Notes about the settings aren't about any input, so no file is named.
+        if self.cause.as_warning().is_some_and(Warning::is_note) {
+            return write!(f, "{}: {}", self.program, self.cause);
         }
+        // Output errors are about the output, so they name it instead of the input.
+        let subject = match self.cause.as_error() {
+            Some(Error::WriteOutput { output, .. }) => output_name(output.as_deref()),
+            Some(Error::CreateOutput { path, .. } | Error::OutputExists { path }) => {
+                path.display().to_string()
+            }
+            // Checked before any input is opened, with the amount needed on a second line.
+            Some(Error::MemoryLimitTooLow { required, limit }) => {
+                return write!(
+                    f,
+                    "{program}: {}\n{program}: {}",
+                    self.cause,
+                    memory_needed(*required, *limit),
+                    program = self.program,
+                );
+            }
+            // Refused before any input is opened, so no file is named, like upstream.
+            Some(Error::CompressedOutputToTerminal | Error::CompressedInputFromTerminal) => {
+                return write!(f, "{}: {}", self.program, self.cause);
+            }
+            _ if self.general => return write!(f, "{}: {}", self.program, self.cause),
+            _ => input_name(self.file.as_deref()),
+        };
+        write!(f, "{}: {subject}: {}", self.program, self.cause)
     }
 }
 
+/// Line stating how much memory the settings need and what the limit is, in MiB rounded
+/// up, or in bytes for a limit below 1 MiB.
+fn memory_needed(required: u64, limit: u64) -> String {
+    const MIB: u64 = 1024 * 1024;
+    let limit = if limit < MIB {
+        format!("{limit} B")
+    } else {
+        format!("{} MiB", limit.div_ceil(MIB))
+    };
+    tr!(
+        "{required} MiB of memory is required. The limit is {limit}.",
+        required = required.div_ceil(MIB),
+        limit = limit,
+    )
+    .to_string()
+}
+
+/// Name of an input in messages: its path, or `(stdin)` for no path, an empty one or `-`.
+pub(crate) fn input_name(path: Option<&Path>) -> String {
+    path.filter(|path| !path.as_os_str().is_empty() && *path != Path::new("-"))
+        .map_or_else(
+            || tr!("(stdin)").to_string(),
+            |path| path.display().to_string(),
+        )
+}
+
+/// Name of an output in messages: its path, or `(stdout)`.
+pub(crate) fn output_name(path: Option<&Path>) -> String {
+    path.map_or_else(
+        || tr!("(stdout)").to_string(),
+        |path| path.display().to_string(),
+    )
+}
+
+/// Error for a failed write to stdout.
+pub(crate) fn stdout_write_error(source: io::Error) -> DiagnosticCause {
+    DiagnosticCause::from(Error::WriteOutput {
+        output: None,
+        source: IoErrorNoCode::new(source),
+    })
+}
+
 impl std::error::Error for Diagnostic {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         Some(&self.cause)
@@ -191,14 +291,14 @@ pub fn format_diagnostic_for_stderr(quiet: u8, diagnostic: &Diagnostic) -> Optio
 #[derive(Debug, Error, Clone, PartialEq, Eq)]
 pub enum Warning {
     /// Input file lacks recognized compression extension
-    #[error("Filename has an unknown suffix, skipping")]
+    #[error("{}", tr!("Filename has an unknown suffix, skipping"))]
     InvalidExtension {
         /// Path to the input file
         path: PathBuf,
     },
 
     /// Input file already has the target suffix
-    #[error("Already has `{}` suffix, skipping", suffix)]
+    #[error("{}", tr!("File already has '{suffix}' suffix, skipping", suffix = suffix))]
     AlreadyHasSuffix {
         /// Path to the input file
         path: PathBuf,
@@ -206,12 +306,163 @@ pub enum Warning {
         suffix: String,
     },
 
+    /// Input is a directory
+    #[error("{}", tr!("Is a directory, skipping"))]
+    Directory,
+
+    /// Input is a symbolic link and the output would go to a file
+    #[error("{}", tr!("Is a symbolic link, skipping"))]
+    SymbolicLink,
+
+    /// Input is not a regular file and the output would go to a file
+    #[error("{}", tr!("Not a regular file, skipping"))]
+    NotRegularFile,
+
+    /// Input has the setuid or setgid bit set
+    #[error("{}", tr!("File has setuid or setgid bit set, skipping"))]
+    SetuidOrSetgid,
+
+    /// Input has the sticky bit set
+    #[error("{}", tr!("File has sticky bit set, skipping"))]
+    StickyBit,
+
+    /// Input has more than one hard link
+    #[error("{}", tr!("Input file has more than one hard link, skipping"))]
+    HardLinks,
+
     /// The input uses an integrity check type that isn't supported by the linked liblzma.
-    #[error("Unsupported type of integrity check (Check ID {check_id})")]
+    #[error(
+        "{}",
+        tr!("Unsupported type of integrity check (Check ID {check_id})", check_id = check_id)
+    )]
     UnsupportedCheck {
         /// Integrity check ID from the XZ Stream Header.
         check_id: u32,
     },
+
+    /// `--threads` asked for more threads than there are processor threads.
+    #[error(
+        "{}",
+        tr!(
+            "Reduced the number of threads from {requested} to {maximum} \
+             (the number of available processor threads)",
+            requested = requested,
+            maximum = maximum,
+        )
+    )]
+    ThreadsReduced {
+        /// Thread count given on the command line.
+        requested: usize,
+        /// Number of processor threads used instead.
+        maximum: u32,
+    },
+
+    /// The threaded decoder won't start all `--threads` within `--memlimit-mt-decompress`.
+    #[error(
+        "{}",
+        tr!(
+            "Reduced the number of threads from {requested} to {reduced} to not exceed \
+             the memory usage limit of {limit} MiB",
+            requested = requested,
+            reduced = reduced,
+            limit = limit.div_ceil(1024 * 1024),
+        )
+    )]
+    DecoderThreadsReduced {
+        /// Threads the input could use.
+        requested: u32,
+        /// Threads that fit into the limit.
+        reduced: u32,
+        /// Multi-threaded decompression limit in bytes.
+        limit: u64,
+    },
+
+    /// The encoder won't start all `--threads` within `--memlimit-compress`.
+    #[error(
+        "{}",
+        tr!(
+            "Reduced the number of threads from {requested} to {reduced} to not exceed \
+             the memory usage limit of {limit} MiB",
+            requested = requested,
+            reduced = reduced,
+            limit = limit.div_ceil(1024 * 1024),
+        )
+    )]
+    EncoderThreadsReduced {
+        /// Threads requested, after clamping to the processor threads.
+        requested: u32,
+        /// Threads that fit into the limit.
+        reduced: u32,
+        /// Compression memory limit in bytes.
+        limit: u64,
+    },
+
+    /// Multithreaded compression exceeds `--memlimit-compress`, so a single thread is used.
+    #[error(
+        "{}",
+        tr!(
+            "Switching to single-threaded mode to not exceed the memory usage limit of \
+             {limit} MiB",
+            limit = limit.div_ceil(1024 * 1024),
+        )
+    )]
+    SingleThreadedMode {
+        /// Compression memory limit in bytes.
+        limit: u64,
+    },
+
+    /// Even one encoder thread exceeds `--memlimit-compress`, so the dictionary shrinks.
+    #[error(
+        "{}",
+        tr!(
+            "Adjusted {filter} dictionary size from {original} MiB to {reduced} MiB to not \
+             exceed the memory usage limit of {limit} MiB",
+            filter = if *filter == FilterType::Lzma1 { "LZMA1" } else { "LZMA2" },
+            original = original / (1024 * 1024),
+            reduced = reduced / (1024 * 1024),
+            limit = limit.div_ceil(1024 * 1024),
+        )
+    )]
+    DictionaryReduced {
+        /// Filter whose dictionary shrank, LZMA1 or LZMA2.
+        filter: FilterType,
+        /// Dictionary size of the preset or filter chain, in bytes.
+        original: u32,
+        /// Dictionary size used instead, in bytes.
+        reduced: u32,
+        /// Compression memory limit in bytes.
+        limit: u64,
+    },
+
+    /// `--ignore-check` disables the integrity check verification.
+    #[error(
+        "--ignore-check: {}",
+        tr!("Integrity checks are not verified; corrupt data may go undetected")
+    )]
+    IntegrityCheckIgnored,
+
+    /// The ownership or permissions of the input couldn't be copied to the output file.
+    #[error("{message}")]
+    CopyMetadata {
+        /// Description of the attribute that couldn't be set and why
+        message: String,
+    },
+}
+
+impl Warning {
+    /// Returns `true` for the notes about adjusted or risky settings, which are reported
+    /// once per run rather than for an input and don't affect the exit status.
+    pub fn is_note(&self) -> bool {
+        matches!(
+            self,
+            Warning::ThreadsReduced { .. }
+                | Warning::DecoderThreadsReduced { .. }
+                | Warning::EncoderThreadsReduced { .. }
+                | Warning::SingleThreadedMode { .. }
+                | Warning::DictionaryReduced { .. }
+                | Warning::IntegrityCheckIgnored
+        )
+    }
 }
 
 /// Main error type for XZ CLI operations.
@@ -225,8 +476,18 @@ pub enum Error {
         source: IoErrorNoCode,
     },
 
-    /// Failed to create output file
+    /// Failed to read the list of files given with `--files` or `--files0`
     #[error("{}: {source}", path.display())]
+    ReadFileList {
+        /// Path of the list
+        path: PathBuf,
+        /// Underlying I/O error
+        #[source]
+        source: IoErrorNoCode,
+    },
+
+    /// Failed to create output file
+    #[error("{source}")]
     CreateOutput {
         /// Path to the output file
         path: PathBuf,
@@ -236,14 +497,14 @@ pub enum Error {
     },
 
     /// Output file already exists
-    #[error("{}: Output file already exists", path.display())]
+    #[error("{}", tr!("File exists"))]
     OutputExists {
         /// Path to the existing file
         path: PathBuf,
     },
 
     /// Cannot determine output filename
-    #[error("Cannot determine output filename")]
+    #[error("{}", tr!("Cannot determine output filename"))]
     InvalidOutputFilename {
         /// Path to the input file
         path: PathBuf,
@@ -256,6 +517,16 @@ pub enum Error {
         message: String,
     },
 
+    /// The compression settings need more memory than `--memlimit-compress` allows and
+    /// `--no-adjust` forbids adapting them.
+    #[error("{}", tr!("Memory usage limit is too low for the given filter setup."))]
+    MemoryLimitTooLow {
+        /// Memory needed by the encoder, in bytes.
+        required: u64,
+        /// Compression memory limit in bytes.
+        limit: u64,
+    },
+
     /// Decompression operation failed
     #[error("{message}")]
     Decompression {
@@ -264,7 +535,7 @@ pub enum Error {
     },
 
     /// Invalid compression level
-    #[error("Unsupported preset: {level}")]
+    #[error("{}", tr!("Unsupported preset: {level}", level = level))]
     InvalidCompressionLevel {
         /// The invalid level value
         level: u32,
@@ -277,15 +548,8 @@ pub enum Error {
         message: String,
     },
 
-    /// Thread count too large
-    #[error("The number of threads must not exceed {}", u32::MAX)]
-    InvalidThreadCount {
-        /// The invalid thread count
-        count: usize,
-    },
-
     /// Failed to remove input file
-    #[error("Cannot remove: {source}")]
+    #[error("{}: {source}", tr!("Cannot remove"))]
     RemoveFile {
         /// Underlying I/O error
         #[source]
@@ -293,11 +557,15 @@ pub enum Error {
     },
 
     /// Invalid memory limit format
-    #[error("Invalid memory limit: {0}")]
+    #[error("{}: {_0}", tr!("Invalid memory limit"))]
     InvalidMemoryLimit(String),
 
+    /// Invalid `--block-list` value
+    #[error("{}: {_0}", tr!("Invalid block list"))]
+    InvalidBlockList(String),
+
     /// Failed to extract file information
-    #[error("File format not recognized ({message})")]
+    #[error("{message}")]
     FileInfoExtraction {
         /// Path to the file
         path: String,
@@ -306,12 +574,26 @@ pub enum Error {
     },
 
     /// List mode does not support reading from stdin.
-    #[error("--list does not support reading from standard input")]
+    #[error("{}", tr!("--list does not support reading from standard input"))]
     ListModeStdinUnsupported,
 
-    /// Failed to write to stdout/stderr.
-    #[error("{source}")]
+    /// Compressed output would go to a terminal without `--force`.
+    #[error("{}", tr!("Compressed data cannot be written to a terminal"))]
+    CompressedOutputToTerminal,
+
+    /// Compressed input would be read from a terminal without `--force`.
+    #[error("{}", tr!("Compressed data cannot be read from a terminal"))]
+    CompressedInputFromTerminal,
+
+    /// Standard output is the file the input is read from.
+    #[error("{}", tr!("Input and output are the same file"))]
+    SameInputAndOutput,
+
+    /// Failed to write the output.
+    #[error("{}", tr!("Write error: {source}", source = source))]
     WriteOutput {
+        /// Output file, or `None` for stdout.
+        output: Option<PathBuf>,
         /// Underlying I/O error.
         #[source]
         source: IoErrorNoCode,
@@ -0,0 +1,83 @@
+//! Callbacks for tools that embed the CLI operations instead of spawning a subprocess.
+
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+
+use crate::error::Diagnostic;
+
+/// Something that happened while processing, passed to a [`Callback`].
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// More of the current input was read.
+    Progress {
+        /// Bytes read from the input so far
+        bytes_read: u64,
+        /// Bytes written to the output so far
+        bytes_written: u64,
+    },
+    /// A diagnostic was recorded for an input, or for the run as a whole.
+    Diagnostic(&'a Diagnostic),
+}
+
+/// Callback that receives the [`Event`]s of an operation as they happen.
+pub type Callback<'a> = dyn FnMut(Event<'_>) + 'a;
+
+/// Wraps `input` and `output` so that every read reports the byte counts to `callback`.
+pub(crate) fn track<'a, R: Read, W: Write>(
+    input: R,
+    output: W,
+    callback: &'a mut Callback<'_>,
+) -> (ReportingReader<'a, R>, ReportingWriter<W>) {
+    let bytes_written = Rc::new(Cell::new(0));
+    let reader = ReportingReader {
+        inner: input,
+        callback,
+        bytes_read: 0,
+        bytes_written: Rc::clone(&bytes_written),
+    };
+    let writer = ReportingWriter {
+        inner: output,
+        bytes_written,
+    };
+    (reader, writer)
+}
+
+/// Reader that reports [`Event::Progress`] after each read.
+pub(crate) struct ReportingReader<'a, R> {
+    inner: R,
+    callback: &'a mut Callback<'a>,
+    bytes_read: u64,
+    bytes_written: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for ReportingReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes_read += read as u64;
+        (self.callback)(Event::Progress {
+            bytes_read: self.bytes_read,
+            bytes_written: self.bytes_written.get(),
+        });
+        Ok(read)
+    }
+}
+
+/// Writer that counts the bytes written for [`ReportingReader`].
+pub(crate) struct ReportingWriter<W> {
+    inner: W,
+    bytes_written: Rc<Cell<u64>>,
+}
+
+impl<W: Write> Write for ReportingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes_written
+            .set(self.bytes_written.get() + written as u64);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,241 @@
+//! Formatting helpers for `xz --info-memory` and `xz --version`.
+
+use std::io::{self, Write};
+
+use xz_core::{Hardware, Version};
+
+use crate::config::CliConfig;
+use crate::error::{stdout_write_error, Result};
+use crate::format::list::format_memusage;
+
+/// Upstream XZ Utils release whose command line this implementation follows (5.8.1), in
+/// liblzma's `MAJOR * 10000000 + MINOR * 10000 + PATCH * 10 + STABILITY` encoding.
+const XZ_COMPAT_VERSION: u32 = 50_080_012;
+
+/// Linked liblzma reported by `xz --version`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BackendInfo {
+    /// Version string of the linked library.
+    pub version: String,
+    /// Whether the library was built from the bundled sources.
+    pub vendored: bool,
+    /// Whether the library supports multi-threaded coding.
+    pub threads: bool,
+}
+
+impl BackendInfo {
+    /// Describe the liblzma this binary is running with.
+    pub(crate) fn current() -> Self {
+        Self {
+            version: Version.to_string(),
+            vendored: Version::is_vendored(),
+            threads: Version::has_threads(),
+        }
+    }
+}
+
+/// Memory usage limits reported by `xz --info-memory`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MemoryInfo {
+    /// Total physical memory in bytes (`0` if unknown).
+    pub physical_memory: u64,
+    /// Number of processor threads.
+    pub threads: u32,
+    /// Compression limit, `None` when disabled.
+    pub compression: Option<u64>,
+    /// Decompression limit, `None` when disabled.
+    pub decompression: Option<u64>,
+    /// Limit used to reduce the number of threads when decompressing.
+    pub mt_decompression: u64,
+    /// Default memory limit for `-T0`.
+    pub mt_default: u64,
+}
+
+impl MemoryInfo {
+    /// Collect the memory limits in effect for `config`.
+    ///
+    /// # Parameters
+    ///
+    /// * `config` - CLI configuration with the memory usage limits
+    ///
+    /// # Returns
+    ///
+    /// The hardware information and limits to report.
+    pub(crate) fn new(config: &CliConfig) -> Self {
+        let physical_memory = Hardware::physical_memory();
+        // Like upstream, a quarter of the RAM is a safe default for threaded decoding.
+        let mt_default = physical_memory / 4;
+        let mt_decompression = config.mt_memory_limit.unwrap_or(mt_default);
+
+        Self {
+            physical_memory,
+            threads: Hardware::cpu_threads().max(1),
+            compression: config.compression_memory_limit,
+            decompression: config.memory_limit,
+            mt_decompression: config
+                .memory_limit
+                .map_or(mt_decompression, |limit| limit.min(mt_decompression)),
+            mt_default,
+        }
+    }
+}
+
+/// Format a byte count as `N MiB (N B)`, rounding the MiB value up.
+fn format_limit(bytes: u64) -> String {
+    format!("{} ({bytes} B)", format_memusage(bytes))
+}
+
+/// Write `xz --info-memory` output.
+///
+/// The robot format is a single tab-separated line with the physical memory, compression
+/// limit, decompression limit, multi-threaded decompression limit, `-T0` default limit and
+/// thread count; disabled limits are printed as `0`.
+///
+/// # Parameters
+///
+/// * `out` - Destination for the report
+/// * `info` - Hardware information and limits to print
+/// * `robot` - Whether to use the machine-readable format
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if writing fails.
+pub(crate) fn write_info_memory(out: &mut impl Write, info: MemoryInfo, robot: bool) -> Result<()> {
+    if robot {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            info.physical_memory,
+            info.compression.unwrap_or(0),
+            info.decompression.unwrap_or(0),
+            info.mt_decompression,
+            info.mt_default,
+            info.threads
+        )
+        .map_err(stdout_write_error)?;
+        return Ok(());
+    }
+
+    let limit = |value: Option<u64>| value.map_or_else(|| "Disabled".to_string(), format_limit);
+    writeln!(out, "Hardware information:").map_err(stdout_write_error)?;
+    writeln!(
+        out,
+        "  Amount of physical memory (RAM):  {}",
+        format_limit(info.physical_memory)
+    )
+    .map_err(stdout_write_error)?;
+    writeln!(out, "  Number of processor threads:      {}", info.threads)
+        .map_err(stdout_write_error)?;
+    writeln!(out).map_err(stdout_write_error)?;
+    writeln!(out, "Memory usage limits:").map_err(stdout_write_error)?;
+    writeln!(
+        out,
+        "  Compression:                      {}",
+        limit(info.compression)
+    )
+    .map_err(stdout_write_error)?;
+    writeln!(
+        out,
+        "  Decompression:                    {}",
+        limit(info.decompression)
+    )
+    .map_err(stdout_write_error)?;
+    writeln!(
+        out,
+        "  Multi-threaded decompression:     {}",
+        format_limit(info.mt_decompression)
+    )
+    .map_err(stdout_write_error)?;
+    writeln!(
+        out,
+        "  Default for -T0:                  {}",
+        format_limit(info.mt_default)
+    )
+    .map_err(stdout_write_error)?;
+    Ok(())
+}
+
+/// Print `xz --info-memory` output to stdout.
+///
+/// # Parameters
+///
+/// * `config` - CLI configuration with the memory limits and robot flag
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if writing to stdout fails.
+pub fn print_info_memory(config: &CliConfig) -> Result<()> {
+    let info = MemoryInfo::new(config);
+    write_info_memory(&mut io::stdout().lock(), info, config.robot)
+}
+
+/// Write `xz --version` output.
+///
+/// The first line names the tool, the second the linked liblzma with where it comes from
+/// and whether it supports threading, e.g. `liblzma 5.8.1 (vendored, multi-threaded)`.
+///
+/// # Parameters
+///
+/// * `out` - Destination for the report
+/// * `program` - Tool name
+/// * `version` - Tool version
+/// * `backend` - Linked liblzma to describe
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if writing fails.
+pub(crate) fn write_version(
+    out: &mut impl Write,
+    program: &str,
+    version: &str,
+    backend: &BackendInfo,
+) -> Result<()> {
+    let source = if backend.vendored {
+        "vendored"
+    } else {
+        "system"
+    };
+    let threads = if backend.threads {
+        "multi-threaded"
+    } else {
+        "single-threaded"
+    };
+    writeln!(out, "{program} {version}").map_err(stdout_write_error)?;
+    writeln!(out, "liblzma {} ({source}, {threads})", backend.version)
+        .map_err(stdout_write_error)?;
+    Ok(())
+}
+
+/// Print `xz --version` output to stdout.
+///
+/// # Parameters
+///
+/// * `program` - Tool name
+/// * `version` - Tool version
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if writing to stdout fails.
+pub fn print_version(program: &str, version: &str) -> Result<()> {
+    write_version(
+        &mut io::stdout().lock(),
+        program,
+        version,
+        &BackendInfo::current(),
+    )
+}
+
+/// Print `xz --robot --version` output to stdout.
+///
+/// Prints `XZ_VERSION=` with the upstream release this command line follows and
+/// `LIBLZMA_VERSION=` with the linked liblzma, both as encoded version numbers.
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if writing to stdout fails.
+pub fn print_robot_version() -> Result<()> {
+    let mut out = io::stdout().lock();
+    writeln!(out, "XZ_VERSION={XZ_COMPAT_VERSION}").map_err(stdout_write_error)?;
+    writeln!(out, "LIBLZMA_VERSION={}", Version::number()).map_err(stdout_write_error)?;
+    Ok(())
+}
@@ -1,11 +1,29 @@
 //! Formatting helpers for `xz -l` / `xz -l -v`.
 
+use std::fmt::Display;
 use std::io;
 use std::path::Path;
 
-use crate::error::{DiagnosticCause, Error, IoErrorNoCode, Result};
+use crate::config::ListSizes;
+use crate::error::{stdout_write_error, Result};
+use crate::i18n::tr;
 use crate::utils::{bytes, math};
-use xz_core::file_info::{BlockInfo, StreamInfo};
+use xz_core::file_info::{BlockDetails, BlockInfo, StreamInfo};
+use xz_core::options::IntegrityCheck;
+
+/// Encoded XZ Utils 5.0.0, the first stable release and the baseline for every `.xz` file.
+const XZ_VERSION_5_0_0: u32 = 50_000_002;
+/// Encoded XZ Utils 5.4.0, which added the ARM64 filter.
+const XZ_VERSION_5_4_0: u32 = 50_040_002;
+/// Encoded XZ Utils 5.6.0, which added the RISC-V filter.
+const XZ_VERSION_5_6_0: u32 = 50_060_002;
+
+/// Filter ID of the ARM64 BCJ filter.
+const FILTER_ARM64: u64 = 0x0A;
+/// Filter ID of the RISC-V BCJ filter.
+const FILTER_RISCV: u64 = 0x0B;
+
+const MIB: u64 = 1024 * 1024;
 
 /// Output context for `xz -l` formatting across multiple files.
 #[derive(Debug, Clone, Copy)]
@@ -19,7 +37,7 @@ pub(crate) struct ListOutputContext {
 }
 
 /// Summary information for one `xz -l` entry.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy)]
 pub(crate) struct ListSummary {
     /// Number of streams in the file.
     pub stream_count: u64,
@@ -31,47 +49,206 @@ pub(crate) struct ListSummary {
     pub uncompressed: u64,
     /// Bitmask of integrity checks used across all streams.
     pub checks_mask: u32,
+    /// Total Stream Padding in bytes.
+    pub padding: u64,
+    /// Memory needed to decompress, in bytes (`-vv` only).
+    pub memusage: u64,
+    /// Whether every Block Header stores both sizes (`-vv` only).
+    pub all_have_sizes: bool,
+    /// Minimum XZ Utils version that can decompress the file, in liblzma's
+    /// `MAJOR * 10000000 + MINOR * 10000 + PATCH * 10 + STABILITY` encoding (`-vv` only).
+    pub min_version: u32,
 }
 
-/// Convert an XZ "index checks" bitmask into a human-readable check name.
-///
-/// Returns "None" for no checks, "Mixed" for multiple different checks,
-/// or the specific check name (CRC32, CRC64, SHA256) for a single check type.
+impl Default for ListSummary {
+    fn default() -> Self {
+        Self {
+            stream_count: 0,
+            block_count: 0,
+            compressed: 0,
+            uncompressed: 0,
+            checks_mask: 0,
+            padding: 0,
+            memusage: 0,
+            all_have_sizes: true,
+            min_version: XZ_VERSION_5_0_0,
+        }
+    }
+}
+
+impl ListSummary {
+    /// Add the values of another file's summary to these totals.
+    pub(crate) fn accumulate(&mut self, other: ListSummary) {
+        self.stream_count += other.stream_count;
+        self.block_count += other.block_count;
+        self.compressed += other.compressed;
+        self.uncompressed += other.uncompressed;
+        self.checks_mask |= other.checks_mask;
+        self.padding += other.padding;
+        self.memusage = self.memusage.max(other.memusage);
+        self.all_have_sizes &= other.all_have_sizes;
+        self.min_version = self.min_version.max(other.min_version);
+    }
+
+    /// Fold the Block Header details of one file into this summary.
+    pub(crate) fn add_block_details(&mut self, details: &[BlockDetails]) {
+        for detail in details {
+            self.memusage = self.memusage.max(detail.header.memusage);
+            self.all_have_sizes &= detail.header.compressed_size.is_some()
+                && detail.header.uncompressed_size.is_some();
+            self.min_version = self
+                .min_version
+                .max(block_min_version(&detail.header.filter_ids));
+        }
+    }
+}
+
+/// Minimum XZ Utils version able to decode a Block using the given filter chain.
 ///
 /// # Parameters
 ///
-/// * `checks_mask` - Bitmask where each bit represents a check type
+/// * `filter_ids` - Filter IDs from the Block Header
 ///
 /// # Returns
 ///
-/// A static string slice with the check name.
-pub(crate) fn format_check_name(checks_mask: u32) -> &'static str {
-    if checks_mask == 0 {
-        return "None";
+/// The encoded version number (see [`ListSummary::min_version`]).
+pub(crate) fn block_min_version(filter_ids: &[u64]) -> u32 {
+    filter_ids
+        .iter()
+        .map(|&id| match id {
+            FILTER_ARM64 => XZ_VERSION_5_4_0,
+            FILTER_RISCV => XZ_VERSION_5_6_0,
+            _ => XZ_VERSION_5_0_0,
+        })
+        .max()
+        .unwrap_or(XZ_VERSION_5_0_0)
+}
+
+/// Format an encoded XZ Utils version number as `MAJOR.MINOR.PATCH`.
+pub(crate) fn format_version(version: u32) -> String {
+    format!(
+        "{}.{}.{}",
+        version / 10_000_000,
+        version / 10_000 % 1000,
+        version / 10 % 1000
+    )
+}
+
+/// Format a memory amount rounded up to whole MiB, as printed by upstream `xz -lvv`.
+pub(crate) fn format_memusage(bytes: u64) -> String {
+    format!("{} MiB", bytes.div_ceil(MIB))
+}
+
+/// Format a stored check value the way upstream `xz -lvv` shows it.
+///
+/// CRC32 and CRC64 are stored little endian and shown as numbers; SHA-256 is shown as
+/// the digest bytes in stored order.
+fn format_check_value(detail: &BlockDetails) -> String {
+    if detail.check_value.is_empty() {
+        return "---".to_string();
     }
 
-    if !checks_mask.is_power_of_two() {
-        return "Mixed";
+    let hex = |b: &u8| format!("{b:02x}");
+    match detail.check {
+        IntegrityCheck::Crc32 | IntegrityCheck::Crc64 => {
+            detail.check_value.iter().rev().map(hex).collect()
+        }
+        IntegrityCheck::None | IntegrityCheck::Sha256 => {
+            detail.check_value.iter().map(hex).collect()
+        }
+    }
+}
+
+/// Upstream's Block Header size flags: `c` and `u` when the header stores the compressed
+/// and uncompressed size respectively, `-` otherwise.
+fn format_size_flags(detail: &BlockDetails) -> String {
+    let compressed = if detail.header.compressed_size.is_some() {
+        'c'
+    } else {
+        '-'
+    };
+    let uncompressed = if detail.header.uncompressed_size.is_some() {
+        'u'
+    } else {
+        '-'
+    };
+    format!("{compressed}{uncompressed}")
+}
+
+/// Pair every block with the stream containing it, using the per-stream block counts.
+fn block_streams<'a>(
+    streams: &'a [StreamInfo],
+    blocks: &[BlockInfo],
+) -> Vec<Option<&'a StreamInfo>> {
+    let mut stream_idx: usize = 0;
+    let mut remaining_in_stream: u64 = streams.get(stream_idx).map_or(0, |s| s.block_count);
+
+    blocks
+        .iter()
+        .map(|_| {
+            while remaining_in_stream == 0 && stream_idx + 1 < streams.len() {
+                stream_idx += 1;
+                remaining_in_stream = streams[stream_idx].block_count;
+            }
+            remaining_in_stream = remaining_in_stream.saturating_sub(1);
+            streams.get(stream_idx)
+        })
+        .collect()
+}
+
+/// Name of a single stream's integrity check.
+pub(crate) fn stream_check_name(check: Option<IntegrityCheck>) -> &'static str {
+    match check {
+        Some(IntegrityCheck::None) => "None",
+        Some(IntegrityCheck::Crc32) => "CRC32",
+        Some(IntegrityCheck::Crc64) => "CRC64",
+        Some(IntegrityCheck::Sha256) => "SHA-256",
+        None => "Unknown",
     }
+}
 
-    match checks_mask.trailing_zeros() {
-        0 => "None",
-        1 => "CRC32",
-        4 => "CRC64",
-        10 => "SHA256",
-        _ => "Unknown",
+/// Name of the integrity check with the given check ID, as printed by upstream xz.
+fn check_id_name(id: u32) -> String {
+    match id {
+        0 => "None".to_string(),
+        1 => "CRC32".to_string(),
+        4 => "CRC64".to_string(),
+        10 => "SHA-256".to_string(),
+        _ => format!("Unknown-{id}"),
     }
 }
 
+/// Convert an XZ "index checks" bitmask into a human-readable list of check names.
+///
+/// Every check present in the mask is listed in check ID order, separated by a comma
+/// (followed by a space in verbose output), matching upstream `xz -l`.
+///
+/// # Parameters
+///
+/// * `checks_mask` - Bitmask where each bit represents a check type
+/// * `space_after_comma` - Whether to separate names with `", "` instead of `","`
+///
+/// # Returns
+///
+/// The check names, or "None" for an empty mask.
+pub(crate) fn format_check_names(checks_mask: u32, space_after_comma: bool) -> String {
+    if checks_mask == 0 {
+        return check_id_name(0);
+    }
+
+    let separator = if space_after_comma { ", " } else { "," };
+    (0..u32::BITS)
+        .filter(|id| checks_mask & (1 << id) != 0)
+        .map(check_id_name)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
 fn write_stdout_line(line: &str) -> Result<()> {
     use std::io::Write;
 
     let mut out = io::stdout().lock();
-    writeln!(out, "{line}").map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
+    writeln!(out, "{line}").map_err(stdout_write_error)?;
     Ok(())
 }
 
@@ -84,26 +261,31 @@ fn write_stdout_line(line: &str) -> Result<()> {
 ///
 /// * `totals` - Accumulated summary across all processed files
 /// * `file_count` - Total number of files processed
+/// * `sizes` - How to print the byte counts
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` on success, or an error if writing to stdout fails.
-pub(crate) fn print_list_totals(totals: ListSummary, file_count: usize) -> Result<()> {
+pub(crate) fn print_list_totals(
+    totals: ListSummary,
+    file_count: usize,
+    sizes: ListSizes,
+) -> Result<()> {
     // This matches the separator line printed by upstream `xz -l` for multiple files.
     write_stdout_line(
         "-------------------------------------------------------------------------------",
     )?;
 
-    let ratio = math::ratio_fraction(totals.compressed, totals.uncompressed);
-    let check = format_check_name(totals.checks_mask);
-    let label = format!("{file_count} files");
+    let ratio = math::format_list_ratio(totals.compressed, totals.uncompressed);
+    let check = format_check_names(totals.checks_mask, false);
+    let label = tr!("{count} files", count = file_count);
 
     write_stdout_line(&format!(
-        "{:>5} {:>7} {:>12} {:>12} {:>6.3}  {:<5}   {}",
+        "{:>5} {:>7} {:>12} {:>12} {:>6}  {:<7} {}",
         totals.stream_count,
         totals.block_count,
-        bytes::format_list_size(totals.compressed),
-        bytes::format_list_size(totals.uncompressed),
+        bytes::format_size(totals.compressed, sizes),
+        bytes::format_size(totals.uncompressed, sizes),
         ratio,
         check,
         label
@@ -134,13 +316,10 @@ pub(crate) fn write_list_header_if_needed(ctx: ListOutputContext) -> Result<()>
     let mut out = io::stdout().lock();
     writeln!(
         out,
-        "Strms  Blocks   Compressed Uncompressed  Ratio  Check   Filename"
+        "{}",
+        tr!("Strms  Blocks   Compressed Uncompressed  Ratio  Check   Filename")
     )
-    .map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
+    .map_err(stdout_write_error)?;
     Ok(())
 }
 
@@ -152,40 +331,105 @@ pub(crate) fn write_list_header_if_needed(ctx: ListOutputContext) -> Result<()>
 ///
 /// * `summary` - File summary information to display
 /// * `input_path` - Path to the file being listed
+/// * `sizes` - How to print the byte counts
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` on success, or an error if writing to stdout fails.
-pub(crate) fn write_list_row(summary: ListSummary, input_path: &Path) -> Result<()> {
+pub(crate) fn write_list_row(
+    summary: ListSummary,
+    input_path: &Path,
+    sizes: ListSizes,
+) -> Result<()> {
     use std::io::Write;
 
-    let ratio = math::ratio_fraction(summary.compressed, summary.uncompressed);
-    let check = format_check_name(summary.checks_mask);
+    let ratio = math::format_list_ratio(summary.compressed, summary.uncompressed);
+    let check = format_check_names(summary.checks_mask, false);
 
     let mut out = io::stdout().lock();
     writeln!(
         out,
-        "{:>5} {:>7} {:>12} {:>12} {:>6.3}  {:<5}   {}",
+        "{:>5} {:>7} {:>12} {:>12} {:>6}  {:<7} {}",
         summary.stream_count,
         summary.block_count,
-        bytes::format_list_size(summary.compressed),
-        bytes::format_list_size(summary.uncompressed),
+        bytes::format_size(summary.compressed, sizes),
+        bytes::format_size(summary.uncompressed, sizes),
         ratio,
         check,
         input_path.display()
     )
-    .map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
+    .map_err(stdout_write_error)?;
+    Ok(())
+}
+
+/// Write the summary lines shared by per-file verbose reports and the totals section.
+fn write_verbose_summary(
+    out: &mut impl io::Write,
+    summary: ListSummary,
+    sizes: ListSizes,
+) -> Result<()> {
+    let ratio = math::format_list_ratio(summary.compressed, summary.uncompressed);
+    let check = format_check_names(summary.checks_mask, true);
+
+    write_field(out, tr!("Streams:"), summary.stream_count)?;
+    write_field(out, tr!("Blocks:"), summary.block_count)?;
+    write_field(
+        out,
+        tr!("Compressed size:"),
+        bytes::format_size_with_bytes(summary.compressed, sizes),
+    )?;
+    write_field(
+        out,
+        tr!("Uncompressed size:"),
+        bytes::format_size_with_bytes(summary.uncompressed, sizes),
+    )?;
+    write_field(out, tr!("Ratio:"), ratio)?;
+    write_field(out, tr!("Check:"), check)?;
+    write_field(
+        out,
+        tr!("Stream Padding:"),
+        bytes::format_size(summary.padding, sizes),
+    )?;
+    Ok(())
+}
+
+/// Write the `-vv` memory, header size and version lines.
+fn write_requirements(out: &mut impl io::Write, summary: ListSummary) -> Result<()> {
+    write_field(
+        out,
+        tr!("Memory needed:"),
+        format_memusage(summary.memusage),
+    )?;
+    write_field(
+        out,
+        tr!("Sizes in headers:"),
+        if summary.all_have_sizes {
+            tr!("Yes")
+        } else {
+            tr!("No")
+        },
+    )?;
+    writeln!(
+        out,
+        "  {} {}",
+        tr!("Minimum XZ Utils version:"),
+        format_version(summary.min_version)
+    )
+    .map_err(stdout_write_error)?;
     Ok(())
 }
 
+/// Write an indented `label value` line of the verbose reports, aligning the values.
+fn write_field(out: &mut impl io::Write, label: &str, value: impl Display) -> Result<()> {
+    writeln!(out, "  {label:<18} {value}").map_err(stdout_write_error)
+}
+
 /// Write verbose output for `xz -l -v` mode.
 ///
 /// Prints detailed information about the file, including per-stream and per-block
-/// tables. The output format matches upstream `xz -l -v`.
+/// tables. The output format matches upstream `xz -l -v`; when Block Header `details`
+/// are given (`xz -l -vv`) the block table gains check values, header sizes, size flags,
+/// memory usage and filter chains, followed by the memory and version requirements.
 ///
 /// # Parameters
 ///
@@ -194,25 +438,27 @@ pub(crate) fn write_list_row(summary: ListSummary, input_path: &Path) -> Result<
 /// * `summary` - Overall file summary
 /// * `streams` - Per-stream information to display
 /// * `blocks` - Per-block information to display (should be sorted by `number_in_file`)
+/// * `details` - Per-block header details in the same order as `blocks` (`-vv` only)
+/// * `sizes` - How to print the byte counts
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` on success, or an error if writing to stdout fails.
-#[allow(clippy::too_many_lines)]
 pub(crate) fn write_verbose_report(
     input_path: &Path,
     ctx: ListOutputContext,
     summary: ListSummary,
     streams: &[StreamInfo],
     blocks: &[BlockInfo],
+    details: Option<&[BlockDetails]>,
+    sizes: ListSizes,
 ) -> Result<()> {
     use std::io::Write;
 
-    let ratio = math::ratio_fraction(summary.compressed, summary.uncompressed);
-    let check = format_check_name(summary.checks_mask);
-    let padding_total: u64 = streams.iter().map(|s| s.padding).sum();
-
     let mut out = io::stdout().lock();
+    if ctx.file_index > 1 {
+        writeln!(out).map_err(stdout_write_error)?;
+    }
     writeln!(
         out,
         "{} ({}/{})",
@@ -220,144 +466,293 @@ pub(crate) fn write_verbose_report(
         ctx.file_index,
         ctx.file_count
     )
-    .map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
-    writeln!(out, "  Streams:           {}", summary.stream_count).map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
-    writeln!(out, "  Blocks:            {}", summary.block_count).map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
-    writeln!(
-        out,
-        "  Compressed size:   {}",
-        bytes::format_list_size_with_bytes(summary.compressed)
-    )
-    .map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
-    writeln!(
-        out,
-        "  Uncompressed size: {}",
-        bytes::format_list_size_with_bytes(summary.uncompressed)
-    )
-    .map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
-    writeln!(out, "  Ratio:             {ratio:.3}").map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
-    writeln!(out, "  Check:             {check}").map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
+    .map_err(stdout_write_error)?;
+    write_verbose_summary(&mut out, summary, sizes)?;
+    let count = |value: u64| bytes::format_count(value, sizes);
+
+    writeln!(out, "  {}", tr!("Streams:")).map_err(stdout_write_error)?;
     writeln!(
         out,
-        "  Stream Padding:    {}",
-        bytes::format_list_size(padding_total)
+        "{}",
+        tr!("    Stream    Blocks      CompOffset    UncompOffset        CompSize      UncompSize  Ratio  Check      Padding")
     )
-    .map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
+    .map_err(stdout_write_error)?;
 
-    writeln!(out, "  Streams:").map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
+    for stream in streams {
+        let stream_ratio =
+            math::format_list_ratio(stream.compressed_size, stream.uncompressed_size);
+        writeln!(
+            out,
+            "{:>10} {:>9} {:>15} {:>15} {:>15} {:>15}  {:>5}  {:<10} {:>7}",
+            stream.number,
+            stream.block_count,
+            count(stream.compressed_offset),
+            count(stream.uncompressed_offset),
+            count(stream.compressed_size),
+            count(stream.uncompressed_size),
+            stream_ratio,
+            stream_check_name(stream.check),
+            count(stream.padding)
+        )
+        .map_err(stdout_write_error)?;
+    }
+
+    if !blocks.is_empty() {
+        // The check value column is as wide as the longest check value in the file.
+        let check_width = details
+            .and_then(|details| details.iter().map(|d| d.check_value.len() * 2).max())
+            .unwrap_or(0)
+            .max("CheckVal".len());
+
+        writeln!(out, "  {}", tr!("Blocks:")).map_err(stdout_write_error)?;
+        write!(
+            out,
+            "{}",
+            tr!("    Stream     Block      CompOffset    UncompOffset       TotalSize      UncompSize  Ratio  Check")
+        )
+        .map_err(stdout_write_error)?;
+        if details.is_some() {
+            write!(
+                out,
+                "      {:<check_width$}  Header  Flags        CompSize    MemUsage  Filters",
+                "CheckVal"
+            )
+            .map_err(stdout_write_error)?;
+        }
+        writeln!(out).map_err(stdout_write_error)?;
+
+        for (i, (block, stream)) in blocks
+            .iter()
+            .zip(block_streams(streams, blocks))
+            .enumerate()
+        {
+            let block_ratio = math::format_list_ratio(block.total_size, block.uncompressed_size);
+            let check = stream_check_name(stream.and_then(|s| s.check));
+            write!(
+                out,
+                "{:>10} {:>9} {:>15} {:>15} {:>15} {:>15}  {:>5}  ",
+                stream.map_or(0, |s| s.number),
+                block.number_in_stream,
+                count(block.compressed_file_offset),
+                count(block.uncompressed_file_offset),
+                count(block.total_size),
+                count(block.uncompressed_size),
+                block_ratio,
+            )
+            .map_err(stdout_write_error)?;
+
+            match details.and_then(|details| details.get(i)) {
+                Some(detail) => {
+                    let check_value = format_check_value(detail);
+                    let flags = format_size_flags(detail);
+                    writeln!(
+                        out,
+                        "{check:<10} {check_value:<check_width$}  {:>6}  {flags:<5} {:>15} {:>11}  {}",
+                        detail.header.header_size,
+                        count(detail.compressed_size),
+                        format_memusage(detail.header.memusage),
+                        detail.header.filters
+                    )
+                    .map_err(stdout_write_error)?;
+                }
+                None => writeln!(out, "{check}").map_err(stdout_write_error)?,
+            }
+        }
+    }
+
+    if details.is_some() {
+        write_requirements(&mut out, summary)?;
+    }
+
+    Ok(())
+}
+
+/// Print the totals section after multiple files listed with `xz -l -v`.
+///
+/// # Parameters
+///
+/// * `totals` - Accumulated summary across all processed files
+/// * `file_count` - Total number of files processed
+/// * `very_verbose` - Whether to include the `-vv` requirement lines
+/// * `sizes` - How to print the byte counts
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if writing to stdout fails.
+pub(crate) fn print_verbose_list_totals(
+    totals: ListSummary,
+    file_count: usize,
+    very_verbose: bool,
+    sizes: ListSizes,
+) -> Result<()> {
+    use std::io::Write;
+
+    let mut out = io::stdout().lock();
+    writeln!(out).map_err(stdout_write_error)?;
+    writeln!(out, "{}", tr!("Totals:")).map_err(stdout_write_error)?;
+    write_field(&mut out, tr!("Number of files:"), file_count)?;
+    write_verbose_summary(&mut out, totals, sizes)?;
+    if very_verbose {
+        write_requirements(&mut out, totals)?;
+    }
+    Ok(())
+}
+
+/// Write `xz --robot -l` output for one file.
+///
+/// Prints the tab-separated `name` and `file` lines; with `verbose` also one `stream` line
+/// per stream and one `block` line per block, and with Block Header `details` (`-vv`) the
+/// extra block columns and a `summary` line. The field order matches upstream xz.
+///
+/// # Parameters
+///
+/// * `input_path` - Path to the file being listed
+/// * `summary` - Overall file summary
+/// * `streams` - Per-stream information
+/// * `blocks` - Per-block information (should be sorted by `number_in_file`)
+/// * `verbose` - Whether to print the `stream` and `block` lines
+/// * `details` - Per-block header details in the same order as `blocks` (`-vv` only)
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if writing to stdout fails.
+pub(crate) fn write_robot_report(
+    input_path: &Path,
+    summary: ListSummary,
+    streams: &[StreamInfo],
+    blocks: &[BlockInfo],
+    verbose: bool,
+    details: Option<&[BlockDetails]>,
+) -> Result<()> {
+    use std::io::Write;
+
+    let mut out = io::stdout().lock();
+    writeln!(out, "name\t{}", input_path.display()).map_err(stdout_write_error)?;
     writeln!(
         out,
-        "    Stream    Blocks      CompOffset    UncompOffset        CompSize      UncompSize  Ratio  Check      Padding"
+        "file\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        summary.stream_count,
+        summary.block_count,
+        summary.compressed,
+        summary.uncompressed,
+        math::format_list_ratio(summary.compressed, summary.uncompressed),
+        format_check_names(summary.checks_mask, false),
+        summary.padding
     )
-    .map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
+    .map_err(stdout_write_error)?;
+
+    if !verbose {
+        return Ok(());
+    }
 
     for stream in streams {
-        let stream_ratio = math::ratio_fraction(stream.compressed_size, stream.uncompressed_size);
         writeln!(
             out,
-            "{:>10} {:>9} {:>15} {:>15} {:>15} {:>15}  {:>5.3}  {:<5} {:>12}",
+            "stream\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
             stream.number,
             stream.block_count,
             stream.compressed_offset,
             stream.uncompressed_offset,
             stream.compressed_size,
             stream.uncompressed_size,
-            stream_ratio,
-            check,
+            math::format_list_ratio(stream.compressed_size, stream.uncompressed_size),
+            stream_check_name(stream.check),
             stream.padding
         )
-        .map_err(|source| {
-            DiagnosticCause::from(Error::WriteOutput {
-                source: IoErrorNoCode::new(source),
-            })
-        })?;
+        .map_err(stdout_write_error)?;
     }
 
-    writeln!(out, "  Blocks:").map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
-    writeln!(
-        out,
-        "    Stream     Block      CompOffset    UncompOffset       TotalSize      UncompSize  Ratio  Check"
-    )
-    .map_err(|source| {
-        DiagnosticCause::from(Error::WriteOutput {
-            source: IoErrorNoCode::new(source),
-        })
-    })?;
-
-    let mut stream_idx: usize = 0;
-    let mut remaining_in_stream: u64 = streams.get(stream_idx).map_or(0, |s| s.block_count);
-
-    for block in blocks {
-        while remaining_in_stream == 0 && stream_idx + 1 < streams.len() {
-            stream_idx += 1;
-            remaining_in_stream = streams[stream_idx].block_count;
-        }
-        let stream_number = streams.get(stream_idx).map_or(0, |s| s.number);
-        remaining_in_stream = remaining_in_stream.saturating_sub(1);
-
-        let block_ratio = math::ratio_fraction(block.total_size, block.uncompressed_size);
-        writeln!(
+    for (i, (block, stream)) in blocks
+        .iter()
+        .zip(block_streams(streams, blocks))
+        .enumerate()
+    {
+        write!(
             out,
-            "{:>10} {:>9} {:>15} {:>15} {:>15} {:>15}  {:>5.3}  {}",
-            stream_number,
+            "block\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            stream.map_or(0, |s| s.number),
             block.number_in_stream,
+            block.number_in_file,
             block.compressed_file_offset,
             block.uncompressed_file_offset,
             block.total_size,
             block.uncompressed_size,
-            block_ratio,
-            check
+            math::format_list_ratio(block.total_size, block.uncompressed_size),
+            stream_check_name(stream.and_then(|s| s.check))
+        )
+        .map_err(stdout_write_error)?;
+        if let Some(detail) = details.and_then(|details| details.get(i)) {
+            write!(
+                out,
+                "\t{}\t{}\t{}\t{}\t{}\t{}",
+                format_check_value(detail),
+                detail.header.header_size,
+                format_size_flags(detail),
+                detail.compressed_size,
+                detail.header.memusage,
+                detail.header.filters
+            )
+            .map_err(stdout_write_error)?;
+        }
+        writeln!(out).map_err(stdout_write_error)?;
+    }
+
+    if details.is_some() {
+        writeln!(
+            out,
+            "summary\t{}\t{}\t{}",
+            summary.memusage,
+            if summary.all_have_sizes { "yes" } else { "no" },
+            summary.min_version
         )
-        .map_err(|source| {
-            DiagnosticCause::from(Error::WriteOutput {
-                source: IoErrorNoCode::new(source),
-            })
-        })?;
+        .map_err(stdout_write_error)?;
     }
 
     Ok(())
 }
+
+/// Print the `totals` line after `xz --robot -l` output.
+///
+/// # Parameters
+///
+/// * `totals` - Accumulated summary across all processed files
+/// * `file_count` - Total number of files processed
+/// * `very_verbose` - Whether to append the `-vv` memory, header size and version fields
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if writing to stdout fails.
+pub(crate) fn print_robot_list_totals(
+    totals: ListSummary,
+    file_count: usize,
+    very_verbose: bool,
+) -> Result<()> {
+    use std::io::Write;
+
+    let mut out = io::stdout().lock();
+    write!(
+        out,
+        "totals\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        totals.stream_count,
+        totals.block_count,
+        totals.compressed,
+        totals.uncompressed,
+        math::format_list_ratio(totals.compressed, totals.uncompressed),
+        format_check_names(totals.checks_mask, false),
+        totals.padding,
+        file_count
+    )
+    .map_err(stdout_write_error)?;
+    if very_verbose {
+        write!(
+            out,
+            "\t{}\t{}\t{}",
+            totals.memusage,
+            if totals.all_have_sizes { "yes" } else { "no" },
+            totals.min_version
+        )
+        .map_err(stdout_write_error)?;
+    }
+    writeln!(out).map_err(stdout_write_error)?;
+    Ok(())
+}
@@ -3,4 +3,6 @@
 //! This module contains presentation-focused helpers (string formatting and
 //! printing routines) that are separate from the CLI orchestration logic.
 
+pub(crate) mod info;
 pub(crate) mod list;
+pub(crate) mod summary;
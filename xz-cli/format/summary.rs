@@ -0,0 +1,151 @@
+//! Result lines printed by `--verbose` after each file.
+
+use std::time::Duration;
+
+use xz_core::config::DecodeMode;
+use xz_core::options::IntegrityCheck;
+
+use crate::config::{CliConfig, OperationMode};
+use crate::format::list::stream_check_name;
+use crate::io::{format_elapsed, format_sizes, format_uncompressed_speed};
+use crate::utils::bytes::format_list_size;
+
+/// Format the `-v` result line for one file, like upstream `xz` prints it when stderr
+/// isn't a terminal.
+///
+/// The compressed and uncompressed sizes and their ratio are followed by the uncompressed
+/// throughput once three seconds have passed and by the elapsed time once one has.
+///
+/// # Parameters
+///
+/// * `name` - Name of the input, e.g. `(stdin)`
+/// * `mode` - Operation that produced the counts
+/// * `bytes_read` - Bytes read from the input
+/// * `bytes_written` - Bytes written to the output
+/// * `elapsed` - Time spent on the file
+///
+/// # Returns
+///
+/// The line, e.g. `file.txt: 576 B / 2929.7 KiB = 0.000, 1.2 MiB/s, 0:04`.
+pub(crate) fn format_file_result(
+    name: &str,
+    mode: OperationMode,
+    bytes_read: u64,
+    bytes_written: u64,
+    elapsed: Duration,
+) -> String {
+    let (compressed, uncompressed) = if mode.is_compressing() {
+        (bytes_written, bytes_read)
+    } else {
+        (bytes_read, bytes_written)
+    };
+    let mut line = format!(
+        "{name}: {}",
+        format_sizes(compressed, uncompressed, format_list_size)
+    );
+    for field in [
+        format_uncompressed_speed(uncompressed, elapsed),
+        format_elapsed(elapsed),
+    ] {
+        if !field.is_empty() {
+            line.push_str(", ");
+            line.push_str(&field);
+        }
+    }
+    line
+}
+
+/// Describe the filter chain and integrity check used for compression, for `-vv`.
+///
+/// The filters are shown with the option that selected them, e.g.
+/// `--lzma2=preset=6, CRC64` or `--filters=delta:dist=4 lzma2, CRC32`.
+pub(crate) fn format_compression_details(config: &CliConfig) -> String {
+    let filters = if let Some(filters) = &config.filters {
+        format!("--filters={filters}")
+    } else if let Some(lzma2) = &config.lzma2 {
+        format!("--lzma2={lzma2}")
+    } else if let Some(lzma1) = &config.lzma1 {
+        format!("--lzma1={lzma1}")
+    } else {
+        let filter = if config.format == DecodeMode::Lzma {
+            "lzma1"
+        } else {
+            "lzma2"
+        };
+        let extreme = if config.extreme { "e" } else { "" };
+        format!("--{filter}=preset={}{extreme}", config.level.unwrap_or(6))
+    };
+
+    // `.lzma` and raw streams have no integrity check.
+    let check = if matches!(config.format, DecodeMode::Auto | DecodeMode::Xz) {
+        config.check
+    } else {
+        IntegrityCheck::None
+    };
+    let check = stream_check_name(Some(check));
+    format!("{filters}, {check}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test the per-file line matches upstream's sizes, speed and time.
+    #[test]
+    fn file_result_matches_upstream() {
+        let line = format_file_result(
+            "z.bin",
+            OperationMode::Compress,
+            3_000_000,
+            576,
+            Duration::from_millis(500),
+        );
+        assert_eq!(line, "z.bin: 576 B / 2929.7 KiB = 0.000");
+
+        let line = format_file_result(
+            "a.txt",
+            OperationMode::Compress,
+            20 << 20,
+            1 << 20,
+            Duration::from_secs(4),
+        );
+        assert_eq!(
+            line,
+            "a.txt: 1024.0 KiB / 20.0 MiB = 0.050, 5.0 MiB/s, 0:04"
+        );
+
+        let line = format_file_result(
+            "(stdin)",
+            OperationMode::Decompress,
+            100,
+            40,
+            Duration::from_secs(1),
+        );
+        assert_eq!(line, "(stdin): 100 B / 40 B = 2.500, 0:01");
+    }
+
+    /// Test the `-vv` details name the selected filters and check.
+    #[test]
+    fn compression_details_name_filters_and_check() {
+        let config = CliConfig {
+            level: Some(9),
+            extreme: true,
+            check: IntegrityCheck::Sha256,
+            ..CliConfig::default()
+        };
+        assert_eq!(
+            format_compression_details(&config),
+            "--lzma2=preset=9e, SHA-256"
+        );
+
+        let config = CliConfig {
+            format: DecodeMode::Lzma,
+            lzma1: Some("dict=1MiB".to_string()),
+            ..CliConfig::default()
+        };
+        assert_eq!(
+            format_compression_details(&config),
+            "--lzma1=dict=1MiB, None"
+        );
+    }
+}
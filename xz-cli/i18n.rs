@@ -0,0 +1,361 @@
+//! Message catalogs for translated user-facing strings.
+//!
+//! Translations are read from GNU gettext `.mo` files, so distributions can ship them the
+//! same way as for upstream `xz`: `LOCALEDIR/LANG/LC_MESSAGES/xz-rs.mo`. The locale comes
+//! from `LANGUAGE`, `LC_ALL`, `LC_MESSAGES` and `LANG` with the usual gettext precedence,
+//! and `LOCALEDIR` defaults to `/usr/share/locale` (set `XZ_RS_LOCALEDIR` at build time to
+//! change the default, or at run time to override it).
+//!
+//! Messages with arguments use named `{placeholders}` instead of `printf` conversions, and
+//! translations must keep the same names. Strings without a translation, and everything
+//! in the `C` locale, stay in English.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Text domain, i.e. the name of the `.mo` files.
+pub const TEXT_DOMAIN: &str = "xz-rs";
+
+/// Environment variable overriding the directory with the message catalogs.
+pub const LOCALEDIR_ENV: &str = "XZ_RS_LOCALEDIR";
+
+/// Directory with the message catalogs when [`LOCALEDIR_ENV`] isn't set at run time.
+const DEFAULT_LOCALEDIR: &str = match option_env!("XZ_RS_LOCALEDIR") {
+    Some(dir) => dir,
+    None => "/usr/share/locale",
+};
+
+/// Magic number at the start of a `.mo` file, read in the file's byte order.
+const MO_MAGIC: u32 = 0x9504_12de;
+
+/// Translate a message, optionally substituting named arguments.
+///
+/// `tr!("Is a directory, skipping")` returns the translation as `&'static str`, while
+/// `tr!("{count} files", count = n)` returns a `String` with the arguments filled in.
+macro_rules! tr {
+    ($msgid:literal) => {
+        $crate::i18n::gettext($msgid)
+    };
+    ($msgid:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::substitute(
+            $crate::i18n::gettext($msgid),
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),+],
+        )
+    };
+}
+
+pub(crate) use tr;
+
+/// Translations loaded from a single `.mo` file.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Parse the contents of a GNU gettext `.mo` file.
+    ///
+    /// Entries with a message context or plural forms are ignored, as are empty
+    /// translations and entries that aren't valid UTF-8. Returns `None` if the data
+    /// isn't a valid `.mo` file.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let word = |offset: usize| -> Option<[u8; 4]> {
+            data.get(offset..offset.checked_add(4)?)?.try_into().ok()
+        };
+        let read_u32: fn([u8; 4]) -> u32 = match word(0)? {
+            bytes if u32::from_le_bytes(bytes) == MO_MAGIC => u32::from_le_bytes,
+            bytes if u32::from_be_bytes(bytes) == MO_MAGIC => u32::from_be_bytes,
+            _ => return None,
+        };
+        let field = |offset: usize| word(offset).map(read_u32).map(|value| value as usize);
+
+        // Only the major revision 0 layout is understood.
+        if field(4)? >> 16 != 0 {
+            return None;
+        }
+        let count = field(8)?;
+        let originals = field(12)?;
+        let translations = field(16)?;
+
+        // Both tables have to fit in the file, which also bounds a hostile count.
+        let table_fits = |table: usize| {
+            count
+                .checked_mul(8)
+                .and_then(|len| table.checked_add(len))
+                .is_some_and(|end| end <= data.len())
+        };
+        if !table_fits(originals) || !table_fits(translations) {
+            return None;
+        }
+
+        let string = |table: usize, index: usize| -> Option<&[u8]> {
+            let entry = table.checked_add(index.checked_mul(8)?)?;
+            let len = field(entry)?;
+            let start = field(entry + 4)?;
+            data.get(start..start.checked_add(len)?)
+        };
+
+        let mut messages = HashMap::with_capacity(count);
+        for index in 0..count {
+            let (msgid, msgstr) = (string(originals, index)?, string(translations, index)?);
+            let (Ok(msgid), Ok(msgstr)) = (std::str::from_utf8(msgid), std::str::from_utf8(msgstr))
+            else {
+                continue;
+            };
+            if msgid.is_empty() || msgstr.is_empty() || msgid.contains(['\0', '\u{4}']) {
+                continue;
+            }
+            messages.insert(msgid.to_string(), msgstr.to_string());
+        }
+
+        Some(Self { messages })
+    }
+
+    /// Look up the translation of `msgid`.
+    pub fn get(&self, msgid: &str) -> Option<&str> {
+        self.messages.get(msgid).map(String::as_str)
+    }
+}
+
+/// Translate `msgid` with the catalog of the current locale.
+///
+/// Returns `msgid` itself if there is no translation.
+pub fn gettext(msgid: &'static str) -> &'static str {
+    static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+    CATALOG
+        .get_or_init(load_catalog)
+        .get(msgid)
+        .unwrap_or(msgid)
+}
+
+/// Replace the `{name}` placeholders in `template` with the matching arguments.
+///
+/// `{{` and `}}` stand for literal braces, and unknown placeholders are kept as they are.
+pub fn substitute(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        if let Some(after) = rest.strip_prefix("{{").or_else(|| rest.strip_prefix("}}")) {
+            out.push_str(&rest[..1]);
+            rest = after;
+            continue;
+        }
+
+        let placeholder = rest
+            .strip_prefix('{')
+            .and_then(|body| body.split_once('}'))
+            .and_then(|(name, after)| {
+                let (_, value) = args.iter().find(|(arg, _)| *arg == name)?;
+                Some((value, after))
+            });
+        match placeholder {
+            Some((value, after)) => {
+                out.push_str(&value.to_string());
+                rest = after;
+            }
+            None => {
+                out.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Load the catalog for the current locale, or an empty one.
+fn load_catalog() -> Catalog {
+    let localedir = std::env::var_os(LOCALEDIR_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map_or_else(|| PathBuf::from(DEFAULT_LOCALEDIR), PathBuf::from);
+    let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+    let locale = env("LC_ALL")
+        .or_else(|| env("LC_MESSAGES"))
+        .or_else(|| env("LANG"));
+    let languages = message_languages(locale.as_deref(), env("LANGUAGE").as_deref());
+
+    languages
+        .iter()
+        .flat_map(|language| locale_variants(language))
+        .find_map(|variant| {
+            let data = fs::read(catalog_path(&localedir, &variant)).ok()?;
+            Catalog::parse(&data)
+        })
+        .unwrap_or_default()
+}
+
+/// Languages to look up, in order of preference.
+///
+/// Like GNU gettext, `LANGUAGE` is only honored when the locale isn't `C`.
+fn message_languages(locale: Option<&str>, language: Option<&str>) -> Vec<String> {
+    let Some(locale) = locale.filter(|locale| !matches!(*locale, "C" | "POSIX")) else {
+        return Vec::new();
+    };
+    if locale.starts_with("C.") {
+        return Vec::new();
+    }
+
+    match language {
+        Some(list) => list
+            .split(':')
+            .filter(|entry| !entry.is_empty())
+            .map(ToString::to_string)
+            .collect(),
+        None => vec![locale.to_string()],
+    }
+}
+
+/// Expand `ll_CC.codeset@modifier` to itself, `ll_CC@modifier`, `ll_CC` and `ll`.
+fn locale_variants(locale: &str) -> Vec<String> {
+    let (base, modifier) = match locale.split_once('@') {
+        Some((base, modifier)) => (base, Some(modifier)),
+        None => (locale, None),
+    };
+    let base = base.split_once('.').map_or(base, |(base, _)| base);
+    let language = base.split_once('_').map_or(base, |(language, _)| language);
+
+    let mut variants = vec![locale.to_string()];
+    if let Some(modifier) = modifier {
+        variants.push(format!("{base}@{modifier}"));
+    }
+    variants.push(base.to_string());
+    variants.push(language.to_string());
+    variants.dedup();
+    variants
+}
+
+fn catalog_path(localedir: &Path, language: &str) -> PathBuf {
+    localedir
+        .join(language)
+        .join("LC_MESSAGES")
+        .join(format!("{TEXT_DOMAIN}.mo"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a little-endian `.mo` file from `(msgid, msgstr)` pairs.
+    fn mo_file(entries: &[(&str, &str)]) -> Vec<u8> {
+        let header_len = 28;
+        let tables_len = entries.len() * 16;
+        let mut strings = Vec::new();
+        let mut originals = Vec::new();
+        let mut translations = Vec::new();
+        for (table, pick) in [(&mut originals, 0), (&mut translations, 1)] {
+            for entry in entries {
+                let text = if pick == 0 { entry.0 } else { entry.1 };
+                let offset = header_len + tables_len + strings.len();
+                table.extend_from_slice(&u32::try_from(text.len()).unwrap().to_le_bytes());
+                table.extend_from_slice(&u32::try_from(offset).unwrap().to_le_bytes());
+                strings.extend_from_slice(text.as_bytes());
+                strings.push(0);
+            }
+        }
+
+        let count = u32::try_from(entries.len()).unwrap();
+        let mut data = Vec::new();
+        for value in [MO_MAGIC, 0, count, 28, 28 + count * 8, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend(originals);
+        data.extend(translations);
+        data.extend(strings);
+        data
+    }
+
+    /// Test that translations are read from a `.mo` file, skipping the header entry and
+    /// entries with a context.
+    #[test]
+    fn catalog_parses_mo_files() {
+        let data = mo_file(&[
+            ("", "Content-Type: text/plain; charset=UTF-8\n"),
+            (
+                "Is a directory, skipping",
+                "Ist ein Verzeichnis, wird übersprungen",
+            ),
+            ("ctx\u{4}Check", "Prüfung"),
+        ]);
+        let catalog = Catalog::parse(&data).unwrap();
+
+        assert_eq!(
+            catalog.get("Is a directory, skipping"),
+            Some("Ist ein Verzeichnis, wird übersprungen")
+        );
+        assert_eq!(catalog.get(""), None);
+        assert_eq!(catalog.get("Check"), None);
+        assert!(Catalog::parse(b"not a catalog").is_none());
+        assert!(Catalog::parse(&data[..40]).is_none());
+    }
+
+    /// Test that a header whose tables don't fit in the file is rejected before anything
+    /// is allocated for its entries.
+    #[test]
+    fn catalog_rejects_hostile_header() {
+        let mut data = Vec::new();
+        for value in [MO_MAGIC, 0, u32::MAX, 28, 28, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        assert!(Catalog::parse(&data).is_none());
+
+        let data = mo_file(&[("Check", "Prüfung"), ("Block", "Block")]);
+        assert!(Catalog::parse(&data[..28 + 8]).is_none());
+        assert!(Catalog::parse(&data).is_some());
+    }
+
+    /// Test that an entry that isn't valid UTF-8 is skipped without losing the others.
+    #[test]
+    fn catalog_skips_invalid_utf8_entries() {
+        let mut data = mo_file(&[("Check", "Prüfung"), ("Block", "Block")]);
+        let umlaut = data
+            .windows(2)
+            .position(|pair| pair == "ü".as_bytes())
+            .unwrap();
+        data[umlaut] = 0xff;
+        let catalog = Catalog::parse(&data).unwrap();
+
+        assert_eq!(catalog.get("Check"), None);
+        assert_eq!(catalog.get("Block"), Some("Block"));
+    }
+
+    /// Test that placeholders are replaced by name and escaped braces are kept.
+    #[test]
+    fn substitute_fills_named_placeholders() {
+        assert_eq!(
+            substitute("{count} files, {{literal}} {missing}", &[("count", &3)]),
+            "3 files, {literal} {missing}"
+        );
+        assert_eq!(substitute("no placeholders", &[]), "no placeholders");
+        assert_eq!(substitute("tail {", &[]), "tail {");
+    }
+
+    /// Test that the languages are picked from `LANGUAGE` and the locale like gettext does.
+    #[test]
+    fn languages_follow_gettext_precedence() {
+        assert!(message_languages(None, Some("de")).is_empty());
+        assert!(message_languages(Some("C.UTF-8"), Some("de")).is_empty());
+        assert_eq!(
+            message_languages(Some("fr_FR.UTF-8"), None),
+            ["fr_FR.UTF-8"]
+        );
+        assert_eq!(
+            message_languages(Some("fr_FR.UTF-8"), Some("de::pt_BR")),
+            ["de", "pt_BR"]
+        );
+        assert_eq!(
+            locale_variants("sr_RS.UTF-8@latin"),
+            ["sr_RS.UTF-8@latin", "sr_RS@latin", "sr_RS", "sr"]
+        );
+        assert_eq!(locale_variants("de"), ["de"]);
+    }
+}
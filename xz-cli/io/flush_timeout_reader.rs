@@ -0,0 +1,153 @@
+//! Reader used to implement `--flush-timeout`.
+
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Size of the chunks read by the background thread.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of chunks the background thread may read ahead.
+const READ_AHEAD: usize = 4;
+
+/// A reader that reports [`io::ErrorKind::TimedOut`] when input stalls.
+///
+/// The inner reader runs on a background thread so that waiting for input can time out.
+/// Like upstream `xz`, a timeout is reported when more than `timeout` has passed since the
+/// previous flush and no input is available, and only if data arrived since that flush.
+/// Combined with [`CompressionOptions::with_flush_on_timeout`] this flushes the output
+/// whenever the input goes quiet.
+///
+/// [`CompressionOptions::with_flush_on_timeout`]: xz_core::options::CompressionOptions::with_flush_on_timeout
+pub(crate) struct FlushTimeoutReader {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    pos: usize,
+    timeout: Duration,
+    last_flush: Instant,
+    unflushed: bool,
+    eof: bool,
+}
+
+impl FlushTimeoutReader {
+    /// Starts reading `inner` on a background thread.
+    pub(crate) fn new<R: Read + Send + 'static>(mut inner: R, timeout: Duration) -> Self {
+        let (sender, chunks) = mpsc::sync_channel(READ_AHEAD);
+        thread::spawn(move || loop {
+            let mut chunk = vec![0; CHUNK_SIZE];
+            let message = match inner.read(&mut chunk) {
+                Ok(read) => {
+                    chunk.truncate(read);
+                    Ok(chunk)
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => Err(err),
+            };
+            let last = !matches!(&message, Ok(chunk) if !chunk.is_empty());
+            if sender.send(message).is_err() || last {
+                break;
+            }
+        });
+
+        Self {
+            chunks,
+            chunk: Vec::new(),
+            pos: 0,
+            timeout,
+            last_flush: Instant::now(),
+            unflushed: false,
+            eof: false,
+        }
+    }
+
+    /// Waits for the next chunk, or returns `None` when a flush is due first.
+    fn next_chunk(&mut self) -> Option<io::Result<Vec<u8>>> {
+        if !self.unflushed {
+            return Some(self.chunks.recv().unwrap_or_else(|_| Ok(Vec::new())));
+        }
+
+        let wait = self.timeout.saturating_sub(self.last_flush.elapsed());
+        match self.chunks.recv_timeout(wait) {
+            Ok(message) => Some(message),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Ok(Vec::new())),
+        }
+    }
+}
+
+impl Read for FlushTimeoutReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.chunk.len() {
+            if self.eof {
+                return Ok(0);
+            }
+
+            let Some(message) = self.next_chunk() else {
+                self.unflushed = false;
+                self.last_flush = Instant::now();
+                return Err(io::ErrorKind::TimedOut.into());
+            };
+            self.chunk = message?;
+            self.pos = 0;
+            if self.chunk.is_empty() {
+                self.eof = true;
+                return Ok(0);
+            }
+            self.unflushed = true;
+        }
+
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A reader that returns each chunk after a delay.
+    struct DelayedReader(Vec<(Duration, &'static [u8])>);
+
+    impl Read for DelayedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+            let (delay, chunk) = self.0.remove(0);
+            thread::sleep(delay);
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    fn read_event(reader: &mut FlushTimeoutReader) -> io::Result<Vec<u8>> {
+        let mut buf = [0u8; 16];
+        reader.read(&mut buf).map(|read| buf[..read].to_vec())
+    }
+
+    /// Test that a stall after data times out once and reading then continues.
+    #[test]
+    fn stalled_input_times_out_once() {
+        let inner = DelayedReader(vec![
+            (Duration::ZERO, b"first"),
+            (Duration::from_millis(300), b"second"),
+            (Duration::from_millis(300), b""),
+        ]);
+        let mut reader = FlushTimeoutReader::new(inner, Duration::from_millis(20));
+
+        assert_eq!(read_event(&mut reader).unwrap(), b"first");
+        let err = read_event(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        // Nothing arrived since the flush, so the next read blocks until data arrives.
+        assert_eq!(read_event(&mut reader).unwrap(), b"second");
+        assert_eq!(
+            read_event(&mut reader).unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+        assert!(read_event(&mut reader).unwrap().is_empty());
+        assert!(read_event(&mut reader).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+//! Input file wrapper that gives the kernel page cache hints.
+
+use std::fs::File;
+use std::io::{self, Read};
+
+/// Amount of input read between two requests to drop the pages behind the read position.
+const DROP_INTERVAL: u64 = 8 * 1024 * 1024;
+
+/// A file read sequentially from start to end, with page cache hints where supported.
+///
+/// The kernel is told that the file is read sequentially, which enlarges its read-ahead,
+/// and every [`DROP_INTERVAL`] bytes that the pages already read are no longer needed.
+/// Compressing a large batch of files this way doesn't evict the page cache of the rest
+/// of the machine. Pages that were cached before are dropped as well, which only costs a
+/// re-read if something else needs them again.
+///
+/// On platforms without `posix_fadvise` this is a plain [`File`].
+pub(crate) struct HintedFile {
+    file: File,
+    pos: u64,
+    dropped: u64,
+}
+
+impl HintedFile {
+    /// Wraps `file`, which must be positioned at its start.
+    pub(crate) fn new(file: File) -> Self {
+        let hinted = Self {
+            file,
+            pos: 0,
+            dropped: 0,
+        };
+        hinted.advise(0, 0, Advice::Sequential);
+        hinted
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    fn advise(&self, offset: u64, len: u64, advice: Advice) {
+        use std::os::unix::io::AsRawFd;
+
+        let (Ok(offset), Ok(len)) = (libc::off_t::try_from(offset), libc::off_t::try_from(len))
+        else {
+            return;
+        };
+        let advice = match advice {
+            Advice::Sequential => libc::POSIX_FADV_SEQUENTIAL,
+            Advice::DontNeed => libc::POSIX_FADV_DONTNEED,
+        };
+        // SAFETY: Only passes plain integers and a descriptor owned by `self.file`. The
+        // hints don't affect correctness, so failures are ignored.
+        unsafe {
+            libc::posix_fadvise(self.file.as_raw_fd(), offset, len, advice);
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    fn advise(&self, _offset: u64, _len: u64, _advice: Advice) {}
+}
+
+/// Kind of page cache hint.
+#[derive(Debug, Clone, Copy)]
+enum Advice {
+    /// The file is read from start to end.
+    Sequential,
+    /// The range won't be read again.
+    DontNeed,
+}
+
+impl Read for HintedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.file.read(buf)?;
+        self.pos += read as u64;
+
+        if read == 0 || self.pos - self.dropped >= DROP_INTERVAL {
+            self.advise(self.dropped, self.pos - self.dropped, Advice::DontNeed);
+            self.dropped = self.pos;
+        }
+        Ok(read)
+    }
+}
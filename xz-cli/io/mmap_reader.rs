@@ -0,0 +1,102 @@
+//! Reader used to implement `--mmap`.
+
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::os::unix::io::AsRawFd;
+use std::ptr::NonNull;
+
+/// A reader over a read-only memory mapping of a whole file.
+///
+/// Reads are served from the mapping without `read(2)` calls, and [`BufRead`] hands out
+/// slices of the mapping directly. The kernel is told that the file is read sequentially
+/// so that it reads ahead aggressively and drops pages behind the current position.
+///
+/// Like any mapping, the process receives `SIGBUS` if the file is truncated while it is
+/// read, which is why mapping is opt-in.
+pub(crate) struct MmapReader {
+    map: NonNull<u8>,
+    len: usize,
+    pos: usize,
+}
+
+// SAFETY: `NonNull` is `!Send` only because it might alias data owned by this thread.
+// The mapping belongs to the process rather than the thread that created it, the reader
+// owns it exclusively (no other handle points into it and `drop` unmaps it once), and it
+// is read-only, so moving the reader to another thread moves sole access along with it.
+unsafe impl Send for MmapReader {}
+
+impl MmapReader {
+    /// Maps `file`, or returns `None` if it is empty or can't be mapped.
+    pub(crate) fn new(file: &File) -> Option<Self> {
+        let len = usize::try_from(file.metadata().ok()?.len()).ok()?;
+        if len == 0 {
+            return None;
+        }
+
+        // SAFETY: A fresh read-only private mapping of `len` bytes of a valid descriptor;
+        // the result is checked against `MAP_FAILED` before use.
+        let map = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            return None;
+        }
+
+        // SAFETY: `map` covers `len` bytes. The advice only affects performance, so its
+        // result is ignored.
+        unsafe {
+            libc::madvise(map, len, libc::MADV_SEQUENTIAL);
+        }
+
+        Some(Self {
+            map: NonNull::new(map.cast())?,
+            len,
+            pos: 0,
+        })
+    }
+
+    fn remaining(&self) -> &[u8] {
+        // SAFETY: The mapping is `len` bytes long and stays mapped until `drop`.
+        let data = unsafe { std::slice::from_raw_parts(self.map.as_ptr(), self.len) };
+        &data[self.pos..]
+    }
+}
+
+impl Read for MmapReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = {
+            let remaining = self.remaining();
+            let read = remaining.len().min(buf.len());
+            buf[..read].copy_from_slice(&remaining[..read]);
+            read
+        };
+        self.pos += read;
+        Ok(read)
+    }
+}
+
+impl BufRead for MmapReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.remaining())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.len);
+    }
+}
+
+impl Drop for MmapReader {
+    fn drop(&mut self) {
+        // SAFETY: Unmaps exactly the region mapped in `new`, which is no longer borrowed.
+        unsafe {
+            libc::munmap(self.map.as_ptr().cast(), self.len);
+        }
+    }
+}
@@ -1,23 +1,60 @@
 //! File I/O operations and path manipulation for XZ CLI.
 
 use std::ffi::OsStr;
-use std::fs::{File, OpenOptions};
+use std::fs::{File, FileTimes, Metadata, OpenOptions, Permissions};
 use std::io;
 use std::path::{Path, PathBuf};
 
 use crate::config::{CliConfig, OperationMode, DEFAULT_BUFFER_SIZE, LZMA_EXTENSION, XZ_EXTENSION};
 use crate::error::{DiagnosticCause, Error, IoErrorNoCode, Result, Warning};
+use crate::i18n::tr;
 
-mod sparse_writer;
+mod flush_timeout_reader;
+mod hinted_file;
+#[cfg(unix)]
+mod mmap_reader;
+mod pending_output;
+mod progress;
+mod read_ahead;
+#[cfg(target_os = "linux")]
+pub(crate) mod splice;
+mod write_tracker;
 
-pub(crate) use sparse_writer::SparseFileWriter;
+pub(crate) use flush_timeout_reader::FlushTimeoutReader;
+use hinted_file::HintedFile;
+#[cfg(unix)]
+pub(crate) use mmap_reader::MmapReader;
+pub(crate) use pending_output::PendingOutput;
+pub(crate) use progress::{format_elapsed, format_sizes, format_uncompressed_speed, Progress};
+pub(crate) use read_ahead::{ReadAhead, READ_AHEAD_SIZE};
+pub(crate) use write_tracker::WriteTracker;
 
 #[cfg(test)]
 mod tests;
 
+/// Suffixes recognized when decompressing and what replaces them, like upstream `xz`.
+const DECOMPRESS_SUFFIXES: [(&str, &str); 5] = [
+    (".xz", ""),
+    (".txz", ".tar"),
+    (".lzma", ""),
+    (".tlz", ".tar"),
+    (".lz", ""),
+];
+
+/// Returns the suffixes that mark a file as already compressed with `extension`.
+///
+/// Raw streams have no suffix of their own, so only a custom suffix applies to them.
+fn compressed_suffixes(extension: &str) -> &'static [&'static str] {
+    match extension {
+        XZ_EXTENSION => &[".xz", ".txz"],
+        LZMA_EXTENSION => &[".lzma", ".tlz"],
+        _ => &[],
+    }
+}
+
 /// Checks if a file path has a recognized compression extension.
 ///
-/// Recognizes `.xz` and `.lzma` extensions (case-insensitive).
+/// Recognizes `.xz`, `.txz`, `.lzma`, `.tlz` and `.lz` extensions (case-insensitive).
 ///
 /// # Parameters
 ///
@@ -25,15 +62,112 @@ mod tests;
 ///
 /// # Returns
 ///
-/// Returns `true` if the file has a `.xz` or `.lzma` extension, `false` otherwise.
+/// Returns `true` if the file has one of the recognized extensions, `false` otherwise.
 pub fn has_compression_extension(path: &Path) -> bool {
-    // Get the extension as a lowercase string, if present
-    if let Some(ext) = path.extension().and_then(OsStr::to_str) {
-        let ext = ext.to_ascii_lowercase();
-        ext == XZ_EXTENSION || ext == LZMA_EXTENSION
-    } else {
-        false
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .and_then(strip_known_suffix)
+        .is_some()
+}
+
+/// Checks if a file starts like a format that is decompressed (`.xz`, `.lzma` or `.lz`).
+///
+/// Unlike [`has_compression_extension`], this looks at the magic bytes and ignores the name.
+///
+/// # Parameters
+///
+/// * `path` - The file to check
+///
+/// # Returns
+///
+/// Returns `true` if the file looks compressed. Files that can't be read return `false`,
+/// so that opening them reports the actual error.
+pub fn has_compressed_content(path: &Path) -> bool {
+    File::open(path)
+        .and_then(|mut file| xz_core::read_decode_format_probe_prefix(&mut file))
+        .is_ok_and(|prefix| xz_core::is_known_decode_format(&prefix))
+}
+
+/// Returns `file_name` with a recognized compression suffix replaced, e.g. `a.txz` -> `a.tar`.
+fn strip_known_suffix(file_name: &str) -> Option<String> {
+    DECOMPRESS_SUFFIXES
+        .iter()
+        .find_map(|(compressed, uncompressed)| {
+            let stem_len = file_name.len().checked_sub(compressed.len())?;
+            let tail = file_name.get(stem_len..)?;
+            (stem_len > 0 && tail.eq_ignore_ascii_case(compressed))
+                .then(|| format!("{}{uncompressed}", &file_name[..stem_len]))
+        })
+}
+
+/// Returns `file_name` without the custom `suffix` given with `--suffix`.
+fn strip_custom_suffix<'a>(file_name: &'a str, suffix: &str) -> Option<&'a str> {
+    let suffix = suffix.strip_prefix('.').unwrap_or(suffix);
+    let stem = file_name.strip_suffix(suffix)?.strip_suffix('.')?;
+    (!stem.is_empty()).then_some(stem)
+}
+
+/// Skips inputs that upstream `xz` refuses to process, with the same warnings.
+///
+/// Directories are always skipped. When the output goes to a file, symbolic links and
+/// anything but regular files are skipped as well, and so are files with more than one
+/// hard link or with the setuid, setgid or sticky bit set, unless `--force` or `--keep`
+/// is used: removing such an input afterwards would affect more than the named file.
+///
+/// # Parameters
+///
+/// * `path` - The input file path
+/// * `config` - CLI configuration providing the mode and the `--stdout`, `--force` and
+///   `--keep` flags
+///
+/// # Errors
+///
+/// Returns a [`Warning`] describing why the input is skipped. Inputs that can't be
+/// inspected are accepted so that opening them reports the actual error.
+pub(crate) fn check_input_file(path: &Path, config: &CliConfig) -> Result<()> {
+    let to_file = !config.stdout
+        && matches!(
+            config.mode,
+            OperationMode::Compress | OperationMode::Decompress | OperationMode::Recompress
+        );
+    let allow_shared = config.force || config.keep;
+
+    let Ok(link_metadata) = std::fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if link_metadata.file_type().is_symlink() && to_file && !allow_shared {
+        return Err(DiagnosticCause::from(Warning::SymbolicLink));
     }
+
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return Ok(());
+    };
+    if metadata.is_dir() {
+        return Err(DiagnosticCause::from(Warning::Directory));
+    }
+    if !to_file {
+        return Ok(());
+    }
+    if !metadata.is_file() {
+        return Err(DiagnosticCause::from(Warning::NotRegularFile));
+    }
+
+    #[cfg(unix)]
+    if !allow_shared {
+        use std::os::unix::fs::MetadataExt;
+
+        if metadata.mode() & 0o6000 != 0 {
+            return Err(DiagnosticCause::from(Warning::SetuidOrSetgid));
+        }
+        if metadata.mode() & 0o1000 != 0 {
+            return Err(DiagnosticCause::from(Warning::StickyBit));
+        }
+        if metadata.nlink() > 1 {
+            return Err(DiagnosticCause::from(Warning::HardLinks));
+        }
+    }
+
+    Ok(())
 }
 
 /// Generates an output filename based on input path and operation mode.
@@ -43,6 +177,8 @@ pub fn has_compression_extension(path: &Path) -> bool {
 /// * `input` - The input file path
 /// * `mode` - The operation mode
 /// * `suffix` - Optional custom suffix for compression (e.g., ".myext")
+/// * `default_extension` - Extension of the output format without the dot; empty for raw
+///   streams
 /// * `force` - Whether to allow compression even if file already has target suffix
 ///
 /// # Returns
@@ -53,9 +189,10 @@ pub fn has_compression_extension(path: &Path) -> bool {
 ///
 /// Returns an error in these cases:
 ///
-/// - Decompression mode: Input file lacks a recognized compression extension
-/// - Decompression mode: Cannot determine a valid file stem from the input path
-/// - Compression mode: File already has target suffix (unless force is true)
+/// - Decompression mode: Input file has neither a recognized compression suffix nor the
+///   custom `suffix`
+/// - Compression mode: File already has a suffix of the output format or the custom
+///   `suffix` (unless force is true)
 pub fn generate_output_filename(
     input: &Path,
     mode: OperationMode,
@@ -69,16 +206,25 @@ pub fn generate_output_filename(
             // Strip leading dot from suffix if present
             let extension = suffix.map_or(default_extension, |s| s.strip_prefix('.').unwrap_or(s));
 
-            // Check if the file already has the target suffix (unless force is enabled)
+            // Like upstream, files that already have a suffix of the output format or the
+            // custom suffix are skipped, so batch jobs don't create `file.xz.xz` chains.
             if !force {
-                if let Some(file_name) = input.file_name().and_then(OsStr::to_str) {
-                    let target_suffix = format!(".{extension}");
-                    if file_name.ends_with(&target_suffix) {
-                        return Err(DiagnosticCause::from(Warning::AlreadyHasSuffix {
-                            path: input.to_path_buf(),
-                            suffix: target_suffix,
-                        }));
-                    }
+                let file_name = input
+                    .file_name()
+                    .and_then(OsStr::to_str)
+                    .unwrap_or_default();
+                let existing = compressed_suffixes(default_extension)
+                    .iter()
+                    .map(|suffix| (*suffix).to_string())
+                    .chain(suffix.map(|_| format!(".{extension}")))
+                    .find(|suffix| {
+                        file_name.len() > suffix.len() && file_name.ends_with(suffix.as_str())
+                    });
+                if let Some(existing) = existing {
+                    return Err(DiagnosticCause::from(Warning::AlreadyHasSuffix {
+                        path: input.to_path_buf(),
+                        suffix: existing,
+                    }));
                 }
             }
 
@@ -95,46 +241,29 @@ pub fn generate_output_filename(
             Ok(output)
         }
         OperationMode::Decompress | OperationMode::Cat => {
-            // If suffix is specified, check for that suffix
-            if let Some(suf) = suffix {
-                let suf_with_dot = if suf.starts_with('.') {
-                    suf.to_string()
-                } else {
-                    format!(".{suf}")
-                };
-
-                if let Some(file_name) = input.file_name().and_then(OsStr::to_str) {
-                    if file_name.ends_with(&suf_with_dot) {
-                        let parent = input.parent().unwrap_or_else(|| Path::new("."));
-                        let new_name = &file_name[..file_name.len() - suf_with_dot.len()];
-                        return Ok(parent.join(new_name));
-                    }
-                }
-
-                return Err(DiagnosticCause::from(Warning::InvalidExtension {
-                    path: input.to_path_buf(),
-                }));
-            }
+            // Like upstream, the known suffixes are tried before a custom `--suffix`.
+            let file_name = input
+                .file_name()
+                .and_then(OsStr::to_str)
+                .unwrap_or_default();
+            let new_name = strip_known_suffix(file_name).or_else(|| {
+                suffix
+                    .and_then(|suffix| strip_custom_suffix(file_name, suffix))
+                    .map(str::to_string)
+            });
 
-            // Ensure the input file has a recognized compression extension
-            if !has_compression_extension(input) {
+            let Some(new_name) = new_name else {
                 return Err(DiagnosticCause::from(Warning::InvalidExtension {
                     path: input.to_path_buf(),
                 }));
-            }
-
-            // Get the file stem (filename without last extension)
-            let stem = input
-                .file_stem()
-                .ok_or_else(|| Error::InvalidOutputFilename {
-                    path: input.to_path_buf(),
-                })
-                .map_err(DiagnosticCause::from)?;
+            };
 
             // Use the parent directory, or current directory if none
             let parent = input.parent().unwrap_or_else(|| Path::new("."));
-            Ok(parent.join(stem))
+            Ok(parent.join(new_name))
         }
+        // The recompressed file replaces the input
+        OperationMode::Recompress => Ok(input.to_path_buf()),
         // No output file for test mode or list mode
         OperationMode::Test | OperationMode::List => Ok(PathBuf::new()),
     }
@@ -157,11 +286,23 @@ pub fn generate_output_filename(
 ///
 /// Returns an error if the file cannot be opened.
 pub fn open_input(path: &Path) -> Result<Box<dyn io::Read>> {
+    open_buffered_input(path, DEFAULT_BUFFER_SIZE, true)
+}
+
+/// Opens an input reader like [`open_input`] with a buffer of `capacity` bytes.
+///
+/// With `io_hints`, regular files are read through a [`HintedFile`] that keeps them from
+/// filling the page cache (disabled by `--no-io-hints`).
+pub(crate) fn open_buffered_input(
+    path: &Path,
+    capacity: usize,
+    io_hints: bool,
+) -> Result<Box<dyn io::Read>> {
     let path = (!path.as_os_str().is_empty() && path != Path::new("-")).then_some(path);
 
     let Some(path) = path else {
         return Ok(Box::new(io::BufReader::with_capacity(
-            DEFAULT_BUFFER_SIZE,
+            capacity,
             io::stdin(),
         )));
     };
@@ -172,10 +313,45 @@ pub fn open_input(path: &Path) -> Result<Box<dyn io::Read>> {
         })
     })?;
 
-    Ok(Box::new(io::BufReader::with_capacity(
-        DEFAULT_BUFFER_SIZE,
-        file,
-    )))
+    Ok(buffered_file(file, capacity, io_hints))
+}
+
+/// Wraps an opened input file in a reader with a buffer of `capacity` bytes.
+fn buffered_file(file: File, capacity: usize, io_hints: bool) -> Box<dyn io::Read> {
+    if io_hints && file.metadata().is_ok_and(|metadata| metadata.is_file()) {
+        return Box::new(io::BufReader::with_capacity(
+            capacity,
+            HintedFile::new(file),
+        ));
+    }
+    Box::new(io::BufReader::with_capacity(capacity, file))
+}
+
+/// Opens an input reader that maps regular files into memory (`--mmap`).
+///
+/// Falls back to [`open_buffered_input`] for standard input and for files that can't be
+/// mapped, such as empty files, pipes or files on platforms without `mmap`.
+pub(crate) fn open_mapped_input(
+    path: &Path,
+    capacity: usize,
+    io_hints: bool,
+) -> Result<Box<dyn io::Read>> {
+    #[cfg(unix)]
+    if !path.as_os_str().is_empty() && path != Path::new("-") {
+        let file = File::open(path).map_err(|source| {
+            DiagnosticCause::from(Error::OpenInput {
+                source: IoErrorNoCode::new(source),
+            })
+        })?;
+        if file.metadata().is_ok_and(|metadata| metadata.is_file()) {
+            if let Some(reader) = MmapReader::new(&file) {
+                return Ok(Box::new(reader));
+            }
+        }
+        return Ok(buffered_file(file, capacity, io_hints));
+    }
+
+    open_buffered_input(path, capacity, io_hints)
 }
 
 /// Opens an output writer for the given path or stdout.
@@ -204,7 +380,7 @@ pub fn open_output(path: Option<&Path>, config: &CliConfig) -> Result<Box<dyn io
 
     if use_stdout {
         Ok(Box::new(io::BufWriter::with_capacity(
-            DEFAULT_BUFFER_SIZE,
+            config.buffer_size,
             io::stdout(),
         )))
     } else if let Some(path) = path {
@@ -224,39 +400,134 @@ pub fn open_output(path: Option<&Path>, config: &CliConfig) -> Result<Box<dyn io
         };
 
         Ok(Box::new(io::BufWriter::with_capacity(
-            DEFAULT_BUFFER_SIZE,
+            config.buffer_size,
             file,
         )))
     } else {
         // Fallback to stdout if no path is provided
         Ok(Box::new(io::BufWriter::with_capacity(
-            DEFAULT_BUFFER_SIZE,
+            config.buffer_size,
             io::stdout(),
         )))
     }
 }
 
-/// Opens an output file for writing, applying `--force` overwrite semantics.
+/// Copies the attributes of the input to the finished `output` file, like upstream `xz`.
 ///
-/// This is a lower-level helper used when the caller needs to keep the output as a [`File`]
-/// (e.g. to implement sparse output with `Seek`).
+/// The access and modification times and the permission bits are always copied, and the
+/// owner and group where the file system permits it. If the group can't be set, the group
+/// and other permissions are reduced to what both of them allowed so that the output isn't
+/// more accessible than the input. Failing to set the timestamps is ignored like upstream.
 ///
-/// # Errors
+/// # Parameters
 ///
-/// Returns an error if the file exists and overwrite isn't forced, or if creation fails.
-pub fn open_output_file(path: &Path, config: &CliConfig) -> Result<File> {
-    match open_output_file_with_options(path, config.force) {
-        Ok(file) => Ok(file),
-        Err(source) if source.kind() == io::ErrorKind::AlreadyExists && !config.force => {
-            Err(DiagnosticCause::from(Error::OutputExists {
-                path: path.to_path_buf(),
-            }))
+/// * `metadata` - Metadata of the input file, taken before reading it changed the access time
+/// * `output` - The output file that was created from the input
+///
+/// # Returns
+///
+/// A warning for the first attribute that couldn't be set, or `None` on success.
+pub(crate) fn copy_file_metadata(metadata: &Metadata, output: &Path) -> Option<Warning> {
+    let warning = copy_permissions(metadata, output).err();
+
+    let mut times = FileTimes::new();
+    if let Ok(accessed) = metadata.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if let Ok(modified) = metadata.modified() {
+        times = times.set_modified(modified);
+    }
+    let _ = File::open(output).and_then(|file| file.set_times(times));
+
+    warning
+}
+
+/// Returns `true` if standard output is the regular file the input is read from, as in
+/// `xz -dc file >> file`, where the output would be read back as input.
+///
+/// # Parameters
+///
+/// * `input` - Metadata of the input file, or `None` for standard input
+#[cfg(unix)]
+pub(crate) fn is_output_of_input(input: Option<&Metadata>) -> bool {
+    use std::os::fd::{AsFd, BorrowedFd};
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = |fd: BorrowedFd<'_>| {
+        fd.try_clone_to_owned()
+            .map(File::from)
+            .and_then(|file| file.metadata())
+            .ok()
+    };
+    let Some(output) = metadata(io::stdout().as_fd()).filter(Metadata::is_file) else {
+        return false;
+    };
+    let stdin;
+    let input = match input {
+        Some(input) => input,
+        None => match metadata(io::stdin().as_fd()) {
+            Some(metadata) => {
+                stdin = metadata;
+                &stdin
+            }
+            None => return false,
+        },
+    };
+    input.dev() == output.dev() && input.ino() == output.ino()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_output_of_input(_input: Option<&Metadata>) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn copy_permissions(metadata: &Metadata, output: &Path) -> std::result::Result<(), Warning> {
+    use std::os::unix::fs::{chown, MetadataExt, PermissionsExt};
+
+    let warning = |message: String| Warning::CopyMetadata { message };
+    let mut result = Ok(());
+
+    // Only root can give a file away, so like upstream only root is told about failures.
+    let is_root = std::fs::metadata(output).is_ok_and(|output| output.uid() == 0);
+    if let Err(source) = chown(output, Some(metadata.uid()), None) {
+        if is_root {
+            result = Err(warning(tr!(
+                "Cannot set the file owner: {error}",
+                error = IoErrorNoCode::new(source)
+            )));
         }
-        Err(source) => Err(DiagnosticCause::from(Error::CreateOutput {
-            path: path.to_path_buf(),
-            source: IoErrorNoCode::new(source),
-        })),
     }
+
+    let mut mode = metadata.mode() & 0o777;
+    if let Err(source) = chown(output, None, Some(metadata.gid())) {
+        result = result.and(Err(warning(tr!(
+            "Cannot set the file group: {error}",
+            error = IoErrorNoCode::new(source)
+        ))));
+        let shared = (mode >> 3) & mode & 0o007;
+        mode = (mode & 0o700) | (shared << 3) | shared;
+    }
+
+    if let Err(source) = std::fs::set_permissions(output, Permissions::from_mode(mode)) {
+        result = result.and(Err(warning(tr!(
+            "Cannot set the file permissions: {error}",
+            error = IoErrorNoCode::new(source)
+        ))));
+    }
+    result
+}
+
+#[cfg(not(unix))]
+fn copy_permissions(metadata: &Metadata, output: &Path) -> std::result::Result<(), Warning> {
+    std::fs::set_permissions(output, metadata.permissions()).map_err(|source| {
+        Warning::CopyMetadata {
+            message: tr!(
+                "Cannot set the file permissions: {error}",
+                error = IoErrorNoCode::new(source)
+            ),
+        }
+    })
 }
 
 /// Opens an output file for writing, applying `--force` overwrite semantics.
@@ -0,0 +1,355 @@
+//! Output files that only appear under their final name once they are complete.
+
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::{DiagnosticCause, Error, IoErrorNoCode, Result};
+
+/// An output file that is written under a temporary name next to its destination.
+///
+/// [`PendingOutput::commit`] renames the finished file to the final name. Dropping a pending
+/// output without committing it removes the temporary file, so an error, a signal or a full
+/// disk never leaves a truncated file that a later run would take for a complete one.
+#[derive(Debug)]
+pub(crate) struct PendingOutput {
+    /// Name the output gets once it's complete
+    path: PathBuf,
+    /// Temporary file in the same directory, so the rename doesn't cross file systems
+    temp_path: PathBuf,
+    /// Handle on the temporary file for syncing it, which keeps working after the
+    /// permissions of a read-only input were copied to the file
+    file: File,
+    /// Whether an existing file at `path` may be replaced
+    replace: bool,
+    /// Whether the temporary file has been renamed
+    committed: bool,
+}
+
+impl PendingOutput {
+    /// Creates the temporary file for the output `path`.
+    ///
+    /// # Parameters
+    ///
+    /// * `path` - Final path of the output file
+    /// * `force` - Whether an existing file at `path` may be replaced
+    ///
+    /// # Returns
+    ///
+    /// The pending output and the temporary file to write to.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OutputExists`] if `path` exists and `force` is `false`, or
+    /// [`Error::CreateOutput`] if the temporary file can't be created.
+    pub(crate) fn create(path: &Path, force: bool) -> Result<(Self, File)> {
+        if !force && path.symlink_metadata().is_ok() {
+            return Err(DiagnosticCause::from(Error::OutputExists {
+                path: path.to_path_buf(),
+            }));
+        }
+
+        let create_error = |source| {
+            DiagnosticCause::from(Error::CreateOutput {
+                path: path.to_path_buf(),
+                source: IoErrorNoCode::new(source),
+            })
+        };
+
+        let mut attempt = 0;
+        loop {
+            let temp_path = temp_path_for(path, attempt);
+            match open_temp_file(&temp_path) {
+                Ok(file) => {
+                    let pending = Self {
+                        path: path.to_path_buf(),
+                        temp_path,
+                        file: file.try_clone().map_err(create_error)?,
+                        replace: force,
+                        committed: false,
+                    };
+                    return Ok((pending, file));
+                }
+                // A stale file from an earlier run with the same process ID; try the next name.
+                Err(source) if source.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => {
+                    attempt += 1;
+                }
+                Err(source) => return Err(create_error(source)),
+            }
+        }
+    }
+
+    /// Returns the path of the temporary file.
+    pub(crate) fn temp_path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    /// Moves the finished output to its final name.
+    ///
+    /// Without `force` at creation, a file that appeared at the final name in the meantime
+    /// is never replaced.
+    ///
+    /// # Parameters
+    ///
+    /// * `synchronous` - Whether to flush the file and the directory entry for its final
+    ///   name to the storage device, so the output survives a power loss once the input
+    ///   is removed
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OutputExists`] if the final name was taken since the output was
+    /// created without `force`, or [`Error::CreateOutput`] if syncing or the rename fails;
+    /// the temporary file is removed if it wasn't renamed.
+    pub(crate) fn commit(mut self, synchronous: bool) -> Result<()> {
+        let commit_error = |source| {
+            DiagnosticCause::from(Error::CreateOutput {
+                path: self.path.clone(),
+                source: IoErrorNoCode::new(source),
+            })
+        };
+
+        if synchronous {
+            self.file.sync_all().map_err(commit_error)?;
+        }
+        let renamed = if self.replace {
+            std::fs::rename(&self.temp_path, &self.path)
+        } else {
+            rename_noreplace(&self.temp_path, &self.path)
+        };
+        match renamed {
+            Err(source) if source.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(DiagnosticCause::from(Error::OutputExists {
+                    path: self.path.clone(),
+                }));
+            }
+            result => result.map_err(commit_error)?,
+        }
+        self.committed = true;
+        if synchronous {
+            sync_parent_dir(&self.path).map_err(commit_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PendingOutput {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// Returns the hidden temporary name for `path`, e.g. `.file.xz.1234-0.tmp`.
+fn temp_path_for(path: &Path, attempt: u32) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}-{attempt}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+/// Renames `from` to `to`, failing with [`io::ErrorKind::AlreadyExists`] if `to` exists.
+///
+/// The check and the rename are one step, so a file created at `to` after
+/// [`PendingOutput::create`] checked for it is never overwritten. Linux does this with
+/// `renameat2(RENAME_NOREPLACE)`; elsewhere, and on file systems that don't support that,
+/// the file gets a hard link at `to` and loses its temporary name.
+fn rename_noreplace(from: &Path, to: &Path) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = |path: &Path| {
+            CString::new(path.as_os_str().as_bytes())
+                .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))
+        };
+        let (from_c, to_c) = (path(from)?, path(to)?);
+        // SAFETY: Both paths are NUL-terminated strings that outlive the call.
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_renameat2,
+                libc::AT_FDCWD,
+                from_c.as_ptr(),
+                libc::AT_FDCWD,
+                to_c.as_ptr(),
+                libc::RENAME_NOREPLACE,
+            )
+        };
+        if result == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        if !matches!(err.raw_os_error(), Some(libc::EINVAL | libc::ENOSYS)) {
+            return Err(err);
+        }
+    }
+
+    match std::fs::hard_link(from, to) {
+        Ok(()) => {
+            // The output is complete under its final name; a leftover temporary name is
+            // only untidy.
+            let _ = std::fs::remove_file(from);
+            Ok(())
+        }
+        // File systems without hard links can only be checked right before the rename.
+        Err(err)
+            if err.kind() != io::ErrorKind::AlreadyExists && to.symlink_metadata().is_err() =>
+        {
+            std::fs::rename(from, to)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Flushes the directory containing `path`, making a rename into it durable.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+/// Directories can't be opened for syncing here; the rename is flushed with the file system.
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Creates a new temporary file that only the owner can access until it's finished.
+fn open_temp_file(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    /// Test that committing moves the temporary file next to the output to its final name.
+    #[test]
+    fn commit_renames_to_final_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.xz");
+
+        let (pending, mut file) = PendingOutput::create(&path, false).unwrap();
+        let temp_path = pending.temp_path().to_path_buf();
+        assert_eq!(temp_path.parent(), Some(dir.path()));
+        assert!(!path.exists());
+
+        file.write_all(b"data").unwrap();
+        drop(file);
+        pending.commit(false).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"data");
+        assert!(!temp_path.exists());
+    }
+
+    /// Test that a synchronous commit flushes the file and still renames it.
+    #[test]
+    fn synchronous_commit_renames_to_final_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.xz");
+
+        let (pending, mut file) = PendingOutput::create(&path, false).unwrap();
+        let temp_path = pending.temp_path().to_path_buf();
+        file.write_all(b"data").unwrap();
+        drop(file);
+        pending.commit(true).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"data");
+        assert!(!temp_path.exists());
+    }
+
+    /// Test that a synchronous commit works once the output was made read-only, as it is
+    /// when the permissions of a read-only input are copied to it.
+    #[cfg(unix)]
+    #[test]
+    fn synchronous_commit_of_read_only_output() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.xz");
+
+        let (pending, mut file) = PendingOutput::create(&path, false).unwrap();
+        file.write_all(b"data").unwrap();
+        drop(file);
+        let read_only = std::fs::Permissions::from_mode(0o444);
+        std::fs::set_permissions(pending.temp_path(), read_only).unwrap();
+        pending.commit(true).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"data");
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o444);
+    }
+
+    /// Test that an output dropped without committing leaves no file behind.
+    #[test]
+    fn drop_without_commit_removes_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.xz");
+
+        let (pending, _file) = PendingOutput::create(&path, false).unwrap();
+        let temp_path = pending.temp_path().to_path_buf();
+        assert!(temp_path.exists());
+
+        drop(pending);
+        assert!(!temp_path.exists());
+        assert!(!path.exists());
+    }
+
+    /// Test that an existing output is only replaced with `force`.
+    #[test]
+    fn existing_output_requires_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.xz");
+        std::fs::write(&path, b"old").unwrap();
+
+        let err = PendingOutput::create(&path, false).unwrap_err();
+        assert!(matches!(
+            err,
+            DiagnosticCause::Error(Error::OutputExists { .. })
+        ));
+
+        let (pending, mut file) = PendingOutput::create(&path, true).unwrap();
+        file.write_all(b"new").unwrap();
+        drop(file);
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+
+        pending.commit(false).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+    }
+
+    /// Test that a file created at the final name while the output was written is kept
+    /// without `force`.
+    #[test]
+    fn commit_never_replaces_late_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.xz");
+
+        let (pending, mut file) = PendingOutput::create(&path, false).unwrap();
+        let temp_path = pending.temp_path().to_path_buf();
+        file.write_all(b"new").unwrap();
+        drop(file);
+        std::fs::write(&path, b"late").unwrap();
+
+        let err = pending.commit(false).unwrap_err();
+        assert!(matches!(
+            err,
+            DiagnosticCause::Error(Error::OutputExists { .. })
+        ));
+        assert_eq!(std::fs::read(&path).unwrap(), b"late");
+        assert!(!temp_path.exists());
+    }
+}
@@ -0,0 +1,395 @@
+//! Live progress display used by `--verbose` when stderr is a terminal.
+
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::config::OperationMode;
+use crate::signals;
+use crate::utils::bytes::{format_progress_size, format_speed};
+use crate::utils::math::ratio_fraction;
+
+/// How often the progress line is redrawn, like upstream `xz`.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How quickly a progress report requested by a signal is printed.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Byte counts shared between the tracked streams and the display thread.
+#[derive(Default)]
+struct Counters {
+    input: AtomicU64,
+    output: AtomicU64,
+}
+
+/// Counts the bytes of one operation and prints its progress line on stderr.
+///
+/// The line is drawn from a background thread so that it keeps updating while the
+/// (de)compressor is busy, and it is cleared when the `Progress` is dropped.
+pub(crate) struct Progress {
+    counters: Arc<Counters>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Progress {
+    /// Starts tracking an operation in `mode`.
+    ///
+    /// With `automatic` the line is redrawn in place every second. Otherwise it is only
+    /// printed, on a line of its own, when a signal asks for it (see [`crate::signals`]).
+    /// `input_size` is the size of the input file, if known, and is used for the
+    /// percentage and the remaining time estimate.
+    pub(crate) fn start(mode: OperationMode, input_size: Option<u64>, automatic: bool) -> Self {
+        let counters = Arc::new(Counters::default());
+        if !automatic && !cfg!(unix) {
+            return Self {
+                counters,
+                stop: None,
+                thread: None,
+            };
+        }
+
+        let (stop, stopped) = mpsc::channel::<()>();
+        let shared = Arc::clone(&counters);
+        let started = Instant::now();
+        let thread = thread::spawn(move || {
+            let mut drawn = 0;
+            let mut next_update = started + UPDATE_INTERVAL;
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(POLL_INTERVAL) {
+                let requested = signals::take_progress_request();
+                let due = automatic && Instant::now() >= next_update;
+                if !requested && !due {
+                    continue;
+                }
+
+                let snapshot = Snapshot::new(mode, input_size, &shared, started.elapsed());
+                let line = snapshot.line();
+                if automatic {
+                    // Pad over the previous line in case this one is shorter.
+                    eprint!("\r{line:drawn$}\r");
+                    drawn = drawn.max(line.len());
+                    next_update = Instant::now() + UPDATE_INTERVAL;
+                } else {
+                    eprintln!("{}", line.trim_end());
+                }
+            }
+            if drawn > 0 {
+                eprint!("\r{:drawn$}\r", "");
+            }
+        });
+
+        Self {
+            counters,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Wraps the input stream so that its bytes are counted.
+    pub(crate) fn track_input<R: Read>(&self, inner: R) -> ProgressReader<R> {
+        ProgressReader {
+            inner,
+            counters: Arc::clone(&self.counters),
+        }
+    }
+
+    /// Wraps the output stream so that its bytes are counted.
+    pub(crate) fn track_output<W: Write>(&self, inner: W) -> ProgressWriter<W> {
+        ProgressWriter {
+            inner,
+            counters: Arc::clone(&self.counters),
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the display thread, which clears the line and exits.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Reader that counts the bytes read for [`Progress`].
+pub(crate) struct ProgressReader<R> {
+    inner: R,
+    counters: Arc<Counters>,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.counters
+            .input
+            .fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+/// Writer that counts the bytes written for [`Progress`].
+pub(crate) struct ProgressWriter<W> {
+    inner: W,
+    counters: Arc<Counters>,
+}
+
+impl<W: Write> Write for ProgressWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.counters
+            .output
+            .fetch_add(written as u64, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// State of an operation at one progress update.
+#[derive(Debug, Clone, Copy)]
+struct Snapshot {
+    in_pos: u64,
+    in_size: Option<u64>,
+    compressed: u64,
+    uncompressed: u64,
+    elapsed: Duration,
+}
+
+impl Snapshot {
+    fn new(
+        mode: OperationMode,
+        in_size: Option<u64>,
+        counters: &Counters,
+        elapsed: Duration,
+    ) -> Self {
+        let in_pos = counters.input.load(Ordering::Relaxed);
+        let out_pos = counters.output.load(Ordering::Relaxed);
+        let (compressed, uncompressed) = if mode.is_compressing() {
+            (out_pos, in_pos)
+        } else {
+            (in_pos, out_pos)
+        };
+        Self {
+            in_pos,
+            in_size,
+            compressed,
+            uncompressed,
+            elapsed,
+        }
+    }
+
+    /// Formats the progress line with the column widths used by upstream `xz`.
+    fn line(&self) -> String {
+        format!(
+            " {:>6} {:>35}   {:>9} {:>10}   {:>10}",
+            self.percentage(),
+            self.sizes(),
+            self.speed(),
+            format_elapsed(self.elapsed),
+            self.remaining(),
+        )
+    }
+
+    /// Input size if it is known and hasn't been exceeded.
+    fn known_size(&self) -> Option<u64> {
+        self.in_size.filter(|&size| size > 0 && self.in_pos <= size)
+    }
+
+    fn percentage(&self) -> String {
+        let Some(size) = self.known_size() else {
+            return "--- %".to_string();
+        };
+        // Never show 100.0 % before the operation has actually finished.
+        #[allow(clippy::cast_precision_loss)]
+        let percentage = self.in_pos as f64 / size as f64 * 99.9;
+        format!("{percentage:.1} %")
+    }
+
+    fn sizes(&self) -> String {
+        format_sizes(self.compressed, self.uncompressed, format_progress_size)
+    }
+
+    fn speed(&self) -> String {
+        format_uncompressed_speed(self.uncompressed, self.elapsed)
+    }
+
+    /// Estimated remaining time; left empty until the estimate is meaningful.
+    fn remaining(&self) -> String {
+        let Some(size) = self.known_size() else {
+            return String::new();
+        };
+        if self.in_pos < 1 << 19 || self.elapsed < Duration::from_secs(8) {
+            return String::new();
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let estimate =
+            (size - self.in_pos) as f64 * self.elapsed.as_secs_f64() / self.in_pos as f64;
+        // Input may be consumed well before the output is, so never claim zero seconds.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        format_remaining((estimate as u64).max(1))
+    }
+}
+
+/// Formats `compressed / uncompressed = ratio` like upstream `xz`, with `size` formatting
+/// the byte counts. Ratios above 9.999, or without uncompressed data, print as `> 9.999`.
+pub(crate) fn format_sizes(compressed: u64, uncompressed: u64, size: fn(u64) -> String) -> String {
+    let sizes = format!("{} / {}", size(compressed), size(uncompressed));
+    let ratio = ratio_fraction(compressed, uncompressed);
+    if uncompressed == 0 || ratio > 9.999 {
+        format!("{sizes} > 9.999")
+    } else {
+        format!("{sizes} = {ratio:.3}")
+    }
+}
+
+/// Uncompressed throughput; left empty for the first three seconds.
+pub(crate) fn format_uncompressed_speed(uncompressed: u64, elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(3) {
+        return String::new();
+    }
+    format_speed(uncompressed, elapsed)
+}
+
+/// Formats elapsed time as `M:SS` or `H:MM:SS`; empty below one second.
+pub(crate) fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    if seconds == 0 || seconds > (9999 * 60 + 59) * 60 + 59 {
+        return String::new();
+    }
+
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+/// Formats a remaining time estimate, rounded up more coarsely the larger it is.
+fn format_remaining(seconds: u64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+
+    if seconds <= 10 {
+        format!("{seconds} s")
+    } else if seconds <= 50 {
+        format!("{} s", seconds.div_ceil(5) * 5)
+    } else if seconds <= 590 {
+        let seconds = seconds.div_ceil(10) * 10;
+        format!("{} min {} s", seconds / MINUTE, seconds % MINUTE)
+    } else if seconds <= 59 * MINUTE {
+        format!("{} min", seconds.div_ceil(MINUTE))
+    } else if seconds <= 9 * HOUR + 50 * MINUTE {
+        let minutes = seconds.div_ceil(10 * MINUTE) * 10;
+        format!("{} h {} min", minutes / 60, minutes % 60)
+    } else if seconds <= 23 * HOUR {
+        format!("{} h", seconds.div_ceil(HOUR))
+    } else if seconds <= 9 * DAY + 23 * HOUR {
+        let hours = seconds.div_ceil(HOUR);
+        format!("{} d {} h", hours / 24, hours % 24)
+    } else if seconds <= 999 * DAY {
+        format!("{} d", seconds.div_ceil(DAY))
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(in_pos: u64, in_size: Option<u64>, out_pos: u64, elapsed: u64) -> Snapshot {
+        let counters = Counters::default();
+        counters.input.store(in_pos, Ordering::Relaxed);
+        counters.output.store(out_pos, Ordering::Relaxed);
+        Snapshot::new(
+            OperationMode::Compress,
+            in_size,
+            &counters,
+            Duration::from_secs(elapsed),
+        )
+    }
+
+    /// Test a full progress line for compression of a file with a known size.
+    #[test]
+    fn line_matches_upstream_layout() {
+        let line = snapshot(50 << 20, Some(100 << 20), 10 << 20, 10).line();
+        assert_eq!(
+            line,
+            " 50.0 %         10.0 MiB / 50.0 MiB = 0.200   5.0 MiB/s       0:10         10 s"
+        );
+    }
+
+    /// Test that positions and sizes beyond 4 GiB aren't truncated.
+    #[test]
+    fn sizes_beyond_4_gib() {
+        let progress = snapshot(6 << 30, Some(12 << 30), 3 << 30, 60);
+        assert_eq!(progress.percentage(), "50.0 %");
+        assert_eq!(progress.sizes(), "3072.0 MiB / 6144.0 MiB = 0.500");
+        assert_eq!(progress.remaining(), "1 min 0 s");
+    }
+
+    /// Test that unknown or exceeded input sizes hide the percentage and the estimate.
+    #[test]
+    fn unknown_size_hides_percentage_and_estimate() {
+        let progress = snapshot(2 << 20, None, 0, 20);
+        assert_eq!(progress.percentage(), "--- %");
+        assert_eq!(progress.sizes(), "0.0 MiB / 2.0 MiB = 0.000");
+        assert!(progress.remaining().is_empty());
+
+        let progress = snapshot(2 << 20, Some(1 << 20), 0, 20);
+        assert_eq!(progress.percentage(), "--- %");
+        assert!(snapshot(0, Some(1), 0, 1).sizes().ends_with("> 9.999"));
+    }
+
+    /// Test speed and elapsed time formatting.
+    #[test]
+    fn speed_and_elapsed_formatting() {
+        assert!(snapshot(1 << 20, None, 0, 2).speed().is_empty());
+        assert_eq!(snapshot(30 << 10, None, 0, 4).speed(), "7.5 KiB/s");
+        assert_eq!(snapshot(4 << 30, None, 0, 4).speed(), "1.0 GiB/s");
+        assert_eq!(format_elapsed(Duration::from_millis(999)), "");
+        assert_eq!(format_elapsed(Duration::from_secs(75)), "1:15");
+        assert_eq!(format_elapsed(Duration::from_secs(3725)), "1:02:05");
+    }
+
+    /// Test that remaining time estimates are rounded up like upstream `xz`.
+    #[test]
+    fn remaining_time_rounding() {
+        assert_eq!(format_remaining(7), "7 s");
+        assert_eq!(format_remaining(41), "45 s");
+        assert_eq!(format_remaining(121), "2 min 10 s");
+        assert_eq!(format_remaining(1000), "17 min");
+        assert_eq!(format_remaining(3 * 3600 + 1), "3 h 10 min");
+        assert_eq!(format_remaining(20 * 3600), "20 h");
+        assert_eq!(format_remaining(50 * 3600), "2 d 2 h");
+        assert_eq!(format_remaining(20 * 86_400), "20 d");
+        assert_eq!(format_remaining(1000 * 86_400), "");
+    }
+
+    /// Test that tracked streams count bytes.
+    #[test]
+    fn tracked_streams_count_bytes() {
+        let progress = Progress::start(OperationMode::Decompress, None, false);
+        let mut input = progress.track_input(&b"compressed"[..]);
+        let mut output = progress.track_output(Vec::new());
+        io::copy(&mut input, &mut output).unwrap();
+
+        let snapshot = Snapshot::new(
+            OperationMode::Decompress,
+            None,
+            &progress.counters,
+            Duration::ZERO,
+        );
+        assert_eq!(snapshot.compressed, 10);
+        assert_eq!(snapshot.uncompressed, 10);
+    }
+}
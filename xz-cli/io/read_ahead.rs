@@ -0,0 +1,71 @@
+//! Background prefetch of the next input file.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::thread::{self, JoinHandle};
+
+use super::HintedFile;
+use crate::error::{DiagnosticCause, Error, IoErrorNoCode, Result};
+
+/// Amount of the next file read ahead while the current one is processed.
+pub(crate) const READ_AHEAD_SIZE: usize = 1024 * 1024;
+
+/// Reader of a file and the first bytes read from it.
+type Prefetched = (Box<dyn Read + Send>, Vec<u8>);
+
+/// An input file that is opened and partly read on a background thread.
+///
+/// When many small files are decompressed to stdout, this overlaps opening and reading
+/// the next file with decoding the current one. Only regular files are read ahead, as
+/// opening anything else may block or have side effects.
+pub(crate) struct ReadAhead {
+    handle: JoinHandle<io::Result<Prefetched>>,
+}
+
+impl ReadAhead {
+    /// Starts reading up to `limit` bytes of `path`, or returns `None` if it isn't a
+    /// regular file.
+    pub(crate) fn start(path: &Path, limit: usize, io_hints: bool) -> Option<Self> {
+        if !std::fs::metadata(path).is_ok_and(|metadata| metadata.is_file()) {
+            return None;
+        }
+
+        let path = path.to_path_buf();
+        let handle = thread::Builder::new()
+            .name("xz-read-ahead".into())
+            .spawn(move || {
+                let file = File::open(path)?;
+                let mut reader: Box<dyn Read + Send> = if io_hints {
+                    Box::new(HintedFile::new(file))
+                } else {
+                    Box::new(file)
+                };
+                let mut head = Vec::new();
+                reader.by_ref().take(limit as u64).read_to_end(&mut head)?;
+                Ok((reader, head))
+            })
+            .ok()?;
+        Some(Self { handle })
+    }
+
+    /// Waits for the prefetch and returns a reader over the whole file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OpenInput`] if the file couldn't be opened or read.
+    pub(crate) fn finish(self, capacity: usize) -> Result<Box<dyn Read>> {
+        let prefetched = self
+            .handle
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("read-ahead thread panicked")));
+        let (reader, head) = prefetched.map_err(|source| {
+            DiagnosticCause::from(Error::OpenInput {
+                source: IoErrorNoCode::new(source),
+            })
+        })?;
+        Ok(Box::new(
+            io::Cursor::new(head).chain(io::BufReader::with_capacity(capacity, reader)),
+        ))
+    }
+}
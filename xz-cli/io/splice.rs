@@ -0,0 +1,220 @@
+//! Copying between file descriptors inside the kernel on Linux.
+//!
+//! `xz -dc` copies standard input that isn't compressed to standard output unchanged.
+//! When either side is a pipe, `splice(2)` moves the bytes without copying them through
+//! user space, and `copy_file_range(2)` does the same between two regular files. Other
+//! descriptors, or kernels and file systems that refuse the call, get an ordinary copy.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd};
+use std::os::unix::fs::FileTypeExt;
+use std::ptr;
+
+use crate::signals::{self, AbortOnSignal};
+
+/// Bytes moved by one system call
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// Error of [`copy`], by the side that failed
+#[derive(Debug)]
+pub(crate) enum CopyError {
+    /// Reading the input failed.
+    Read(io::Error),
+    /// Writing the output failed.
+    Write(io::Error),
+}
+
+/// How [`copy`] moves the data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    Splice,
+    CopyFileRange,
+    Userspace,
+}
+
+/// Returns standard input as a [`File`] on a duplicate of its descriptor.
+///
+/// Reading it bypasses the buffer of [`io::Stdin`], so nothing is read ahead of what the
+/// caller asks for and the rest stays in the descriptor for [`copy`].
+pub(crate) fn raw_stdin() -> io::Result<File> {
+    Ok(File::from(io::stdin().as_fd().try_clone_to_owned()?))
+}
+
+/// Returns standard output as a [`File`] on a duplicate of its descriptor.
+///
+/// Anything buffered in [`io::Stdout`] has to be flushed before writing to it.
+pub(crate) fn raw_stdout() -> io::Result<File> {
+    Ok(File::from(io::stdout().as_fd().try_clone_to_owned()?))
+}
+
+/// Copies `input` to `output` from their current positions until the end of the input.
+///
+/// Like [`AbortOnSignal`], fails with [`io::ErrorKind::Interrupted`] once a stop signal
+/// has arrived.
+///
+/// # Returns
+///
+/// The number of bytes copied.
+///
+/// # Errors
+///
+/// Returns [`CopyError::Read`] or [`CopyError::Write`] if reading or writing fails.
+pub(crate) fn copy(input: &File, output: &File) -> Result<u64, CopyError> {
+    let mut method = method(input, output);
+    let mut copied = 0_u64;
+    while method != Method::Userspace {
+        if signals::user_abort().is_some() {
+            return Err(CopyError::Read(io::ErrorKind::Interrupted.into()));
+        }
+        // SAFETY: Both descriptors stay open for the call, and the null offsets make the
+        // kernel use and advance their file positions instead of reading through them.
+        let moved = unsafe {
+            match method {
+                Method::Splice => libc::splice(
+                    input.as_raw_fd(),
+                    ptr::null_mut(),
+                    output.as_raw_fd(),
+                    ptr::null_mut(),
+                    CHUNK_SIZE,
+                    libc::SPLICE_F_MOVE,
+                ),
+                _ => libc::copy_file_range(
+                    input.as_raw_fd(),
+                    ptr::null_mut(),
+                    output.as_raw_fd(),
+                    ptr::null_mut(),
+                    CHUNK_SIZE,
+                    0,
+                ),
+            }
+        };
+        match u64::try_from(moved) {
+            // Files in `/proc` and similar claim to be empty to `copy_file_range`, so the
+            // end of the input is only trusted once something was copied.
+            Ok(0) if method == Method::CopyFileRange && copied == 0 => {
+                method = Method::Userspace;
+            }
+            Ok(0) => return Ok(copied),
+            Ok(moved) => copied += moved,
+            Err(_) => {
+                let err = io::Error::last_os_error();
+                match err.raw_os_error() {
+                    Some(libc::EINTR) => {}
+                    Some(
+                        libc::EINVAL
+                        | libc::ENOSYS
+                        | libc::EXDEV
+                        | libc::EOPNOTSUPP
+                        | libc::EPERM
+                        | libc::EBADF,
+                    ) if copied == 0 => method = Method::Userspace,
+                    Some(libc::EPIPE | libc::ENOSPC | libc::EDQUOT | libc::EFBIG) => {
+                        return Err(CopyError::Write(err));
+                    }
+                    _ => return Err(CopyError::Read(err)),
+                }
+            }
+        }
+    }
+    Ok(copied + copy_userspace(input, output)?)
+}
+
+/// Chooses how to copy from `input` to `output` by the types of their descriptors.
+fn method(input: &File, output: &File) -> Method {
+    let file_type = |file: &File| file.metadata().map(|metadata| metadata.file_type()).ok();
+    let (Some(input), Some(output)) = (file_type(input), file_type(output)) else {
+        return Method::Userspace;
+    };
+    if input.is_fifo() || output.is_fifo() {
+        Method::Splice
+    } else if input.is_file() && output.is_file() {
+        Method::CopyFileRange
+    } else {
+        Method::Userspace
+    }
+}
+
+/// Copies `input` to `output` through a buffer.
+fn copy_userspace(mut input: &File, mut output: &File) -> Result<u64, CopyError> {
+    let mut input = AbortOnSignal(&mut input);
+    let mut buffer = vec![0_u8; CHUNK_SIZE];
+    let mut copied = 0_u64;
+    loop {
+        let read = input.read(&mut buffer).map_err(CopyError::Read)?;
+        if read == 0 {
+            return Ok(copied);
+        }
+        output
+            .write_all(&buffer[..read])
+            .map_err(CopyError::Write)?;
+        copied += read as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::{Read, Seek, SeekFrom, Write};
+
+    use super::{copy, method, Method};
+
+    fn temp_file(dir: &tempfile::TempDir, name: &str, contents: &[u8]) -> File {
+        let path = dir.path().join(name);
+        fs::write(&path, contents).unwrap();
+        File::options().read(true).write(true).open(path).unwrap()
+    }
+
+    /// Regular files are copied with `copy_file_range` from the current input position.
+    #[test]
+    fn copies_between_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents: Vec<u8> = (0..300_000_u32).map(|i| (i % 251) as u8).collect();
+        let mut input = temp_file(&dir, "input", &contents);
+        let output = temp_file(&dir, "output", b"");
+        assert_eq!(method(&input, &output), Method::CopyFileRange);
+
+        input.seek(SeekFrom::Start(7)).unwrap();
+        assert_eq!(copy(&input, &output).unwrap(), contents.len() as u64 - 7);
+        assert_eq!(fs::read(dir.path().join("output")).unwrap(), &contents[7..]);
+    }
+
+    /// A pipe on either side is spliced.
+    #[test]
+    fn copies_into_pipe() {
+        let dir = tempfile::tempdir().unwrap();
+        let contents = b"spliced into a pipe".repeat(1000);
+        let input = temp_file(&dir, "input", &contents);
+        let (mut reader, writer) = std::io::pipe().unwrap();
+        let writer = File::from(std::os::fd::OwnedFd::from(writer));
+        assert_eq!(method(&input, &writer), Method::Splice);
+
+        let reading = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            reader.read_to_end(&mut received).unwrap();
+            received
+        });
+        assert_eq!(copy(&input, &writer).unwrap(), contents.len() as u64);
+        drop(writer);
+        assert_eq!(reading.join().unwrap(), contents);
+    }
+
+    /// Appending output refuses `copy_file_range`, so the data is copied through a buffer.
+    #[test]
+    fn falls_back_to_userspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = temp_file(&dir, "input", b"copied through a buffer");
+        let mut output = File::options()
+            .append(true)
+            .create(true)
+            .open(dir.path().join("output"))
+            .unwrap();
+        output.write_all(b"> ").unwrap();
+
+        assert_eq!(copy(&input, &output).unwrap(), 23);
+        assert_eq!(
+            fs::read(dir.path().join("output")).unwrap(),
+            b"> copied through a buffer"
+        );
+    }
+}
@@ -1,9 +1,4 @@
-use std::fs::File;
 use std::io;
-use std::io::Read;
-use std::io::Seek;
-use std::io::SeekFrom;
-use std::io::Write;
 use std::path::PathBuf;
 
 use tempfile::TempDir;
@@ -11,8 +6,7 @@ use tempfile::TempDir;
 use crate::config::CliConfig;
 use crate::error::{DiagnosticCause, Error};
 
-use super::SparseFileWriter;
-use super::{open_output, open_output_file};
+use super::open_output;
 
 fn temp_file(name: &str) -> io::Result<(TempDir, PathBuf)> {
     let dir = tempfile::tempdir()?;
@@ -20,116 +14,6 @@ fn temp_file(name: &str) -> io::Result<(TempDir, PathBuf)> {
     Ok((dir, path))
 }
 
-/// Sparse writer preserves correct length for all-zero output.
-#[test]
-fn sparse_writer_sets_len_for_trailing_hole() {
-    let (_dir, path) = temp_file("all-zero.tmp").unwrap();
-
-    let file = File::create(&path).unwrap();
-
-    let mut w = SparseFileWriter::new(file);
-
-    let zeros = vec![0u8; 128 * 1024];
-    w.write_all(&zeros).unwrap();
-    w.flush().unwrap();
-
-    let meta = std::fs::metadata(&path).unwrap();
-    assert_eq!(meta.len(), zeros.len() as u64);
-
-    let mut f = File::open(&path).unwrap();
-    let mut head = [1u8; 16];
-    f.read_exact(&mut head).unwrap();
-    assert!(head.iter().all(|&b| b == 0));
-
-    f.seek(SeekFrom::End(-16)).unwrap();
-    let mut tail = [1u8; 16];
-    f.read_exact(&mut tail).unwrap();
-    assert!(tail.iter().all(|&b| b == 0));
-}
-
-/// Sparse writer preserves correct contents across a large zero run.
-#[test]
-fn sparse_writer_keeps_data_around_hole() {
-    let (_dir, path) = temp_file("mixed.tmp").unwrap();
-
-    let file = File::create(&path).unwrap();
-    let mut w = SparseFileWriter::new(file);
-
-    w.write_all(b"ABC").unwrap();
-    let zeros = vec![0u8; 8192];
-    w.write_all(&zeros).unwrap();
-    w.write_all(b"XYZ").unwrap();
-    w.flush().unwrap();
-
-    let expected_len: u64 = 3 + 8192 + 3;
-    let meta = std::fs::metadata(&path).unwrap();
-    assert_eq!(meta.len(), expected_len);
-
-    let mut f = File::open(&path).unwrap();
-    let mut buf = [0u8; 3];
-    f.read_exact(&mut buf).unwrap();
-    assert_eq!(&buf, b"ABC");
-
-    f.seek(SeekFrom::Start(3 + 123)).unwrap();
-    let mut mid = [1u8; 1];
-    f.read_exact(&mut mid).unwrap();
-    assert_eq!(mid[0], 0);
-
-    f.seek(SeekFrom::End(-3)).unwrap();
-    let mut tail = [0u8; 3];
-    f.read_exact(&mut tail).unwrap();
-    assert_eq!(&tail, b"XYZ");
-}
-
-/// Small zero runs below the threshold are written literally.
-#[test]
-fn sparse_writer_writes_small_zero_runs_literally() {
-    let (_dir, path) = temp_file("small-zero.tmp").unwrap();
-
-    let file = File::create(&path).unwrap();
-    let mut w = SparseFileWriter::with_threshold(file, 1 << 20);
-
-    let mut data = Vec::new();
-    data.extend_from_slice(b"ABC");
-    data.extend(std::iter::repeat_n(0u8, 1024));
-    data.extend_from_slice(b"XYZ");
-
-    w.write_all(&data).unwrap();
-    w.flush().unwrap();
-
-    let mut read_back = Vec::new();
-    File::open(&path)
-        .unwrap()
-        .read_to_end(&mut read_back)
-        .unwrap();
-    assert_eq!(read_back, data);
-}
-
-/// Zero runs spanning multiple writes still produce correct output.
-#[test]
-fn sparse_writer_handles_zero_runs_split_across_writes() {
-    let (_dir, path) = temp_file("split.tmp").unwrap();
-
-    let file = File::create(&path).unwrap();
-    let mut w = SparseFileWriter::with_threshold(file, 64);
-
-    w.write_all(b"ABC").unwrap();
-    w.write_all(&[0u8; 32]).unwrap();
-    w.write_all(&[0u8; 32]).unwrap();
-    w.write_all(b"XYZ").unwrap();
-    w.flush().unwrap();
-
-    let expected_len: u64 = 3 + 64 + 3;
-    let meta = std::fs::metadata(&path).unwrap();
-    assert_eq!(meta.len(), expected_len);
-
-    let mut f = File::open(&path).unwrap();
-    f.seek(SeekFrom::Start(3)).unwrap();
-    let mut zeros = vec![1u8; 64];
-    f.read_exact(&mut zeros).unwrap();
-    assert!(zeros.iter().all(|&b| b == 0));
-}
-
 /// Test that `open_output` rejects existing file atomically without force.
 #[test]
 fn open_output_rejects_existing_file_atomically_without_force() {
@@ -144,16 +28,74 @@ fn open_output_rejects_existing_file_atomically_without_force() {
     ));
 }
 
-/// Test that `open_output_file` rejects existing file atomically without force.
+/// Test that a mapped input yields the file contents and falls back for empty files.
+#[cfg(unix)]
 #[test]
-fn open_output_file_rejects_existing_file_atomically_without_force() {
-    let (_dir, path) = temp_file("existing-file.tmp").unwrap();
-    std::fs::write(&path, b"existing").unwrap();
+fn mapped_input_reads_whole_file() {
+    use std::io::Read;
+
+    let (_dir, path) = temp_file("mapped.bin").unwrap();
+    let data: Vec<u8> = (0..=255u8).cycle().take(70_000).collect();
+    std::fs::write(&path, &data).unwrap();
+
+    let mut reader = super::MmapReader::new(&std::fs::File::open(&path).unwrap()).unwrap();
+    let mut read = Vec::new();
+    let mut chunk = [0; 4096];
+    loop {
+        let n = reader.read(&mut chunk).unwrap();
+        if n == 0 {
+            break;
+        }
+        read.extend_from_slice(&chunk[..n]);
+    }
+    assert_eq!(read, data);
+
+    std::fs::write(&path, b"").unwrap();
+    assert!(super::MmapReader::new(&std::fs::File::open(&path).unwrap()).is_none());
+    let mut read = Vec::new();
+    super::open_mapped_input(&path, 16, true)
+        .unwrap()
+        .read_to_end(&mut read)
+        .unwrap();
+    assert!(read.is_empty());
+}
 
-    let config = CliConfig::default();
-    let err = open_output_file(&path, &config).err().unwrap();
-    assert!(matches!(
-        err,
-        DiagnosticCause::Error(Error::OutputExists { .. })
-    ));
+/// Test that page cache hints don't change what is read across drop intervals.
+#[test]
+fn hinted_file_reads_whole_file() {
+    use std::io::Read;
+
+    let (_dir, path) = temp_file("hinted.bin").unwrap();
+    let data: Vec<u8> = (0..=250u8).cycle().take(9 * 1024 * 1024).collect();
+    std::fs::write(&path, &data).unwrap();
+
+    let mut read = Vec::new();
+    super::open_buffered_input(&path, 64 * 1024, true)
+        .unwrap()
+        .read_to_end(&mut read)
+        .unwrap();
+    assert!(read == data);
+}
+
+/// Test that a read-ahead input yields the prefetched head followed by the rest.
+#[test]
+fn read_ahead_reads_whole_file() {
+    use std::io::Read;
+
+    let (_dir, path) = temp_file("ahead.bin").unwrap();
+    let data: Vec<u8> = (0..=250u8).cycle().take(100_000).collect();
+    std::fs::write(&path, &data).unwrap();
+
+    for limit in [0, 4096, 200_000] {
+        let mut read = Vec::new();
+        super::ReadAhead::start(&path, limit, true)
+            .unwrap()
+            .finish(1024)
+            .unwrap()
+            .read_to_end(&mut read)
+            .unwrap();
+        assert!(read == data);
+    }
+
+    assert!(super::ReadAhead::start(path.parent().unwrap(), 4096, true).is_none());
 }
@@ -0,0 +1,58 @@
+//! Writer wrapper that remembers the first write error.
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// Records the first error returned by a writer wrapped with [`WriteTracker::track`].
+///
+/// The compressor reports every failure as a generic coder error, so this is how the
+/// caller tells a failed write to the output apart from a problem with the input.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct WriteTracker {
+    error: Rc<RefCell<Option<io::Error>>>,
+}
+
+impl WriteTracker {
+    /// Wraps `inner` so that its errors are recorded in this tracker.
+    pub(crate) fn track<W: Write>(&self, inner: W) -> TrackedWriter<W> {
+        TrackedWriter {
+            inner,
+            tracker: self.clone(),
+        }
+    }
+
+    /// Takes the recorded error, if a write or flush failed.
+    pub(crate) fn take(&self) -> Option<io::Error> {
+        self.error.borrow_mut().take()
+    }
+
+    fn record<T>(&self, result: io::Result<T>) -> io::Result<T> {
+        result.map_err(|err| {
+            let mut slot = self.error.borrow_mut();
+            if slot.is_some() || err.kind() == io::ErrorKind::Interrupted {
+                return err;
+            }
+            // The original goes to the tracker; the caller gets an equivalent error.
+            let copy = io::Error::new(err.kind(), err.to_string());
+            *slot = Some(err);
+            copy
+        })
+    }
+}
+
+/// Writer returned by [`WriteTracker::track`].
+pub(crate) struct TrackedWriter<W> {
+    inner: W,
+    tracker: WriteTracker,
+}
+
+impl<W: Write> Write for TrackedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tracker.record(self.inner.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.tracker.record(self.inner.flush())
+    }
+}
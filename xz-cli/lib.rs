@@ -6,22 +6,42 @@
 
 mod config;
 mod error;
+mod events;
 mod format;
+pub mod i18n;
 mod io;
+mod logging;
 mod lzma1;
-mod operations;
+pub mod operations;
 mod process;
+mod sandbox;
+mod signals;
 mod utils;
 
 #[cfg(test)]
 mod tests;
 
-pub use config::{CliConfig, OperationMode, DEFAULT_BUFFER_SIZE, LZMA_EXTENSION, XZ_EXTENSION};
+pub use config::{
+    CliConfig, ListSizes, OperationMode, DEFAULT_BUFFER_SIZE, LZIP_EXTENSION, LZMA_EXTENSION,
+    XZ_EXTENSION,
+};
 pub use error::{
     format_diagnostic_for_stderr, Diagnostic, DiagnosticCause, Error, ExitStatus, IoErrorNoCode,
     Report, Result, Severity, Warning,
 };
-pub use io::{generate_output_filename, has_compression_extension, open_input, open_output};
-pub use operations::{compress_file, decompress_file};
-pub use process::{cleanup_input_file, parse_memory_limit, process_file, run_cli};
-pub use utils::argfiles;
+pub use events::{Callback, Event};
+pub use format::info::{print_info_memory, print_robot_version, print_version};
+pub use io::{
+    generate_output_filename, has_compressed_content, has_compression_extension, open_input,
+    open_output,
+};
+pub use logging::{LogArgs, LogLevel, LOG_ENV};
+pub use lzma1::parse_lzma1_options;
+pub use operations::{
+    compress_file, compress_file_with, decompress_file, decompress_file_with, recompress_file,
+};
+pub use process::{
+    cleanup_input_file, exit_with_error, parse_block_list, parse_block_size, parse_memory_limit,
+    process_file, resolve_input_files, run_cli, run_cli_with, run_tool,
+};
+pub use utils::{argfiles, env_options, user_config};
@@ -0,0 +1,302 @@
+//! Structured event log for debugging batch jobs.
+//!
+//! Disabled unless `--log-level` or `RUST_LOG` selects a level. Each event is written to
+//! stderr as one `logfmt` line of `key=value` pairs, separate from the diagnostics and
+//! `-v` output, so log collectors can filter and parse it:
+//!
+//! ```text
+//! ts=1700000000.123 level=info event=file.close path=a.txt result=ok bytes_read=1048576 bytes_written=2048 open_ms=0.210 process_ms=35.802 finish_ms=0.391
+//! ```
+
+use std::fmt::{Display, Write as _};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use clap::{Args, ValueEnum};
+
+use xz_core::config::StreamSummary;
+
+use crate::error::{DiagnosticCause, Result};
+
+/// Environment variable read by the tools when `--log-level` isn't given
+pub const LOG_ENV: &str = "RUST_LOG";
+
+/// Verbosity of the event log, from the fewest events to the most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum LogLevel {
+    /// Inputs that failed
+    Error,
+    /// Inputs that finished with a warning
+    Warn,
+    /// Settings, their adjustments, and every finished input with its phase timings
+    Info,
+    /// Inputs and outputs as they are opened
+    Debug,
+    /// Every phase of every input as it ends
+    Trace,
+}
+
+impl LogLevel {
+    /// Returns the level as written in the log and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    /// Returns the level [`LOG_ENV`] selects for `program`, if any.
+    ///
+    /// Like `env_logger`, the variable is a comma-separated list of `level` and
+    /// `target=level` directives. A target applies if it is `program` or starts with
+    /// `xz_cli`; the last directive that applies wins, and `off` disables the log.
+    pub fn from_env(program: &str) -> Option<Self> {
+        std::env::var(LOG_ENV)
+            .ok()
+            .and_then(|directives| Self::from_directives(&directives, program))
+    }
+
+    fn from_directives(directives: &str, program: &str) -> Option<Self> {
+        let mut selected = None;
+        for directive in directives.split(',').map(str::trim) {
+            let (target, level) = match directive.split_once('=') {
+                Some((target, level)) => (Some(target), level),
+                None => (None, directive),
+            };
+            if target.is_some_and(|target| target != program && !target.starts_with("xz_cli")) {
+                continue;
+            }
+            if level.eq_ignore_ascii_case("off") {
+                selected = Some(None);
+            } else if let Ok(level) = LogLevel::from_str(level, true) {
+                selected = Some(Some(level));
+            }
+        }
+        selected.flatten()
+    }
+}
+
+/// The `--log-level` option shared by the tools
+#[derive(Debug, Clone, Default, Args)]
+pub struct LogArgs {
+    /// Log structured events to stderr, up to this level of detail.
+    ///
+    /// Without this option, the level is taken from the `RUST_LOG` environment variable.
+    #[arg(long = "log-level", value_name = "LEVEL", value_enum)]
+    pub log_level: Option<LogLevel>,
+}
+
+/// Phases of processing one input, timed for the log
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Phase {
+    /// Checking the input and opening it and the output
+    Open,
+    /// Compressing or decompressing
+    Process,
+    /// Copying the file attributes, renaming the output and removing the input
+    Finish,
+}
+
+impl Phase {
+    const ALL: [Phase; 3] = [Phase::Open, Phase::Process, Phase::Finish];
+
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Open => "open",
+            Phase::Process => "process",
+            Phase::Finish => "finish",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Phase::Open => "open_ms",
+            Phase::Process => "process_ms",
+            Phase::Finish => "finish_ms",
+        }
+    }
+}
+
+/// Times the phases of one input; a phase starts when the previous one ends.
+pub(crate) struct PhaseTimer {
+    level: Option<LogLevel>,
+    last: Instant,
+    durations: [Option<Duration>; 3],
+}
+
+impl PhaseTimer {
+    /// Starts timing the first phase.
+    pub(crate) fn start(level: Option<LogLevel>) -> Self {
+        Self {
+            level,
+            last: Instant::now(),
+            durations: [None; 3],
+        }
+    }
+
+    /// Ends `phase` of the input named `path`.
+    pub(crate) fn end(&mut self, phase: Phase, path: &str) {
+        let now = Instant::now();
+        let duration = now - self.last;
+        self.last = now;
+        self.durations[phase as usize] = Some(duration);
+        event(
+            self.level,
+            LogLevel::Trace,
+            "file.phase",
+            &[
+                ("path", &path),
+                ("phase", &phase.name()),
+                ("ms", &Millis(duration)),
+            ],
+        );
+    }
+
+    /// Logs the result of the input named `path`, with the phases that ended.
+    pub(crate) fn finish(&self, path: &str, result: &Result<Option<StreamSummary>>) {
+        let (level, outcome, error) = match result {
+            Ok(_) => (LogLevel::Info, "ok", None),
+            Err(cause @ DiagnosticCause::Warning(_)) => {
+                (LogLevel::Warn, "warning", Some(cause.to_string()))
+            }
+            Err(cause @ DiagnosticCause::Error(_)) => {
+                (LogLevel::Error, "error", Some(cause.to_string()))
+            }
+        };
+        if !enabled(self.level, level) {
+            return;
+        }
+
+        let summary = result.as_ref().ok().copied().flatten();
+        let bytes_read = summary.map(|summary| summary.bytes_read);
+        let bytes_written = summary.map(|summary| summary.bytes_written);
+        let durations: Vec<(&str, Millis)> = Phase::ALL
+            .iter()
+            .filter_map(|&phase| Some((phase.key(), Millis(self.durations[phase as usize]?))))
+            .collect();
+
+        let mut fields: Vec<(&str, &dyn Display)> = vec![("path", &path), ("result", &outcome)];
+        if let Some(error) = &error {
+            fields.push(("error", error));
+        }
+        if let (Some(read), Some(written)) = (&bytes_read, &bytes_written) {
+            fields.push(("bytes_read", read));
+            fields.push(("bytes_written", written));
+        }
+        fields.extend(durations.iter().map(|(key, ms)| (*key, ms as &dyn Display)));
+        event(self.level, level, "file.close", &fields);
+    }
+}
+
+/// A duration printed in milliseconds
+struct Millis(Duration);
+
+impl Display for Millis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.3}", self.0.as_secs_f64() * 1000.0)
+    }
+}
+
+/// Returns `true` if events of `level` are logged when the log is set to `selected`.
+pub(crate) fn enabled(selected: Option<LogLevel>, level: LogLevel) -> bool {
+    selected.is_some_and(|selected| level <= selected)
+}
+
+/// Writes the event `name` with `fields` to stderr if `level` is enabled by `selected`.
+pub(crate) fn event(
+    selected: Option<LogLevel>,
+    level: LogLevel,
+    name: &str,
+    fields: &[(&str, &dyn Display)],
+) {
+    if enabled(selected, level) {
+        eprintln!("{}", format_event(SystemTime::now(), level, name, fields));
+    }
+}
+
+/// Formats one log line.
+fn format_event(
+    time: SystemTime,
+    level: LogLevel,
+    name: &str,
+    fields: &[(&str, &dyn Display)],
+) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut line = format!(
+        "ts={}.{:03} level={} event={name}",
+        since_epoch.as_secs(),
+        since_epoch.subsec_millis(),
+        level.name()
+    );
+    for (key, value) in fields {
+        let value = value.to_string();
+        let plain = !value.is_empty()
+            && !value
+                .chars()
+                .any(|c| c.is_whitespace() || c.is_control() || c == '"' || c == '=');
+        if plain {
+            let _ = write!(line, " {key}={value}");
+        } else {
+            let _ = write!(line, " {key}={value:?}");
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `RUST_LOG` directives apply to this program or crate, and the last one wins.
+    #[test]
+    fn env_directives() {
+        assert_eq!(
+            LogLevel::from_directives("debug", "xz"),
+            Some(LogLevel::Debug)
+        );
+        assert_eq!(
+            LogLevel::from_directives("xz=TRACE", "xz"),
+            Some(LogLevel::Trace)
+        );
+        assert_eq!(LogLevel::from_directives("unxz=trace", "xz"), None);
+        assert_eq!(
+            LogLevel::from_directives("warn,xz_cli::process=info", "xz"),
+            Some(LogLevel::Info)
+        );
+        assert_eq!(LogLevel::from_directives("info,xz=off", "xz"), None);
+        assert_eq!(LogLevel::from_directives("hyper=debug,bogus", "xz"), None);
+    }
+
+    /// Values are quoted when they would otherwise break the `key=value` format.
+    #[test]
+    fn logfmt_lines() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let line = format_event(
+            time,
+            LogLevel::Info,
+            "file.close",
+            &[
+                ("path", &"my file.txt"),
+                ("result", &"ok"),
+                ("ms", &Millis(Duration::from_micros(1500))),
+                ("empty", &""),
+            ],
+        );
+        assert_eq!(
+            line,
+            "ts=1700000000.123 level=info event=file.close path=\"my file.txt\" result=ok \
+             ms=1.500 empty=\"\""
+        );
+    }
+
+    /// Only levels up to the selected one are logged.
+    #[test]
+    fn level_filter() {
+        assert!(enabled(Some(LogLevel::Info), LogLevel::Warn));
+        assert!(enabled(Some(LogLevel::Info), LogLevel::Info));
+        assert!(!enabled(Some(LogLevel::Info), LogLevel::Debug));
+        assert!(!enabled(None, LogLevel::Error));
+    }
+}
@@ -74,15 +74,16 @@ pub fn parse_lzma1_options(input: &str) -> ParseResult<CliOptions> {
         if part.is_empty() {
             continue;
         }
-        let (key, value) = part.split_once('=').ok_or_else(|| {
-            invalid_option("Options must be 'name=value' pairs separated with commas".to_string())
-        })?;
+        let pairs_error = || {
+            invalid_option(format!(
+                "{part}: Options must be 'name=value' pairs separated with commas"
+            ))
+        };
+        let (key, value) = part.split_once('=').ok_or_else(pairs_error)?;
         let key = key.trim();
         let value = value.trim();
         if value.is_empty() {
-            return Err(invalid_option(
-                "Options must be 'name=value' pairs separated with commas".into(),
-            ));
+            return Err(pairs_error());
         }
 
         match key {
@@ -1,23 +1,27 @@
 //! Compression and decompression operations for XZ CLI.
 
 use std::fs::File;
-use std::io;
+use std::io::{self, Read as _};
+use std::num::NonZeroU64;
 use std::path::Path;
 
 use xz_core::{
-    config::EncodeFormat,
-    file_info,
+    config::{DecodeMode, EncodeFormat, StreamSummary},
+    file_info, is_decode_format,
     options::lzma1::Lzma1Options,
     options::{
         BcjOptions, Compression, CompressionOptions, DecompressionOptions, DeltaOptions,
-        FilterConfig, FilterOptions, FilterType, Flags, LzmaOptions,
+        FilterConfig, FilterOptions, FilterType, Flags, IntegrityCheck, LzmaOptions,
+        MemlimitAdjustment,
     },
-    pipeline::{compress, decompress},
-    ratio, Error as CoreError, UnknownInputPolicy,
+    pipeline::{compress, decompress, recompress, verify_blocks},
+    read_decode_format_probe_prefix, BackendError, Error as CoreError, UnknownInputPolicy,
+    XZ_STREAM_HEADER_MAGIC,
 };
 
-use crate::config::CliConfig;
+use crate::config::{CliConfig, OperationMode};
 use crate::error::{DiagnosticCause, Error, IoErrorNoCode, Result, Warning};
+use crate::events::{self, Callback};
 use crate::format::list::{self, ListOutputContext, ListSummary};
 use crate::lzma1::parse_lzma1_options;
 
@@ -37,6 +41,9 @@ fn xz_message_from_core_error(err: &CoreError) -> String {
     match err {
         CoreError::Backend(backend) => backend.xz_message().to_string(),
         CoreError::InvalidOption(message) => message.clone(),
+        CoreError::MemoryLimitTooLow { .. } => {
+            "Memory usage limit is too low for the given filter setup.".to_string()
+        }
         _ => err.to_string(),
     }
 }
@@ -337,6 +344,14 @@ fn apply_filters_override(
     Ok(options)
 }
 
+/// Convert a `--threads` value to a [`xz_core::Threading`] setting.
+///
+/// `0` selects every available processor thread and larger counts than the host has are
+/// clamped by xz-core (the CLI warns about that up front).
+fn thread_setting(threads: usize) -> xz_core::Threading {
+    xz_core::Threading::Exact(u32::try_from(threads).unwrap_or(u32::MAX))
+}
+
 /// Apply `--threads` to compression options when supported by the container format.
 fn apply_threads_for_compression(
     mut options: CompressionOptions,
@@ -353,26 +368,10 @@ fn apply_threads_for_compression(
         return Ok(options);
     }
 
-    let thread_count = u32::try_from(threads)
-        .map_err(|_| DiagnosticCause::from(Error::InvalidThreadCount { count: threads }))?;
-    options = options.with_threads(xz_core::Threading::Exact(thread_count));
+    options = options.with_threads(thread_setting(threads));
     Ok(options)
 }
 
-/// Emit verbose/robot output for a completed compression operation.
-fn emit_compress_summary(config: &CliConfig, bytes_read: u64, bytes_written: u64) {
-    if !(config.verbose || config.robot) {
-        return;
-    }
-
-    let ratio = ratio(bytes_written, bytes_read);
-    if config.robot {
-        eprintln!("{bytes_read} {bytes_written} {ratio:.1}");
-    } else {
-        eprintln!("Compressed {bytes_read} bytes to {bytes_written} bytes ({ratio:.1}% ratio)");
-    }
-}
-
 /// Compresses data from an input reader to an output writer.
 ///
 /// Uses the XZ compression format with settings specified in [`CliConfig`].
@@ -387,14 +386,13 @@ fn emit_compress_summary(config: &CliConfig, bytes_read: u64, bytes_written: u64
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on successful compression.
+/// Returns the bytes read and written on successful compression.
 ///
 /// # Errors
 ///
 /// Returns an error in these cases:
 ///
 /// - Invalid compression level (must be 0-9)
-/// - Invalid thread count (too large for [`u32`])
 /// - Compression operation failure from the underlying XZ library
 /// - I/O errors during read or write operations
 ///
@@ -405,7 +403,26 @@ pub fn compress_file(
     mut input: impl io::Read,
     mut output: impl io::Write,
     config: &CliConfig,
-) -> Result<()> {
+) -> Result<StreamSummary> {
+    let (options, _) = compression_options(config)?;
+
+    // Perform compression and handle errors
+    let summary = compress(&mut input, &mut output, &options).map_err(|e| {
+        let message = xz_message_from_core_error(&e);
+        DiagnosticCause::from(Error::Compression { message })
+    })?;
+
+    Ok(summary)
+}
+
+/// Build the compression options for `config`, fitted into the compression memory limit.
+///
+/// # Returns
+///
+/// The options and the settings that were lowered to fit into the limit.
+fn compression_options(
+    config: &CliConfig,
+) -> Result<(CompressionOptions, Vec<MemlimitAdjustment>)> {
     let encode_format = resolve_encode_format(config);
 
     let compression_level = resolve_compression_level(config)?;
@@ -417,41 +434,108 @@ pub fn compress_file(
     let options = apply_lzma1_overrides(options, config, encode_format, compression_level)?;
     let options = apply_lzma2_overrides(options, config, encode_format, compression_level)?;
     let options = apply_filters_override(options, config, encode_format, compression_level)?;
-    let options = apply_threads_for_compression(options, config, encode_format)?;
+    let mut options = apply_threads_for_compression(options, config, encode_format)?;
+    options = apply_block_options(options, config, encode_format);
+    options = options
+        .with_flush_on_timeout(config.flush_timeout.is_some())
+        .with_auto_adjust(!config.no_adjust);
+    if let Some(limit) = config.compression_memory_limit.and_then(NonZeroU64::new) {
+        options = options.with_memlimit(limit);
+    }
 
-    // Perform compression and handle errors
-    let summary = compress(&mut input, &mut output, &options).map_err(|e| {
-        let message = xz_message_from_core_error(&e);
-        DiagnosticCause::from(Error::Compression { message })
-    })?;
+    options.fit_to_memlimit().map_err(|e| match e {
+        CoreError::MemoryLimitTooLow { required, limit } => {
+            DiagnosticCause::from(Error::MemoryLimitTooLow { required, limit })
+        }
+        e => DiagnosticCause::from(Error::Compression {
+            message: xz_message_from_core_error(&e),
+        }),
+    })
+}
+
+/// Returns the error for compression settings that need more memory than
+/// `--memlimit-compress` allows with `--no-adjust`, or when nothing fits at all.
+///
+/// Like upstream, this is checked once before any input is opened.
+pub(crate) fn compression_memlimit_error(config: &CliConfig) -> Option<DiagnosticCause> {
+    match compression_options(config) {
+        Err(err @ DiagnosticCause::Error(Error::MemoryLimitTooLow { .. })) => Some(err),
+        _ => None,
+    }
+}
 
-    emit_compress_summary(config, summary.bytes_read, summary.bytes_written);
+/// Returns the notices for the settings lowered to fit into `--memlimit-compress`.
+///
+/// Like upstream, a reduced thread count is only reported when `--threads` asked for it,
+/// while switching to single-threaded mode always is.
+/// Invalid options give no notices; compressing the first file reports them.
+pub(crate) fn compression_memlimit_notes(config: &CliConfig) -> Vec<Warning> {
+    let Some(limit) = config.compression_memory_limit.filter(|&limit| limit > 0) else {
+        return Vec::new();
+    };
+    let Ok((_, adjustments)) = compression_options(config) else {
+        return Vec::new();
+    };
 
-    Ok(())
+    adjustments
+        .into_iter()
+        .filter_map(|adjustment| match adjustment {
+            MemlimitAdjustment::Threads { requested, reduced } => config
+                .threads
+                .is_some()
+                .then_some(Warning::EncoderThreadsReduced {
+                    requested,
+                    reduced,
+                    limit,
+                }),
+            MemlimitAdjustment::SingleThreaded => Some(Warning::SingleThreadedMode { limit }),
+            MemlimitAdjustment::DictSize {
+                filter,
+                original,
+                reduced,
+            } => Some(Warning::DictionaryReduced {
+                filter,
+                original,
+                reduced,
+                limit,
+            }),
+        })
+        .collect()
 }
 
-/// Emit verbose/robot output for a completed decompression operation.
-fn emit_decompress_summary(config: &CliConfig, bytes_read: u64, bytes_written: u64) {
-    if !(config.verbose || config.robot) {
-        return;
+/// Apply `--block-size` and `--block-list`; like upstream they only affect `.xz` output.
+fn apply_block_options(
+    mut options: CompressionOptions,
+    config: &CliConfig,
+    encode_format: EncodeFormat,
+) -> CompressionOptions {
+    if encode_format != EncodeFormat::Xz {
+        return options;
     }
 
-    let ratio = ratio(bytes_written, bytes_read);
-    if config.robot {
-        eprintln!("{bytes_read} {bytes_written} {ratio:.1}");
-    } else {
-        eprintln!(
-            "Decompressed {bytes_read} bytes to {bytes_written} bytes ({ratio:.1}% expansion)"
-        );
+    if let Some(size) = config.block_size.and_then(NonZeroU64::new) {
+        options = options.with_block_size(Some(size));
     }
+    if let Some(list) = &config.block_list {
+        options = options.with_block_list(list.clone());
+    }
+    options
 }
 
-/// Apply `--memlimit` to decompression options when a nonzero limit is configured.
+/// Apply `--memlimit-decompress` and `--memlimit-mt-decompress` to decompression options.
+///
+/// The decompression limit is the hard limit for every decoder. The multi-threaded limit
+/// only makes the threaded decoder use fewer threads and never exceeds the hard limit.
 fn apply_memlimit(mut options: DecompressionOptions, config: &CliConfig) -> DecompressionOptions {
-    if let Some(memory_limit) = config.memory_limit {
-        if let Some(limit) = std::num::NonZeroU64::new(memory_limit) {
-            options = options.with_memlimit(limit);
-        }
+    let hard = config.memory_limit.and_then(NonZeroU64::new);
+    if let Some(limit) = hard {
+        options = options.with_memlimit(limit).with_memlimit_stop(Some(limit));
+    }
+
+    if let Some(limit) = config.mt_memory_limit.and_then(NonZeroU64::new) {
+        options = options
+            .with_memlimit(hard.map_or(limit, |hard| hard.min(limit)))
+            .with_memlimit_stop(Some(hard.unwrap_or(NonZeroU64::MAX)));
     }
     options
 }
@@ -465,19 +549,16 @@ fn apply_threads_for_decompression(
         return Ok(options);
     };
 
-    if config.format == xz_core::config::DecodeMode::Auto {
-        // Auto-detect mode cannot use liblzma's multi-threaded decoder. Ignore the
-        // CLI thread request here so common `xz -d -T4 file.xz` style invocations
-        // keep working instead of failing up front.
-        return Ok(options);
-    }
-    if config.format == xz_core::config::DecodeMode::Lzma {
+    // In auto-detect mode xz-core switches `.xz` input to the multi-threaded decoder and
+    // decodes other formats single-threaded. `.lzma` and `.lz` are always single-threaded.
+    if matches!(
+        config.format,
+        xz_core::config::DecodeMode::Lzma | xz_core::config::DecodeMode::Lzip
+    ) {
         return Ok(options);
     }
 
-    let thread_count = u32::try_from(threads)
-        .map_err(|_| DiagnosticCause::from(Error::InvalidThreadCount { count: threads }))?;
-    options = options.with_threads(xz_core::Threading::Exact(thread_count));
+    options = options.with_threads(thread_setting(threads));
     Ok(options)
 }
 
@@ -501,7 +582,7 @@ fn decompress_raw(
     input: &mut impl io::Read,
     output: &mut impl io::Write,
     config: &CliConfig,
-) -> Result<()> {
+) -> Result<StreamSummary> {
     let Some(raw_lzma1) = config.lzma1.as_deref() else {
         return Err(DiagnosticCause::from(Error::InvalidOption {
             message: "--format=raw requires --lzma1 filter options".into(),
@@ -521,20 +602,53 @@ fn decompress_raw(
         DiagnosticCause::from(Error::Decompression { message })
     })?;
 
-    emit_decompress_summary(config, outcome.bytes_read, outcome.bytes_written);
-    Ok(())
+    let summary = StreamSummary {
+        bytes_read: outcome.bytes_read,
+        bytes_written: outcome.bytes_written,
+    };
+    Ok(summary)
 }
 
 /// Emit an unsupported integrity-check warning when applicable.
-fn warn_unsupported_check(unsupported_check_id: Option<u32>, config: &CliConfig) -> Result<()> {
-    if let Some(check_id) = unsupported_check_id {
-        if !config.no_warn {
-            return Err(DiagnosticCause::from(Warning::UnsupportedCheck {
-                check_id,
-            }));
+fn warn_unsupported_check(unsupported_check_id: Option<u32>) -> Result<()> {
+    match unsupported_check_id {
+        Some(check_id) => Err(DiagnosticCause::from(Warning::UnsupportedCheck {
+            check_id,
+        })),
+        None => Ok(()),
+    }
+}
+
+/// Compresses like [`compress_file`], reporting progress to `callback`.
+///
+/// # Parameters
+///
+/// * `input` - Reader providing uncompressed data
+/// * `output` - Writer receiving compressed data
+/// * `config` - CLI configuration specifying compression level, threads, and verbosity
+/// * `callback` - Receives an [`Event::Progress`](crate::Event::Progress) after every read
+///   from `input`
+///
+/// # Returns
+///
+/// Returns the bytes read and written on successful compression.
+///
+/// # Errors
+///
+/// Returns the same errors as [`compress_file`].
+pub fn compress_file_with(
+    input: impl io::Read,
+    output: impl io::Write,
+    config: &CliConfig,
+    callback: Option<&mut Callback<'_>>,
+) -> Result<StreamSummary> {
+    match callback {
+        Some(callback) => {
+            let (input, output) = events::track(input, output, callback);
+            compress_file(input, output, config)
         }
+        None => compress_file(input, output, config),
     }
-    Ok(())
 }
 
 /// Decompresses XZ or LZMA data from an input reader to an output writer.
@@ -551,13 +665,12 @@ fn warn_unsupported_check(unsupported_check_id: Option<u32>, config: &CliConfig)
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on successful decompression.
+/// Returns the bytes read and written on successful decompression.
 ///
 /// # Errors
 ///
 /// Returns an error in these cases:
 ///
-/// - Invalid thread count (too large for [`u32`])
 /// - Corrupted or invalid input data
 /// - Memory limit exceeded during decompression
 /// - Decompression operation failure from the underlying XZ library
@@ -567,7 +680,7 @@ pub fn decompress_file(
     mut output: impl io::Write,
     config: &CliConfig,
     stdin_input: bool,
-) -> Result<()> {
+) -> Result<StreamSummary> {
     if config.format == xz_core::config::DecodeMode::Raw {
         return decompress_raw(&mut input, &mut output, config);
     }
@@ -587,21 +700,305 @@ pub fn decompress_file(
         UnknownInputPolicy::Error
     };
 
+    let options = decompression_options(config, unknown_input_policy)?;
+
+    let outcome = decompress(&mut input, &mut output, &options).map_err(|e| {
+        let message = xz_message_from_core_error(&e);
+        DiagnosticCause::from(Error::Decompression { message })
+    })?;
+
+    let summary = StreamSummary {
+        bytes_read: outcome.bytes_read,
+        bytes_written: outcome.bytes_written,
+    };
+
+    warn_unsupported_check(outcome.unsupported_check_id)?;
+    Ok(summary)
+}
+
+/// Build the decompression options for `config`.
+fn decompression_options(
+    config: &CliConfig,
+    unknown_input_policy: UnknownInputPolicy,
+) -> Result<DecompressionOptions> {
     let options = DecompressionOptions::default()
         .with_mode(config.format)
         .with_flags(build_decoder_flags(config))
         .with_unknown_input_policy(unknown_input_policy);
     let options = apply_threads_for_decompression(options, config)?;
-    let options = apply_memlimit(options, config);
+    Ok(apply_memlimit(options, config))
+}
 
-    let outcome = decompress(&mut input, &mut output, &options).map_err(|e| {
-        let message = xz_message_from_core_error(&e);
-        DiagnosticCause::from(Error::Decompression { message })
+/// Decompresses the input and compresses it again with the compression settings.
+///
+/// The decoded data goes straight to the encoder, so this streams like the other
+/// operations and needs no temporary file for the uncompressed data. As the result
+/// replaces the input under the same name, it keeps the container of the input: `.xz`
+/// stays `.xz` and `.lzma` stays `.lzma`, whatever `--format` says.
+///
+/// # Parameters
+///
+/// * `input` - Reader providing compressed XZ or LZMA data
+/// * `output` - Writer receiving the data compressed again
+/// * `config` - CLI configuration specifying both the decoder and the encoder settings
+///
+/// # Returns
+///
+/// Returns the compressed bytes read and written on success.
+///
+/// # Errors
+///
+/// Returns an error in these cases:
+///
+/// - Raw streams, which can't be told apart from other data
+/// - `.lz` input, as there is no lzip encoder
+/// - Invalid compression settings
+/// - Input that isn't compressed, or is corrupt
+/// - I/O errors during read or write operations
+pub fn recompress_file(
+    mut input: impl io::Read,
+    mut output: impl io::Write,
+    config: &CliConfig,
+) -> Result<StreamSummary> {
+    if config.format == DecodeMode::Raw {
+        return Err(DiagnosticCause::from(Error::InvalidOption {
+            message: "--recompress doesn't support --format=raw".into(),
+        }));
+    }
+
+    let prefix = read_decode_format_probe_prefix(&mut input).map_err(|source| {
+        DiagnosticCause::from(Error::Decompression {
+            message: source.to_string(),
+        })
+    })?;
+    let container = if is_decode_format(&prefix, DecodeMode::Lzip) {
+        return Err(DiagnosticCause::from(Error::Compression {
+            message: "--recompress doesn't support .lz files".into(),
+        }));
+    } else if is_decode_format(&prefix, DecodeMode::Lzma) {
+        DecodeMode::Lzma
+    } else {
+        DecodeMode::Xz
+    };
+    let mut input = io::Cursor::new(prefix).chain(input);
+
+    let decompression = decompression_options(config, UnknownInputPolicy::Error)?;
+    // `.lzma` has no integrity check, so `--check` only applies to the `.xz` files.
+    let check = match container {
+        DecodeMode::Lzma => IntegrityCheck::None,
+        _ => config.check,
+    };
+    let (compression, _) = compression_options(&CliConfig {
+        format: container,
+        check,
+        ..config.clone()
     })?;
 
-    emit_decompress_summary(config, outcome.bytes_read, outcome.bytes_written);
+    recompress(&mut input, &mut output, &decompression, &compression).map_err(|e| {
+        let message = xz_message_from_core_error(&e);
+        DiagnosticCause::from(Error::Compression { message })
+    })
+}
+
+/// Decompresses like [`decompress_file`], reporting progress to `callback`.
+///
+/// # Parameters
+///
+/// * `input` - Reader providing compressed data
+/// * `output` - Writer receiving decompressed data
+/// * `config` - CLI configuration specifying threads, memory limits, and verbosity
+/// * `stdin_input` - Indicates that the current input source is standard input
+/// * `callback` - Receives an [`Event::Progress`](crate::Event::Progress) after every read
+///   from `input`
+///
+/// # Returns
+///
+/// Returns the bytes read and written on successful decompression.
+///
+/// # Errors
+///
+/// Returns the same errors as [`decompress_file`].
+pub fn decompress_file_with(
+    input: impl io::Read,
+    output: impl io::Write,
+    config: &CliConfig,
+    stdin_input: bool,
+    callback: Option<&mut Callback<'_>>,
+) -> Result<StreamSummary> {
+    match callback {
+        Some(callback) => {
+            let (input, output) = events::track(input, output, callback);
+            decompress_file(input, output, config, stdin_input)
+        }
+        None => decompress_file(input, output, config, stdin_input),
+    }
+}
 
-    warn_unsupported_check(outcome.unsupported_check_id, config)
+/// Reads the Index of a `.xz` file for the `--test` diagnostics.
+fn read_test_file_info(
+    input_path: &Path,
+    config: &CliConfig,
+) -> Option<(File, file_info::FileInfo)> {
+    let mut file = File::open(input_path).ok()?;
+    let memlimit = config.memory_limit.and_then(NonZeroU64::new);
+    let info = file_info::extract_file_info(&mut file, memlimit).ok()?;
+    Some((file, info))
+}
+
+/// Number of decoder threads that fit into `limit` when each needs `per_thread` bytes.
+///
+/// At least one thread always runs: liblzma then decodes single-threaded instead.
+pub(crate) fn fit_decoder_threads(requested: u32, per_thread: u64, limit: u64) -> u32 {
+    let fit = limit.checked_div(per_thread).unwrap_or(u64::MAX);
+    u32::try_from(fit)
+        .unwrap_or(u32::MAX)
+        .clamp(1, requested.max(1))
+}
+
+/// Returns the note for `--threads` that won't all fit into `--memlimit-mt-decompress`.
+///
+/// liblzma's threaded decoder gives each thread the decoder for its Block and buffers for
+/// the Block's compressed and uncompressed data, and starts fewer threads when they don't
+/// fit into the limit. That is estimated from the Block Headers of a multi-block `.xz`
+/// file, so the reduction can be reported like upstream reports it for compression.
+///
+/// # Returns
+///
+/// [`Warning::DecoderThreadsReduced`], or `None` if all threads fit or the input isn't a
+/// seekable multi-block `.xz` file.
+pub(crate) fn decoder_threads_note(input_path: &Path, config: &CliConfig) -> Option<Warning> {
+    let decoding = matches!(
+        config.mode,
+        OperationMode::Decompress | OperationMode::Cat | OperationMode::Test
+    );
+    let threaded_format = matches!(
+        config.format,
+        xz_core::config::DecodeMode::Auto | xz_core::config::DecodeMode::Xz
+    );
+    if !decoding || !threaded_format {
+        return None;
+    }
+    let limit = config.mt_memory_limit?;
+    let limit = config.memory_limit.map_or(limit, |hard| hard.min(limit));
+    let maximum = xz_core::available_threads();
+    let threads = match u32::try_from(config.threads?).unwrap_or(u32::MAX) {
+        0 => maximum,
+        threads => threads.min(maximum),
+    };
+    if threads <= 1 {
+        return None;
+    }
+
+    let (mut file, info) = read_test_file_info(input_path, config)?;
+    let blocks = info.blocks();
+    if blocks.len() <= 1 {
+        return None;
+    }
+    let details = file_info::read_block_details(&mut file, &info).ok()?;
+    let per_thread = blocks
+        .iter()
+        .zip(&details)
+        .map(|(block, detail)| {
+            detail
+                .header
+                .memusage
+                .saturating_add(block.total_size)
+                .saturating_add(block.uncompressed_size)
+        })
+        .max()?;
+
+    let requested = threads.min(u32::try_from(blocks.len()).unwrap_or(u32::MAX));
+    let reduced = fit_decoder_threads(requested, per_thread, limit);
+    (reduced < requested).then_some(Warning::DecoderThreadsReduced {
+        requested,
+        reduced,
+        limit,
+    })
+}
+
+/// Pluralizes `noun` for `count`, e.g. "1 stream" or "3 streams".
+fn count_noun(count: u64, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+/// Describes a `.xz` file that passed `--test`: its integrity check and Stream and Block counts.
+///
+/// # Returns
+///
+/// A summary such as `CRC64, 1 stream, 3 blocks`, or `None` if the input isn't a `.xz`
+/// file with a readable Index.
+pub(crate) fn describe_tested_file(input_path: &Path, config: &CliConfig) -> Option<String> {
+    let (_, info) = read_test_file_info(input_path, config)?;
+    Some(format!(
+        "{}, {}, {}",
+        list::format_check_names(info.checks(), true),
+        count_noun(info.stream_count(), "stream"),
+        count_noun(info.block_count(), "block")
+    ))
+}
+
+/// Locates the damaged Block of a `.xz` file that failed `--test`.
+///
+/// Every Block listed in the Index is decoded on its own with
+/// [`xz_core::pipeline::verify_blocks`].
+///
+/// # Returns
+///
+/// A location such as `block 3 of 5 in stream 1, at about byte 131164`, or `None` if the
+/// Index can't be read or every Block decodes.
+pub(crate) fn locate_test_failure(input_path: &Path, config: &CliConfig) -> Option<String> {
+    let (file, info) = read_test_file_info(input_path, config)?;
+    let mut options = DecompressionOptions::default().with_flags(build_decoder_flags(config));
+    options = apply_memlimit(options, config);
+
+    let failure = verify_blocks(io::BufReader::new(file), &info, &options).ok()??;
+    Some(format!(
+        "block {} of {} in stream {}, at about byte {}",
+        failure.block,
+        info.block_count(),
+        failure.stream,
+        failure.compressed_offset
+    ))
+}
+
+/// Returns the message upstream `xz --list` prints when the Index of a file can't be read.
+///
+/// Upstream reads the Stream Header before the Index, so input that doesn't start like a
+/// `.xz` file isn't recognized, while damage elsewhere means the file is corrupt.
+fn list_error_message(file: &mut File, err: &CoreError) -> String {
+    use std::io::{Read, Seek, SeekFrom};
+
+    // A Stream Header and a Stream Footer, 12 bytes each.
+    const MIN_XZ_FILE_SIZE: u64 = 24;
+
+    match file.metadata().map(|metadata| metadata.len()) {
+        Ok(0) => return "File is empty".to_string(),
+        Ok(size) if size < MIN_XZ_FILE_SIZE => {
+            return "Too small to be a valid .xz file".to_string();
+        }
+        _ => {}
+    }
+
+    match err {
+        CoreError::Backend(BackendError::MemLimitError | BackendError::MemError)
+        | CoreError::Io(_) => xz_message_from_core_error(err),
+        _ => {
+            let mut magic = [0u8; XZ_STREAM_HEADER_MAGIC.len()];
+            let recognized = file
+                .seek(SeekFrom::Start(0))
+                .and_then(|_| file.read_exact(&mut magic))
+                .is_ok_and(|()| magic == XZ_STREAM_HEADER_MAGIC);
+            if recognized {
+                BackendError::DataError.xz_message().into()
+            } else {
+                BackendError::FormatError.xz_message().into()
+            }
+        }
+    }
 }
 
 /// Lists information about an XZ compressed file.
@@ -662,7 +1059,7 @@ pub fn list_file(input_path: &Path, config: &CliConfig) -> Result<()> {
 /// - The file is not a valid XZ file
 /// - Memory limit is exceeded during analysis
 /// - Writing to stdout fails (e.g., broken pipe)
-pub fn list_file_with_context(
+pub(crate) fn list_file_with_context(
     input_path: &Path,
     config: &CliConfig,
     ctx: ListOutputContext,
@@ -678,50 +1075,62 @@ pub fn list_file_with_context(
     })?;
 
     // Extract file info
-    let memlimit = config.memory_limit.and_then(std::num::NonZeroU64::new);
+    let memlimit = config.memory_limit.and_then(NonZeroU64::new);
     let info = file_info::extract_file_info(&mut file, memlimit).map_err(|e| {
         DiagnosticCause::from(Error::FileInfoExtraction {
             path: input_path.display().to_string(),
-            message: e.to_string(),
+            message: list_error_message(&mut file, &e),
         })
     })?;
 
-    let summary = ListSummary {
+    let streams = info.streams();
+    let mut summary = ListSummary {
         stream_count: info.stream_count(),
         block_count: info.block_count(),
         compressed: info.file_size(),
         uncompressed: info.uncompressed_size(),
         checks_mask: info.checks(),
+        padding: streams.iter().map(|s| s.padding).sum(),
+        ..ListSummary::default()
     };
 
-    if config.robot {
-        use std::io::Write;
-
-        // Machine-readable output
-        let mut out = io::stdout().lock();
-        writeln!(
-            out,
-            "{}\t{}\t{}\t{}\t{:.3}\t{}",
-            input_path.display(),
-            info.stream_count(),
-            info.block_count(),
-            info.file_size(),
-            info.uncompressed_size(),
-            ratio(info.file_size(), info.uncompressed_size())
-        )
-        .map_err(|source| {
-            DiagnosticCause::from(Error::WriteOutput {
-                source: IoErrorNoCode::new(source),
+    let mut blocks = info.blocks();
+    blocks.sort_by_key(|b| b.number_in_file);
+    let details = if config.very_verbose {
+        let details = file_info::read_block_details(&mut file, &info).map_err(|e| {
+            DiagnosticCause::from(Error::FileInfoExtraction {
+                path: input_path.display().to_string(),
+                message: xz_message_from_core_error(&e),
             })
         })?;
+        summary.add_block_details(&details);
+        Some(details)
+    } else {
+        None
+    };
+
+    if config.robot {
+        list::write_robot_report(
+            input_path,
+            summary,
+            &streams,
+            &blocks,
+            config.verbose,
+            details.as_deref(),
+        )?;
     } else if config.verbose {
-        let streams = info.streams();
-        let mut blocks = info.blocks();
-        blocks.sort_by_key(|b| b.number_in_file);
-        list::write_verbose_report(input_path, ctx, summary, &streams, &blocks)?;
+        list::write_verbose_report(
+            input_path,
+            ctx,
+            summary,
+            &streams,
+            &blocks,
+            details.as_deref(),
+            config.list_sizes,
+        )?;
     } else {
         list::write_list_header_if_needed(ctx)?;
-        list::write_list_row(summary, input_path)?;
+        list::write_list_row(summary, input_path, config.list_sizes)?;
     }
 
     Ok(summary)
@@ -1,21 +1,79 @@
 //! High-level file processing and CLI orchestration.
 
-use std::io;
+use std::fs::{File, Metadata};
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+
+use xz_core::config::StreamSummary;
+use xz_core::sparse::SparseFileWriter;
+use xz_core::{is_decode_format, read_decode_format_probe_prefix, BackendError};
 
 use crate::config::{CliConfig, OperationMode};
-use crate::error::{DiagnosticCause, Error, ExitStatus, IoErrorNoCode, Report, Result};
-use crate::format::list::{print_list_totals, ListOutputContext, ListSummary};
+use crate::error::{
+    format_diagnostic_for_stderr, input_name, output_name, stdout_write_error, Diagnostic,
+    DiagnosticCause, Error, ExitStatus, IoErrorNoCode, Report, Result, Warning,
+};
+use crate::events::{self, Callback, Event};
+use crate::format::list::{
+    print_list_totals, print_robot_list_totals, print_verbose_list_totals, ListOutputContext,
+    ListSummary,
+};
+use crate::format::summary::{format_compression_details, format_file_result};
 use crate::io::{
-    generate_output_filename, open_input, open_output, open_output_file, SparseFileWriter,
+    check_input_file, copy_file_metadata, generate_output_filename, is_output_of_input,
+    open_buffered_input, open_mapped_input, open_output, FlushTimeoutReader, PendingOutput,
+    Progress, ReadAhead, WriteTracker, READ_AHEAD_SIZE,
+};
+use crate::logging::{self, LogLevel, Phase, PhaseTimer};
+use crate::operations::{
+    compress_file, compression_memlimit_error, compression_memlimit_notes, decoder_threads_note,
+    decompress_file, describe_tested_file, list_file, list_file_with_context, locate_test_failure,
+    recompress_file,
 };
-use crate::operations::{compress_file, decompress_file, list_file, list_file_with_context};
+use crate::sandbox;
+use crate::signals::{self, AbortOnSignal};
+use crate::utils::argfiles::FileListArgs;
+
+/// Returns `true` if a single input can be processed without any file system access once
+/// it's open, like upstream's strict sandbox.
+///
+/// Inputs that are written to a file are never sandboxed this strictly. Of the others, a
+/// named input must also not be needed afterwards: `-v` reads it again to describe it or
+/// to locate a failure, and it's removed unless kept.
+fn strict_sandbox_allowed(config: &CliConfig, is_stdin: bool) -> bool {
+    if config.mode == OperationMode::List {
+        return false;
+    }
+    is_stdin
+        || (!config.verbose && (config.stdout || config.keep || config.mode == OperationMode::Test))
+}
 
 /// Returns `true` if the input path is stdin.
 fn is_stdin_path(input_path: &Path) -> bool {
     input_path.as_os_str().is_empty() || input_path == Path::new("-")
 }
 
+/// Fails with upstream's "File format not recognized" if `config.format` can't decode the input.
+///
+/// Upstream `xz` only looks at the name of an input it has recognized, so an input in an
+/// unknown format is reported as such rather than skipped for its suffix. Inputs that can't
+/// be read are left to the regular open and decode errors.
+fn check_input_format(input_path: &Path, config: &CliConfig) -> Result<()> {
+    let Ok(mut file) = File::open(input_path) else {
+        return Ok(());
+    };
+    match read_decode_format_probe_prefix(&mut file) {
+        Ok(prefix) if !is_decode_format(&prefix, config.format) => {
+            Err(DiagnosticCause::from(Error::Decompression {
+                message: BackendError::FormatError.xz_message().into(),
+            }))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Removes the input file after successful processing.
 ///
 /// Automatically determines whether to remove the input file based on the
@@ -34,8 +92,11 @@ fn is_stdin_path(input_path: &Path) -> bool {
 ///
 /// Returns an error if file removal fails.
 pub fn cleanup_input_file(input_path: &Path, config: &CliConfig) -> Result<()> {
-    // Never delete input file in Test mode
-    if config.mode == OperationMode::Test || config.mode == OperationMode::List {
+    // Never delete input file in Test mode; a recompressed file replaces its input
+    if matches!(
+        config.mode,
+        OperationMode::Test | OperationMode::List | OperationMode::Recompress
+    ) {
         return Ok(());
     }
 
@@ -93,6 +154,45 @@ pub fn cleanup_input_file(input_path: &Path, config: &CliConfig) -> Result<()> {
 /// - Compression/decompression operation fails
 /// - Input file removal fails (when cleanup is enabled)
 pub fn process_file(input_path: &Path, config: &CliConfig) -> Result<()> {
+    process_input(input_path, config, false, None, None).map(|_| ())
+}
+
+/// Processes one input like [`process_file`], returning its byte counts.
+///
+/// With `strict_sandbox`, file system access is dropped once the input and output are
+/// open; see [`strict_sandbox_allowed`]. `callback` receives the progress of the input.
+/// `read_ahead` is the input already being read in the background, if any.
+/// The counts are `None` in list mode, which reads the file without converting it.
+fn process_input(
+    input_path: &Path,
+    config: &CliConfig,
+    strict_sandbox: bool,
+    callback: Option<&mut Callback<'_>>,
+    read_ahead: Option<ReadAhead>,
+) -> Result<Option<StreamSummary>> {
+    let mut timer = PhaseTimer::start(config.log_level);
+    let result = convert_input(
+        input_path,
+        config,
+        strict_sandbox,
+        callback,
+        read_ahead,
+        &mut timer,
+    );
+    timer.finish(&input_name(Some(input_path)), &result);
+    result
+}
+
+/// Body of [`process_input`], ending each [`Phase`] of the input on `timer`.
+fn convert_input(
+    input_path: &Path,
+    config: &CliConfig,
+    strict_sandbox: bool,
+    callback: Option<&mut Callback<'_>>,
+    read_ahead: Option<ReadAhead>,
+    timer: &mut PhaseTimer,
+) -> Result<Option<StreamSummary>> {
+    let name = input_name(Some(input_path));
     let is_stdin = is_stdin_path(input_path);
 
     if matches!(config.format, xz_core::config::DecodeMode::Raw)
@@ -109,6 +209,19 @@ pub fn process_file(input_path: &Path, config: &CliConfig) -> Result<()> {
         }));
     }
 
+    // Like upstream, compressed data isn't read from a terminal unless forced.
+    if is_stdin
+        && config.mode != OperationMode::Compress
+        && !config.force
+        && io::stdin().is_terminal()
+    {
+        return Err(DiagnosticCause::from(Error::CompressedInputFromTerminal));
+    }
+
+    if !is_stdin {
+        check_input_file(input_path, config)?;
+    }
+
     // Use empty PathBuf for stdin, otherwise use the provided path
     let input_path_buf = if is_stdin {
         PathBuf::new()
@@ -116,13 +229,29 @@ pub fn process_file(input_path: &Path, config: &CliConfig) -> Result<()> {
         input_path.to_path_buf()
     };
 
-    let input = open_input(input_path)?;
+    // Taken before reading, which may update the access time.
+    let input_metadata = (!is_stdin)
+        .then(|| std::fs::metadata(input_path).ok())
+        .flatten();
+    let read_ahead_input = read_ahead.is_some();
+    let mut input: Box<dyn io::Read> = match (read_ahead, config.flush_timeout) {
+        (Some(read_ahead), _) => read_ahead.finish(config.buffer_size)?,
+        // Only input that can stall needs a timeout; regular files never do.
+        (None, Some(timeout)) if is_stdin && config.mode == OperationMode::Compress => {
+            Box::new(FlushTimeoutReader::new(io::stdin(), timeout))
+        }
+        _ if config.mmap && config.mode != OperationMode::List => {
+            open_mapped_input(input_path, config.buffer_size, config.io_hints)?
+        }
+        _ => open_buffered_input(input_path, config.buffer_size, config.io_hints)?,
+    };
 
     // Determine output path
     let output_path = if is_stdin
         || config.stdout
         || config.mode == OperationMode::Cat
         || config.mode == OperationMode::Test
+        || config.mode == OperationMode::List
     {
         None
     } else {
@@ -130,87 +259,285 @@ pub fn process_file(input_path: &Path, config: &CliConfig) -> Result<()> {
             (OperationMode::Compress, xz_core::config::DecodeMode::Lzma) => {
                 crate::config::LZMA_EXTENSION
             }
+            // Raw mode requires `--suffix`, checked above.
+            (OperationMode::Compress, xz_core::config::DecodeMode::Raw) => "",
             _ => crate::config::XZ_EXTENSION,
         };
-        Some(generate_output_filename(
+        let output_path = generate_output_filename(
             &input_path_buf,
             config.mode,
             config.suffix.as_deref(),
             default_extension,
             config.force,
-        )?)
+        );
+        if matches!(
+            output_path,
+            Err(DiagnosticCause::Warning(Warning::InvalidExtension { .. }))
+        ) {
+            check_input_format(&input_path_buf, config)?;
+        }
+        Some(output_path?)
     };
 
-    // Open output
-    let output: Box<dyn io::Write> = match (
+    // Writing to the input through stdout would read the output back, possibly forever.
+    if output_path.is_none()
+        && !matches!(config.mode, OperationMode::Test | OperationMode::List)
+        && (is_stdin || input_metadata.is_some())
+        && is_output_of_input(input_metadata.as_ref())
+    {
+        return Err(DiagnosticCause::from(Error::SameInputAndOutput));
+    }
+
+    // Output files are written under a temporary name and only renamed once complete.
+    let (pending_output, output): (Option<PendingOutput>, Box<dyn io::Write>) =
+        match output_path.as_deref() {
+            Some(path) => {
+                // A recompressed file always replaces its input, but only once complete.
+                let replace = config.force || config.mode == OperationMode::Recompress;
+                let (pending, file) = PendingOutput::create(path, replace)?;
+                let output: Box<dyn io::Write> =
+                    if config.mode == OperationMode::Decompress && config.sparse {
+                        // When decompressing to a file, attempt to create sparse output by
+                        // seeking over long zero runs
+                        Box::new(SparseFileWriter::new(file))
+                    } else {
+                        Box::new(io::BufWriter::with_capacity(config.buffer_size, file))
+                    };
+                (Some(pending), output)
+            }
+            None => (None, open_output(None, config)?),
+        };
+    if strict_sandbox && pending_output.is_none() {
+        sandbox::enable_strict();
+    }
+    logging::event(
+        config.log_level,
+        LogLevel::Debug,
+        "file.open",
+        &[
+            ("path", &name),
+            (
+                "size",
+                &input_metadata
+                    .as_ref()
+                    .filter(|metadata| metadata.is_file())
+                    .map_or_else(
+                        || "unknown".to_string(),
+                        |metadata| metadata.len().to_string(),
+                    ),
+            ),
+            ("output", &output_name(output_path.as_deref())),
+        ],
+    );
+    timer.end(Phase::Open, &name);
+
+    // Like upstream, the live progress line is only drawn for `-v` on a terminal. It is
+    // cleared when `progress` goes out of scope, including on errors.
+    let show_progress = config.verbose
+        && !config.robot
+        && config.mode != OperationMode::List
+        && io::stderr().is_terminal();
+
+    // `xz -dc` copies stdin that isn't compressed to stdout unchanged, which Linux can do
+    // without passing the data through this process. The copy isn't observable byte by
+    // byte, so it's left to the coder when progress is drawn or reported to a callback.
+    #[cfg(target_os = "linux")]
+    if is_stdin
+        && config.mode == OperationMode::Decompress
+        && config.stdout
+        && config.format == xz_core::config::DecodeMode::Auto
+        && !read_ahead_input
+        && !show_progress
+        && callback.is_none()
+    {
+        let started = Instant::now();
+        if let Some(summary) = splice_passthrough(&mut input)? {
+            timer.end(Phase::Process, &name);
+            emit_file_result(config, &name, summary, started.elapsed());
+            timer.end(Phase::Finish, &name);
+            return Ok(Some(summary));
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = read_ahead_input;
+
+    let progress = Progress::start(
         config.mode,
-        config.sparse,
-        config.stdout,
-        output_path.as_deref(),
-    ) {
-        (OperationMode::Decompress, true, false, Some(path)) => {
-            // When decompressing to a file, attempt to create sparse output by seeking over
-            // long zero runs
-            let file = open_output_file(path, config)?;
-            Box::new(SparseFileWriter::new(file))
+        input_metadata
+            .as_ref()
+            .filter(|metadata| metadata.is_file())
+            .map(Metadata::len),
+        show_progress,
+    );
+    let input = AbortOnSignal(progress.track_input(input));
+    let write_errors = WriteTracker::default();
+    let output = write_errors.track(progress.track_output(output));
+    let (input, output): (Box<dyn io::Read + '_>, Box<dyn io::Write + '_>) = match callback {
+        Some(callback) => {
+            let (input, output) = events::track(input, output, callback);
+            (Box::new(input), Box::new(output))
         }
-        _ => open_output(output_path.as_deref(), config)?,
+        None => (Box::new(input), Box::new(output)),
     };
 
     // Process based on mode
-    match config.mode {
-        OperationMode::Compress => {
-            compress_file(input, output, config)?;
-        }
+    let started = Instant::now();
+    let outcome = match config.mode {
+        OperationMode::Compress => compress_file(input, output, config).map(Some),
         OperationMode::Decompress | OperationMode::Cat => {
-            match decompress_file(input, output, config, is_stdin) {
-                Ok(()) => (),
-                Err(DiagnosticCause::Warning(
-                    w @ crate::error::Warning::UnsupportedCheck { .. },
-                )) => {
-                    cleanup_input_file(input_path, config)?;
-                    return Err(DiagnosticCause::Warning(w));
-                }
-                Err(other) => return Err(other),
-            }
+            decompress_file(input, output, config, is_stdin).map(Some)
         }
+        // In test mode, decompress but discard output
         OperationMode::Test => {
-            // In test mode, decompress but discard output
-            decompress_file(input, io::sink(), config, is_stdin)?;
-
-            if config.verbose || config.robot {
-                if config.robot {
-                    let display = input_path.display();
-                    eprintln!("OK {display}");
-                } else {
-                    let display = input_path.display();
-                    eprintln!("Test successful: {display}");
-                }
-            }
+            decompress_file(input, progress.track_output(io::sink()), config, is_stdin).map(Some)
+        }
+        OperationMode::List => list_file(input_path, config).map(|()| None),
+        OperationMode::Recompress => recompress_file(input, output, config).map(Some),
+    };
+    // The coder reports a failed write like any other error; name the output instead.
+    let outcome = outcome.map_err(|err| match write_errors.take() {
+        Some(source) => DiagnosticCause::from(Error::WriteOutput {
+            output: output_path.clone(),
+            source: IoErrorNoCode::new(source),
+        }),
+        None => err,
+    });
+    // The data was fully processed despite this warning, so the file is finished normally.
+    let (summary, check_warning) = match outcome {
+        Ok(summary) => (summary, None),
+        Err(DiagnosticCause::Warning(warning @ Warning::UnsupportedCheck { .. })) => {
+            (None, Some(warning))
         }
-        OperationMode::List => {
-            list_file(input_path, config)?;
+        Err(DiagnosticCause::Error(Error::Decompression { message }))
+            if config.mode == OperationMode::Test && config.verbose && !is_stdin =>
+        {
+            let message = match locate_test_failure(input_path, config) {
+                Some(location) => format!("{message} ({location})"),
+                None => message,
+            };
+            return Err(DiagnosticCause::from(Error::Decompression { message }));
+        }
+        // Dropping `pending_output` removes the incomplete output.
+        Err(err) => return Err(err),
+    };
+    timer.end(Phase::Process, &name);
+
+    if let Some(summary) = summary {
+        emit_file_result(config, &name, summary, started.elapsed());
+    }
+
+    if config.mode == OperationMode::Test && check_warning.is_none() && config.verbose {
+        let display = input_name(Some(input_path));
+        if let Some(details) = (!is_stdin)
+            .then(|| describe_tested_file(input_path, config))
+            .flatten()
+        {
+            eprintln!("Test successful: {display} ({details})");
+        } else {
+            eprintln!("Test successful: {display}");
+        }
+    }
+
+    // `-vv` follows the `-v` result line with the filters and check of the stream.
+    if config.very_verbose && !config.robot && check_warning.is_none() {
+        let details = match config.mode {
+            OperationMode::Compress | OperationMode::Recompress => {
+                Some(format_compression_details(config))
+            }
+            OperationMode::Decompress | OperationMode::Cat if !is_stdin => {
+                describe_tested_file(input_path, config)
+            }
+            _ => None,
+        };
+        if let Some(details) = details {
+            eprintln!("  Details: {details}");
         }
     }
 
+    // Like upstream, the output gets the input's attributes before the input is removed.
+    let metadata_warning = pending_output
+        .as_ref()
+        .zip(input_metadata.as_ref())
+        .and_then(|(pending, metadata)| copy_file_metadata(metadata, pending.temp_path()));
+    if let Some(pending) = pending_output {
+        pending.commit(config.synchronous)?;
+    }
+
     // Remove input file if allowed
     cleanup_input_file(input_path, config)?;
+    timer.end(Phase::Finish, &name);
 
-    Ok(())
+    check_warning
+        .or(metadata_warning)
+        .map_or(Ok(summary), |warning| {
+            Err(DiagnosticCause::Warning(warning))
+        })
+}
+
+/// Copies stdin to stdout inside the kernel if it isn't in a format that can be
+/// decompressed, like the unknown input policy of [`decompress_file`] does for `xz -dc`.
+///
+/// Only the bytes needed to recognize the format are read, straight from the descriptor.
+/// If they belong to a known format, or the input is empty, they are put back in front of
+/// `input`, which is then decompressed as usual.
+///
+/// # Returns
+///
+/// The summary of the copy, or `None` if `input` still has to be decompressed.
+///
+/// # Errors
+///
+/// Returns an error if reading stdin or writing stdout fails.
+#[cfg(target_os = "linux")]
+fn splice_passthrough(input: &mut Box<dyn io::Read>) -> Result<Option<StreamSummary>> {
+    use std::io::{Read as _, Write as _};
+
+    use crate::io::splice::{self, CopyError};
+
+    let read_error = |source: io::Error| {
+        DiagnosticCause::from(Error::Decompression {
+            message: source.to_string(),
+        })
+    };
+
+    let stdin = splice::raw_stdin().map_err(read_error)?;
+    let prefix = read_decode_format_probe_prefix(&mut AbortOnSignal(&stdin)).map_err(read_error)?;
+    if prefix.is_empty() || xz_core::is_known_decode_format(&prefix) {
+        let rest = std::mem::replace(input, Box::new(io::empty()));
+        *input = Box::new(io::Cursor::new(prefix).chain(rest));
+        return Ok(None);
+    }
+
+    // Earlier inputs may have left output in the buffer of `io::Stdout`.
+    io::stdout().flush().map_err(stdout_write_error)?;
+    let mut stdout = splice::raw_stdout().map_err(stdout_write_error)?;
+    stdout.write_all(&prefix).map_err(stdout_write_error)?;
+    let copied = splice::copy(&stdin, &stdout).map_err(|err| match err {
+        CopyError::Read(source) => read_error(source),
+        CopyError::Write(source) => stdout_write_error(source),
+    })?;
+
+    let total = prefix.len() as u64 + copied;
+    Ok(Some(StreamSummary {
+        bytes_read: total,
+        bytes_written: total,
+    }))
 }
 
 /// Parses a memory limit string with an optional size suffix.
 ///
 /// Accepts numeric values with optional suffixes: `K`/`KiB`, `M`/`MiB`, or `G`/`GiB`.
 /// All suffixes are case-insensitive. Values without a suffix are interpreted as bytes.
+/// A value like `50%` is a percentage of the total physical memory, and `max` means no
+/// limit, which is reported as `0` like an explicit `0`.
 ///
 /// # Parameters
 ///
-/// * `s` - The memory limit string to parse (e.g., "1024", "1K", "1MiB", "2G")
+/// * `s` - The memory limit string to parse (e.g., "1024", "1K", "1MiB", "2G", "40%")
 ///
 /// # Returns
 ///
-/// The memory limit in bytes as a [`u64`].
+/// The memory limit in bytes as a [`u64`], or `0` for no limit.
 ///
 /// # Errors
 ///
@@ -218,13 +545,10 @@ pub fn process_file(input_path: &Path, config: &CliConfig) -> Result<()> {
 ///
 /// - The input string is empty
 /// - The numeric part cannot be parsed as a valid [`u64`]
-/// - The suffix is not one of K, KiB, M, MiB, G, GiB, or a digit
+/// - The suffix is not one of K, KiB, M, MiB, G, GiB, %, or a digit
+/// - A percentage is outside the range 1-100
 /// - The result would overflow [`u64`] after applying the multiplier
 pub fn parse_memory_limit(s: &str) -> Result<u64> {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-
     let s = s.trim();
     if s.is_empty() {
         return Err(DiagnosticCause::from(Error::InvalidMemoryLimit(
@@ -233,6 +557,100 @@ pub fn parse_memory_limit(s: &str) -> Result<u64> {
     }
 
     let normalized = s.to_ascii_uppercase();
+    if normalized == "MAX" {
+        return Ok(0);
+    }
+    if let Some(number_part) = normalized.strip_suffix('%') {
+        return parse_memory_percentage(number_part);
+    }
+
+    parse_size(&normalized, "memory limit")
+        .map_err(|message| DiagnosticCause::from(Error::InvalidMemoryLimit(message)))
+}
+
+/// Parses a `--block-size` value with an optional size suffix.
+///
+/// Accepts the same suffixes as [`parse_memory_limit`]; `0` selects the default Block
+/// size of the preset.
+///
+/// # Parameters
+///
+/// * `s` - The block size to parse (e.g., "4MiB")
+///
+/// # Returns
+///
+/// The Block size in bytes.
+///
+/// # Errors
+///
+/// Returns an error if the size is empty, invalid, or overflows [`u64`].
+pub fn parse_block_size(s: &str) -> Result<u64> {
+    parse_size(&s.trim().to_ascii_uppercase(), "block size").map_err(|message| {
+        DiagnosticCause::from(Error::InvalidOption {
+            message: format!("Invalid block size: {message}"),
+        })
+    })
+}
+
+/// Parses a `--block-list` value: comma-separated uncompressed Block sizes.
+///
+/// Each size accepts the same suffixes as [`parse_memory_limit`]. An empty entry repeats
+/// the previous size, and `0` is only allowed as the last entry, where it puts the rest
+/// of the input into a single Block.
+///
+/// # Parameters
+///
+/// * `s` - The block list to parse (e.g., "1MiB,2MiB,0")
+///
+/// # Returns
+///
+/// The Block sizes in bytes.
+///
+/// # Errors
+///
+/// Returns an error if the list is empty, a size is invalid or overflows [`u64`], an
+/// empty entry has no previous size, or `0` is used before the last entry.
+pub fn parse_block_list(s: &str) -> Result<Vec<u64>> {
+    let invalid = |message: String| DiagnosticCause::from(Error::InvalidBlockList(message));
+
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(invalid("Empty block list".to_string()));
+    }
+
+    let entries: Vec<&str> = s.split(',').map(str::trim).collect();
+    let mut sizes = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let size = if entry.is_empty() {
+            *sizes
+                .last()
+                .ok_or_else(|| invalid("The first block size is missing".to_string()))?
+        } else {
+            parse_size(&entry.to_ascii_uppercase(), "block size").map_err(invalid)?
+        };
+        if size == 0 && index + 1 != entries.len() {
+            return Err(invalid(
+                "0 can only be used as the last element".to_string(),
+            ));
+        }
+        sizes.push(size);
+    }
+
+    Ok(sizes)
+}
+
+/// Parses an upper-case size with an optional `K`/`KiB`, `M`/`MiB` or `G`/`GiB` suffix.
+///
+/// Errors are returned as messages naming `what` is being parsed.
+fn parse_size(normalized: &str, what: &str) -> std::result::Result<u64, String> {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if normalized.is_empty() {
+        return Err(format!("Empty {what}"));
+    }
+
     let (number_part, multiplier) = if let Some(number_part) = normalized.strip_suffix("KIB") {
         (number_part, KB)
     } else if let Some(number_part) = normalized.strip_suffix("MIB") {
@@ -250,27 +668,40 @@ pub fn parse_memory_limit(s: &str) -> Result<u64> {
         .last()
         .is_some_and(|ch| ch.is_ascii_digit())
     {
-        (normalized.as_str(), 1)
+        (normalized, 1)
     } else {
         let invalid_suffix = normalized.chars().last().unwrap_or('?');
-        return Err(DiagnosticCause::from(Error::InvalidMemoryLimit(format!(
-            "Invalid memory limit suffix: {invalid_suffix}"
-        ))));
+        return Err(format!("Invalid {what} suffix: {invalid_suffix}"));
     };
 
-    let number: u64 = number_part.parse().map_err(|_| {
-        DiagnosticCause::from(Error::InvalidMemoryLimit(format!(
-            "Invalid number: {number_part}"
-        )))
-    })?;
+    let number: u64 = number_part
+        .parse()
+        .map_err(|_| format!("Invalid number: {number_part}"))?;
 
     number.checked_mul(multiplier).ok_or_else(|| {
-        DiagnosticCause::from(Error::InvalidMemoryLimit(
-            "Memory limit too large (overflow)".to_string(),
-        ))
+        let mut what = what.to_string();
+        what[..1].make_ascii_uppercase();
+        format!("{what} too large (overflow)")
     })
 }
 
+/// Converts the number in a `N%` memory limit to bytes of physical memory.
+fn parse_memory_percentage(number_part: &str) -> Result<u64> {
+    let percentage = number_part
+        .parse::<u64>()
+        .ok()
+        .filter(|percentage| (1..=100).contains(percentage))
+        .ok_or_else(|| {
+            DiagnosticCause::from(Error::InvalidMemoryLimit(
+                "Percentage must be in the range [1, 100]".to_string(),
+            ))
+        })?;
+
+    let bytes = u128::from(xz_core::Hardware::physical_memory()) * u128::from(percentage) / 100;
+    // At most 100% of a `u64` amount of memory, so this can't overflow.
+    Ok(u64::try_from(bytes).unwrap_or(u64::MAX))
+}
+
 /// Processes multiple files in list mode, accumulating totals and handling multi-file output.
 ///
 /// # Parameters
@@ -283,13 +714,23 @@ pub fn parse_memory_limit(s: &str) -> Result<u64> {
 ///
 /// Returns `Ok(())` on success, or an error if any file operation fails.
 /// Gracefully handles `BrokenPipe` errors by returning `Ok(())`.
-fn process_list_files(files: &[PathBuf], config: &CliConfig, program: &str) -> Report {
+fn process_list_files(
+    files: &[PathBuf],
+    config: &CliConfig,
+    program: &str,
+    mut callback: Option<&mut Callback<'_>>,
+) -> Report {
     let mut report = Report::default();
     let total = files.len();
     let mut header_printed = false;
     let mut totals = ListSummary::default();
+    // Like upstream, the totals only count the files that could be listed.
+    let mut listed = 0;
 
     for (idx, file) in files.iter().enumerate() {
+        if signals::user_abort().is_some() {
+            return report;
+        }
         let ctx = ListOutputContext {
             file_index: idx + 1,
             file_count: total,
@@ -297,31 +738,43 @@ fn process_list_files(files: &[PathBuf], config: &CliConfig, program: &str) -> R
         };
         header_printed |= ctx.print_header;
 
-        match list_file_with_context(file, config, ctx) {
+        match check_input_file(file, config)
+            .and_then(|()| list_file_with_context(file, config, ctx))
+        {
             Ok(summary) => {
-                totals.stream_count += summary.stream_count;
-                totals.block_count += summary.block_count;
-                totals.compressed += summary.compressed;
-                totals.uncompressed += summary.uncompressed;
-                totals.checks_mask |= summary.checks_mask;
+                totals.accumulate(summary);
+                listed += 1;
             }
             Err(err) => {
                 // Handle broken pipe gracefully (e.g., when piping to `head`).
                 if is_broken_pipe(&err) {
                     return report;
                 }
-                report.record(err, program, Some(file));
+                record(
+                    &mut report,
+                    callback.as_deref_mut(),
+                    err,
+                    program,
+                    Some(file),
+                );
             }
         }
     }
 
-    // Print summary line for multiple files (non-verbose, non-robot mode)
-    if total > 1 && !config.robot && !config.verbose {
-        if let Err(err) = print_list_totals(totals, total) {
+    // Robot mode always ends with a totals line; the human formats only for multiple files.
+    if config.robot || listed > 1 {
+        let result = if config.robot {
+            print_robot_list_totals(totals, listed, config.very_verbose)
+        } else if config.verbose {
+            print_verbose_list_totals(totals, listed, config.very_verbose, config.list_sizes)
+        } else {
+            print_list_totals(totals, listed, config.list_sizes)
+        };
+        if let Err(err) = result {
             if is_broken_pipe(&err) {
                 return report;
             }
-            report.record(err, program, None);
+            record(&mut report, callback, err, program, None);
         }
     }
 
@@ -338,17 +791,62 @@ fn process_list_files(files: &[PathBuf], config: &CliConfig, program: &str) -> R
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` if all files were processed successfully.
-fn process_files(files: &[PathBuf], config: &CliConfig, program: &str) -> Report {
+/// Returns a [`Report`] of the diagnostics. With `-v`, a totals line is printed after
+/// more than one file was processed successfully.
+fn process_files(
+    files: &[PathBuf],
+    config: &CliConfig,
+    program: &str,
+    sandbox: bool,
+    mut callback: Option<&mut Callback<'_>>,
+) -> Report {
     let mut report = Report::default();
-    for file in files {
-        match process_file(file, config) {
-            Ok(()) => {}
+    let strict_sandbox = match files {
+        [file] => sandbox && strict_sandbox_allowed(config, is_stdin_path(file)),
+        _ => false,
+    };
+    // Concatenating to stdout reads the next file while the current one is decoded.
+    let read_ahead = !config.mmap
+        && (config.mode == OperationMode::Cat
+            || config.mode == OperationMode::Decompress && config.stdout);
+    let mut next_input = None;
+    for (index, file) in files.iter().enumerate() {
+        if signals::user_abort().is_some() {
+            return report;
+        }
+        let current_input = next_input.take();
+        if read_ahead {
+            next_input = files
+                .get(index + 1)
+                .filter(|next| !is_stdin_path(next))
+                .and_then(|next| ReadAhead::start(next, READ_AHEAD_SIZE, config.io_hints));
+        }
+        // Like the option warnings, this note doesn't affect the exit status.
+        if config.quiet == 0 || logging::enabled(config.log_level, LogLevel::Info) {
+            if let Some(note) = decoder_threads_note(file, config) {
+                record_note(&mut report, callback.as_deref_mut(), note, program, config);
+            }
+        }
+        match process_input(
+            file,
+            config,
+            strict_sandbox,
+            callback.as_deref_mut(),
+            current_input,
+        ) {
+            Ok(_) => {}
             Err(err) => {
-                if is_broken_pipe(&err) {
+                // An interrupted file is reported through the exit status only.
+                if is_broken_pipe(&err) || signals::user_abort().is_some() {
                     return report;
                 }
-                report.record(err, program, Some(file));
+                record(
+                    &mut report,
+                    callback.as_deref_mut(),
+                    err,
+                    program,
+                    Some(file),
+                );
             }
         }
     }
@@ -361,6 +859,10 @@ fn process_files(files: &[PathBuf], config: &CliConfig, program: &str) -> Report
 /// files sequentially and provides enhanced error messages with program name and
 /// file context.
 ///
+/// It leaves the process as it is, so it's safe to call from programs that embed the
+/// tools: no signal handlers are installed, no sandbox is applied and the environment
+/// isn't read. The tools themselves use [`run_tool`] instead.
+///
 /// # Parameters
 ///
 /// * `files` - Slice of input file paths to process. Empty slice reads from stdin.
@@ -376,10 +878,131 @@ fn process_files(files: &[PathBuf], config: &CliConfig, program: &str) -> Report
 /// This function does not fail fast. It continues processing remaining files
 /// after per-file errors and aggregates the exit code like upstream `xz`.
 pub fn run_cli(files: &[PathBuf], config: &CliConfig, program: &str) -> Report {
+    run(files, config, program, false, None)
+}
+
+/// Runs a command line tool like [`run_cli`], taking over the process for it.
+///
+/// Only the `main` of a tool should call this. It installs the signal handlers:
+/// `SIGINT`, `SIGTERM` or `SIGHUP` stop the run, remove the partially written output
+/// file and yield [`ExitStatus::Signal`], and `SIGUSR1` prints the progress line of the
+/// current file. Without a log level in `config`, it's taken from the environment.
+///
+/// Where the platform supports it, the process is then sandboxed for the rest of its
+/// life: only reading, writing, creating and removing regular files stays allowed, and a
+/// single input written to stdout loses file system access once it's open.
+///
+/// # Parameters
+///
+/// * `files` - Slice of input file paths to process. Empty slice reads from stdin.
+/// * `config` - CLI configuration specifying operation mode and options.
+/// * `program` - Program name to include in error messages (e.g., "xz", "unxz").
+///
+/// # Returns
+///
+/// Returns a [`Report`] containing the aggregated exit status and all diagnostics.
+pub fn run_tool(files: &[PathBuf], config: &CliConfig, program: &str) -> Report {
+    run(files, config, program, true, None)
+}
+
+/// Runs a CLI command like [`run_cli`], reporting its progress as it goes.
+///
+/// Like [`run_cli`], this neither installs signal handlers nor sandboxes the process,
+/// so the caller keeps control over both. Inputs and outputs follow the same rules as the
+/// command line tools, including the output file names and `--keep`/`--force`.
+///
+/// # Parameters
+///
+/// * `files` - Slice of input file paths to process. Empty slice reads from stdin.
+/// * `config` - CLI configuration specifying operation mode and options.
+/// * `program` - Program name to include in error messages (e.g., "xz", "unxz").
+/// * `callback` - Receives the progress of each input and every diagnostic as soon as
+///   it's recorded.
+///
+/// # Returns
+///
+/// Returns a [`Report`] containing the aggregated exit status and all diagnostics.
+pub fn run_cli_with(
+    files: &[PathBuf],
+    config: &CliConfig,
+    program: &str,
+    callback: &mut Callback<'_>,
+) -> Report {
+    run(files, config, program, false, Some(callback))
+}
+
+/// Collects the input files of a command line tool from its operands and file list.
+///
+/// An error reading the list isn't tied to an input file, so it's reported without a
+/// file name, and the process exits like with [`exit_with_error`].
+///
+/// # Parameters
+///
+/// * `file_list` - The `--files`/`--files0` options of the tool
+/// * `operands` - Files given on the command line
+/// * `program` - Program name to include in the error message
+/// * `quiet` - How many times `--quiet` was given
+///
+/// # Returns
+///
+/// The files to process, or `None` if a list was given but there are no files at all.
+pub fn resolve_input_files(
+    file_list: &FileListArgs,
+    operands: &[PathBuf],
+    program: &str,
+    quiet: u8,
+) -> Option<Vec<PathBuf>> {
+    file_list
+        .resolve(operands)
+        .unwrap_or_else(|err| exit_with_error(err, program, quiet))
+}
+
+/// Reports an error that ends a command line tool before any file is processed, then
+/// exits with the error status.
+///
+/// The message goes through [`format_diagnostic_for_stderr`] like every other diagnostic,
+/// so `-qq` silences it.
+///
+/// # Parameters
+///
+/// * `cause` - What went wrong
+/// * `program` - Program name to include in the error message
+/// * `quiet` - How many times `--quiet` was given
+pub fn exit_with_error(cause: DiagnosticCause, program: &str, quiet: u8) -> ! {
+    let diagnostic = Diagnostic::general(cause, program);
+    if let Some(msg) = format_diagnostic_for_stderr(quiet, &diagnostic) {
+        eprintln!("{msg}");
+    }
+    process::exit(ExitStatus::Error.code());
+}
+
+/// Shared body of [`run_cli`], [`run_tool`] and [`run_cli_with`]; `standalone` is set for
+/// the tools themselves, which own the process.
+fn run(
+    files: &[PathBuf],
+    config: &CliConfig,
+    program: &str,
+    standalone: bool,
+    mut callback: Option<&mut Callback<'_>>,
+) -> Report {
     let mut report = Report::default();
 
+    // The tools own the environment; embedders opt in through the config only.
+    let from_env;
+    let config = if standalone && config.log_level.is_none() {
+        from_env = CliConfig {
+            log_level: LogLevel::from_env(program),
+            ..config.clone()
+        };
+        &from_env
+    } else {
+        config
+    };
+
     if config.mode == OperationMode::List && files.is_empty() {
-        report.record(
+        record(
+            &mut report,
+            callback,
             DiagnosticCause::from(Error::ListModeStdinUnsupported),
             program,
             None,
@@ -388,28 +1011,237 @@ pub fn run_cli(files: &[PathBuf], config: &CliConfig, program: &str) -> Report {
         return report;
     }
 
+    // Like upstream, compressed data isn't written to a terminal unless forced.
+    let to_stdout = config.stdout || files.is_empty() || files.iter().any(|f| is_stdin_path(f));
+    if config.mode.is_compressing() && !config.force && to_stdout && io::stdout().is_terminal() {
+        record(
+            &mut report,
+            callback,
+            DiagnosticCause::from(Error::CompressedOutputToTerminal),
+            program,
+            None,
+        );
+        report.status = ExitStatus::Error;
+        return report;
+    }
+
+    if let Some(err) = config
+        .mode
+        .is_compressing()
+        .then(|| compression_memlimit_error(config))
+        .flatten()
+    {
+        record(&mut report, callback, err, program, None);
+        report.status = ExitStatus::Error;
+        return report;
+    }
+
+    if standalone {
+        signals::install();
+    }
+
+    let started = Instant::now();
+    log_run_start(config, files);
+    let log_settings = logging::enabled(config.log_level, LogLevel::Info);
+    let warnings = if config.quiet == 0 || log_settings {
+        option_warnings(config)
+    } else {
+        Vec::new()
+    };
+    for warning in warnings {
+        // Like upstream's note about reducing threads to fit the memory limit, these are
+        // informational only and don't affect the exit status.
+        record_note(
+            &mut report,
+            callback.as_deref_mut(),
+            warning,
+            program,
+            config,
+        );
+    }
+
+    if standalone {
+        sandbox::init();
+    }
+
     if files.is_empty() {
-        match process_file(Path::new(""), config) {
-            Ok(()) => {}
+        let strict_sandbox = standalone && strict_sandbox_allowed(config, true);
+        match process_input(
+            Path::new(""),
+            config,
+            strict_sandbox,
+            callback.as_deref_mut(),
+            None,
+        ) {
+            Ok(_) => {}
             Err(err) => {
-                if !is_broken_pipe(&err) {
-                    report.record(err, program, None);
+                if !is_broken_pipe(&err) && signals::user_abort().is_none() {
+                    record(&mut report, callback, err, program, None);
                 }
             }
         }
-    } else if config.mode == OperationMode::List {
-        report = process_list_files(files, config, program);
     } else {
-        report = process_files(files, config, program);
+        let files_report = if config.mode == OperationMode::List {
+            process_list_files(files, config, program, callback)
+        } else {
+            process_files(files, config, program, standalone, callback)
+        };
+        // The notes recorded so far left the status untouched.
+        report.status = files_report.status;
+        report.diagnostics.extend(files_report.diagnostics);
     }
 
+    // `--no-warn` keeps the messages but, like upstream, not their exit status.
+    if config.no_warn && report.status == ExitStatus::Warning {
+        report.status = ExitStatus::Ok;
+    }
+    if let Some(signal) = signals::user_abort() {
+        report.status = ExitStatus::Signal(signal);
+    }
+    logging::event(
+        config.log_level,
+        LogLevel::Info,
+        "run.end",
+        &[
+            ("status", &report.status.code()),
+            ("files", &files.len().max(1)),
+            ("diagnostics", &report.diagnostics.len()),
+            (
+                "elapsed_ms",
+                &format!("{:.3}", started.elapsed().as_secs_f64() * 1000.0),
+            ),
+        ],
+    );
     report
 }
 
+/// Logs the settings of a run before its first input.
+fn log_run_start(config: &CliConfig, files: &[PathBuf]) {
+    if !logging::enabled(config.log_level, LogLevel::Info) {
+        return;
+    }
+    let setting = |value: Option<String>| value.unwrap_or_else(|| "default".to_string());
+    logging::event(
+        config.log_level,
+        LogLevel::Info,
+        "run.start",
+        &[
+            ("mode", &format!("{:?}", config.mode).to_lowercase()),
+            ("format", &format!("{:?}", config.format).to_lowercase()),
+            ("files", &files.len().max(1)),
+            (
+                "preset",
+                &setting(config.level.map(|level| level.to_string())),
+            ),
+            ("extreme", &config.extreme),
+            (
+                "threads",
+                &setting(config.threads.map(|threads| threads.to_string())),
+            ),
+            (
+                "memlimit",
+                &setting(config.memory_limit.map(|limit| limit.to_string())),
+            ),
+        ],
+    );
+}
+
+/// Logs a setting that was adjusted or ignored, such as a reduced thread count.
+fn log_adjustment(config: &CliConfig, message: &dyn std::fmt::Display) {
+    logging::event(
+        config.log_level,
+        LogLevel::Info,
+        "settings.adjusted",
+        &[("message", message)],
+    );
+}
+
+/// Records the note `warning` in `report`, logs it and passes it to `callback`.
+fn record_note(
+    report: &mut Report,
+    callback: Option<&mut Callback<'_>>,
+    warning: Warning,
+    program: &str,
+    config: &CliConfig,
+) {
+    log_adjustment(config, &warning);
+    report.note(warning, program);
+    if let (Some(callback), Some(diagnostic)) = (callback, report.diagnostics.last()) {
+        callback(Event::Diagnostic(diagnostic));
+    }
+}
+
+/// Records `cause` in `report` and passes the new diagnostic to `callback`.
+fn record(
+    report: &mut Report,
+    callback: Option<&mut Callback<'_>>,
+    cause: DiagnosticCause,
+    program: &str,
+    file: Option<&Path>,
+) {
+    report.record(cause, program, file);
+    if let (Some(callback), Some(diagnostic)) = (callback, report.diagnostics.last()) {
+        callback(Event::Diagnostic(diagnostic));
+    }
+}
+
+/// Returns the warnings about the command line options.
+fn option_warnings(config: &CliConfig) -> Vec<Warning> {
+    let mut warnings: Vec<Warning> = [thread_count_warning(config), ignore_check_warning(config)]
+        .into_iter()
+        .flatten()
+        .collect();
+    if config.mode.is_compressing() {
+        warnings.extend(compression_memlimit_notes(config));
+    }
+    warnings
+}
+
+/// Returns the warning for a `--threads` value above the available processor threads.
+///
+/// xz-core clamps such requests to the number of processor threads; `-T0` uses all of them.
+fn thread_count_warning(config: &CliConfig) -> Option<Warning> {
+    // `.lzma` and `.lz` are always single-threaded and ignore `--threads`.
+    if config.mode == OperationMode::List
+        || matches!(
+            config.format,
+            xz_core::config::DecodeMode::Lzma | xz_core::config::DecodeMode::Lzip
+        )
+    {
+        return None;
+    }
+
+    let requested = config.threads?;
+    let maximum = xz_core::available_threads();
+    let exceeds = u32::try_from(requested).map_or(true, |count| count > maximum);
+    exceeds.then_some(Warning::ThreadsReduced { requested, maximum })
+}
+
+/// Returns the warning for `--ignore-check` when decompressing.
+fn ignore_check_warning(config: &CliConfig) -> Option<Warning> {
+    let decompressing = matches!(
+        config.mode,
+        OperationMode::Decompress | OperationMode::Cat | OperationMode::Test
+    );
+    (config.ignore_check && decompressing).then_some(Warning::IntegrityCheckIgnored)
+}
+
+/// Print the `-v` result line for a file that was processed completely.
+fn emit_file_result(config: &CliConfig, name: &str, summary: StreamSummary, elapsed: Duration) {
+    let StreamSummary {
+        bytes_read,
+        bytes_written,
+    } = summary;
+    if config.verbose {
+        let line = format_file_result(name, config.mode, bytes_read, bytes_written, elapsed);
+        eprintln!("{line}");
+    }
+}
+
 /// Returns `true` if the diagnostic cause is a `BrokenPipe` write error.
 fn is_broken_pipe(err: &DiagnosticCause) -> bool {
     match err.as_error() {
-        Some(crate::error::Error::WriteOutput { source }) => {
+        Some(crate::error::Error::WriteOutput { source, .. }) => {
             source.kind() == io::ErrorKind::BrokenPipe
         }
         _ => false,
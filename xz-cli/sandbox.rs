@@ -0,0 +1,168 @@
+//! Process sandboxing for the CLI tools.
+//!
+//! Like upstream `xz`, the process gives up the rights it doesn't need once the options are
+//! parsed, so a bug in the decoder can't be turned into access to other files or the network.
+//! [`init`] still allows reading, creating and removing regular files, which compressing to
+//! files needs. [`enable_strict`] is used once the only input and stdout are open and drops
+//! file system access altogether.
+//!
+//! Linux uses Landlock, OpenBSD `pledge` and FreeBSD Capsicum, as selected when liblzma-sys
+//! was built (see [`xz_core::Version::sandbox`]). Elsewhere, when disabled there, and on kernels
+//! without support, both calls do nothing: the sandbox is a safety net, not a requirement.
+
+#[cfg(any(target_os = "linux", target_os = "openbsd", target_os = "freebsd"))]
+use xz_core::Version;
+
+/// Restricts the process to the file operations needed to process files.
+pub(crate) fn init() {
+    #[cfg(target_os = "linux")]
+    if Version::sandbox() == Some("landlock") {
+        landlock::restrict(landlock::FILE_ACCESS);
+    }
+
+    #[cfg(target_os = "openbsd")]
+    if Version::sandbox() == Some("pledge") {
+        openbsd::pledge(c"stdio rpath wpath cpath fattr");
+    }
+}
+
+/// Removes access to the file system; only the already open files stay usable.
+pub(crate) fn enable_strict() {
+    #[cfg(target_os = "linux")]
+    if Version::sandbox() == Some("landlock") {
+        landlock::restrict(0);
+    }
+
+    #[cfg(target_os = "openbsd")]
+    if Version::sandbox() == Some("pledge") {
+        openbsd::pledge(c"stdio");
+    }
+
+    #[cfg(target_os = "freebsd")]
+    if Version::sandbox() == Some("capsicum") {
+        // SAFETY: `cap_enter` takes no arguments; failure leaves the process unrestricted.
+        unsafe {
+            libc::cap_enter();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod landlock {
+    use std::mem;
+    use std::ptr;
+
+    /// `struct landlock_ruleset_attr` from `<linux/landlock.h>`.
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+        handled_access_net: u64,
+    }
+
+    const CREATE_RULESET_VERSION: libc::c_uint = 1 << 0;
+
+    const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+    const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+    const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+    const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+    const ACCESS_FS_REFER: u64 = 1 << 13;
+    const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+    const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+
+    const ACCESS_NET_BIND_TCP: u64 = 1 << 0;
+    const ACCESS_NET_CONNECT_TCP: u64 = 1 << 1;
+
+    /// Rights needed to read inputs, write outputs through a temporary file, rename it into
+    /// place, sync its directory and remove the input.
+    pub(super) const FILE_ACCESS: u64 = ACCESS_FS_WRITE_FILE
+        | ACCESS_FS_READ_FILE
+        | ACCESS_FS_READ_DIR
+        | ACCESS_FS_REMOVE_FILE
+        | ACCESS_FS_MAKE_REG
+        | ACCESS_FS_TRUNCATE;
+
+    /// Denies every file system and network right the kernel knows, except `allowed`.
+    ///
+    /// Errors are ignored: without Landlock the process just keeps running unrestricted.
+    pub(super) fn restrict(allowed: u64) {
+        // SAFETY: querying the ABI version takes no attribute and has no side effects.
+        let abi = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                ptr::null::<RulesetAttr>(),
+                0_usize,
+                CREATE_RULESET_VERSION,
+            )
+        };
+        if abi < 1 {
+            return;
+        }
+
+        // Rights the running kernel can restrict: ABI 1 has the first 13, later versions
+        // add one each, and ABI 4 adds the network.
+        let mut handled_access_fs = (1 << 13) - 1;
+        if abi >= 2 {
+            handled_access_fs |= ACCESS_FS_REFER;
+        }
+        if abi >= 3 {
+            handled_access_fs |= ACCESS_FS_TRUNCATE;
+        }
+        if abi >= 5 {
+            handled_access_fs |= ACCESS_FS_IOCTL_DEV;
+        }
+        let handled_access_net = if abi >= 4 {
+            ACCESS_NET_BIND_TCP | ACCESS_NET_CONNECT_TCP
+        } else {
+            0
+        };
+
+        // Rights that are handled but not allowed by any rule are denied everywhere, so
+        // leaving the allowed ones unhandled is all that is needed.
+        let attr = RulesetAttr {
+            handled_access_fs: handled_access_fs & !allowed,
+            handled_access_net,
+        };
+
+        // SAFETY: `attr` is a valid `landlock_ruleset_attr` of the given size.
+        let ruleset = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr,
+                mem::size_of::<RulesetAttr>(),
+                0_u32,
+            )
+        };
+        let Ok(ruleset) = libc::c_int::try_from(ruleset) else {
+            return;
+        };
+        if ruleset < 0 {
+            return;
+        }
+
+        // SAFETY: `prctl` and `landlock_restrict_self` only take integer arguments, and
+        // `ruleset` is a file descriptor owned here and closed right after.
+        unsafe {
+            // Required to restrict an unprivileged process; it also keeps later `exec`s
+            // from gaining privileges.
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) == 0 {
+                libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0_u32);
+            }
+            libc::close(ruleset);
+        }
+    }
+}
+
+#[cfg(target_os = "openbsd")]
+mod openbsd {
+    use std::ffi::CStr;
+    use std::ptr;
+
+    /// Restricts the process to `promises`; errors leave it unrestricted.
+    pub(super) fn pledge(promises: &CStr) {
+        // SAFETY: `promises` is a valid C string and a null `execpromises` keeps them as is.
+        unsafe {
+            libc::pledge(promises.as_ptr(), ptr::null());
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Signal handling for the CLI tools.
+//!
+//! Like upstream `xz`, `SIGINT`, `SIGTERM` and `SIGHUP` don't kill the process right away.
+//! The handler only records the signal; reading the input then fails, the partially written
+//! output file is removed, and the tool exits with status `128 + signal`. `SIGUSR1` (and
+//! `SIGINFO` where it exists) asks for the current progress line to be printed.
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+/// Number of the signal that asked the process to stop, or `0`.
+static USER_ABORT: AtomicI32 = AtomicI32::new(0);
+
+/// Set when a progress report was requested and not yet printed.
+static PROGRESS_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the signal handlers; calling it more than once has no further effect.
+///
+/// Signals that were ignored when the process started, e.g. under `nohup`, stay ignored.
+pub(crate) fn install() {
+    #[cfg(unix)]
+    {
+        static INSTALLED: std::sync::Once = std::sync::Once::new();
+        INSTALLED.call_once(unix::install);
+    }
+}
+
+/// Returns the signal that asked the process to stop, if any.
+pub(crate) fn user_abort() -> Option<i32> {
+    match USER_ABORT.load(Ordering::Relaxed) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+/// Returns `true` once for every progress report requested by a signal.
+pub(crate) fn take_progress_request() -> bool {
+    PROGRESS_REQUESTED.swap(false, Ordering::Relaxed)
+}
+
+/// Reader that fails with [`io::ErrorKind::Interrupted`] once a stop signal has arrived.
+///
+/// The handlers are installed without `SA_RESTART`, so a read blocked on a pipe or a
+/// terminal returns early when a signal arrives; reads interrupted by other signals are
+/// retried.
+pub(crate) struct AbortOnSignal<R>(pub(crate) R);
+
+impl<R: Read> Read for AbortOnSignal<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if user_abort().is_some() {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            match self.0.read(buf) {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::mem;
+    use std::ptr;
+    use std::sync::atomic::Ordering;
+
+    use super::{PROGRESS_REQUESTED, USER_ABORT};
+
+    extern "C" fn request_abort(signal: libc::c_int) {
+        USER_ABORT.store(signal, Ordering::Relaxed);
+    }
+
+    extern "C" fn request_progress(_signal: libc::c_int) {
+        PROGRESS_REQUESTED.store(true, Ordering::Relaxed);
+    }
+
+    pub(super) fn install() {
+        for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+            set_handler(signal, request_abort);
+        }
+        set_handler(libc::SIGUSR1, request_progress);
+        #[cfg(any(
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "freebsd",
+            target_os = "dragonfly",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        ))]
+        set_handler(libc::SIGINFO, request_progress);
+    }
+
+    fn set_handler(signal: libc::c_int, handler: extern "C" fn(libc::c_int)) {
+        // SAFETY: `sigaction` is given valid pointers to zero-initialized structs, and the
+        // handlers only store to atomics, which is async-signal-safe.
+        unsafe {
+            let mut old: libc::sigaction = mem::zeroed();
+            if libc::sigaction(signal, ptr::null(), &mut old) != 0
+                || old.sa_sigaction == libc::SIG_IGN
+            {
+                return;
+            }
+
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handler as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            // No `SA_RESTART`, so blocking reads notice the signal.
+            action.sa_flags = 0;
+            libc::sigaction(signal, &action, ptr::null_mut());
+        }
+    }
+}
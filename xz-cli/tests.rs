@@ -1,4 +1,4 @@
-use std::io::Cursor;
+use std::io::{self, Cursor};
 use std::path::{Path, PathBuf};
 
 use xz_core::options::CompressionOptions;
@@ -67,6 +67,43 @@ fn parse_memory_limit_zero() {
     assert_eq!(parse_memory_limit("0K").unwrap(), 0);
 }
 
+/// Test percentages of physical memory and the `max` keyword
+#[test]
+fn parse_memory_limit_percentage_and_max() {
+    let ram = xz_core::Hardware::physical_memory();
+    assert_eq!(parse_memory_limit("100%").unwrap(), ram);
+    assert_eq!(parse_memory_limit("50%").unwrap(), ram / 2);
+    assert_eq!(parse_memory_limit("max").unwrap(), 0);
+    assert_eq!(parse_memory_limit("MAX").unwrap(), 0);
+    assert!(parse_memory_limit("0%").is_err());
+    assert!(parse_memory_limit("101%").is_err());
+    assert!(parse_memory_limit("%").is_err());
+}
+
+/// Test parsing of `--block-list` values
+#[test]
+fn parse_block_list_sizes() {
+    assert_eq!(parse_block_list("1024").unwrap(), [1024]);
+    assert_eq!(
+        parse_block_list("1KiB, 2M,,0").unwrap(),
+        [1024, 2 * 1024 * 1024, 2 * 1024 * 1024, 0]
+    );
+    assert!(parse_block_list("").is_err());
+    assert!(parse_block_list(",1K").is_err());
+    assert!(parse_block_list("0,1K").is_err());
+    assert!(parse_block_list("1X").is_err());
+    assert!(parse_block_list("50%").is_err());
+}
+
+/// Test parsing of `--block-size` values
+#[test]
+fn parse_block_size_units() {
+    assert_eq!(parse_block_size("0").unwrap(), 0);
+    assert_eq!(parse_block_size("4MiB").unwrap(), 4 * 1024 * 1024);
+    assert!(parse_block_size("").is_err());
+    assert!(parse_block_size("max").is_err());
+}
+
 /// Test recognition of valid compression extensions
 #[test]
 fn has_compression_extension_valid() {
@@ -74,6 +111,7 @@ fn has_compression_extension_valid() {
     assert!(has_compression_extension(Path::new("file.lzma")));
     assert!(has_compression_extension(Path::new("FILE.XZ")));
     assert!(has_compression_extension(Path::new("FILE.LZMA")));
+    assert!(has_compression_extension(Path::new("file.lz")));
     assert!(has_compression_extension(Path::new("archive.tar.xz")));
 }
 
@@ -88,6 +126,21 @@ fn has_compression_extension_invalid() {
 }
 
 /// Test paths without extensions
+#[test]
+fn has_compressed_content_checks_magic_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let compressed = dir.path().join("data.txt");
+    let plain = dir.path().join("plain.xz");
+    let mut data = Vec::new();
+    compress(&b"data"[..], &mut data, &CompressionOptions::default()).unwrap();
+    std::fs::write(&compressed, data).unwrap();
+    std::fs::write(&plain, b"plain text").unwrap();
+
+    assert!(has_compressed_content(&compressed));
+    assert!(!has_compressed_content(&plain));
+    assert!(!has_compressed_content(&dir.path().join("missing")));
+}
+
 #[test]
 fn has_compression_extension_no_extension() {
     assert!(!has_compression_extension(Path::new("filename")));
@@ -160,6 +213,12 @@ fn generate_output_filename_decompress_basic() {
         generate_output_filename(input, OperationMode::Decompress, None, XZ_EXTENSION, false)
             .unwrap();
     assert_eq!(output, PathBuf::from("test"));
+
+    let input = Path::new("test.lz");
+    let output =
+        generate_output_filename(input, OperationMode::Decompress, None, XZ_EXTENSION, false)
+            .unwrap();
+    assert_eq!(output, PathBuf::from("test"));
 }
 
 /// Test decompression with paths
@@ -280,10 +339,57 @@ fn generate_output_filename_decompress_custom_suffix() {
     assert_eq!(output, PathBuf::from("file"));
 }
 
+/// Test the known suffixes are still recognized when a custom suffix is given
+#[test]
+fn generate_output_filename_decompress_known_suffix_with_custom_suffix() {
+    let input = Path::new("test.txt.xz");
+    let output = generate_output_filename(
+        input,
+        OperationMode::Decompress,
+        Some("myext"),
+        XZ_EXTENSION,
+        false,
+    )
+    .unwrap();
+    assert_eq!(output, PathBuf::from("test.txt"));
+}
+
+/// Test `.txz` and `.tlz` decompress to `.tar` like upstream xz
+#[test]
+fn generate_output_filename_decompress_tar_abbreviations() {
+    let input = Path::new("archive.txz");
+    let output =
+        generate_output_filename(input, OperationMode::Decompress, None, XZ_EXTENSION, false)
+            .unwrap();
+    assert_eq!(output, PathBuf::from("archive.tar"));
+
+    let input = Path::new("archive.tlz");
+    let output =
+        generate_output_filename(input, OperationMode::Decompress, None, XZ_EXTENSION, false)
+            .unwrap();
+    assert_eq!(output, PathBuf::from("archive.tar"));
+}
+
+/// Test a name that is only a suffix has no name left to decompress to
+#[test]
+fn generate_output_filename_decompress_suffix_only() {
+    let result = generate_output_filename(
+        Path::new(".xz"),
+        OperationMode::Decompress,
+        None,
+        XZ_EXTENSION,
+        false,
+    );
+    assert!(matches!(
+        result,
+        Err(DiagnosticCause::Warning(Warning::InvalidExtension { .. }))
+    ));
+}
+
 /// Test decompression with custom suffix fails on wrong extension
 #[test]
 fn generate_output_filename_decompress_custom_suffix_mismatch() {
-    let input = Path::new("test.txt.xz");
+    let input = Path::new("test.txt.gz");
     let result = generate_output_filename(
         input,
         OperationMode::Decompress,
@@ -339,6 +445,64 @@ fn generate_output_filename_compress_already_has_suffix() {
     ));
 }
 
+/// Test compression skips every suffix of the output format, like upstream xz
+#[test]
+fn generate_output_filename_compress_skips_format_suffixes() {
+    for (name, extension) in [
+        ("archive.txz", XZ_EXTENSION),
+        ("archive.tlz", LZMA_EXTENSION),
+        ("file.lzma", LZMA_EXTENSION),
+    ] {
+        let result = generate_output_filename(
+            Path::new(name),
+            OperationMode::Compress,
+            None,
+            extension,
+            false,
+        );
+        assert!(
+            matches!(
+                result,
+                Err(DiagnosticCause::Warning(Warning::AlreadyHasSuffix { .. }))
+            ),
+            "{name} should be skipped"
+        );
+    }
+
+    // A custom suffix doesn't make the format's own suffixes acceptable.
+    let result = generate_output_filename(
+        Path::new("file.xz"),
+        OperationMode::Compress,
+        Some("bin"),
+        XZ_EXTENSION,
+        false,
+    );
+    assert!(matches!(
+        result,
+        Err(DiagnosticCause::Warning(Warning::AlreadyHasSuffix { suffix, .. })) if suffix == ".xz"
+    ));
+
+    // Suffixes of other formats are fine, and raw streams only have the custom suffix.
+    let output = generate_output_filename(
+        Path::new("file.lzma"),
+        OperationMode::Compress,
+        None,
+        XZ_EXTENSION,
+        false,
+    )
+    .unwrap();
+    assert_eq!(output, PathBuf::from("file.lzma.xz"));
+    let output = generate_output_filename(
+        Path::new("file.xz"),
+        OperationMode::Compress,
+        Some("raw"),
+        "",
+        false,
+    )
+    .unwrap();
+    assert_eq!(output, PathBuf::from("file.xz.raw"));
+}
+
 /// Test compression with force flag allows files with target suffix
 #[test]
 fn generate_output_filename_compress_force_allows_suffix() {
@@ -719,7 +883,7 @@ fn default_decodes_concatenated_xz_streams() {
 
     let mut decoded = Vec::new();
     match decompress_file(Cursor::new(concatenated), &mut decoded, &config, false) {
-        Ok(()) => {}
+        Ok(_) => {}
         Err(err) => panic!("decompress_file(default) failed: {err:?}"),
     }
 
@@ -749,9 +913,335 @@ fn single_stream_ignores_trailing_stream() {
 
     let mut decoded = Vec::new();
     match decompress_file(Cursor::new(concatenated), &mut decoded, &config, false) {
-        Ok(()) => {}
+        Ok(_) => {}
         Err(err) => panic!("decompress_file(--single-stream) failed: {err:?}"),
     }
 
     assert_eq!(decoded, plain_a);
 }
+
+/// List sizes switch units like upstream `xz -l` and round to one decimal.
+#[test]
+fn format_list_size_matches_upstream() {
+    use crate::utils::bytes::{format_list_size, format_list_size_with_bytes};
+
+    assert_eq!(format_list_size(68), "68 B");
+    assert_eq!(format_list_size(9999), "9999 B");
+    assert_eq!(format_list_size(10_000), "9.8 KiB");
+    assert_eq!(format_list_size(588_895), "575.1 KiB");
+    assert_eq!(format_list_size(9_000_000), "8789.1 KiB");
+    assert_eq!(format_list_size(20_000_000), "19.1 MiB");
+    assert_eq!(format_list_size(u64::MAX), "16777216.0 TiB");
+    assert_eq!(format_list_size_with_bytes(5000), "5000 B");
+    assert_eq!(format_list_size_with_bytes(10_000), "9.8 KiB (10000 B)");
+}
+
+/// Ratios that are undefined or too large to be meaningful print as `---`.
+#[test]
+fn format_list_ratio_matches_upstream() {
+    use crate::utils::math::format_list_ratio;
+
+    assert_eq!(format_list_ratio(17_960, 588_895), "0.030");
+    assert_eq!(format_list_ratio(68, 2), "---");
+    assert_eq!(format_list_ratio(32, 0), "---");
+}
+
+/// Test check names for single and mixed integrity checks.
+#[test]
+fn format_check_names_matches_upstream() {
+    use crate::format::list::format_check_names;
+
+    assert_eq!(format_check_names(1 << 4, false), "CRC64");
+    assert_eq!(format_check_names(1 << 10, true), "SHA-256");
+    assert_eq!(
+        format_check_names((1 << 4) | (1 << 10), false),
+        "CRC64,SHA-256"
+    );
+    assert_eq!(
+        format_check_names((1 << 1) | (1 << 4), true),
+        "CRC32, CRC64"
+    );
+    assert_eq!(format_check_names(1 << 3, false), "Unknown-3");
+}
+
+/// Test the `-vv` memory and version formatting helpers.
+#[test]
+fn list_requirements_formatting() {
+    use crate::format::list::{block_min_version, format_memusage, format_version};
+
+    assert_eq!(format_memusage(0), "0 MiB");
+    assert_eq!(format_memusage(1), "1 MiB");
+    assert_eq!(format_memusage(9 * 1024 * 1024), "9 MiB");
+    assert_eq!(format_version(block_min_version(&[0x21])), "5.0.0");
+    assert_eq!(format_version(block_min_version(&[0x0A, 0x21])), "5.4.0");
+    assert_eq!(format_version(block_min_version(&[0x0B, 0x21])), "5.6.0");
+}
+
+/// Test `--info-memory` output in the human and robot formats.
+#[test]
+fn info_memory_formats() {
+    use crate::format::info::{write_info_memory, MemoryInfo};
+
+    let info = MemoryInfo {
+        physical_memory: 6_294_937_600,
+        threads: 4,
+        compression: Some(5000),
+        decompression: None,
+        mt_decompression: 1_573_734_400,
+        mt_default: 1_573_734_400,
+    };
+
+    let mut robot = Vec::new();
+    write_info_memory(&mut robot, info, true).unwrap();
+    assert_eq!(
+        String::from_utf8(robot).unwrap(),
+        "6294937600\t5000\t0\t1573734400\t1573734400\t4\n"
+    );
+
+    let mut human = Vec::new();
+    write_info_memory(&mut human, info, false).unwrap();
+    let human = String::from_utf8(human).unwrap();
+    assert!(human.contains("  Amount of physical memory (RAM):  6004 MiB (6294937600 B)\n"));
+    assert!(human.contains("  Compression:                      1 MiB (5000 B)\n"));
+    assert!(human.contains("  Decompression:                    Disabled\n"));
+    assert!(human.contains("  Default for -T0:                  1501 MiB (1573734400 B)\n"));
+}
+
+/// Test `--version` describes the linked liblzma.
+#[test]
+fn version_describes_backend() {
+    use crate::format::info::{write_version, BackendInfo};
+
+    let backend = BackendInfo {
+        version: "5.8.1".to_string(),
+        vendored: true,
+        threads: true,
+    };
+    let mut out = Vec::new();
+    write_version(&mut out, "xz", "0.1.1", &backend).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "xz 0.1.1\nliblzma 5.8.1 (vendored, multi-threaded)\n"
+    );
+
+    let backend = BackendInfo {
+        vendored: false,
+        threads: false,
+        ..backend
+    };
+    let mut out = Vec::new();
+    write_version(&mut out, "xz", "0.1.1", &backend).unwrap();
+    assert!(String::from_utf8(out)
+        .unwrap()
+        .ends_with("liblzma 5.8.1 (system, single-threaded)\n"));
+}
+
+/// Test the callback of `compress_file_with` sees the final byte counts.
+#[test]
+fn compress_file_with_reports_progress() {
+    let data = b"progress reported to the callback ".repeat(100);
+    let mut last = None;
+    let mut callback = |event: Event<'_>| {
+        if let Event::Progress {
+            bytes_read,
+            bytes_written,
+        } = event
+        {
+            last = Some((bytes_read, bytes_written));
+        }
+    };
+
+    let mut compressed = Vec::new();
+    let summary = compress_file_with(
+        Cursor::new(&data),
+        &mut compressed,
+        &CliConfig::default(),
+        Some(&mut callback),
+    )
+    .unwrap();
+
+    let (bytes_read, _) = last.expect("no progress reported");
+    assert_eq!(bytes_read, data.len() as u64);
+    assert_eq!(summary.bytes_read, data.len() as u64);
+    assert_eq!(summary.bytes_written, compressed.len() as u64);
+}
+
+/// Test `run_cli_with` follows the file rules of the tools and reports diagnostics.
+#[test]
+fn run_cli_with_reports_diagnostics() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.txt");
+    std::fs::write(&input, b"embedded run").unwrap();
+    let missing = dir.path().join("missing.txt");
+
+    let mut diagnostics = Vec::new();
+    let mut progress = 0;
+    let mut callback = |event: Event<'_>| match event {
+        Event::Progress { .. } => progress += 1,
+        Event::Diagnostic(diagnostic) => diagnostics.push(diagnostic.file.clone()),
+    };
+
+    let config = CliConfig::default();
+    let report = run_cli_with(
+        &[input.clone(), missing.clone()],
+        &config,
+        "xz",
+        &mut callback,
+    );
+
+    assert_eq!(report.status, ExitStatus::Error);
+    assert_eq!(diagnostics, [Some(missing)]);
+    assert!(progress > 0);
+    // Like the tools, the input is replaced by the compressed file.
+    assert!(!input.exists());
+    assert!(dir.path().join("input.txt.xz").exists());
+}
+
+/// Test the estimate of the decoder threads that fit into `--memlimit-mt-decompress`.
+#[test]
+fn decoder_threads_fit_memory_limit() {
+    use crate::operations::fit_decoder_threads;
+
+    assert_eq!(fit_decoder_threads(4, 10 << 20, 100 << 20), 4);
+    assert_eq!(fit_decoder_threads(4, 10 << 20, 25 << 20), 2);
+    // Even a single thread that doesn't fit still decodes.
+    assert_eq!(fit_decoder_threads(4, 10 << 20, 1 << 20), 1);
+    assert_eq!(fit_decoder_threads(4, 0, 1 << 20), 4);
+
+    let warning = Warning::DecoderThreadsReduced {
+        requested: 4,
+        reduced: 2,
+        limit: 25 << 20,
+    };
+    assert_eq!(
+        warning.to_string(),
+        "Reduced the number of threads from 4 to 2 to not exceed the memory usage limit of 25 MiB"
+    );
+}
+
+/// Test that a reduced dictionary names its filter and rounds the sizes like upstream.
+#[test]
+fn dictionary_reduced_names_filter() {
+    use xz_core::options::FilterType;
+
+    let warning = Warning::DictionaryReduced {
+        filter: FilterType::Lzma1,
+        original: (3 << 20) + (1 << 19),
+        reduced: 1 << 20,
+        limit: (20 << 20) + 1,
+    };
+    assert_eq!(
+        warning.to_string(),
+        "Adjusted LZMA1 dictionary size from 3 MiB to 1 MiB to not exceed the memory usage \
+         limit of 21 MiB"
+    );
+
+    let warning = Warning::DictionaryReduced {
+        filter: FilterType::Lzma2,
+        original: 64 << 20,
+        reduced: 8 << 20,
+        limit: 100 << 20,
+    };
+    assert!(warning
+        .to_string()
+        .starts_with("Adjusted LZMA2 dictionary size"));
+}
+
+/// Test that refusing to write compressed data to or read it from a terminal names no
+/// file, like upstream.
+#[test]
+fn terminal_refusal_names_no_file() {
+    let cause = DiagnosticCause::from(Error::CompressedOutputToTerminal);
+    assert_eq!(
+        Diagnostic::new(cause, "xz", None).to_string(),
+        "xz: Compressed data cannot be written to a terminal"
+    );
+
+    let cause = DiagnosticCause::from(Error::CompressedInputFromTerminal);
+    assert_eq!(
+        Diagnostic::new(cause, "xz", None).to_string(),
+        "xz: Compressed data cannot be read from a terminal"
+    );
+}
+
+/// Test that errors about the invocation name no file, but output errors still name it.
+#[test]
+fn general_diagnostics_name_no_file() {
+    let cause = DiagnosticCause::from(Error::InvalidOption {
+        message: "Value of the option 'pb' must be in the range [0, 4]".into(),
+    });
+    assert_eq!(
+        Diagnostic::general(cause, "xz").to_string(),
+        "xz: Value of the option 'pb' must be in the range [0, 4]"
+    );
+    let cause = DiagnosticCause::from(Error::WriteOutput {
+        output: None,
+        source: IoErrorNoCode::new(io::Error::from(io::ErrorKind::BrokenPipe)),
+    });
+    assert!(Diagnostic::general(cause, "xz")
+        .to_string()
+        .starts_with("xz: (stdout): "));
+}
+
+/// Test that notes about the settings name no file and leave the exit status untouched.
+#[test]
+fn notes_keep_status_and_name_no_file() {
+    let mut report = Report::default();
+    report.note(Warning::IntegrityCheckIgnored, "xz");
+    assert_eq!(report.status, ExitStatus::Ok);
+    assert_eq!(
+        format_diagnostic_for_stderr(0, &report.diagnostics[0]).as_deref(),
+        Some(
+            "xz: --ignore-check: Integrity checks are not verified; corrupt data may go undetected"
+        )
+    );
+    assert_eq!(
+        format_diagnostic_for_stderr(1, &report.diagnostics[0]),
+        None
+    );
+}
+
+/// Test that diagnostics name stdin, and that write errors name the output instead.
+#[test]
+fn diagnostics_name_stdin_and_stdout() {
+    let input = Path::new("input.txt");
+    let read_error = DiagnosticCause::from(Error::Compression {
+        message: "Unexpected end of input".into(),
+    });
+    assert_eq!(
+        Diagnostic::new(read_error, "xz", None).to_string(),
+        "xz: (stdin): Unexpected end of input"
+    );
+    let dash_error = DiagnosticCause::from(Error::Compression {
+        message: "Unexpected end of input".into(),
+    });
+    assert_eq!(
+        Diagnostic::new(dash_error, "xz", Some(Path::new("-"))).to_string(),
+        "xz: (stdin): Unexpected end of input"
+    );
+
+    let write_error = |output: Option<PathBuf>| {
+        DiagnosticCause::from(Error::WriteOutput {
+            output,
+            source: IoErrorNoCode::new(std::io::Error::from_raw_os_error(28)),
+        })
+    };
+    let to_stdout = Diagnostic::new(write_error(None), "xz", Some(input));
+    assert_eq!(
+        to_stdout.to_string(),
+        "xz: (stdout): Write error: No space left on device"
+    );
+    let to_file = Diagnostic::new(write_error(Some("input.txt.xz".into())), "xz", Some(input));
+    assert_eq!(
+        to_file.to_string(),
+        "xz: input.txt.xz: Write error: No space left on device"
+    );
+
+    let exists = DiagnosticCause::from(Error::OutputExists {
+        path: "input.txt.xz".into(),
+    });
+    assert_eq!(
+        Diagnostic::new(exists, "xz", Some(input)).to_string(),
+        "xz: input.txt.xz: File exists"
+    );
+}
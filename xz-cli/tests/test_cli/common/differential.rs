@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::Path;
+
+use super::{find_system_binary, BinaryType, Fixture, Output};
+
+/// A command run by both our binary and the system tool of the same name
+pub struct Parity<'a> {
+    tool: &'a str,
+    files: Vec<(&'a str, &'a [u8])>,
+    args: Vec<&'a str>,
+    stdin: Option<&'a [u8]>,
+}
+
+impl<'a> Parity<'a> {
+    /// Run `tool` with `args` in an otherwise empty directory
+    pub fn new(tool: &'a str, args: &[&'a str]) -> Self {
+        Self {
+            tool,
+            files: Vec::new(),
+            args: args.to_vec(),
+            stdin: None,
+        }
+    }
+
+    /// Create `name` with `contents` in the directory before running
+    #[must_use]
+    pub fn file(mut self, name: &'a str, contents: &'a [u8]) -> Self {
+        self.files.push((name, contents));
+        self
+    }
+
+    /// Feed `stdin` to the command
+    #[must_use]
+    pub fn stdin(mut self, stdin: &'a [u8]) -> Self {
+        self.stdin = Some(stdin);
+        self
+    }
+
+    fn fixture(&self) -> Fixture {
+        let (names, contents): (Vec<&str>, Vec<&[u8]>) = self.files.iter().copied().unzip();
+        Fixture::with_files(&names, &contents)
+    }
+}
+
+/// Run `parity` with our binary and with the system one, each in its own copy of the
+/// files, and assert that they behave the same.
+///
+/// Compared are the exit code, stdout, the shape of stderr and the files left behind.
+/// Compressed data only has to decompress to the same bytes, since the system tool may
+/// be a different xz release. Messages only have to name the same program and file on
+/// the same number of lines, so wording changes between releases don't count.
+///
+/// Does nothing when the system tool isn't installed.
+///
+/// # Panics
+///
+/// Panics if the two runs differ.
+pub async fn assert_parity(parity: &Parity<'_>) {
+    if find_system_binary(parity.tool).is_none() || find_system_binary("xz").is_none() {
+        return;
+    }
+    let context = format!("{} {:?}", parity.tool, parity.args);
+
+    let mut ours = parity.fixture();
+    let ours_output = ours
+        .run_in_root(BinaryType::cargo(parity.tool), &parity.args, parity.stdin)
+        .await;
+    let mut theirs = parity.fixture();
+    let theirs_output = theirs
+        .run_in_root(BinaryType::system(parity.tool), &parity.args, parity.stdin)
+        .await;
+
+    assert_eq!(
+        ours_output.status.code(),
+        theirs_output.status.code(),
+        "{context}: exit codes differ\nours: {}\nsystem: {}",
+        ours_output.stderr,
+        theirs_output.stderr
+    );
+    assert_eq!(
+        stderr_shape(&ours_output),
+        stderr_shape(&theirs_output),
+        "{context}: stderr differs\nours: {}\nsystem: {}",
+        ours_output.stderr,
+        theirs_output.stderr
+    );
+    assert_same_data(
+        &mut theirs,
+        &ours_output.stdout_raw,
+        &theirs_output.stdout_raw,
+        &format!("{context}: stdout"),
+    )
+    .await;
+
+    let ours_files = directory_files(ours.root_dir_path());
+    let theirs_files = directory_files(theirs.root_dir_path());
+    let names = |files: &[(String, Vec<u8>)]| -> Vec<String> {
+        files.iter().map(|(name, _)| name.clone()).collect()
+    };
+    assert_eq!(
+        names(&ours_files),
+        names(&theirs_files),
+        "{context}: files left behind differ"
+    );
+    for ((name, ours_data), (_, theirs_data)) in ours_files.iter().zip(&theirs_files) {
+        assert_same_data(
+            &mut theirs,
+            ours_data,
+            theirs_data,
+            &format!("{context}: {name}"),
+        )
+        .await;
+    }
+}
+
+/// Message lines reduced to the program and the file they name
+///
+/// `xz: file.xz: Compressed data is corrupt` becomes `xz: file.xz`, and a message
+/// without a file, such as `xz: Reduced the number of threads ...`, becomes `xz`. The
+/// program is reduced to its file name, since the system tool is run by its full path.
+fn stderr_shape(output: &Output) -> Vec<String> {
+    output
+        .stderr
+        .lines()
+        .map(|line| {
+            let mut parts = line.splitn(3, ": ");
+            let program = parts.next().unwrap_or_default();
+            let program = Path::new(program)
+                .file_name()
+                .map_or(program.into(), |name| name.to_string_lossy());
+            match (parts.next(), parts.next()) {
+                (Some(file), Some(_)) => format!("{program}: {file}"),
+                _ => program.into_owned(),
+            }
+        })
+        .collect()
+}
+
+/// Assert that two outputs are identical or decompress to identical data.
+async fn assert_same_data(fixture: &mut Fixture, ours: &[u8], theirs: &[u8], context: &str) {
+    if ours == theirs {
+        return;
+    }
+
+    let ours = fixture
+        .run_with_stdin_raw(BinaryType::system("xz"), &["-dc"], ours)
+        .await;
+    let theirs = fixture
+        .run_with_stdin_raw(BinaryType::system("xz"), &["-dc"], theirs)
+        .await;
+    assert!(
+        ours.status.success() && theirs.status.success(),
+        "{context}: data differs and isn't compressed"
+    );
+    assert!(
+        ours.stdout_raw == theirs.stdout_raw,
+        "{context}: data decompresses differently"
+    );
+}
+
+/// Names and contents of the files in `dir`, sorted by name
+fn directory_files(dir: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut files: Vec<(String, Vec<u8>)> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            (name, fs::read(entry.path()).unwrap())
+        })
+        .collect();
+    files.sort();
+    files
+}
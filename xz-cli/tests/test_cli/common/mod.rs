@@ -6,10 +6,12 @@ use tokio::io::AsyncWriteExt;
 use tokio::sync::oneshot;
 
 mod data;
+mod differential;
 mod upstream;
 mod vectors;
 
 pub use data::{generate_random_data, BINARY_DATA, REPETITIVE_DATA, SAMPLE_TEXT};
+pub use differential::{assert_parity, Parity};
 pub use upstream::{assert_generated_roundtrip, generated_abc, generated_random, generated_text};
 pub use vectors::Vector;
 
@@ -38,7 +40,7 @@ impl BinaryType {
     /// # Panics
     ///
     /// Panics if the binary cannot be found.
-    fn get_path(&self) -> String {
+    pub fn get_path(&self) -> String {
         match self {
             BinaryType::Cargo(name) => {
                 let bin_env = format!("CARGO_BIN_EXE_{name}");
@@ -361,6 +363,23 @@ impl Fixture {
         self.run(BinaryType::cargo(name), args).await
     }
 
+    /// Spawn a cargo binary with piped stdin, stdout and stderr for interactive tests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the binary cannot be found or spawned.
+    pub fn spawn_cargo(&self, name: &str, args: &[&str]) -> tokio::process::Child {
+        tokio::process::Command::new(BinaryType::cargo(name).get_path())
+            .args(args)
+            .current_dir(self.root_dir.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap()
+    }
+
     /// Run a system binary with the specified arguments if available
     pub async fn run_system(&mut self, name: &str, args: &[&str]) -> Option<Output> {
         if find_system_binary(name).is_some() {
@@ -394,10 +413,14 @@ impl Fixture {
         args: &[&str],
         env_vars: &[(&str, &str)],
         stdin_bytes: Option<Vec<u8>>,
+        in_root: bool,
         kill_receiver: oneshot::Receiver<()>,
     ) -> Output {
         let bin_path = binary_type.get_path();
         let mut command = tokio::process::Command::new(&bin_path);
+        if in_root {
+            command.current_dir(self.root_dir.path());
+        }
         command
             .args(args)
             .envs(env_vars.iter().copied())
@@ -476,7 +499,7 @@ impl Fixture {
 
         let (kill_sender, kill_receiver) = oneshot::channel();
         let output = self
-            .run_until_killed(&binary_type, args, &[], stdin_bytes, kill_receiver)
+            .run_until_killed(&binary_type, args, &[], stdin_bytes, false, kill_receiver)
             .await;
         drop(kill_sender);
         output
@@ -496,7 +519,42 @@ impl Fixture {
     ) -> Output {
         let (kill_sender, kill_receiver) = oneshot::channel();
         let output = self
-            .run_until_killed(&binary_type, args, &[], Some(stdin.to_vec()), kill_receiver)
+            .run_until_killed(
+                &binary_type,
+                args,
+                &[],
+                Some(stdin.to_vec()),
+                false,
+                kill_receiver,
+            )
+            .await;
+        drop(kill_sender);
+        output
+    }
+
+    /// Run a binary from inside the fixture directory, so relative paths in `args`
+    /// and in its messages name fixture files.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the process cannot be spawned, if writing to stdin fails, or if
+    /// awaiting process output fails.
+    pub async fn run_in_root(
+        &mut self,
+        binary_type: BinaryType,
+        args: &[&str],
+        stdin: Option<&[u8]>,
+    ) -> Output {
+        let (kill_sender, kill_receiver) = oneshot::channel();
+        let output = self
+            .run_until_killed(
+                &binary_type,
+                args,
+                &[],
+                stdin.map(<[u8]>::to_vec),
+                true,
+                kill_receiver,
+            )
             .await;
         drop(kill_sender);
         output
@@ -527,7 +585,7 @@ impl Fixture {
     ) -> Output {
         let (kill_sender, kill_receiver) = oneshot::channel();
         let output = self
-            .run_until_killed(&binary_type, args, env_vars, None, kill_receiver)
+            .run_until_killed(&binary_type, args, env_vars, None, false, kill_receiver)
             .await;
         drop(kill_sender);
         output
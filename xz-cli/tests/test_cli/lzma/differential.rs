@@ -0,0 +1,43 @@
+use crate::add_test;
+use crate::common::{assert_parity, Parity, Vector, SAMPLE_TEXT};
+
+/// `.lzma` vectors upstream decodes and rejects
+const VECTORS: [&str; 8] = [
+    "good-known_size-with_eopm.lzma",
+    "good-known_size-without_eopm.lzma",
+    "good-unknown_size-with_eopm.lzma",
+    "bad-too_big_size-with_eopm.lzma",
+    "bad-too_small_size-without_eopm-1.lzma",
+    "bad-too_small_size-without_eopm-2.lzma",
+    "bad-too_small_size-without_eopm-3.lzma",
+    "bad-unknown_size-without_eopm.lzma",
+];
+
+// Test compressing to `.lzma` files and to stdout.
+add_test!(compress_modes, async {
+    let text = SAMPLE_TEXT.as_bytes();
+    for args in [
+        &["f.txt"][..],
+        &["-k", "f.txt"],
+        &["-c", "f.txt"],
+        &["-k", "-9", "f.txt"],
+    ] {
+        assert_parity(&Parity::new("lzma", args).file("f.txt", text)).await;
+    }
+    assert_parity(&Parity::new("lzma", &["-k", "f.lzma"]).file("f.lzma", text)).await;
+});
+
+// Test unlzma and lzcat on good and corrupt vectors.
+add_test!(decompress_vectors, async {
+    for name in VECTORS {
+        let vector = Vector::bundled(name);
+        for (tool, args) in [
+            ("unlzma", &["-k", name][..]),
+            ("unlzma", &["-t", name]),
+            ("lzcat", &[name]),
+            ("lzcat", &["-q", name]),
+        ] {
+            assert_parity(&Parity::new(tool, args).file(name, vector.data())).await;
+        }
+    }
+});
@@ -1,3 +1,4 @@
 mod basic;
 mod cli_options;
+mod differential;
 mod vectors;
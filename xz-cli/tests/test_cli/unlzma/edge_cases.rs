@@ -9,9 +9,14 @@ add_test!(unknown_suffix_is_skipped, async {
     let mut fixture = Fixture::with_file(FILE_NAME, data);
     let file_path = fixture.path(FILE_NAME);
 
+    let out = fixture.run_cargo("lzma", &["-k", &file_path]).await;
+    assert!(out.status.success());
+    let renamed_path = fixture.path("no_suffix.bin");
+    std::fs::rename(fixture.lzma_path(FILE_NAME), &renamed_path).unwrap();
+
     // unlzma in file->file mode needs to remove a suffix to determine output name.
     // If there is no recognized suffix, it should warn and skip (like upstream xz).
-    let out = fixture.run_cargo("unlzma", &[&file_path]).await;
+    let out = fixture.run_cargo("unlzma", &[&renamed_path]).await;
     assert!(!out.status.success());
     assert!(
         out.stderr
@@ -274,3 +274,65 @@ add_test!(no_sparse_option_affects_output_allocation, async {
         );
     }
 });
+
+// Test unxz --single-stream stops after the first stream
+add_test!(single_stream_option, async {
+    const FILE_NAME: &str = "single_stream.txt";
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let output = fixture.run_cargo("xz", &["-k", &file_path]).await;
+    assert!(output.status.success());
+
+    let compressed = std::fs::read(&compressed_path).unwrap();
+    let concat_path = fixture.path("concatenated.xz");
+    std::fs::write(&concat_path, [compressed.as_slice(), &compressed].concat()).unwrap();
+
+    let output = fixture
+        .run_cargo("unxz", &["--single-stream", "-c", &concat_path])
+        .await;
+    assert!(output.status.success(), "unxz failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+
+    let output = fixture.run_cargo("unxz", &["-c", &concat_path]).await;
+    assert!(output.status.success(), "unxz failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, [data.as_slice(), &data].concat());
+});
+
+// Test unxz reads null-separated input names with --files0
+add_test!(files0_option_reads_list, async {
+    const FILE_NAMES: [&str; 2] = ["files0_a.txt", "files0_b.txt"];
+    const LIST_FILE: &str = "files0_list.bin";
+
+    let data_a = generate_random_data(KB);
+    let data_b = generate_random_data(2 * KB);
+    let mut fixture = Fixture::with_files(&FILE_NAMES, &[&data_a, &data_b]);
+
+    let mut list = Vec::new();
+    for name in FILE_NAMES {
+        let output = fixture.run_cargo("xz", &[&fixture.path(name)]).await;
+        assert!(output.status.success());
+        list.extend_from_slice(fixture.compressed_path(name).as_bytes());
+        list.push(0);
+    }
+    std::fs::write(fixture.path(LIST_FILE), list).unwrap();
+
+    let list_path = fixture.path(LIST_FILE);
+    let output = fixture.run_cargo("unxz", &["--files0", &list_path]).await;
+    assert!(output.status.success());
+    fixture.assert_files(&FILE_NAMES, &[&data_a, &data_b]);
+});
+
+// Test unxz reports a missing --files list without a file context
+add_test!(files_option_missing_list, async {
+    let mut fixture = Fixture::with_file("dummy.txt", b"dummy");
+
+    let list_path = fixture.path("missing_list.txt");
+    let output = fixture.run_cargo("unxz", &["--files", &list_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output
+        .stderr
+        .contains(&format!("unxz: {list_path}: No such file or directory")));
+});
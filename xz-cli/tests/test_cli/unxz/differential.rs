@@ -0,0 +1,31 @@
+use crate::add_test;
+use crate::common::{assert_parity, Parity, Vector};
+
+// Test decompressing in place, with -k, -c and -t, and over an existing output.
+add_test!(decompress_modes, async {
+    let good = Vector::bundled("good-1-check-crc32.xz");
+    for args in [
+        &["f.xz"][..],
+        &["-k", "f.xz"],
+        &["-c", "f.xz"],
+        &["-t", "f.xz"],
+        &["-k", "f.xz", "missing.xz"],
+    ] {
+        assert_parity(&Parity::new("unxz", args).file("f.xz", good.data())).await;
+    }
+
+    for args in [&["f.xz"][..], &["-f", "f.xz"], &["-q", "f.xz"]] {
+        let parity = Parity::new("unxz", args)
+            .file("f.xz", good.data())
+            .file("f", b"existing");
+        assert_parity(&parity).await;
+    }
+});
+
+// Test inputs without a known suffix.
+add_test!(unknown_suffix, async {
+    let good = Vector::bundled("good-1-check-crc32.xz");
+    for args in [&["f.bin"][..], &["-q", "f.bin"], &["-c", "f.bin"]] {
+        assert_parity(&Parity::new("unxz", args).file("f.bin", good.data())).await;
+    }
+});
@@ -108,6 +108,31 @@ add_test!(decompress_to_stdout, async {
     assert!(fixture.file_exists(&format!("{FILE_NAME}.xz")));
 });
 
+// Test the sandbox the tool enters leaves every round trip working: files replaced by
+// their outputs, and a single input written to stdout without file system access
+add_test!(sandboxed_round_trip, async {
+    const NAMES: [&str; 2] = ["sandbox_a.txt", "sandbox_b.txt"];
+    let contents: [&[u8]; 2] = [SAMPLE_TEXT.as_bytes(), REPETITIVE_DATA.as_bytes()];
+
+    let mut fixture = Fixture::with_files(&NAMES, &contents);
+    let paths = NAMES.map(|name| fixture.path(name));
+    let compressed = NAMES.map(|name| fixture.compressed_path(name));
+
+    let output = fixture.run_cargo("xz", &[&paths[0], &paths[1]]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert!(!fixture.file_exists(NAMES[0]));
+
+    let output = fixture.run_cargo("xz", &["-dc", &compressed[0]]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, contents[0]);
+
+    let output = fixture
+        .run_cargo("xz", &["-d", &compressed[0], &compressed[1]])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    fixture.assert_files(&NAMES, &contents);
+});
+
 // Test compression with repetitive data
 add_test!(compress_repetitive_data, async {
     const FILE_NAME: &str = "repetitive.txt";
@@ -170,3 +195,157 @@ add_test!(test_integrity, async {
     // Compressed file should still exist
     assert!(fixture.file_exists(&format!("{FILE_NAME}.xz")));
 });
+
+// Test that compression and decompression keep the timestamps and permissions of the input
+#[cfg(unix)]
+add_test!(round_trip_preserves_metadata, async {
+    use std::fs::{self, File, FileTimes, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, SystemTime};
+
+    const FILE_NAME: &str = "metadata.txt";
+    let mut fixture = Fixture::with_file(FILE_NAME, SAMPLE_TEXT.as_bytes());
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let accessed = SystemTime::UNIX_EPOCH + Duration::from_secs(1_100_000_000);
+    File::options()
+        .write(true)
+        .open(&file_path)
+        .unwrap()
+        .set_times(
+            FileTimes::new()
+                .set_accessed(accessed)
+                .set_modified(modified),
+        )
+        .unwrap();
+    fs::set_permissions(&file_path, Permissions::from_mode(0o640)).unwrap();
+
+    let assert_metadata = |path: &str| {
+        let metadata = fs::metadata(path).unwrap();
+        assert_eq!(metadata.modified().unwrap(), modified, "{path}");
+        assert_eq!(metadata.accessed().unwrap(), accessed, "{path}");
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o640, "{path}");
+    };
+
+    let output = fixture.run_cargo("xz", &[&file_path]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert_metadata(&compressed_path);
+
+    let output = fixture.run_cargo("xz", &["-d", &compressed_path]).await;
+    assert!(output.status.success(), "xz -d failed: {}", output.stderr);
+    assert_metadata(&file_path);
+});
+
+// Test that `--recompress` rewrites a file in place with new settings and its metadata
+#[cfg(unix)]
+add_test!(recompress_replaces_file_in_place, async {
+    use std::fs::{self, File, FileTimes, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, SystemTime};
+
+    const FILE_NAME: &str = "recompress.txt";
+    let data = REPETITIVE_DATA.repeat(64).into_bytes();
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let output = fixture
+        .run_cargo("xz", &["-0", "-C", "crc32", &file_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    File::options()
+        .write(true)
+        .open(&compressed_path)
+        .unwrap()
+        .set_times(FileTimes::new().set_modified(modified))
+        .unwrap();
+    fs::set_permissions(&compressed_path, Permissions::from_mode(0o640)).unwrap();
+
+    let output = fixture
+        .run_cargo(
+            "xz",
+            &["--recompress", "-9", "-C", "sha256", &compressed_path],
+        )
+        .await;
+    assert!(
+        output.status.success(),
+        "xz --recompress failed: {}",
+        output.stderr
+    );
+
+    let metadata = fs::metadata(&compressed_path).unwrap();
+    assert_eq!(metadata.modified().unwrap(), modified);
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+    // The check ID in the Stream Flags shows the new settings; 0x0A is SHA-256.
+    let recompressed = fs::read(&compressed_path).unwrap();
+    assert_eq!(recompressed[7] & 0x0F, 0x0A);
+    let leftovers = fs::read_dir(fixture.root_dir_path()).unwrap().count();
+    assert_eq!(leftovers, 1);
+
+    let output = fixture.run_cargo("xz", &["-d", &compressed_path]).await;
+    assert!(output.status.success(), "xz -d failed: {}", output.stderr);
+    fixture.assert_files(&[FILE_NAME], &[&data]);
+
+    // Data that isn't compressed is reported and left as it is.
+    let output = fixture.run_cargo("xz", &["--recompress", &file_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        output.stderr.contains("File format not recognized"),
+        "stderr: {}",
+        output.stderr
+    );
+    fixture.assert_files(&[FILE_NAME], &[&data]);
+});
+
+// Test that `--recompress` keeps the container of each input, whatever `--format` says
+add_test!(recompress_keeps_input_format, async {
+    const FILE_NAME: &str = "recompress_format.txt";
+    let data = REPETITIVE_DATA.repeat(64).into_bytes();
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let lzma_path = format!("{file_path}.lzma");
+    let xz_path = fixture.compressed_path(FILE_NAME);
+    let output = fixture
+        .run_cargo("xz", &["-k", "-0", "--format=lzma", &file_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    let output = fixture.run_cargo("xz", &["-k", "-0", &file_path]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    // `-C` applies to the `.xz` file only, as `.lzma` has no integrity check.
+    let args = ["--recompress", "-9", "-C", "sha256", &lzma_path, &xz_path];
+    let output = fixture.run_cargo("xz", &args).await;
+    assert!(
+        output.status.success(),
+        "xz --recompress failed: {}",
+        output.stderr
+    );
+    // The `.lzma` header starts with the properties byte and the dictionary size of `-9`.
+    let lzma = std::fs::read(&lzma_path).unwrap();
+    assert_eq!(lzma[..5], [0x5D, 0x00, 0x00, 0x00, 0x04]);
+    let xz = std::fs::read(&xz_path).unwrap();
+    assert_eq!(xz[..6], [0xFD, b'7', b'z', b'X', b'Z', 0x00]);
+
+    // `--format` selects what is decoded, and `.lzma` is encoded as `.lzma` again.
+    let args = ["--format=lzma", "--recompress", "-1", &lzma_path];
+    let output = fixture.run_cargo("xz", &args).await;
+    assert!(
+        output.status.success(),
+        "xz --recompress failed: {}",
+        output.stderr
+    );
+    let lzma = std::fs::read(&lzma_path).unwrap();
+    assert_eq!(lzma[..5], [0x5D, 0x00, 0x00, 0x10, 0x00]);
+
+    for path in [&lzma_path, &xz_path] {
+        let output = fixture.run_cargo("xz", &["-dc", path]).await;
+        assert!(output.status.success(), "xz -dc failed: {}", output.stderr);
+        assert_eq!(output.stdout_raw, data);
+    }
+});
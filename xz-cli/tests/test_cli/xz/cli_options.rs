@@ -1,3 +1,6 @@
+use std::fs::{self, Permissions};
+use std::os::unix::fs::PermissionsExt;
+
 use crate::add_test;
 use crate::common::{
     assert_generated_roundtrip, generate_random_data, generated_abc, generated_random,
@@ -58,6 +61,101 @@ add_test!(thread_option, async {
     }
 });
 
+// `-T0` uses every processor thread for both compression and decompression.
+add_test!(thread_zero_uses_all_threads, async {
+    const FILE_NAME: &str = "thread_zero.txt";
+    let data = generate_random_data(MB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture.run_cargo("xz", &["-T0", "-k", &file_path]).await;
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    let output = fixture
+        .run_cargo("xz", &["-d", "--threads=0", "-c", &compressed_path])
+        .await;
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert_eq!(output.stdout_raw, data);
+});
+
+// More threads than the host has are clamped with a note that keeps the exit status.
+add_test!(thread_count_above_cpu_count_is_clamped, async {
+    const FILE_NAME: &str = "thread_clamp.txt";
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo("xz", &["-T100000", "-k", &file_path])
+        .await;
+    assert!(output.status.success());
+    assert!(
+        output
+            .stderr
+            .contains("xz: Reduced the number of threads from 100000 to "),
+        "stderr: {}",
+        output.stderr
+    );
+
+    let output = fixture
+        .run_cargo("xz", &["-d", "-q", "-T100000", "-c", &compressed_path])
+        .await;
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert_eq!(output.stdout_raw, data);
+});
+
+// `--mmap` reads the input through a memory mapping in every file mode.
+add_test!(mmap_option_roundtrip, async {
+    const FILE_NAME: &str = "mmap_test.txt";
+    let data = generate_random_data(MB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture.run_cargo("xz", &["--mmap", "-k", &file_path]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let output = fixture
+        .run_cargo("xz", &["--mmap", "-t", &compressed_path])
+        .await;
+    assert!(output.status.success(), "xz -t failed: {}", output.stderr);
+
+    let output = fixture
+        .run_cargo("xz", &["--mmap", "-dc", &compressed_path])
+        .await;
+    assert!(output.status.success(), "xz -d failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+});
+
+// `--no-io-hints` only turns off the page cache hints.
+add_test!(no_io_hints_option_roundtrip, async {
+    const FILE_NAME: &str = "io_hints.txt";
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo("xz", &["--no-io-hints", "-k", &file_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let output = fixture
+        .run_cargo("xz", &["--no-io-hints", "-dc", &compressed_path])
+        .await;
+    assert!(output.status.success(), "xz -d failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+});
+
 // Test -M (memory limit) option
 add_test!(memory_limit_option, async {
     const FILE_NAME: &str = "memory_test.txt";
@@ -68,9 +166,9 @@ add_test!(memory_limit_option, async {
     let file_path = fixture.path(FILE_NAME);
     let compressed_path = fixture.compressed_path(FILE_NAME);
 
-    // Test with memory limit
+    // Test with a memory limit the default preset fits into
     let output = fixture
-        .run_cargo("xz", &["-M", "1M", "-k", &file_path])
+        .run_cargo("xz", &["-M", "256M", "-k", &file_path])
         .await;
     assert!(output.status.success());
 
@@ -142,6 +240,25 @@ add_test!(lzma1_options_invalid_rejected, async {
     );
 });
 
+// Test invalid `--lzma2` options are reported before any file is processed.
+add_test!(lzma2_options_invalid_rejected_before_files, async {
+    const FILE_NAME: &str = "lzma2_invalid.txt";
+
+    let data = b"hello";
+    let mut fixture = Fixture::with_file(FILE_NAME, data);
+
+    let file_path = fixture.path(FILE_NAME);
+
+    let output = fixture.run_cargo("xz", &["--lzma2=pb=5", &file_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stderr.trim(),
+        "xz: Value of the option 'pb' must be in the range [0, 4]"
+    );
+    assert!(!fixture.file_exists("lzma2_invalid.txt.xz"));
+    fixture.assert_files(&[FILE_NAME], &[data]);
+});
+
 // Test -v (verbose) option
 add_test!(verbose_option, async {
     const FILE_NAME: &str = "verbose_test.txt";
@@ -169,6 +286,196 @@ add_test!(verbose_option, async {
     assert!(!output.stderr.is_empty() || !output.stdout.is_empty());
 });
 
+// Test -v prints a result line per file and, like upstream, no totals
+add_test!(verbose_prints_line_per_file_without_totals, async {
+    const FILE_1: &str = "verbose_total_1.txt";
+    const FILE_2: &str = "verbose_total_2.txt";
+    let data1 = generate_random_data(KB);
+    let data2 = generate_random_data(2 * KB);
+
+    let mut fixture = Fixture::with_files(&[FILE_1, FILE_2], &[&data1, &data2]);
+    let path1 = fixture.path(FILE_1);
+    let path2 = fixture.path(FILE_2);
+
+    let output = fixture.run_cargo("xz", &["-v", "-k", &path1, &path2]).await;
+    assert!(output.status.success());
+    assert_eq!(
+        output.stderr.matches(" / ").count(),
+        2,
+        "stderr: {}",
+        output.stderr
+    );
+    assert!(
+        output
+            .stderr
+            .lines()
+            .any(|line| line.starts_with(&format!("{path2}: ")) && line.contains(" / 2048 B = ")),
+        "stderr: {}",
+        output.stderr
+    );
+    assert_eq!(
+        output.stderr.lines().count(),
+        2,
+        "stderr: {}",
+        output.stderr
+    );
+    assert!(!output.stderr.contains("Total:"));
+    assert!(!output.stderr.contains("Details:"));
+});
+
+// Test -vv adds the filters and check used for each file
+add_test!(very_verbose_prints_details, async {
+    const FILE_NAME: &str = "very_verbose_test.txt";
+    let data = generate_random_data(KB);
+
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo("xz", &["-vv", "-k", "-9", "-C", "sha256", &file_path])
+        .await;
+    assert!(output.status.success());
+    assert!(
+        output.stderr.lines().any(
+            |line| line.starts_with(&format!("{file_path}: ")) && line.contains(" / 1024 B = ")
+        ),
+        "stderr: {}",
+        output.stderr
+    );
+    assert!(
+        output.stderr.contains("Details: --lzma2=preset=9, SHA-256"),
+        "stderr: {}",
+        output.stderr
+    );
+
+    fixture.remove_file(FILE_NAME);
+
+    let output = fixture
+        .run_cargo("xz", &["-vv", "-dk", &compressed_path])
+        .await;
+    assert!(output.status.success());
+    assert!(
+        output
+            .stderr
+            .contains("Details: SHA-256, 1 stream, 1 block"),
+        "stderr: {}",
+        output.stderr
+    );
+    fixture.assert_files(&[FILE_NAME], &[&data]);
+});
+
+// Test the -v result line matches what upstream prints when stderr isn't a terminal
+add_test!(verbose_result_line_matches_upstream, async {
+    const FILE_NAME: &str = "verbose_zeros.bin";
+    let data = vec![0_u8; 3_000_000];
+
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+    let file_path = fixture.path(FILE_NAME);
+    let output = fixture
+        .run_cargo("xz", &["-v", "-T1", "-c", &file_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    let compressed = output.stdout_raw.len();
+    assert_eq!(
+        output.stderr,
+        format!("{file_path}: {compressed} B / 2929.7 KiB = 0.000\n")
+    );
+
+    let output = fixture
+        .run_with_stdin_raw(BinaryType::cargo("xz"), &["-dv"], &output.stdout_raw)
+        .await;
+    assert!(output.status.success(), "xz -d failed: {}", output.stderr);
+    assert_eq!(
+        output.stderr,
+        format!("(stdin): {compressed} B / 2929.7 KiB = 0.000\n")
+    );
+});
+
+// Test --synchronous still replaces the input with the finished output
+add_test!(synchronous_option, async {
+    const FILE_NAME: &str = "synchronous_test.txt";
+    let data = generate_random_data(KB);
+
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo("xz", &["--synchronous", &file_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert!(!fixture.file_exists(FILE_NAME));
+
+    let output = fixture
+        .run_cargo("xz", &["-d", "--synchronous", &compressed_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    fixture.assert_files(&[FILE_NAME], &[&data]);
+});
+
+// Test --synchronous works for a read-only input, whose permissions the output gets
+add_test!(synchronous_read_only_input, async {
+    const FILE_NAME: &str = "synchronous_read_only.txt";
+    let data = generate_random_data(KB);
+
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    fs::set_permissions(&file_path, Permissions::from_mode(0o444)).unwrap();
+
+    let output = fixture
+        .run_cargo("xz", &["--synchronous", &file_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    let mode = fs::metadata(&compressed_path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o444);
+
+    let output = fixture
+        .run_cargo("xz", &["-d", "--synchronous", &compressed_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    fixture.assert_files(&[FILE_NAME], &[&data]);
+});
+
+// Test the filter presets produce the same output as the chains they stand for
+add_test!(filter_presets_match_filters, async {
+    const FILE_NAME: &str = "preset_test.bin";
+    let data = generate_random_data(16 * KB);
+
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+    let file_path = fixture.path(FILE_NAME);
+
+    for (preset, chain) in [
+        ("--preset-exe-x86", "--filters=x86 lzma2"),
+        ("--preset-exe-arm64", "--filters=arm64 lzma2"),
+        ("--preset-delta4", "--filters=delta:dist=4 lzma2"),
+    ] {
+        let with_preset = fixture
+            .run_cargo("xz", &["-c", "-1", preset, &file_path])
+            .await;
+        assert!(
+            with_preset.status.success(),
+            "{preset}: {}",
+            with_preset.stderr
+        );
+        let with_chain = fixture
+            .run_cargo("xz", &["-c", "-1", chain, &file_path])
+            .await;
+        assert!(
+            with_chain.status.success(),
+            "{chain}: {}",
+            with_chain.stderr
+        );
+        assert_eq!(with_preset.stdout_raw, with_chain.stdout_raw, "{preset}");
+
+        let decoded = fixture
+            .run_with_stdin_raw(BinaryType::cargo("xz"), &["-dc"], &with_preset.stdout_raw)
+            .await;
+        assert_eq!(decoded.stdout_raw, data, "{preset}");
+    }
+});
+
 // Test -q (quiet) option
 add_test!(quiet_option, async {
     const FILE_NAME: &str = "quiet_test.txt";
@@ -239,6 +546,28 @@ add_test!(quiet_suppresses_already_has_suffix_warning, async {
     assert!(output.stderr.is_empty());
 });
 
+// Test -Q keeps the warning message but doesn't turn it into exit status 2
+add_test!(no_warn_keeps_message_and_exits_zero, async {
+    const FILE_NAME: &str = "no_warn.xz";
+    let data = generate_random_data(KB);
+
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+    let file_path = fixture.path(FILE_NAME);
+
+    let output = fixture.run_cargo("xz", &[&file_path]).await;
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stderr.contains("File already has '.xz' suffix"));
+
+    let output = fixture.run_cargo("xz", &["-Q", &file_path]).await;
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stderr.contains("File already has '.xz' suffix"));
+
+    // -Q doesn't hide real errors.
+    let missing = fixture.path("missing.txt");
+    let output = fixture.run_cargo("xz", &["-Q", &missing]).await;
+    assert_eq!(output.status.code(), Some(1));
+});
+
 // Test multiple files compression
 add_test!(multiple_files, async {
     const FILE_1: &str = "file1.txt";
@@ -356,6 +685,55 @@ add_test!(files0_option_reads_list_from_file, async {
     assert!(!fixture.file_exists(&format!("{LIST_FILE}.xz")));
 });
 
+// Test --files takes every non-empty line literally, like upstream, even one that would
+// be a comment or a quoted name with --files-glob.
+add_test!(files_option_takes_names_literally, async {
+    const FILE_1: &str = "#x";
+    const FILE_2: &str = "'quoted'";
+    const LIST_FILE: &str = "files_literal.list";
+
+    let data1 = generate_random_data(KB);
+    let data2 = generate_random_data(KB);
+
+    let mut fixture = Fixture::with_files(
+        &[FILE_1, FILE_2, LIST_FILE],
+        &[&data1, &data2, format!("{FILE_1}\n\n{FILE_2}\n").as_bytes()],
+    );
+    let output = fixture
+        .run_in_root(BinaryType::cargo("xz"), &["--files", LIST_FILE, "-k"], None)
+        .await;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    assert!(fixture.file_exists(&format!("{FILE_1}.xz")));
+    assert!(fixture.file_exists(&format!("{FILE_2}.xz")));
+});
+
+// Test --files-glob skips comments and expands unquoted patterns.
+add_test!(files_option_expands_globs_and_skips_comments, async {
+    use std::fs;
+
+    const FILE_1: &str = "files_glob_1.log";
+    const FILE_2: &str = "files_glob_2.log";
+    const LIST_FILE: &str = "files_glob.list";
+
+    let data1 = generate_random_data(KB);
+    let data2 = generate_random_data(KB);
+
+    let mut fixture = Fixture::with_files(&[FILE_1, FILE_2], &[&data1, &data2]);
+    let pattern = fixture.path("files_glob_*.log");
+
+    let list_path = fixture.path(LIST_FILE);
+    fs::write(&list_path, format!("# logs to archive\n\n{pattern}\n")).unwrap();
+
+    let output = fixture
+        .run_cargo("xz", &["--files", &list_path, "--files-glob", "-k"])
+        .await;
+    assert!(output.status.success(), "{}", output.stderr);
+
+    assert!(fixture.file_exists(&format!("{FILE_1}.xz")));
+    assert!(fixture.file_exists(&format!("{FILE_2}.xz")));
+});
+
 // Test --files0 reads NUL-delimited file names from stdin when FILE is omitted.
 add_test!(files0_option_reads_list_from_stdin, async {
     const FILE_1: &str = "files0_stdin_input_1.txt";
@@ -443,88 +821,56 @@ add_test!(threaded_auto_decompression_does_not_fail, async {
     assert_eq!(output.stdout_raw, data);
 });
 
-// `--robot -c` must keep compressed bytes on stdout and diagnostics on stderr.
-add_test!(robot_stdout_keeps_compressed_stream_clean, async {
-    const FILE_NAME: &str = "robot_stdout.txt";
+// Like upstream, `--robot` has no output format for coding files and is refused before
+// any file is touched.
+add_test!(robot_coding_is_refused, async {
+    const FILE_NAME: &str = "robot_coding.txt";
     let data = generate_random_data(KB);
     let mut fixture = Fixture::with_file(FILE_NAME, &data);
 
     let file_path = fixture.path(FILE_NAME);
-    let output = fixture
-        .run_cargo("xz", &["--robot", "-c", &file_path])
-        .await;
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let output = fixture.run_cargo("xz", &["-k", &file_path]).await;
     assert!(output.status.success());
-    assert!(!output.stderr.is_empty());
 
-    let roundtrip = fixture
-        .run_with_stdin_raw(BinaryType::cargo("xz"), &["-d", "-c"], &output.stdout_raw)
-        .await;
-    assert!(roundtrip.status.success());
-    assert_eq!(roundtrip.stdout_raw, data);
+    for args in [
+        ["--robot", "-c", &file_path],
+        ["--robot", "-dc", &compressed_path],
+        ["--robot", "-t", &compressed_path],
+    ] {
+        let output = fixture.run_cargo("xz", &args).await;
+        assert_eq!(output.status.code(), Some(1), "{args:?}");
+        assert_eq!(
+            output.stderr,
+            "xz: Compression and decompression with --robot are not supported yet.\n"
+        );
+        assert!(output.stdout_raw.is_empty());
+    }
+    assert_eq!(fs::read(&file_path).unwrap(), data);
 });
 
-// `xz -d --robot -c` must keep payload bytes on stdout and diagnostics on stderr.
-add_test!(robot_decompress_stdout_keeps_payload_clean, async {
-    const FILE_NAME: &str = "robot_decompress_stdout.txt";
+// Test -S/--suffix option
+add_test!(custom_suffix_option, async {
+    const FILE_NAME: &str = "suffix_test.txt";
+    const CUSTOM_SUFFIX: &str = "custom";
     let data = generate_random_data(KB);
-    let mut fixture = Fixture::with_file(FILE_NAME, &data);
 
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
     let file_path = fixture.path(FILE_NAME);
-    let compressed_path = fixture.compressed_path(FILE_NAME);
-
-    let output = fixture.run_cargo("xz", &[&file_path]).await;
-    assert!(output.status.success());
+    let custom_compressed_name = format!("{FILE_NAME}.{CUSTOM_SUFFIX}");
+    let custom_compressed = fixture.path(&custom_compressed_name);
 
+    // Compress with custom suffix
     let output = fixture
-        .run_cargo("xz", &["-d", "--robot", "-c", &compressed_path])
+        .run_cargo("xz", &["-S", CUSTOM_SUFFIX, "-k", &file_path])
         .await;
     assert!(output.status.success());
-    assert_eq!(output.stdout_raw, data);
-    assert!(!output.stderr.is_empty());
-});
-
-// `xz -t --robot` must not print status lines to stdout.
-add_test!(robot_test_mode_writes_status_to_stderr, async {
-    const FILE_NAME: &str = "robot_test_mode.txt";
-    let data = generate_random_data(KB);
-    let mut fixture = Fixture::with_file(FILE_NAME, &data);
 
-    let file_path = fixture.path(FILE_NAME);
-    let compressed_path = fixture.compressed_path(FILE_NAME);
+    // Check that the file with custom suffix was created
+    assert!(fixture.file_exists(&custom_compressed_name));
 
-    let output = fixture.run_cargo("xz", &[&file_path]).await;
-    assert!(output.status.success());
-
-    let output = fixture
-        .run_cargo("xz", &["-t", "--robot", &compressed_path])
-        .await;
-    assert!(output.status.success());
-    assert!(output.stdout.is_empty());
-    assert!(output.stderr.contains("OK"));
-});
-
-// Test -S/--suffix option
-add_test!(custom_suffix_option, async {
-    const FILE_NAME: &str = "suffix_test.txt";
-    const CUSTOM_SUFFIX: &str = "custom";
-    let data = generate_random_data(KB);
-
-    let mut fixture = Fixture::with_file(FILE_NAME, &data);
-    let file_path = fixture.path(FILE_NAME);
-    let custom_compressed_name = format!("{FILE_NAME}.{CUSTOM_SUFFIX}");
-    let custom_compressed = fixture.path(&custom_compressed_name);
-
-    // Compress with custom suffix
-    let output = fixture
-        .run_cargo("xz", &["-S", CUSTOM_SUFFIX, "-k", &file_path])
-        .await;
-    assert!(output.status.success());
-
-    // Check that the file with custom suffix was created
-    assert!(fixture.file_exists(&custom_compressed_name));
-
-    // Original file should still exist (we used -k)
-    assert!(fixture.file_exists(FILE_NAME));
+    // Original file should still exist (we used -k)
+    assert!(fixture.file_exists(FILE_NAME));
 
     // Remove original before decompression
     fixture.remove_file(FILE_NAME);
@@ -597,6 +943,38 @@ add_test!(custom_suffix_already_present, async {
     assert!(!output.status.success());
 });
 
+// Test compressing skips `.txz`/`.tlz` names for their format, even with a custom suffix
+add_test!(compressed_tar_suffixes_are_skipped, async {
+    const FILE_NAMES: [&str; 2] = ["archive.txz", "archive.tlz"];
+    let data = generate_random_data(KB);
+
+    let mut fixture = Fixture::with_files(&FILE_NAMES, &[&data, &data]);
+    let txz_path = fixture.path(FILE_NAMES[0]);
+    let tlz_path = fixture.path(FILE_NAMES[1]);
+
+    let output = fixture
+        .run_cargo("xz", &["-k", "-S", ".bin", &txz_path])
+        .await;
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        output
+            .stderr
+            .contains("archive.txz: File already has '.txz' suffix, skipping"),
+        "stderr: {}",
+        output.stderr
+    );
+    assert!(!fixture.file_exists("archive.txz.bin"));
+
+    let output = fixture.run_cargo("lzma", &["-k", &tlz_path]).await;
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!fixture.file_exists("archive.tlz.lzma"));
+
+    // Names of the other format are compressed.
+    let output = fixture.run_cargo("xz", &["-k", &tlz_path]).await;
+    assert!(output.status.success(), "stderr: {}", output.stderr);
+    assert!(fixture.file_exists("archive.tlz.xz"));
+});
+
 // Test --single-stream option decompresses only the first stream
 add_test!(single_stream_option, async {
     use std::io::Write;
@@ -647,6 +1025,69 @@ add_test!(single_stream_option, async {
     assert_eq!(output.stdout_raw, expected_both);
 });
 
+// Trailing garbage is an error unless `--single-stream` is used; Stream Padding is allowed.
+add_test!(single_stream_ignores_trailing_garbage, async {
+    const FILE_NAME: &str = "trailing.txt";
+    let data = b"data before trailing bytes";
+    let mut fixture = Fixture::with_file(FILE_NAME, data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let output = fixture.run_cargo("xz", &["-k", &file_path]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    let compressed = std::fs::read(&compressed_path).unwrap();
+
+    let padded_path = fixture.path("padded.xz");
+    std::fs::write(&padded_path, [compressed.as_slice(), &[0; 8]].concat()).unwrap();
+    let output = fixture.run_cargo("xz", &["-dc", &padded_path]).await;
+    assert!(output.status.success(), "xz -d failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+
+    let garbage_path = fixture.path("garbage.xz");
+    std::fs::write(&garbage_path, [compressed.as_slice(), b"garbage"].concat()).unwrap();
+    let output = fixture.run_cargo("xz", &["-dc", &garbage_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        output.stderr.contains("Unexpected end of input"),
+        "stderr: {}",
+        output.stderr
+    );
+
+    let output = fixture
+        .run_cargo("xz", &["-dc", "--single-stream", &garbage_path])
+        .await;
+    assert!(output.status.success(), "xz -d failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+});
+
+// Data decoded before trailing garbage in a `.lzma` file is still written.
+add_test!(lzma_trailing_garbage_writes_decoded_data, async {
+    const FILE_NAME: &str = "trailing_lzma.txt";
+    let data = b"data before trailing bytes";
+    let mut fixture = Fixture::with_file(FILE_NAME, data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let output = fixture
+        .run_cargo("xz", &["-kc", "--format=lzma", &file_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let garbage_path = fixture.path("garbage.lzma");
+    std::fs::write(
+        &garbage_path,
+        [output.stdout_raw.as_slice(), b"garbage"].concat(),
+    )
+    .unwrap();
+    let output = fixture.run_cargo("xz", &["-dc", &garbage_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        output.stderr.contains("Compressed data is corrupt"),
+        "stderr: {}",
+        output.stderr
+    );
+    assert_eq!(output.stdout_raw, data);
+});
+
 // Test --ignore-check option skips integrity verification
 add_test!(ignore_check_option, async {
     const FILE_NAME: &str = "ignore_check_test.txt";
@@ -671,6 +1112,55 @@ add_test!(ignore_check_option, async {
     fixture.assert_files(&[FILE_NAME], &[data]);
 });
 
+// `--ignore-check` recovers data whose check is corrupt and warns that it isn't verified.
+add_test!(ignore_check_recovers_corrupt_check, async {
+    const FILE_NAME: &str = "corrupt_check.txt";
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let output = fixture.run_cargo("xz", &["-C", "sha256", &file_path]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    // With a single Block, the check field ends right before the Index, whose size is the
+    // Backward Size in the Stream Footer.
+    let mut compressed = std::fs::read(&compressed_path).unwrap();
+    let footer = compressed.len() - 12;
+    let backward_size = u32::from_le_bytes(compressed[footer + 4..footer + 8].try_into().unwrap());
+    let index_size = (backward_size as usize + 1) * 4;
+    compressed[footer - index_size - 1] ^= 0xFF;
+    std::fs::write(&compressed_path, &compressed).unwrap();
+
+    let output = fixture.run_cargo("xz", &["-dc", &compressed_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = fixture
+        .run_cargo("xz", &["-dc", "--ignore-check", &compressed_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+    assert!(
+        output
+            .stderr
+            .contains("--ignore-check: Integrity checks are not verified"),
+        "stderr: {}",
+        output.stderr
+    );
+
+    let output = fixture
+        .run_cargo("xz", &["-dcq", "--ignore-check", &compressed_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert!(output.stderr.is_empty(), "stderr: {}", output.stderr);
+
+    let output = fixture
+        .run_cargo("xzcat", &["--ignore-check", &compressed_path])
+        .await;
+    assert!(output.status.success(), "xzcat failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+});
+
 // Test --no-sparse option disables sparse output when decompressing to a file.
 add_test!(no_sparse_option_affects_output_allocation, async {
     use std::fs;
@@ -735,3 +1225,677 @@ add_test!(no_sparse_option_affects_output_allocation, async {
         );
     }
 });
+
+// `--robot --version` prints the encoded tool and liblzma versions.
+add_test!(robot_version_prints_encoded_versions, async {
+    let mut fixture = Fixture::with_file("unused.txt", b"");
+
+    let output = fixture.run_cargo("xz", &["--robot", "--version"]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "unexpected output: {}", output.stdout);
+    for (line, key) in lines.iter().zip(["XZ_VERSION=", "LIBLZMA_VERSION="]) {
+        let value = line.strip_prefix(key).expect("missing key");
+        assert!(value.parse::<u32>().is_ok(), "not a number: {line}");
+    }
+});
+
+// `--version` names the linked liblzma and how it was built.
+add_test!(version_reports_backend, async {
+    let mut fixture = Fixture::with_file("unused.txt", b"");
+
+    let output = fixture.run_cargo("xz", &["--version"]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "unexpected output: {}", output.stdout);
+    assert!(
+        lines[0].starts_with("xz "),
+        "unexpected output: {}",
+        output.stdout
+    );
+    assert!(
+        lines[1].starts_with("liblzma 5."),
+        "unexpected output: {}",
+        output.stdout
+    );
+    assert!(
+        lines[1].ends_with("-threaded)")
+            && (lines[1].contains("(system, ") || lines[1].contains("(vendored, ")),
+        "unexpected output: {}",
+        output.stdout
+    );
+});
+
+// `--robot --info-memory` prints one line of six tab-separated numbers.
+add_test!(robot_info_memory_prints_limits, async {
+    let mut fixture = Fixture::with_file("unused.txt", b"");
+
+    let output = fixture
+        .run_cargo(
+            "xz",
+            &["--memlimit-compress=5000", "--robot", "--info-memory"],
+        )
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let fields: Vec<u64> = output
+        .stdout
+        .trim_end()
+        .split('\t')
+        .map(|field| field.parse().expect("numeric field"))
+        .collect();
+    assert_eq!(fields.len(), 6);
+    assert_eq!(fields[1], 5000);
+    assert_eq!(fields[2], 0, "decompression limit should be disabled");
+});
+
+// `XZ_OPT` options apply before the command line ones.
+add_test!(xz_opt_environment_options_apply, async {
+    const FILE_NAME: &str = "xz_opt.txt";
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo_with_env("xz", &["-k", &file_path], &[("XZ_DEFAULTS", "-T1 -9")])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let output = fixture
+        .run_cargo_with_env("xz", &["-c", &compressed_path], &[("XZ_OPT", "-d")])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+
+    // The command line wins over the environment.
+    let output = fixture
+        .run_cargo_with_env("xz", &["-z", "-c", &file_path], &[("XZ_OPT", "-d")])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert!(output
+        .stdout_raw
+        .starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]));
+});
+
+// Environment variables may only hold options, and `XZ_DEFAULTS` only tuning options.
+add_test!(xz_environment_options_are_restricted, async {
+    const FILE_NAME: &str = "xz_defaults.txt";
+    let mut fixture = Fixture::with_file(FILE_NAME, b"data");
+
+    let file_path = fixture.path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo_with_env("xz", &["-c", &file_path], &[("XZ_DEFAULTS", "-d")])
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stderr.trim_end(),
+        "xz: XZ_DEFAULTS: --decompress: Option is not allowed in XZ_DEFAULTS"
+    );
+
+    let output = fixture
+        .run_cargo_with_env("xz", &["-c", &file_path], &[("XZ_OPT", "-9 other.txt")])
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stderr.trim_end(),
+        "xz: XZ_OPT: Non-option arguments are not allowed"
+    );
+});
+
+// The configuration file supplies defaults that `XZ_OPT` and the command line override.
+add_test!(xz_config_file_provides_defaults, async {
+    const FILE_NAME: &str = "config_defaults.txt";
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_files(
+        &[FILE_NAME, "config.toml"],
+        &[&data, b"suffix = \".cfg\"\nlevel = 1\n"],
+    );
+
+    let file_path = fixture.path(FILE_NAME);
+    let config_path = fixture.path("config.toml");
+
+    let output = fixture
+        .run_cargo_with_env("xz", &["-k", &file_path], &[("XZ_RS_CONFIG", &config_path)])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert!(fixture.file_exists("config_defaults.txt.cfg"));
+
+    let output = fixture
+        .run_cargo_with_env(
+            "xz",
+            &["-k", "--suffix=.cli", &file_path],
+            &[("XZ_RS_CONFIG", &config_path), ("XZ_OPT", "-S .env")],
+        )
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert!(fixture.file_exists("config_defaults.txt.cli"));
+
+    std::fs::write(&config_path, "level = 12\n").unwrap();
+    let output = fixture
+        .run_cargo_with_env("xz", &["-c", &file_path], &[("XZ_RS_CONFIG", &config_path)])
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stderr.trim_end(),
+        format!("xz: {config_path}:1: level: Expected an integer from 0 to 9")
+    );
+});
+
+// `--memlimit-compress` fails before any input when even a 1 MiB dictionary doesn't fit.
+add_test!(memlimit_compress_too_low_fails, async {
+    const FILE_NAME: &str = "memlimit_compress.txt";
+    let mut fixture = Fixture::with_file(FILE_NAME, b"data");
+
+    let file_path = fixture.path(FILE_NAME);
+    let output = fixture
+        .run_cargo("xz", &["-T1", "--memlimit-compress=1MiB", "-c", &file_path])
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stderr,
+        "xz: Memory usage limit is too low for the given filter setup.\n\
+         xz: 13 MiB of memory is required. The limit is 1 MiB.\n"
+    );
+    assert!(output.stdout_raw.is_empty());
+
+    let output = fixture
+        .run_cargo("xz", &["-T2", "--memlimit-compress=50%", "-c", &file_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+});
+
+// With `--no-adjust`, settings over `--memlimit-compress` fail instead of being lowered.
+add_test!(memlimit_compress_no_adjust_fails, async {
+    const FILE_NAME: &str = "memlimit_no_adjust.txt";
+    let mut fixture = Fixture::with_file(FILE_NAME, b"data");
+
+    let file_path = fixture.path(FILE_NAME);
+    let args = [
+        "--no-adjust",
+        "-9",
+        "-T1",
+        "--memlimit-compress=100MiB",
+        "-k",
+        &file_path,
+    ];
+    let output = fixture.run_cargo("xz", &args).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stderr,
+        "xz: Memory usage limit is too low for the given filter setup.\n\
+         xz: 674 MiB of memory is required. The limit is 100 MiB.\n"
+    );
+    assert!(!fs::exists(fixture.compressed_path(FILE_NAME)).unwrap());
+
+    // Settings that fit are used as they are.
+    let args = [
+        "--no-adjust",
+        "-1",
+        "-T1",
+        "--memlimit-compress=100MiB",
+        "-c",
+        &file_path,
+    ];
+    let output = fixture.run_cargo("xz", &args).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert!(output.stderr.is_empty(), "stderr: {}", output.stderr);
+});
+
+// A dictionary too large for `--memlimit-compress` shrinks with a notice that `-q` hides.
+add_test!(memlimit_compress_shrinks_dictionary, async {
+    const FILE_NAME: &str = "memlimit_dict.txt";
+    let data = generate_random_data(64 * 1024);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let args = ["-9", "-T1", "--memlimit-compress=100MiB", "-k", &file_path];
+    let output = fixture.run_cargo("xz", &args).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert_eq!(
+        output.stderr,
+        "xz: Adjusted LZMA2 dictionary size from 64 MiB to 8 MiB to not exceed \
+         the memory usage limit of 100 MiB\n"
+    );
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let output = fixture.run_cargo("xz", &["-t", &compressed_path]).await;
+    assert!(output.status.success(), "xz -t failed: {}", output.stderr);
+
+    let quiet = [
+        "-q",
+        "-9",
+        "-T1",
+        "--memlimit-compress=100MiB",
+        "-c",
+        &file_path,
+    ];
+    let output = fixture.run_cargo("xz", &quiet).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert!(output.stderr.is_empty(), "stderr: {}", output.stderr);
+});
+
+// `.lzma` output shrinks its LZMA1 dictionary to fit `--memlimit-compress` too.
+add_test!(memlimit_compress_lzma_shrinks_dictionary, async {
+    const FILE_NAME: &str = "memlimit_lzma.txt";
+    let data = generate_random_data(64 * 1024);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let args = [
+        "--format=lzma",
+        "-9",
+        "--memlimit-compress=20MiB",
+        "-c",
+        &file_path,
+    ];
+    let output = fixture.run_cargo("xz", &args).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert_eq!(
+        output.stderr,
+        "xz: Adjusted LZMA1 dictionary size from 64 MiB to 1 MiB to not exceed \
+         the memory usage limit of 20 MiB\n"
+    );
+    // The `.lzma` header stores the dictionary size after the properties byte.
+    assert_eq!(output.stdout_raw[1..5], (1_u32 << 20).to_le_bytes());
+
+    let output = fixture
+        .run_cargo(
+            "xz",
+            &["--no-adjust", args[0], args[1], args[2], "-c", &file_path],
+        )
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout_raw.is_empty());
+});
+
+// Threads over `--memlimit-compress` give way to single-threaded mode before the
+// dictionary shrinks.
+add_test!(memlimit_compress_switches_to_single_threaded, async {
+    const FILE_NAME: &str = "memlimit_single.txt";
+    let data = generate_random_data(64 * 1024);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let args = ["-9", "-T2", "--memlimit-compress=100MiB", "-c", &file_path];
+    let output = fixture.run_cargo("xz", &args).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    let multi_core = std::thread::available_parallelism().is_ok_and(|n| n.get() > 1);
+    if multi_core {
+        assert_eq!(
+            output.stderr,
+            "xz: Switching to single-threaded mode to not exceed the memory usage limit \
+             of 100 MiB\n\
+             xz: Adjusted LZMA2 dictionary size from 64 MiB to 8 MiB to not exceed \
+             the memory usage limit of 100 MiB\n"
+        );
+    }
+    assert!(
+        output
+            .stderr
+            .ends_with("from 64 MiB to 8 MiB to not exceed the memory usage limit of 100 MiB\n"),
+        "stderr: {}",
+        output.stderr
+    );
+});
+
+// The multi-threaded limit only reduces threads; the decompression limit is a hard limit.
+add_test!(memlimit_mt_decompress_is_not_a_hard_limit, async {
+    const FILE_NAME: &str = "memlimit_mt.txt";
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture.run_cargo("xz", &["-9", "-k", &file_path]).await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let output = fixture
+        .run_cargo(
+            "xz",
+            &[
+                "-dc",
+                "-T2",
+                "--memlimit-mt-decompress=1MiB",
+                &compressed_path,
+            ],
+        )
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+
+    let output = fixture
+        .run_cargo(
+            "xz",
+            &["-dc", "--memlimit-decompress=1MiB", &compressed_path],
+        )
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+});
+
+// Threads that don't fit into the multi-threaded limit are reported, like for compression.
+add_test!(threaded_decompression_notes_reduced_threads, async {
+    const FILE_NAME: &str = "mt_blocks.txt";
+    let data = generate_random_data(256 * KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo("xz", &["-k", "-6", "--block-size=64KiB", &file_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    // Each thread of the `-6` decoder needs about 9 MiB, so only one fits into 10 MiB.
+    let output = fixture
+        .run_cargo(
+            "xz",
+            &[
+                "-dc",
+                "-T2",
+                "--memlimit-mt-decompress=10MiB",
+                &compressed_path,
+            ],
+        )
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, data);
+    let multi_core = std::thread::available_parallelism().is_ok_and(|n| n.get() > 1);
+    if multi_core {
+        assert!(
+            output.stderr.contains(
+                "xz: Reduced the number of threads from 2 to 1 to not exceed \
+                 the memory usage limit of 10 MiB"
+            ),
+            "stderr: {}",
+            output.stderr
+        );
+    }
+
+    let output = fixture
+        .run_cargo("xz", &["-dc", "-T2", &compressed_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    assert!(
+        !output.stderr.contains("memory usage limit"),
+        "stderr: {}",
+        output.stderr
+    );
+});
+
+// `-M` sets every limit; percentages are relative to the physical memory.
+add_test!(memlimit_percentage_sets_all_limits, async {
+    let mut fixture = Fixture::with_file("unused.txt", b"");
+
+    let output = fixture
+        .run_cargo("xz", &["-M", "100%", "--robot", "--info-memory"])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+
+    let fields: Vec<u64> = output
+        .stdout
+        .trim_end()
+        .split('\t')
+        .map(|field| field.parse().expect("numeric field"))
+        .collect();
+    assert_eq!(fields[1], fields[0]);
+    assert_eq!(fields[2], fields[0]);
+    assert_eq!(fields[3], fields[0]);
+});
+
+// The robot totals count only the files that could be listed.
+add_test!(robot_list_totals_skip_unlistable_files, async {
+    const FILE_NAME: &str = "listed.txt";
+
+    let mut fixture = Fixture::with_files(&[FILE_NAME, "empty.xz"], &[b"listed", b""]);
+    let output = fixture.run_cargo("xz", &[&fixture.path(FILE_NAME)]).await;
+    assert!(output.status.success());
+
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let empty_path = fixture.path("empty.xz");
+    let output = fixture
+        .run_cargo("xz", &["--robot", "-l", &compressed_path, &empty_path])
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stderr.trim(),
+        format!("xz: {empty_path}: File is empty")
+    );
+
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], format!("name\t{compressed_path}"));
+    assert!(lines[1].starts_with("file\t1\t1\t"));
+    assert!(lines[2].starts_with("totals\t1\t1\t"));
+    assert!(lines[2].ends_with("\t1"), "totals: {}", lines[2]);
+});
+
+/// Number of Blocks reported by `xz --robot --list` for `path`.
+async fn robot_block_count(fixture: &mut Fixture, path: &str) -> u64 {
+    let output = fixture.run_cargo("xz", &["--robot", "-l", path]).await;
+    assert!(output.status.success(), "xz -l failed: {}", output.stderr);
+    let file_line = output
+        .stdout
+        .lines()
+        .find(|line| line.starts_with("file\t"))
+        .expect("file line");
+    file_line.split('\t').nth(2).unwrap().parse().unwrap()
+}
+
+// `--block-size` and `--block-list` split the `.xz` output into Blocks.
+add_test!(block_size_and_block_list_split_blocks, async {
+    const FILE_NAME: &str = "blocks.txt";
+    let data = generate_random_data(10 * KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    for (option, blocks) in [
+        ("--block-size=4KiB", 3),
+        ("--block-list=1KiB,2KiB", 6),
+        ("--block-list=1KiB,0", 2),
+    ] {
+        let output = fixture.run_cargo("xz", &["-kf", option, &file_path]).await;
+        assert!(
+            output.status.success(),
+            "xz {option} failed: {}",
+            output.stderr
+        );
+        assert_eq!(
+            robot_block_count(&mut fixture, &compressed_path).await,
+            blocks,
+            "{option}"
+        );
+
+        let output = fixture.run_cargo("xz", &["-dc", &compressed_path]).await;
+        assert!(output.status.success(), "xz -d failed: {}", output.stderr);
+        assert_eq!(output.stdout_raw, data);
+    }
+
+    let output = fixture
+        .run_cargo("xz", &["-kf", "--block-list=0,1KiB", &file_path])
+        .await;
+    assert!(!output.status.success());
+    assert!(
+        output
+            .stderr
+            .contains("0 can only be used as the last element"),
+        "stderr: {}",
+        output.stderr
+    );
+});
+
+// `--flush-timeout` makes the data read so far decodable while stdin stays open.
+add_test!(flush_timeout_flushes_stalled_stdin, async {
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const LINE: &[u8] = b"log line that must arrive before stdin is closed\n";
+    let mut fixture = Fixture::with_file("unused.txt", b"");
+
+    let mut child = fixture.spawn_cargo("xz", &["--flush-timeout=50", "-c"]);
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    stdin.write_all(LINE).await.unwrap();
+    stdin.flush().await.unwrap();
+
+    // The Stream Header is 12 bytes; anything beyond it is the flushed Block.
+    let mut partial = Vec::new();
+    let mut buf = [0u8; 4096];
+    while partial.len() <= 12 {
+        let read = tokio::time::timeout(Duration::from_secs(10), stdout.read(&mut buf))
+            .await
+            .expect("xz didn't flush the stalled input")
+            .unwrap();
+        assert_ne!(read, 0, "xz closed stdout");
+        partial.extend_from_slice(&buf[..read]);
+    }
+
+    let output = fixture
+        .run_with_stdin_raw(BinaryType::cargo("xz"), &["-dc"], &partial)
+        .await;
+    assert!(
+        output.stdout_raw.starts_with(LINE),
+        "stdout: {}",
+        output.stdout
+    );
+
+    drop(stdin);
+    let mut rest = Vec::new();
+    stdout.read_to_end(&mut rest).await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+    partial.extend_from_slice(&rest);
+
+    let output = fixture
+        .run_with_stdin_raw(BinaryType::cargo("xz"), &["-dc"], &partial)
+        .await;
+    assert!(output.status.success(), "xz -d failed: {}", output.stderr);
+    assert_eq!(output.stdout_raw, LINE);
+});
+
+// `--list-sizes` switches the list sizes to scaled units or grouped bytes, but not in robot mode.
+add_test!(list_sizes_selects_human_or_grouped_bytes, async {
+    const FILE_NAME: &str = "list_sizes.bin";
+    let data = vec![b'x'; 20_000];
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+    let output = fixture.run_cargo("xz", &[&fixture.path(FILE_NAME)]).await;
+    assert!(output.status.success());
+
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let env = [("LC_ALL", "C")];
+    let output = fixture
+        .run_cargo_with_env(
+            "xz",
+            &[
+                "-l",
+                "--list-sizes=human",
+                "--list-precision=2",
+                &compressed_path,
+            ],
+            &env,
+        )
+        .await;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(output.stdout.contains(" 19.53 KiB "), "{}", output.stdout);
+
+    let output = fixture
+        .run_cargo_with_env("xz", &["-lv", "--list-sizes=bytes", &compressed_path], &env)
+        .await;
+    assert!(output.status.success(), "{}", output.stderr);
+    assert!(
+        output.stdout.contains("  Uncompressed size: 20,000 B\n"),
+        "{}",
+        output.stdout
+    );
+
+    let output = fixture
+        .run_cargo(
+            "xz",
+            &["--robot", "-l", "--list-sizes=bytes", &compressed_path],
+        )
+        .await;
+    assert!(output.status.success());
+    assert!(output.stdout.contains("\t20000\t"), "{}", output.stdout);
+
+    let output = fixture
+        .run_cargo("xz", &["-l", "--list-precision=2", &compressed_path])
+        .await;
+    assert!(!output.status.success());
+});
+
+// `--log-level` and `RUST_LOG` add logfmt events to stderr without changing the output.
+add_test!(log_level_events, async {
+    const FILE_NAME: &str = "logged.txt";
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo("xz", &["--log-level=trace", "-k", &file_path])
+        .await;
+    assert!(output.status.success(), "{}", output.stderr);
+    let events: Vec<&str> = output
+        .stderr
+        .lines()
+        .filter_map(|line| line.split(" event=").nth(1))
+        .filter_map(|rest| rest.split(' ').next())
+        .collect();
+    assert_eq!(
+        events,
+        [
+            "run.start",
+            "file.open",
+            "file.phase",
+            "file.phase",
+            "file.phase",
+            "file.close",
+            "run.end"
+        ],
+        "{}",
+        output.stderr
+    );
+    assert!(
+        output.stderr.contains(&format!(
+            " level=info event=file.close path={file_path} result=ok bytes_read={} ",
+            data.len()
+        )),
+        "{}",
+        output.stderr
+    );
+
+    // Errors are logged at `error`, and the diagnostic is still printed.
+    let output = fixture
+        .run_cargo_with_env(
+            "xz",
+            &["-dc", &compressed_path, "missing.xz"],
+            &[("RUST_LOG", "error")],
+        )
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout_raw, data);
+    let lines: Vec<&str> = output.stderr.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", output.stderr);
+    assert!(
+        lines[0].contains(" level=error event=file.close path=missing.xz result=error "),
+        "{}",
+        output.stderr
+    );
+    assert!(
+        lines[1].starts_with("xz: missing.xz: "),
+        "{}",
+        output.stderr
+    );
+
+    // Without either, nothing is logged.
+    let output = fixture.run_cargo("xz", &["-t", &compressed_path]).await;
+    assert!(output.status.success());
+    assert_eq!(output.stderr, "");
+});
@@ -0,0 +1,141 @@
+use crate::add_test;
+use crate::common::{assert_parity, Parity, Vector, SAMPLE_TEXT};
+
+/// Vectors that upstream rejects, covering headers, Blocks, checks and the Index
+const CORRUPT_VECTORS: [&str; 12] = [
+    "bad-0-header_magic.xz",
+    "bad-0-footer_magic.xz",
+    "bad-0-backward_size.xz",
+    "bad-0cat-alone.xz",
+    "bad-1-block_header-1.xz",
+    "bad-1-check-crc32.xz",
+    "bad-1-check-crc64.xz",
+    "bad-1-check-sha256.xz",
+    "bad-1-lzma2-7.xz",
+    "bad-1-stream_flags-2.xz",
+    "bad-2-index-1.xz",
+    "bad-3-index-uncomp-overflow.xz",
+];
+
+fn text() -> &'static [u8] {
+    SAMPLE_TEXT.as_bytes()
+}
+
+// Test compressing a file, which replaces it with the `.xz` file.
+add_test!(compress_replaces_input, async {
+    assert_parity(&Parity::new("xz", &["f.txt"]).file("f.txt", text())).await;
+});
+
+// Test compressing with -k and the thread counts upstream accepts on any machine.
+add_test!(compress_keep_and_threads, async {
+    for args in [
+        &["-k", "f.txt"][..],
+        &["-k", "-T1", "f.txt"],
+        &["-k", "-T0", "f.txt"],
+        &["-k", "-6e", "f.txt"],
+    ] {
+        assert_parity(&Parity::new("xz", args).file("f.txt", text())).await;
+    }
+});
+
+// Test compressing to stdout, from a file and from stdin.
+add_test!(compress_to_stdout, async {
+    assert_parity(&Parity::new("xz", &["-c", "f.txt"]).file("f.txt", text())).await;
+    assert_parity(&Parity::new("xz", &["-c"]).stdin(text())).await;
+    assert_parity(&Parity::new("xz", &[]).stdin(text())).await;
+});
+
+// Test refusing to overwrite an existing output, with and without -f and -q.
+add_test!(existing_output, async {
+    let existing = Vector::bundled("good-1-check-crc64.xz");
+    for args in [
+        &["-k", "f.txt"][..],
+        &["-q", "-k", "f.txt"],
+        &["-f", "-k", "f.txt"],
+    ] {
+        let parity = Parity::new("xz", args)
+            .file("f.txt", text())
+            .file("f.txt.xz", existing.data());
+        assert_parity(&parity).await;
+    }
+});
+
+// Test custom suffixes and inputs that already have the suffix. Unlike upstream, a
+// suffix without a leading dot still gets one, so only dotted suffixes are compared.
+add_test!(suffix_cases, async {
+    let compressed = Vector::bundled("good-1-check-crc64.xz");
+    assert_parity(&Parity::new("xz", &["-k", "-S", ".foo", "f.txt"]).file("f.txt", text())).await;
+    assert_parity(&Parity::new("xz", &["-k", "--suffix=.foo", "f.txt"]).file("f.txt", text()))
+        .await;
+
+    for args in [
+        &["-d", "-S", ".foo", "f.foo"][..],
+        &["-d", "f.foo"],
+        &["-dq", "f.foo"],
+    ] {
+        assert_parity(&Parity::new("xz", args).file("f.foo", compressed.data())).await;
+    }
+    for args in [&["f.xz"][..], &["-q", "f.xz"], &["-qq", "f.xz"]] {
+        assert_parity(&Parity::new("xz", args).file("f.xz", compressed.data())).await;
+    }
+    assert_parity(&Parity::new("xz", &["-k", "-d", "f.txz"]).file("f.txz", compressed.data()))
+        .await;
+});
+
+// Test decompressing with -d, -k, -c and -t.
+add_test!(decompress_modes, async {
+    let compressed = Vector::bundled("good-1-check-crc64.xz");
+    for args in [
+        &["-d", "f.txt.xz"][..],
+        &["-dk", "f.txt.xz"],
+        &["-dc", "f.txt.xz"],
+        &["-t", "f.txt.xz"],
+        &["-dc", "-T1", "f.txt.xz"],
+        &["-dc", "-T0", "f.txt.xz"],
+    ] {
+        assert_parity(&Parity::new("xz", args).file("f.txt.xz", compressed.data())).await;
+    }
+    assert_parity(&Parity::new("xz", &["-dc"]).stdin(compressed.data())).await;
+});
+
+// Test missing inputs next to valid ones.
+add_test!(missing_inputs, async {
+    let compressed = Vector::bundled("good-1-check-crc64.xz");
+    for args in [
+        &["-t", "missing.xz"][..],
+        &["-tq", "missing.xz"],
+        &["-dc", "missing.xz", "f.txt.xz"],
+        &["-dk", "f.txt.xz", "missing.xz"],
+    ] {
+        assert_parity(&Parity::new("xz", args).file("f.txt.xz", compressed.data())).await;
+    }
+});
+
+// Test corrupt inputs: messages, exit codes and no leftover partial output.
+add_test!(corrupt_inputs, async {
+    for name in CORRUPT_VECTORS {
+        let vector = Vector::bundled(name);
+        for args in [
+            &["-t", name][..],
+            &["-tq", name],
+            &["-dc", name],
+            &["-dk", name],
+        ] {
+            assert_parity(&Parity::new("xz", args).file(name, vector.data())).await;
+        }
+    }
+});
+
+// Test truncated input and input with an unsupported check.
+add_test!(truncated_and_unsupported_check, async {
+    let compressed = Vector::bundled("good-1-check-crc64.xz");
+    let truncated = &compressed.data()[..compressed.data().len() - 5];
+    for args in [&["-t", "f.xz"][..], &["-dc", "f.xz"], &["-dk", "f.xz"]] {
+        assert_parity(&Parity::new("xz", args).file("f.xz", truncated)).await;
+    }
+
+    let unsupported = Vector::bundled("unsupported-check.xz");
+    for args in [&["-t", "f.xz"][..], &["-tq", "f.xz"], &["-tqq", "f.xz"]] {
+        assert_parity(&Parity::new("xz", args).file("f.xz", unsupported.data())).await;
+    }
+});
@@ -214,16 +214,47 @@ add_test!(non_existent_file, async {
 
 // Test `xz -d` skips files with unknown suffix like upstream xz.
 add_test!(decompress_unknown_suffix_is_skipped, async {
-    const FILE_NAME: &str = "unknown_suffix_input.bin";
+    const FILE_NAME: &str = "unknown_suffix_input";
 
-    let mut fixture = Fixture::with_file(FILE_NAME, b"not compressed");
-    let file_path = fixture.path(FILE_NAME);
+    let mut fixture = Fixture::with_file(FILE_NAME, b"compressed with another name");
+    let output = fixture.run_cargo("xz", &[&fixture.path(FILE_NAME)]).await;
+    assert!(output.status.success());
 
-    let output = fixture.run_cargo("xz", &["-d", &file_path]).await;
+    let renamed_path = fixture.path("unknown_suffix_input.bin");
+    std::fs::rename(fixture.compressed_path(FILE_NAME), &renamed_path).unwrap();
+
+    let output = fixture.run_cargo("xz", &["-d", &renamed_path]).await;
     assert_eq!(output.status.code(), Some(2));
     assert!(output
         .stderr
         .contains("Filename has an unknown suffix, skipping"));
+
+    // `-c` doesn't need an output name, and `-S` adds a suffix to strip.
+    let output = fixture.run_cargo("xz", &["-dc", &renamed_path]).await;
+    assert!(output.status.success());
+    assert_eq!(output.stdout_raw, b"compressed with another name");
+
+    let output = fixture
+        .run_cargo("xz", &["-d", "-S", ".bin", &renamed_path])
+        .await;
+    assert!(output.status.success(), "stderr: {}", output.stderr);
+    assert!(fixture.file_exists(FILE_NAME));
+});
+
+// Test an unrecognized input is reported as such before its suffix is looked at.
+add_test!(decompress_unknown_format_reported_before_suffix, async {
+    const FILE_NAME: &str = "not_compressed.bin";
+
+    let mut fixture = Fixture::with_file(FILE_NAME, b"not compressed");
+    let file_path = fixture.path(FILE_NAME);
+
+    let output = fixture.run_cargo("xz", &["-d", &file_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        output.stderr.contains("File format not recognized"),
+        "stderr: {}",
+        output.stderr
+    );
 });
 
 // Test `-` as stdin in the middle of the file list.
@@ -267,6 +298,23 @@ add_test!(dash_reads_stdin_in_middle, async {
     fixture.assert_files(&[FILE_1, FILE_2], &[data_1, data_2]);
 });
 
+// Test that diagnostics for `-` name the input `(stdin)` like upstream.
+add_test!(dash_is_named_stdin_in_diagnostics, async {
+    let mut fixture = Fixture::with_file("dummy.txt", b"dummy");
+
+    let output = fixture
+        .run_with_stdin(BinaryType::cargo("xz"), &["-d", "-"], Some(vec!["not xz"]))
+        .await;
+    assert!(!output.status.success());
+    assert!(
+        output
+            .stderr
+            .contains("xz: (stdin): File format not recognized"),
+        "stderr: {}",
+        output.stderr
+    );
+});
+
 // Test upstream behavior: `xz --list` does not accept stdin (`-`).
 add_test!(list_rejects_dash_stdin, async {
     let mut fixture = Fixture::with_file("dummy.txt", b"dummy");
@@ -391,3 +439,313 @@ add_test!(exit_codes_ok_warning_error, async {
     assert!(fixture.file_exists(WARNING_FILE));
     assert!(!fixture.file_exists(&format!("{WARNING_FILE}.xz")));
 });
+
+// Test that SIGINT removes the partially written output and exits with 128 + SIGINT
+#[cfg(unix)]
+add_test!(sigint_removes_partial_output, async {
+    use std::process::Command;
+    use std::time::Duration;
+
+    const FILE_NAME: &str = "slow.bin";
+    // Random data at the slowest preset takes seconds, leaving time to interrupt.
+    let data = generate_random_data(16 * MB);
+    let fixture = Fixture::with_file(FILE_NAME, &data);
+    let file_path = fixture.path(FILE_NAME);
+
+    // The output is written under a hidden temporary name until it's complete.
+    let temp_files = || {
+        std::fs::read_dir(fixture.root_dir_path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy()
+                    .starts_with(&format!(".{FILE_NAME}.xz."))
+            })
+            .count()
+    };
+
+    let mut child = fixture.spawn_cargo("xz", &["-9e", "-T1", "-k", &file_path]);
+    for _ in 0..100 {
+        if temp_files() > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(temp_files(), 1);
+    assert!(!fixture.file_exists(&format!("{FILE_NAME}.xz")));
+
+    let pid = child.id().unwrap().to_string();
+    assert!(Command::new("kill")
+        .args(["-INT", &pid])
+        .status()
+        .unwrap()
+        .success());
+    let status = tokio::time::timeout(Duration::from_secs(10), child.wait())
+        .await
+        .expect("xz didn't stop after SIGINT")
+        .unwrap();
+
+    assert_eq!(status.code(), Some(128 + 2));
+    assert!(!fixture.file_exists(&format!("{FILE_NAME}.xz")));
+    assert_eq!(temp_files(), 0);
+    fixture.assert_files(&[FILE_NAME], &[&data]);
+});
+
+// Test that SIGUSR1 prints the progress line without -v
+#[cfg(unix)]
+add_test!(sigusr1_prints_progress, async {
+    use std::process::Command;
+    use std::time::Duration;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    let fixture = Fixture::with_file("unused.txt", b"");
+    let mut child = fixture.spawn_cargo("xz", &["--flush-timeout=50", "-c"]);
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    stdin.write_all(b"some input").await.unwrap();
+    stdin.flush().await.unwrap();
+
+    // Flushed output shows that xz is reading input, so its handlers are installed.
+    let mut buf = [0u8; 4096];
+    tokio::time::timeout(Duration::from_secs(10), stdout.read(&mut buf))
+        .await
+        .expect("xz didn't flush the stalled input")
+        .unwrap();
+    let pid = child.id().unwrap().to_string();
+    assert!(Command::new("kill")
+        .args(["-USR1", &pid])
+        .status()
+        .unwrap()
+        .success());
+
+    let mut line = String::new();
+    tokio::time::timeout(Duration::from_secs(10), stderr.read_line(&mut line))
+        .await
+        .expect("xz didn't print progress after SIGUSR1")
+        .unwrap();
+    assert!(
+        line.contains("MiB / 0.0 MiB"),
+        "unexpected progress line: {line}"
+    );
+
+    drop(stdin);
+    stdout.read_to_end(&mut Vec::new()).await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+});
+
+// Test that links, special files and files with special mode bits are skipped unless
+// --keep or --force is given, with the upstream warnings
+#[cfg(unix)]
+add_test!(special_inputs_are_skipped, async {
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let mut fixture = Fixture::with_files(
+        &["linked.txt", "sticky.txt", "setuid.txt"],
+        &[b"linked", b"sticky", b"setuid"],
+    );
+    fs::hard_link(fixture.path("linked.txt"), fixture.path("hardlink.txt")).unwrap();
+    symlink(fixture.path("linked.txt"), fixture.path("symlink.txt")).unwrap();
+    fs::set_permissions(fixture.path("sticky.txt"), Permissions::from_mode(0o1644)).unwrap();
+    fs::set_permissions(fixture.path("setuid.txt"), Permissions::from_mode(0o4644)).unwrap();
+    fs::create_dir(fixture.path("directory")).unwrap();
+
+    let cases = [
+        (
+            "hardlink.txt",
+            "Input file has more than one hard link, skipping",
+        ),
+        ("symlink.txt", "Is a symbolic link, skipping"),
+        ("sticky.txt", "File has sticky bit set, skipping"),
+        ("setuid.txt", "File has setuid or setgid bit set, skipping"),
+        ("directory", "Is a directory, skipping"),
+    ];
+    for (name, message) in cases {
+        let path = fixture.path(name);
+        let output = fixture.run_cargo("xz", &[&path]).await;
+        assert_eq!(output.status.code(), Some(2), "{name}: {}", output.stderr);
+        assert_eq!(output.stderr.trim(), format!("xz: {path}: {message}"));
+        assert!(fixture.file_exists(name));
+        assert!(!fixture.file_exists(&format!("{name}.xz")));
+    }
+
+    // Directories are skipped even when writing to stdout.
+    let path = fixture.path("directory");
+    let output = fixture.run_cargo("xz", &["-c", &path]).await;
+    assert_eq!(output.status.code(), Some(2));
+
+    for name in ["hardlink.txt", "symlink.txt", "sticky.txt", "setuid.txt"] {
+        let path = fixture.path(name);
+        let output = fixture.run_cargo("xz", &["-k", &path]).await;
+        assert!(output.status.success(), "{name}: {}", output.stderr);
+        assert!(fixture.file_exists(&format!("{name}.xz")));
+    }
+});
+
+// `-tv` describes intact files and names the Block of a damaged one.
+add_test!(test_verbose_reports_details_and_failing_block, async {
+    const FILE_NAME: &str = "blocks.txt";
+    let data = generate_random_data(400 * 1024);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo("xz", &["--block-size=100KiB", &file_path])
+        .await;
+    assert!(output.status.success());
+
+    let output = fixture.run_cargo("xz", &["-tv", &compressed_path]).await;
+    assert!(output.status.success());
+    assert!(
+        output.stderr.contains(&format!(
+            "Test successful: {compressed_path} (CRC64, 1 stream, 4 blocks)"
+        )),
+        "stderr: {}",
+        output.stderr
+    );
+
+    // Damage the middle of the third Block.
+    let mut compressed = std::fs::read(&compressed_path).unwrap();
+    let info = xz_core::file_info::extract_file_info(&mut Cursor::new(&compressed), None).unwrap();
+    let block = &info.blocks()[2];
+    let offset = block.compressed_file_offset + block.total_size / 2;
+    for byte in &mut compressed[offset as usize..offset as usize + 4] {
+        *byte ^= 0xFF;
+    }
+    std::fs::write(&compressed_path, &compressed).unwrap();
+
+    let output = fixture.run_cargo("xz", &["-tv", &compressed_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        output.stderr.contains(&format!(
+            "xz: {compressed_path}: Compressed data is corrupt (block 3 of 4 in stream 1, \
+             at about byte {})",
+            block.compressed_file_offset
+        )),
+        "stderr: {}",
+        output.stderr
+    );
+
+    // Without `-v` the message stays the same as upstream's.
+    let output = fixture.run_cargo("xz", &["-t", &compressed_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stderr.trim(),
+        format!("xz: {compressed_path}: Compressed data is corrupt")
+    );
+});
+
+// A failed decompression leaves neither a partial output nor its temporary file behind.
+add_test!(failed_decompression_leaves_no_output, async {
+    const FILE_NAME: &str = "partial.txt";
+    let data = generate_random_data(MB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture.run_cargo("xz", &["-0", &file_path]).await;
+    assert!(output.status.success());
+
+    // Truncate the file so decoding fails after writing most of the output.
+    let compressed = std::fs::read(&compressed_path).unwrap();
+    std::fs::write(&compressed_path, &compressed[..compressed.len() - 100]).unwrap();
+
+    let output = fixture.run_cargo("xz", &["-d", &compressed_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stderr.contains("Unexpected end of input"));
+    assert!(!fixture.file_exists(FILE_NAME));
+    assert!(fixture.file_exists("partial.txt.xz"));
+
+    let entries = std::fs::read_dir(fixture.root_dir_path()).unwrap().count();
+    assert_eq!(entries, 1);
+});
+
+// Test that stdin is named `(stdin)` and implies `--keep` without warnings.
+add_test!(stdin_named_in_test_output, async {
+    let mut fixture = Fixture::with_file("dummy.txt", b"dummy");
+
+    let compressed = fixture
+        .run_with_stdin(BinaryType::cargo("xz"), &["-k"], Some(vec!["stdin data"]))
+        .await;
+    assert!(compressed.status.success());
+    assert!(
+        compressed.stderr.is_empty(),
+        "stderr: {}",
+        compressed.stderr
+    );
+
+    let output = fixture
+        .run_with_stdin_raw(BinaryType::cargo("xz"), &["-tv"], &compressed.stdout_raw)
+        .await;
+    assert!(output.status.success());
+    assert!(
+        output.stderr.contains("Test successful: (stdin)"),
+        "stderr: {}",
+        output.stderr
+    );
+});
+
+// Test that `xz -dc` copies uncompressed stdin unchanged, after the output of the inputs
+// before it. The fixture writes all of stdin before reading stdout, so both fit in a pipe.
+add_test!(stdin_passthrough_after_file, async {
+    const FILE_NAME: &str = "first.txt";
+    let first = b"first input\n";
+    let mut passthrough = b"plain ".to_vec();
+    passthrough.extend(generate_random_data(32 * 1024));
+
+    let mut fixture = Fixture::with_file(FILE_NAME, first);
+    let path = fixture.path(FILE_NAME);
+    let output = fixture.run_cargo("xz", &["-k", &path]).await;
+    assert!(output.status.success());
+
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let output = fixture
+        .run_with_stdin_raw(
+            BinaryType::cargo("xz"),
+            &["-dc", &compressed_path, "-"],
+            &passthrough,
+        )
+        .await;
+    assert!(output.status.success(), "stderr: {}", output.stderr);
+    assert!(output.stdout_raw.starts_with(first));
+    assert!(output.stdout_raw[first.len()..] == passthrough[..]);
+});
+
+// Test that output to the input file through stdout is refused instead of reading the
+// output back as input, while the file itself is left as it was.
+#[cfg(unix)]
+add_test!(stdout_to_input_file_is_refused, async {
+    use std::fs::{self, File};
+    use std::process::Stdio;
+
+    const FILE_NAME: &str = "same.txt";
+    let data = generate_random_data(4096);
+    let fixture = Fixture::with_file(FILE_NAME, &data);
+    let path = fixture.path(FILE_NAME);
+
+    for args in [vec!["-c", path.as_str()], vec!["-c"]] {
+        let output = File::options().append(true).open(&path).unwrap();
+        let input = File::open(&path).unwrap();
+        let output = tokio::process::Command::new(BinaryType::cargo("xz").get_path())
+            .args(&args)
+            .stdin(Stdio::from(input))
+            .stdout(Stdio::from(output))
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+            .wait_with_output()
+            .await
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let name = if args.len() == 2 { &path } else { "(stdin)" };
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!("xz: {name}: Input and output are the same file\n")
+        );
+        assert_eq!(fs::read(&path).unwrap(), data);
+    }
+});
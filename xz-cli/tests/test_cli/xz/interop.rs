@@ -302,3 +302,159 @@ add_test!(stdout_compatibility, async {
         assert!(!system_output.stdout.is_empty());
     }
 });
+
+// `xz -l` prints the same table as the system `xz -l`, including size units and totals.
+add_test!(list_matches_system_xz, async {
+    const TEXT: &str = "text.txt";
+    const RANDOM: &str = "random.bin";
+    const TINY: &str = "tiny.txt";
+
+    let text = SAMPLE_TEXT.repeat(2000);
+    let random = generate_random_data(MB);
+    let mut fixture =
+        Fixture::with_files(&[TEXT, RANDOM, TINY], &[text.as_bytes(), &random, b"ab"]);
+
+    let mut compressed = Vec::new();
+    for name in [TEXT, RANDOM, TINY] {
+        let out = fixture.run_cargo("xz", &[&fixture.path(name)]).await;
+        assert!(out.status.success(), "our xz failed: {}", out.stderr);
+        compressed.push(fixture.compressed_path(name));
+    }
+    let args: Vec<&str> = std::iter::once("-l")
+        .chain(compressed.iter().map(String::as_str))
+        .collect();
+
+    let Some(system_out) = fixture.run_system("xz", &args).await else {
+        return;
+    };
+    let our_out = fixture.run_cargo("xz", &args).await;
+    assert!(
+        our_out.status.success(),
+        "our xz -l failed: {}",
+        our_out.stderr
+    );
+    assert_eq!(our_out.stdout, system_out.stdout);
+});
+
+// `xz -lvv` matches the system xz, including block header details, check values and totals.
+add_test!(list_very_verbose_matches_system_xz, async {
+    const TEXT: &str = "text.txt";
+    const RANDOM: &str = "random.bin";
+    const TINY: &str = "tiny.txt";
+
+    let text = SAMPLE_TEXT.repeat(2000);
+    let random = generate_random_data(MB);
+    let mut fixture =
+        Fixture::with_files(&[TEXT, RANDOM, TINY], &[text.as_bytes(), &random, b"ab"]);
+
+    let mut compressed = Vec::new();
+    for (name, check) in [(TEXT, "crc32"), (RANDOM, "crc64"), (TINY, "sha256")] {
+        let out = fixture
+            .run_cargo("xz", &["-C", check, &fixture.path(name)])
+            .await;
+        assert!(out.status.success(), "our xz failed: {}", out.stderr);
+        compressed.push(fixture.compressed_path(name));
+    }
+
+    for verbosity in ["-lv", "-lvv"] {
+        let args: Vec<&str> = std::iter::once(verbosity)
+            .chain(compressed.iter().map(String::as_str))
+            .collect();
+
+        let Some(system_out) = fixture.run_system("xz", &args).await else {
+            return;
+        };
+        let our_out = fixture.run_cargo("xz", &args).await;
+        assert!(
+            our_out.status.success(),
+            "our xz {verbosity} failed: {}",
+            our_out.stderr
+        );
+        assert_eq!(our_out.stdout, system_out.stdout);
+    }
+});
+
+// `xz --robot -l` and `--robot -lv` print the same tab-separated lines as the system xz.
+add_test!(robot_list_matches_system_xz, async {
+    const TEXT: &str = "text.txt";
+    const TINY: &str = "tiny.txt";
+
+    let text = SAMPLE_TEXT.repeat(2000);
+    let mut fixture = Fixture::with_files(&[TEXT, TINY], &[text.as_bytes(), b"ab"]);
+
+    let mut compressed = Vec::new();
+    for (name, check) in [(TEXT, "crc32"), (TINY, "sha256")] {
+        let out = fixture
+            .run_cargo("xz", &["-C", check, &fixture.path(name)])
+            .await;
+        assert!(out.status.success(), "our xz failed: {}", out.stderr);
+        compressed.push(fixture.compressed_path(name));
+    }
+
+    for verbosity in ["-l", "-lv"] {
+        let args: Vec<&str> = ["--robot", verbosity]
+            .into_iter()
+            .chain(compressed.iter().map(String::as_str))
+            .collect();
+
+        let Some(system_out) = fixture.run_system("xz", &args).await else {
+            return;
+        };
+        let our_out = fixture.run_cargo("xz", &args).await;
+        assert!(
+            our_out.status.success(),
+            "our xz --robot {verbosity} failed: {}",
+            our_out.stderr
+        );
+        assert_eq!(our_out.stdout, system_out.stdout);
+    }
+});
+
+// Unlistable files get the system xz's messages and are left out of the robot totals.
+add_test!(robot_list_errors_match_system_xz, async {
+    const GOOD: &str = "good.txt";
+
+    let mut fixture = Fixture::with_files(
+        &[GOOD, "empty.xz", "small.xz", "plain.xz"],
+        &[SAMPLE_TEXT.as_bytes(), b"", b"hello", &[b'x'; 64]],
+    );
+    let out = fixture.run_cargo("xz", &[&fixture.path(GOOD)]).await;
+    assert!(out.status.success(), "our xz failed: {}", out.stderr);
+
+    let good = fixture.compressed_path(GOOD);
+    let mut truncated = std::fs::read(&good).unwrap();
+    truncated.truncate(truncated.len() - 4);
+    std::fs::write(fixture.path("truncated.xz"), truncated).unwrap();
+
+    let paths = [
+        good.clone(),
+        fixture.path("empty.xz"),
+        fixture.path("small.xz"),
+        fixture.path("plain.xz"),
+        fixture.path("truncated.xz"),
+        good,
+    ];
+    let args: Vec<&str> = ["--robot", "-l"]
+        .into_iter()
+        .chain(paths.iter().map(String::as_str))
+        .collect();
+
+    let Some(system_out) = fixture.run_system("xz", &args).await else {
+        return;
+    };
+    let our_out = fixture.run_cargo("xz", &args).await;
+    assert_eq!(our_out.status.code(), system_out.status.code());
+    assert_eq!(our_out.stdout, system_out.stdout);
+    // The system xz may report itself by its full path.
+    let messages = |stderr: &str| -> Vec<String> {
+        stderr
+            .lines()
+            .map(|line| {
+                line.split_once(": ")
+                    .map_or(line, |(_, msg)| msg)
+                    .to_string()
+            })
+            .collect()
+    };
+    assert_eq!(messages(&our_out.stderr), messages(&system_out.stderr));
+});
@@ -1,5 +1,6 @@
 mod basic;
 mod cli_options;
+mod differential;
 mod edge_cases;
 mod formats;
 mod interop;
@@ -398,3 +398,39 @@ add_test!(lzip_lz_format_via_xz, async {
     assert!(output.status.success());
     fixture.assert_files(&["good-1-v1"], &[EXPECTED]);
 });
+
+// Test `--format=lzip` decodes `.lz` files and strips the `.lz` suffix.
+add_test!(lzip_format_option_decodes_lz, async {
+    let vectors = [
+        Vector::bundled("good-2-v1-v1.lz"),
+        Vector::bundled("good-1-check-crc32.xz"),
+    ];
+    let mut fixture = Fixture::with_vectors(&vectors);
+    let lz_path = fixture.path(vectors[0].name());
+    let xz_path = fixture.path(vectors[1].name());
+
+    let output = fixture
+        .run_cargo("xz", &["-d", "-k", "--format=lzip", &lz_path])
+        .await;
+    assert!(output.status.success(), "xz failed: {}", output.stderr);
+    fixture.assert_files(&["good-2-v1-v1"], &[HELLO_WORLD]);
+
+    let output = fixture
+        .run_cargo("xz", &["-t", "--format=lzip", &xz_path])
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        output.stderr.contains("File format not recognized"),
+        "unexpected stderr: {}",
+        output.stderr
+    );
+
+    let output = fixture
+        .run_cargo("xz", &["-z", "--format=lzip", &xz_path])
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        output.stderr.trim(),
+        "xz: Compression of lzip files (.lz) is not supported"
+    );
+});
@@ -180,3 +180,37 @@ add_test!(equivalent_to_xz_dc, async {
     // Both outputs should be identical
     assert!(xzcat_output.stdout == xz_output.stdout);
 });
+
+// Test many small files are concatenated in order while the next one is read ahead,
+// and that a missing file in between is still reported.
+add_test!(many_small_files_in_order, async {
+    const COUNT: usize = 20;
+
+    let names: Vec<String> = (0..COUNT).map(|i| format!("small{i}.txt")).collect();
+    let data: Vec<Vec<u8>> = (0..COUNT).map(|_| generate_random_data(KB)).collect();
+    let name_refs: Vec<&str> = names.iter().map(String::as_str).collect();
+    let data_refs: Vec<&[u8]> = data.iter().map(Vec::as_slice).collect();
+    let mut fixture = Fixture::with_files(&name_refs, &data_refs);
+
+    let paths: Vec<String> = names.iter().map(|name| fixture.path(name)).collect();
+    let mut args = vec!["-k"];
+    args.extend(paths.iter().map(String::as_str));
+    let output = fixture.run_cargo("xz", &args).await;
+    assert!(output.status.success());
+
+    let missing = fixture.path("missing.xz");
+    let mut compressed: Vec<String> = names
+        .iter()
+        .map(|name| fixture.compressed_path(name))
+        .collect();
+    compressed.insert(COUNT / 2, missing.clone());
+    let args: Vec<&str> = compressed.iter().map(String::as_str).collect();
+    let output = fixture.run_cargo("xzcat", &args).await;
+    assert!(!output.status.success());
+    assert!(
+        output.stderr.contains(&missing),
+        "stderr: {}",
+        output.stderr
+    );
+    assert!(output.stdout_raw == data.concat());
+});
@@ -1,5 +1,5 @@
 use crate::add_test;
-use crate::common::{generate_random_data, Fixture};
+use crate::common::{generate_random_data, BinaryType, Fixture};
 use crate::KB;
 
 // Test xzcat with -v (verbose) option
@@ -206,3 +206,36 @@ add_test!(combined_options, async {
     assert!(output.status.success());
     assert!(output.stdout_raw == data);
 });
+
+// Test xzcat reads the input names from standard input with --files
+add_test!(files_option_reads_list_from_stdin, async {
+    const FILE_NAME: &str = "files_stdin.txt";
+
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+    let output = fixture.run_cargo("xz", &[&file_path]).await;
+    assert!(output.status.success());
+
+    let list = format!("{compressed_path}\n");
+    let output = fixture
+        .run_with_stdin(BinaryType::cargo("xzcat"), &["--files"], Some(vec![&list]))
+        .await;
+    assert!(output.status.success());
+    assert!(output.stdout_raw == data);
+});
+
+// Test xzcat refuses to read both the list and the data from standard input
+add_test!(files_option_rejects_stdin_operand, async {
+    let mut fixture = Fixture::with_file("dummy.txt", b"dummy");
+
+    let output = fixture
+        .run_with_stdin(BinaryType::cargo("xzcat"), &["--files"], Some(vec!["-\n"]))
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stderr.contains(
+        "Cannot read data from standard input when reading filenames from standard input"
+    ));
+});
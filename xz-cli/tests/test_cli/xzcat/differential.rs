@@ -0,0 +1,21 @@
+use crate::add_test;
+use crate::common::{assert_parity, Parity, Vector};
+
+// Test concatenating good, corrupt and missing inputs to stdout.
+add_test!(mixed_inputs, async {
+    let good = Vector::bundled("good-1-check-crc64.xz");
+    let corrupt = Vector::bundled("bad-1-check-crc64.xz");
+    for args in [
+        &["a.xz", "a.xz"][..],
+        &["a.xz", "bad.xz", "a.xz"],
+        &["missing.xz", "a.xz"],
+        &["-q", "missing.xz", "a.xz"],
+        &["a.xz", "-", "a.xz"],
+    ] {
+        let parity = Parity::new("xzcat", args)
+            .file("a.xz", good.data())
+            .file("bad.xz", corrupt.data())
+            .stdin(good.data());
+        assert_parity(&parity).await;
+    }
+});
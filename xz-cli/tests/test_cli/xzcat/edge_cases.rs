@@ -299,3 +299,30 @@ add_test!(dash_reads_stdin_in_middle, async {
         .collect();
     assert!(output.stdout_raw == expected);
 });
+
+// Test `-` reads stdin between named files.
+add_test!(dash_reads_stdin_between_files, async {
+    const FILE_NAME: &str = "named.txt";
+
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture.run_cargo("xz", &["-k", &file_path]).await;
+    assert!(output.status.success());
+    let stdin_compressed = fixture
+        .run_cargo("xz", &["-c", &file_path])
+        .await
+        .stdout_raw;
+
+    let output = fixture
+        .run_with_stdin_raw(
+            BinaryType::cargo("xzcat"),
+            &[&compressed_path, "-", &compressed_path],
+            &stdin_compressed,
+        )
+        .await;
+    assert!(output.status.success(), "stderr: {}", output.stderr);
+    assert!(output.stdout_raw == [data.as_slice(); 3].concat());
+});
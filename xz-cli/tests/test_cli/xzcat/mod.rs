@@ -1,4 +1,5 @@
 mod basic;
 mod cli_options;
+mod differential;
 mod edge_cases;
 mod interop;
@@ -50,3 +50,44 @@ add_test!(single_operand_comparison, async {
     let out = fixture.run_cargo("xzcmp", &[&file_xz]).await;
     assert!(out.status.success());
 });
+
+// The first difference is reported like `cmp`, using the operand names.
+add_test!(reports_first_difference_like_cmp, async {
+    const A: &str = "a.txt";
+    const B: &str = "b.txt";
+
+    let mut fixture = Fixture::with_files(&[A, B], &[b"abc\ndef\n", b"abc\ndXf\n"]);
+    let out = fixture.run_cargo("xz", &[&fixture.path(A)]).await;
+    assert!(out.status.success());
+
+    let a_xz = fixture.compressed_path(A);
+    let b_path = fixture.path(B);
+    let out = fixture.run_cargo("xzcmp", &[&a_xz, &b_path]).await;
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        out.stdout.trim_end(),
+        format!("{a_xz} {b_path} differ: char 6, line 2")
+    );
+
+    let out = fixture.run_cargo("xzcmp", &["-s", &a_xz, &b_path]).await;
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+});
+
+// A proper prefix is reported as EOF on the shorter operand.
+add_test!(reports_eof_on_shorter_operand, async {
+    const SHORT: &str = "short.txt";
+    const LONG: &str = "long.txt";
+
+    let mut fixture = Fixture::with_files(&[SHORT, LONG], &[b"abc\n", b"abc\ndef\n"]);
+    let out = fixture.run_cargo("xz", &[&fixture.path(LONG)]).await;
+    assert!(out.status.success());
+
+    let short = fixture.path(SHORT);
+    let long_xz = fixture.compressed_path(LONG);
+    let out = fixture.run_cargo("xzcmp", &[&short, &long_xz]).await;
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out
+        .stderr
+        .contains(&format!("EOF on {short} after byte 4, line 1")));
+});
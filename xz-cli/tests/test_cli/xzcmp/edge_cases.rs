@@ -1,5 +1,5 @@
 use crate::add_test;
-use crate::common::Fixture;
+use crate::common::{BinaryType, Fixture};
 
 // Test that a missing input file yields exit code 2.
 add_test!(missing_operand_yields_exit_2, async {
@@ -27,3 +27,51 @@ add_test!(single_operand_unknown_suffix_is_error, async {
     let out = fixture.run_cargo("xzcmp", &[&fixture.path(FILE)]).await;
     assert!(out.status.code() == Some(2));
 });
+
+// Compressed data on stdin is decompressed before comparing.
+add_test!(compressed_stdin_is_decompressed, async {
+    const FILE: &str = "data.txt";
+    let contents = b"payload\n";
+
+    let mut fixture = Fixture::with_file(FILE, contents);
+    let out = fixture.run_cargo("xz", &["-k", &fixture.path(FILE)]).await;
+    assert!(out.status.success());
+
+    let compressed = std::fs::read(fixture.compressed_path(FILE)).unwrap();
+    let out = fixture
+        .run_with_stdin_raw(
+            BinaryType::cargo("xzcmp"),
+            &["-", &fixture.path(FILE)],
+            &compressed,
+        )
+        .await;
+    assert_eq!(out.status.code(), Some(0));
+});
+
+// Operands are recognized as compressed by their contents, with or without `cmp`.
+add_test!(operands_detected_by_magic_bytes, async {
+    const FILE: &str = "data.txt";
+    let contents = b"payload\n";
+
+    let mut fixture = Fixture::with_file(FILE, contents);
+    let out = fixture.run_cargo("xz", &["-k", &fixture.path(FILE)]).await;
+    assert!(out.status.success());
+
+    let unnamed = fixture.path("data.bin");
+    std::fs::rename(fixture.compressed_path(FILE), &unnamed).unwrap();
+    let out = fixture
+        .run_cargo("xzcmp", &[&unnamed, &fixture.path(FILE)])
+        .await;
+    assert_eq!(out.status.code(), Some(0), "{}", out.stdout);
+
+    // `-l` is left to the external `cmp`, which gets the decompressed contents.
+    let compressed = std::fs::read(&unnamed).unwrap();
+    let out = fixture
+        .run_with_stdin_raw(
+            BinaryType::cargo("xzcmp"),
+            &["-l", "-", &fixture.path(FILE)],
+            &compressed,
+        )
+        .await;
+    assert_eq!(out.status.code(), Some(0), "{}", out.stderr);
+});
@@ -125,7 +125,7 @@ add_test!(memory_limited_compression, async {
     let compressed_path = fixture.compressed_path(FILE_NAME);
 
     // Compress with memory limit
-    let output = fixture.run_cargo("xz", &["-M", "1M", &file_path]).await;
+    let output = fixture.run_cargo("xz", &["-M", "256M", &file_path]).await;
     assert!(output.status.success());
 
     // xzdec should decompress
@@ -133,3 +133,78 @@ add_test!(memory_limited_compression, async {
     assert!(output.status.success());
     assert!(output.stdout_raw == data);
 });
+
+// Test `--memlimit` is enforced and reported like upstream
+add_test!(memlimit_option_is_enforced, async {
+    const FILE_NAME: &str = "memlimit_enforced.txt";
+
+    let data = generate_random_data(KB);
+    let mut fixture = Fixture::with_file(FILE_NAME, &data);
+
+    let file_path = fixture.path(FILE_NAME);
+    let compressed_path = fixture.compressed_path(FILE_NAME);
+
+    let output = fixture.run_cargo("xz", &["-6", &file_path]).await;
+    assert!(output.status.success());
+
+    let output = fixture
+        .run_cargo("xzdec", &["--memlimit=1MiB", &compressed_path])
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout_raw.is_empty());
+    assert!(
+        output.stderr.contains("Memory usage limit reached"),
+        "stderr: {}",
+        output.stderr
+    );
+
+    let output = fixture
+        .run_cargo("xzdec", &["-qq", "-M", "1MiB", &compressed_path])
+        .await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stderr.is_empty());
+
+    let output = fixture
+        .run_cargo("xzdec", &["--memlimit=16MiB", &compressed_path])
+        .await;
+    assert!(output.status.success());
+    assert!(output.stdout_raw == data);
+});
+
+// Test xzdec rejects compression-related options
+add_test!(compression_options_rejected, async {
+    const FILE_NAME: &str = "reject.txt";
+
+    let mut fixture = Fixture::with_file(FILE_NAME, b"data");
+    let file_path = fixture.path(FILE_NAME);
+
+    for flag in ["-z", "-9", "-T2", "--format=xz"] {
+        let output = fixture.run_cargo("xzdec", &[flag, &file_path]).await;
+        assert!(!output.status.success(), "{flag} was accepted");
+        assert!(output.stdout_raw.is_empty());
+    }
+    assert!(!fixture.file_exists("reject.txt.xz"));
+});
+
+// Test xzdec decodes only `.xz`, like upstream
+add_test!(lzma_input_rejected, async {
+    const FILE_NAME: &str = "only_xz.txt";
+
+    let mut fixture = Fixture::with_file(FILE_NAME, b"only xz");
+    let file_path = fixture.path(FILE_NAME);
+    let lzma_path = fixture.lzma_path(FILE_NAME);
+
+    let output = fixture
+        .run_cargo("xz", &["--format=lzma", &file_path])
+        .await;
+    assert!(output.status.success());
+
+    let output = fixture.run_cargo("xzdec", &[&lzma_path]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout_raw.is_empty());
+    assert!(
+        output.stderr.contains("File format not recognized"),
+        "stderr: {}",
+        output.stderr
+    );
+});
@@ -1,5 +1,5 @@
 use crate::add_test;
-use crate::common::Fixture;
+use crate::common::{BinaryType, Fixture};
 
 // Test that a missing input file yields exit code 2 (upstream `xzdiff` contract).
 add_test!(missing_operand_yields_exit_2, async {
@@ -27,3 +27,48 @@ add_test!(single_operand_unknown_suffix_is_error, async {
     let out = fixture.run_cargo("xzdiff", &[&fixture.path(FILE)]).await;
     assert!(out.status.code() == Some(2));
 });
+
+// Test that compressed or plain data on stdin can be compared like `cmd | xzdiff old.xz -`.
+add_test!(stdin_operand_is_decompressed_when_compressed, async {
+    const FILE: &str = "old.txt";
+    let contents = b"same\n";
+
+    let mut fixture = Fixture::with_file(FILE, contents);
+    let out = fixture.run_cargo("xz", &["-k", &fixture.path(FILE)]).await;
+    assert!(out.status.success());
+
+    let old_xz = fixture.compressed_path(FILE);
+    let compressed = std::fs::read(&old_xz).unwrap();
+    let out = fixture
+        .run_with_stdin_raw(BinaryType::cargo("xzdiff"), &[&old_xz, "-"], &compressed)
+        .await;
+    assert_eq!(out.status.code(), Some(0), "{}", out.stderr);
+
+    let out = fixture
+        .run_with_stdin_raw(BinaryType::cargo("xzdiff"), &["-", &old_xz], b"same\n")
+        .await;
+    assert_eq!(out.status.code(), Some(0), "{}", out.stderr);
+
+    let out = fixture
+        .run_with_stdin_raw(BinaryType::cargo("xzdiff"), &[&old_xz, "-"], b"other\n")
+        .await;
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.contains("> other"), "{}", out.stdout);
+});
+
+// Test that operands are recognized as compressed by their contents, not their names.
+add_test!(operands_detected_by_magic_bytes, async {
+    const FILE: &str = "data.txt";
+    let contents = b"payload\n";
+
+    let mut fixture = Fixture::with_file(FILE, contents);
+    let out = fixture.run_cargo("xz", &["-k", &fixture.path(FILE)]).await;
+    assert!(out.status.success());
+
+    let unnamed = fixture.path("data.bin");
+    std::fs::rename(fixture.compressed_path(FILE), &unnamed).unwrap();
+    let out = fixture
+        .run_cargo("xzdiff", &[&unnamed, &fixture.path(FILE)])
+        .await;
+    assert_eq!(out.status.code(), Some(0), "{}", out.stdout);
+});
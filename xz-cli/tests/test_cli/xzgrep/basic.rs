@@ -47,3 +47,52 @@ add_test!(multiple_files_prefix_filenames, async {
     assert!(out.stdout.contains(&format!("{a_xz}:foo")));
     assert!(out.stdout.contains(&format!("{b_xz}:foo")));
 });
+
+// `xzegrep` matches extended regular expressions without an explicit `-E`.
+add_test!(xzegrep_uses_extended_regex, async {
+    const FILE: &str = "file.txt";
+    let contents = b"foo\nbar\nbaz\n";
+
+    let mut fixture = Fixture::with_file(FILE, contents);
+    let out = fixture.run_cargo("xz", &[&fixture.path(FILE)]).await;
+    assert!(out.status.success());
+    let file_xz = fixture.compressed_path(FILE);
+
+    let out = fixture.run_cargo("xzegrep", &["fo+|baz", &file_xz]).await;
+    assert!(out.status.success());
+    assert!(out.stdout.contains("foo"));
+    assert!(out.stdout.contains("baz"));
+    assert!(!out.stdout.contains("bar"));
+});
+
+// `xzegrep` and `xzfgrep` report errors under the name they were invoked as.
+add_test!(grep_variants_report_their_own_name, async {
+    let mut fixture = Fixture::with_file("dummy.txt", b"dummy");
+    let missing = fixture.path("missing.xz");
+
+    for program in ["xzgrep", "xzegrep", "xzfgrep"] {
+        let out = fixture.run_cargo(program, &["foo", &missing]).await;
+        assert_eq!(out.status.code(), Some(2));
+        assert!(
+            out.stderr.starts_with(&format!("{program}: ")),
+            "{program}: {}",
+            out.stderr
+        );
+    }
+});
+
+// `xzfgrep` treats the pattern as a fixed string.
+add_test!(xzfgrep_uses_fixed_strings, async {
+    const FILE: &str = "file.txt";
+    let contents = b"a.c\nabc\n";
+
+    let mut fixture = Fixture::with_file(FILE, contents);
+    let out = fixture.run_cargo("xz", &[&fixture.path(FILE)]).await;
+    assert!(out.status.success());
+    let file_xz = fixture.compressed_path(FILE);
+
+    let out = fixture.run_cargo("xzfgrep", &["a.c", &file_xz]).await;
+    assert!(out.status.success());
+    assert!(out.stdout.contains("a.c"));
+    assert!(!out.stdout.contains("abc"));
+});
@@ -21,3 +21,27 @@ add_test!(version_option_succeeds, async {
         .await;
     assert!(out.status.success());
 });
+
+// Test that options in `PAGER` and `+cmd` arguments reach the pager.
+add_test!(pager_env_options_and_plus_commands, async {
+    const FILE: &str = "pager.txt";
+    let contents = b"needle\n";
+
+    let mut fixture = Fixture::with_file(FILE, contents);
+
+    let out = fixture.run_cargo("xz", &["-k", &fixture.path(FILE)]).await;
+    assert!(out.status.success());
+
+    let file_xz = fixture.compressed_path(FILE);
+    let out = fixture
+        .run_cargo_with_env("xzless", &[&file_xz], &[("PAGER", "cat -n")])
+        .await;
+    assert!(out.status.success());
+    assert!(out.stdout.contains("1\tneedle"));
+
+    let out = fixture
+        .run_cargo_with_env("xzless", &["+/needle", &file_xz], &[("PAGER", "echo")])
+        .await;
+    assert!(out.status.success());
+    assert!(out.stdout.starts_with("+/needle -- "), "{}", out.stdout);
+});
@@ -23,3 +23,22 @@ add_test!(dash_with_other_files_is_error, async {
         .await;
     assert!(out.status.code() == Some(2));
 });
+
+// Test that a `PAGER` that doesn't exist falls back instead of failing.
+add_test!(missing_pager_falls_back, async {
+    const FILE: &str = "fallback.txt";
+    let contents = b"still shown\n";
+
+    let mut fixture = Fixture::with_file(FILE, contents);
+
+    let out = fixture.run_cargo("xz", &["-k", &fixture.path(FILE)]).await;
+    assert!(out.status.success());
+
+    let file_xz = fixture.compressed_path(FILE);
+    let out = fixture
+        .run_cargo_with_env("xzmore", &[&file_xz], &[("PAGER", "/nonexistent/pager")])
+        .await;
+    assert!(out.status.success(), "{}", out.stderr);
+    // `more` may put a header with the file name before the contents.
+    assert!(out.stdout.ends_with("still shown\n"), "{}", out.stdout);
+});
@@ -2,16 +2,110 @@
 //!
 //! The `xz` supports supplying input file names via a separate stream
 //! (either newline-delimited or NUL-delimited). This module provides the shared
-//! logic to read and parse such lists.
+//! options and the logic to read and parse such lists for every CLI tool.
+//!
+//! Like upstream, every non-empty name is taken literally. For lists written by hand,
+//! `--files-glob` turns a newline-delimited list into patterns: blank lines and lines
+//! starting with `#` are skipped, a name can be quoted to keep leading or trailing spaces
+//! (or a leading `#`), and unquoted names are expanded as glob patterns.
 
 use std::ffi::OsString;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 
 #[cfg(unix)]
 use std::os::unix::ffi::OsStringExt;
 
+use crate::error::{DiagnosticCause, Error, IoErrorNoCode, Result};
+
+/// The `--files` and `--files0` options, shared by the tools that take input files.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct FileListArgs {
+    /// Read filenames to process from FILE (one per line); `-` or no FILE means stdin
+    #[arg(
+        long = "files",
+        value_name = "FILE",
+        num_args = 0..=1,
+        default_missing_value = "-",
+        conflicts_with = "files0_from_file"
+    )]
+    pub files_from_file: Option<PathBuf>,
+
+    /// Like --files but filenames are terminated with the null character
+    #[arg(
+        long = "files0",
+        value_name = "FILE",
+        num_args = 0..=1,
+        default_missing_value = "-",
+        conflicts_with = "files_from_file"
+    )]
+    pub files0_from_file: Option<PathBuf>,
+
+    /// Treat --files lines as patterns: skip blank and `#` lines, unquote quoted names and
+    /// expand `*`, `?` and `[...]` in the others
+    #[arg(long = "files-glob")]
+    pub files_glob: bool,
+}
+
+impl FileListArgs {
+    /// Collects the input files from the command line `operands` and the file list.
+    ///
+    /// Like upstream `xz`, the names from the list follow the operands, and when a list is
+    /// read from standard input, no file can be read from there too.
+    ///
+    /// # Parameters
+    ///
+    /// * `operands` - Files given on the command line
+    ///
+    /// # Returns
+    ///
+    /// The files to process, or `None` if a list was given but there are no files at all.
+    /// Without a list, an empty result means standard input, as usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the list can't be read, or if standard input is both the list
+    /// and one of the files.
+    pub fn resolve(&self, operands: &[PathBuf]) -> Result<Option<Vec<PathBuf>>> {
+        let (list, delimiter) = match (&self.files_from_file, &self.files0_from_file) {
+            (Some(list), _) => (list, Delimiter::Line),
+            (None, Some(list)) => (list, Delimiter::Nul),
+            (None, None) => return Ok(Some(operands.to_vec())),
+        };
+
+        let stdin_conflict = |files: &[PathBuf]| {
+            if is_stdin(list) && files.iter().any(|file| is_stdin(file)) {
+                return Err(DiagnosticCause::from(Error::InvalidOption {
+                    message: "Cannot read data from standard input when reading filenames \
+                              from standard input"
+                        .into(),
+                }));
+            }
+            Ok(())
+        };
+
+        // Checked before reading the list so that it isn't consumed in vain.
+        stdin_conflict(operands)?;
+        let listed = read_files(Some(list), delimiter, self.files_glob).map_err(|source| {
+            DiagnosticCause::from(Error::ReadFileList {
+                path: list.clone(),
+                source: IoErrorNoCode::new(source),
+            })
+        })?;
+        stdin_conflict(&listed)?;
+
+        let mut files = operands.to_vec();
+        files.extend(listed);
+        Ok((!files.is_empty()).then_some(files))
+    }
+}
+
+/// Returns `true` if `path` names standard input.
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
 /// Delimiter used to separate file names in an argument file list.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Delimiter {
@@ -25,11 +119,24 @@ pub enum Delimiter {
 ///
 /// If `path` is `None`, or equals `"-"`, the list is read from stdin.
 ///
+/// # Parameters
+///
+/// * `path` - The list to read
+/// * `delimiter` - How the names in the list are separated
+/// * `patterns` - Whether the lines of a line-delimited list are patterns rather than
+///   literal names
+///
 /// # Errors
 ///
 /// Returns an error if:
 /// - the list source cannot be read
-pub fn read_files(path: Option<&Path>, delimiter: Delimiter) -> io::Result<Vec<PathBuf>> {
+/// - a pattern line has an unterminated quote
+/// - a directory can't be read while expanding a pattern
+pub fn read_files(
+    path: Option<&Path>,
+    delimiter: Delimiter,
+    patterns: bool,
+) -> io::Result<Vec<PathBuf>> {
     let mut buf = Vec::new();
 
     if path.is_none() || path == Some(Path::new("-")) {
@@ -39,25 +146,225 @@ pub fn read_files(path: Option<&Path>, delimiter: Delimiter) -> io::Result<Vec<P
     }
 
     match delimiter {
-        Delimiter::Line => parse_line_delimited(&buf),
+        Delimiter::Line => parse_line_delimited(&buf, patterns),
         Delimiter::Nul => parse_nul_delimited(&buf),
     }
 }
 
 /// Parse a line-delimited list of file names.
-fn parse_line_delimited(buf: &[u8]) -> io::Result<Vec<PathBuf>> {
+///
+/// Without `patterns`, every non-empty line is a name, taken literally.
+///
+/// With `patterns`, blank lines and lines whose first non-blank character is `#` are
+/// skipped. A name enclosed in `"` or `'` is taken as is, without the quotes; inside
+/// double quotes, `\"` and `\\` stand for a quote and a backslash. Other names are
+/// expanded as glob patterns.
+fn parse_line_delimited(buf: &[u8], patterns: bool) -> io::Result<Vec<PathBuf>> {
     let mut out = Vec::new();
-    for line in buf.split(|b| *b == b'\n') {
+    for (index, line) in buf.split(|b| *b == b'\n').enumerate() {
         let line = line.strip_suffix(b"\r").unwrap_or(line);
-        if line.is_empty() {
+        if !patterns {
+            if !line.is_empty() {
+                out.push(path_from_bytes(line)?);
+            }
+            continue;
+        }
+
+        let trimmed = line.trim_ascii();
+        if trimmed.is_empty() || trimmed.starts_with(b"#") {
             continue;
         }
-        out.push(path_from_bytes(line)?);
+
+        if let Some(name) = unquote(trimmed).map_err(|message| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {message}", index + 1),
+            )
+        })? {
+            out.push(path_from_bytes(&name)?);
+        } else if is_glob(line) {
+            out.extend(expand_glob(&path_from_bytes(line)?)?);
+        } else {
+            out.push(path_from_bytes(line)?);
+        }
     }
 
     Ok(out)
 }
 
+/// Removes the quotes around `name`.
+///
+/// # Returns
+///
+/// The name without its quotes, or `None` if it isn't quoted.
+///
+/// # Errors
+///
+/// Returns an error if the closing quote is missing or isn't the end of the line.
+fn unquote(name: &[u8]) -> std::result::Result<Option<Vec<u8>>, &'static str> {
+    let Some((&quote @ (b'"' | b'\''), rest)) = name.split_first() else {
+        return Ok(None);
+    };
+
+    let mut out = Vec::with_capacity(rest.len());
+    let mut bytes = rest.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            b'\\' if quote == b'"' => match bytes.next() {
+                Some(&escaped @ (b'"' | b'\\')) => out.push(escaped),
+                Some(&other) => out.extend([byte, other]),
+                None => break,
+            },
+            _ if byte == quote => {
+                return if bytes.as_slice().is_empty() {
+                    Ok(Some(out))
+                } else {
+                    Err("unexpected text after the closing quote")
+                };
+            }
+            _ => out.push(byte),
+        }
+    }
+
+    Err("missing closing quote")
+}
+
+/// Returns `true` if `name` has any glob metacharacter.
+fn is_glob(name: &[u8]) -> bool {
+    name.iter().any(|b| matches!(b, b'*' | b'?' | b'['))
+}
+
+/// Expands the glob `pattern` to the existing paths that match it, in sorted order.
+///
+/// Each path component may use `*`, `?` and `[...]` (with `!` or `^` to negate and `-`
+/// for ranges). Like the shell, a wildcard doesn't match a leading `.`, and a pattern
+/// without any match is kept as is, so that the missing file gets reported later.
+///
+/// # Errors
+///
+/// Returns an error if a directory that the pattern walks through can't be read.
+fn expand_glob(pattern: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut matches = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let name = component.as_os_str();
+        if !is_glob(name.as_encoded_bytes()) {
+            for path in &mut matches {
+                path.push(name);
+            }
+            continue;
+        }
+
+        let mut next = Vec::new();
+        for dir in &matches {
+            let read_dir = if dir.as_os_str().is_empty() {
+                fs::read_dir(".")
+            } else {
+                fs::read_dir(dir)
+            };
+            let entries = match read_dir {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) if err.kind() == io::ErrorKind::NotADirectory => continue,
+                Err(err) => return Err(err),
+            };
+            for entry in entries {
+                let entry = entry?;
+                if glob_match(
+                    name.as_encoded_bytes(),
+                    entry.file_name().as_encoded_bytes(),
+                ) {
+                    next.push(dir.join(entry.file_name()));
+                }
+            }
+        }
+        next.sort();
+        matches = next;
+    }
+
+    // Literal components after the last wildcard may name files that don't exist.
+    matches.retain(|path| fs::symlink_metadata(path).is_ok());
+    if matches.is_empty() {
+        return Ok(vec![pattern.to_path_buf()]);
+    }
+    Ok(matches)
+}
+
+/// Matches a single path component against a glob pattern.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    if name.first() == Some(&b'.') && pattern.first() != Some(&b'.') {
+        return false;
+    }
+
+    // Backtracking to the most recent `*` is enough, as `*` never crosses a `/`.
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        let step = match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some(b'?') => Some(p + 1),
+            Some(b'[') => match_class(&pattern[p..], name[n]).map(|len| p + len),
+            Some(&literal) if literal == name[n] => Some(p + 1),
+            _ => None,
+        };
+        match (step, star) {
+            (Some(next), _) => {
+                p = next;
+                n += 1;
+            }
+            (None, Some((star_p, star_n))) => {
+                p = star_p + 1;
+                n = star_n + 1;
+                star = Some((star_p, star_n + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|b| *b == b'*')
+}
+
+/// Matches `byte` against the bracket expression at the start of `pattern`.
+///
+/// # Returns
+///
+/// The length of the bracket expression if `byte` matches it, or `None` otherwise. An
+/// unterminated `[` matches only itself.
+fn match_class(pattern: &[u8], byte: u8) -> Option<usize> {
+    let mut i = 1;
+    let negated = matches!(pattern.get(i), Some(b'!' | b'^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let Some(&start) = pattern.get(i) else {
+            return (byte == b'[').then_some(1);
+        };
+        if start == b']' && !first {
+            break;
+        }
+        first = false;
+        match (pattern.get(i + 1), pattern.get(i + 2)) {
+            (Some(b'-'), Some(&end)) if end != b']' => {
+                matched |= (start..=end).contains(&byte);
+                i += 3;
+            }
+            _ => {
+                matched |= start == byte;
+                i += 1;
+            }
+        }
+    }
+
+    (matched != negated).then_some(i + 1)
+}
+
 /// Parse a NUL-delimited list of file names.
 fn parse_nul_delimited(buf: &[u8]) -> io::Result<Vec<PathBuf>> {
     let mut out = Vec::new();
@@ -103,7 +410,7 @@ mod tests {
     /// Test that line-delimited paths are parsed correctly.
     #[test]
     fn parses_line_delimited_paths_without_extra_copying_through_utf8() {
-        let parsed = parse_line_delimited(b"alpha\nbeta\r\ngamma\n").unwrap();
+        let parsed = parse_line_delimited(b"alpha\nbeta\r\ngamma\n", false).unwrap();
         assert_eq!(
             parsed,
             vec![
@@ -114,6 +421,161 @@ mod tests {
         );
     }
 
+    /// Test that without patterns every non-empty line is a literal name.
+    #[test]
+    fn line_delimited_keeps_literal_names() {
+        let list = b"#x\n\n  \n'quoted'\n\"open\n  padded  \n*.txt\n";
+        let parsed = parse_line_delimited(list, false).unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                PathBuf::from("#x"),
+                PathBuf::from("  "),
+                PathBuf::from("'quoted'"),
+                PathBuf::from("\"open"),
+                PathBuf::from("  padded  "),
+                PathBuf::from("*.txt"),
+            ]
+        );
+    }
+
+    /// Test that patterns skip blank lines and comments and keep quoted names as is.
+    #[test]
+    fn line_delimited_skips_comments_and_unquotes_names() {
+        let list = b"# inputs\n\n  \nplain name.txt\n  # indented comment\n\
+                     \"  padded  \"\n'#hash'\n\"say \\\"hi\\\"\"\r\n";
+        let parsed = parse_line_delimited(list, true).unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                PathBuf::from("plain name.txt"),
+                PathBuf::from("  padded  "),
+                PathBuf::from("#hash"),
+                PathBuf::from("say \"hi\""),
+            ]
+        );
+    }
+
+    /// Test that a broken quote is reported with its line number.
+    #[test]
+    fn line_delimited_rejects_unterminated_quote() {
+        let err = parse_line_delimited(b"ok\n\"open\n", true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 2"));
+
+        let err = parse_line_delimited(b"'a' b\n", true).unwrap_err();
+        assert!(err.to_string().contains("after the closing quote"));
+    }
+
+    /// Test single component glob matching.
+    #[test]
+    fn glob_match_supports_wildcards_and_classes() {
+        assert!(glob_match(b"*.txt", b"a.txt"));
+        assert!(!glob_match(b"*.txt", b".txt.txt"));
+        assert!(glob_match(b".*", b".hidden"));
+        assert!(glob_match(b"a*b*c", b"aXbYbZc"));
+        assert!(!glob_match(b"a*b*c", b"aXbYbZ"));
+        assert!(glob_match(b"file?.log", b"file1.log"));
+        assert!(!glob_match(b"file?.log", b"file.log"));
+        assert!(glob_match(b"[a-c]x", b"bx"));
+        assert!(!glob_match(b"[!a-c]x", b"bx"));
+        assert!(glob_match(b"[]]", b"]"));
+        assert!(glob_match(b"a[b", b"a[b"));
+    }
+
+    /// Test that unquoted patterns are expanded in sorted order and quoted ones are not.
+    #[test]
+    fn resolve_expands_globs_when_requested() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.txt", "a.txt", "c.log", ".hidden.txt"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let pattern = dir.path().join("*.txt");
+        let missing = dir.path().join("*.none");
+        let list = dir.path().join("list");
+        std::fs::write(
+            &list,
+            format!(
+                "{}\n\"{}\"\n{}\n",
+                pattern.display(),
+                pattern.display(),
+                missing.display()
+            ),
+        )
+        .unwrap();
+
+        let args = FileListArgs {
+            files_from_file: Some(list.clone()),
+            files_glob: true,
+            ..FileListArgs::default()
+        };
+        assert_eq!(
+            args.resolve(&[]).unwrap(),
+            Some(vec![
+                dir.path().join("a.txt"),
+                dir.path().join("b.txt"),
+                pattern.clone(),
+                missing,
+            ])
+        );
+
+        let args = FileListArgs {
+            files_glob: false,
+            ..args
+        };
+        assert_eq!(args.resolve(&[]).unwrap().unwrap()[0], pattern);
+    }
+
+    /// Test that operands are kept when no list is given.
+    #[test]
+    fn resolve_without_list_keeps_operands() {
+        let operands = [PathBuf::from("a.xz")];
+        let files = FileListArgs::default().resolve(&operands).unwrap();
+        assert_eq!(files, Some(operands.to_vec()));
+
+        let files = FileListArgs::default().resolve(&[]).unwrap();
+        assert_eq!(files, Some(Vec::new()));
+    }
+
+    /// Test that list entries follow the operands and an empty list means no files.
+    #[test]
+    fn resolve_appends_list_to_operands() {
+        let dir = tempfile::tempdir().unwrap();
+        let list = dir.path().join("list");
+        std::fs::write(&list, b"b.xz\0c.xz\0").unwrap();
+
+        let args = FileListArgs {
+            files0_from_file: Some(list.clone()),
+            ..FileListArgs::default()
+        };
+        let files = args.resolve(&[PathBuf::from("a.xz")]).unwrap();
+        assert_eq!(
+            files,
+            Some(vec![
+                PathBuf::from("a.xz"),
+                PathBuf::from("b.xz"),
+                PathBuf::from("c.xz")
+            ])
+        );
+
+        std::fs::write(&list, b"").unwrap();
+        assert_eq!(args.resolve(&[]).unwrap(), None);
+    }
+
+    /// Test that standard input can't be both the list and a file.
+    #[test]
+    fn resolve_rejects_stdin_file_with_stdin_list() {
+        let args = FileListArgs {
+            files_from_file: Some(PathBuf::from("-")),
+            ..FileListArgs::default()
+        };
+        let err = args.resolve(&[PathBuf::from("-")]).unwrap_err();
+        assert!(matches!(
+            err,
+            DiagnosticCause::Error(Error::InvalidOption { .. })
+        ));
+    }
+
     /// Test that NUL-delimited paths are parsed correctly.
     #[cfg(unix)]
     #[test]
@@ -1,32 +1,247 @@
 //! Byte-size formatting helpers.
 
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::config::ListSizes;
+
+/// Largest value printed without switching to the next unit, like upstream `xz`.
+const NICE_MAX: u64 = 9999;
+
 /// Format a byte count like upstream `xz -l`.
 ///
-/// Uses `KiB` for values >= 1024 bytes and `MiB` for values >= 1 MiB.
+/// Values up to 9999 are printed in bytes. Larger values are divided by 1024 until they
+/// drop to 9999.9 or below and printed with one decimal, rounded to nearest, in `KiB`,
+/// `MiB`, `GiB` or `TiB`.
 pub(crate) fn format_list_size(bytes: u64) -> String {
-    const KIB: u64 = 1024;
-    const MIB: u64 = 1024 * 1024;
-
-    if bytes >= MIB {
-        let tenths = bytes.saturating_mul(10) / MIB;
-        let whole = tenths / 10;
-        let frac = tenths % 10;
-        format!("{whole}.{frac} MiB")
-    } else if bytes >= KIB {
-        let tenths = bytes.saturating_mul(10) / KIB;
-        let whole = tenths / 10;
-        let frac = tenths % 10;
-        format!("{whole}.{frac} KiB")
-    } else {
-        format!("{bytes} B")
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes <= NICE_MAX {
+        return format!("{bytes} B");
+    }
+
+    format_scaled(bytes, 1024, &UNITS)
+}
+
+/// Format a byte count for the live progress display of upstream `xz -v`.
+///
+/// Sizes start at `MiB` so that the line doesn't jump between units for small files, and
+/// switch to `GiB` or `TiB` above 9999.9 with one decimal.
+pub(crate) fn format_progress_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["MiB", "GiB", "TiB"];
+
+    format_scaled(bytes, 1024 * 1024, &UNITS)
+}
+
+/// Divide `bytes` by `divisor` and by further powers of 1024 until the value drops to
+/// 9999.9 or below, and print it with one decimal, rounded to nearest.
+fn format_scaled(bytes: u64, divisor: u128, units: &[&str]) -> String {
+    // Integer arithmetic in tenths of a unit keeps the result exact for every u64.
+    let value = u128::from(bytes) * 10;
+    let mut divisor = divisor;
+    let mut unit = 0;
+    while unit + 1 < units.len() && value > (u128::from(NICE_MAX) * 10 + 9) * divisor {
+        divisor *= 1024;
+        unit += 1;
     }
+
+    let tenths = (value + divisor / 2) / divisor;
+    format!("{}.{} {}", tenths / 10, tenths % 10, units[unit])
 }
 
 /// Format a size for the verbose output, optionally appending raw bytes.
 pub(crate) fn format_list_size_with_bytes(bytes: u64) -> String {
-    if bytes < 1024 {
+    if bytes <= NICE_MAX {
         format_list_size(bytes)
     } else {
         format!("{} ({bytes} B)", format_list_size(bytes))
     }
 }
+
+/// Format a byte count for `--list` in the given style.
+pub(crate) fn format_size(bytes: u64, sizes: ListSizes) -> String {
+    match sizes {
+        ListSizes::Upstream => format_list_size(bytes),
+        ListSizes::Human { precision } => format_human_size(bytes, precision),
+        ListSizes::Bytes => format!("{} B", group_thousands(bytes)),
+    }
+}
+
+/// Format a byte count for `--list -v` in the given style, with the exact count appended
+/// when the size is scaled.
+pub(crate) fn format_size_with_bytes(bytes: u64, sizes: ListSizes) -> String {
+    match sizes {
+        ListSizes::Upstream => format_list_size_with_bytes(bytes),
+        ListSizes::Human { .. } if bytes >= 1024 => {
+            format!(
+                "{} ({} B)",
+                format_size(bytes, sizes),
+                group_thousands(bytes)
+            )
+        }
+        ListSizes::Human { .. } | ListSizes::Bytes => format_size(bytes, sizes),
+    }
+}
+
+/// Format an offset or size column of the `--list -v` tables in the given style.
+///
+/// Upstream prints these as plain numbers; the other styles group the digits.
+pub(crate) fn format_count(value: u64, sizes: ListSizes) -> String {
+    match sizes {
+        ListSizes::Upstream => value.to_string(),
+        ListSizes::Human { .. } | ListSizes::Bytes => group_thousands(value),
+    }
+}
+
+/// Format a byte count in the largest binary unit that keeps it at 1 or above, with
+/// `precision` decimals rounded to nearest, e.g. `1.50 MiB`. Counts below 1 KiB are
+/// printed in bytes.
+pub(crate) fn format_human_size(bytes: u64, precision: u8) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut divisor: u128 = 1024;
+    let mut unit = 0;
+    while unit + 1 < UNITS.len() && u128::from(bytes) >= divisor * 1024 {
+        divisor *= 1024;
+        unit += 1;
+    }
+
+    // Integer arithmetic in units of 10^-precision keeps the rounding exact.
+    let scale = 10_u128.pow(u32::from(precision));
+    let scaled = (u128::from(bytes) * scale + divisor / 2) / divisor;
+    let (whole, fraction) = (scaled / scale, scaled % scale);
+    if precision == 0 {
+        format!("{whole} {}", UNITS[unit])
+    } else {
+        let width = usize::from(precision);
+        format!("{whole}.{fraction:0width$} {}", UNITS[unit])
+    }
+}
+
+/// Format `value` with the digits grouped by threes, using the thousands separator of
+/// the `LC_NUMERIC` locale, or `,` if the locale doesn't define one.
+pub(crate) fn group_thousands(value: u64) -> String {
+    group_digits(value, thousands_separator())
+}
+
+/// Format `value` with `separator` between each group of three digits.
+fn group_digits(value: u64, separator: &str) -> String {
+    let digits = value.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3 * separator.len());
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push_str(separator);
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// The thousands separator of the user's `LC_NUMERIC` locale, looked up once.
+fn thousands_separator() -> &'static str {
+    static SEPARATOR: OnceLock<String> = OnceLock::new();
+
+    SEPARATOR.get_or_init(|| {
+        #[cfg(unix)]
+        let separator = locale_thousands_separator();
+        #[cfg(not(unix))]
+        let separator = String::new();
+
+        if separator.is_empty() {
+            ",".to_string()
+        } else {
+            separator
+        }
+    })
+}
+
+/// Reads the thousands separator of the locale selected by the environment.
+///
+/// The C library's `LC_NUMERIC` setting is switched to the environment's locale only for
+/// the lookup and restored right after.
+#[cfg(unix)]
+fn locale_thousands_separator() -> String {
+    use std::ffi::{CStr, CString};
+    use std::ptr;
+
+    // SAFETY: `setlocale` and `localeconv` are only called here, once, and the returned
+    // strings are copied before the locale is restored. The restored name is an owned copy.
+    unsafe {
+        let current = libc::setlocale(libc::LC_NUMERIC, ptr::null());
+        if current.is_null() {
+            return String::new();
+        }
+        let current = CString::from(CStr::from_ptr(current));
+
+        let mut separator = String::new();
+        if !libc::setlocale(libc::LC_NUMERIC, c"".as_ptr()).is_null() {
+            let conv = libc::localeconv();
+            if !conv.is_null() && !(*conv).thousands_sep.is_null() {
+                separator = CStr::from_ptr((*conv).thousands_sep)
+                    .to_string_lossy()
+                    .into_owned();
+            }
+        }
+        libc::setlocale(libc::LC_NUMERIC, current.as_ptr());
+        separator
+    }
+}
+
+/// Format a throughput like upstream `xz -v`, e.g. `7.5 KiB/s` or `120 MiB/s`.
+///
+/// Returns an empty string when no time has elapsed or the speed is too high to print.
+pub(crate) fn format_speed(bytes: u64, elapsed: Duration) -> String {
+    const UNITS: [&str; 3] = ["KiB/s", "MiB/s", "GiB/s"];
+
+    if elapsed.is_zero() {
+        return String::new();
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let mut speed = bytes as f64 / 1024.0 / elapsed.as_secs_f64();
+    let mut unit = 0;
+    while speed > 999.0 {
+        speed /= 1024.0;
+        unit += 1;
+        if unit == UNITS.len() {
+            return String::new();
+        }
+    }
+    let decimals = usize::from(speed <= 9.9);
+    format!("{speed:.decimals$} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test scaling to the largest unit with the requested number of decimals.
+    #[test]
+    fn human_size_uses_largest_unit_and_precision() {
+        assert_eq!(format_human_size(0, 1), "0 B");
+        assert_eq!(format_human_size(1023, 2), "1023 B");
+        assert_eq!(format_human_size(1024, 1), "1.0 KiB");
+        assert_eq!(format_human_size(1536, 2), "1.50 KiB");
+        assert_eq!(format_human_size(10_000, 0), "10 KiB");
+        assert_eq!(format_human_size(3 * 1024 * 1024 + 1, 3), "3.000 MiB");
+        assert_eq!(format_human_size(5 << 30, 1), "5.0 GiB");
+        assert_eq!(format_human_size(u64::MAX, 2), "16.00 EiB");
+    }
+
+    /// Test grouping digits by threes.
+    #[test]
+    fn group_digits_inserts_separator_every_three_digits() {
+        assert_eq!(group_digits(0, ","), "0");
+        assert_eq!(group_digits(999, ","), "999");
+        assert_eq!(group_digits(1000, ","), "1,000");
+        assert_eq!(group_digits(1_234_567, "."), "1.234.567");
+        assert_eq!(
+            group_digits(u64::MAX, "\u{202f}"),
+            "18\u{202f}446\u{202f}744\u{202f}073\u{202f}709\u{202f}551\u{202f}615"
+        );
+    }
+}
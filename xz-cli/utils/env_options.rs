@@ -0,0 +1,275 @@
+//! Support for the `XZ_DEFAULTS` and `XZ_OPT` environment variables.
+//!
+//! Upstream `xz` parses both variables before the command line: `XZ_DEFAULTS` first, then
+//! `XZ_OPT`, then the actual arguments, so later options override earlier ones. Values are
+//! split at whitespace and may only contain options. `XZ_DEFAULTS` is meant for user or
+//! system wide defaults (memory limits, thread count, presets), so options that change what
+//! `xz` operates on are rejected there.
+
+use std::ffi::{OsStr, OsString};
+
+use clap::parser::ValueSource;
+use clap::{Arg, Command};
+use thiserror::Error;
+
+/// Environment variable with user or system wide default options.
+pub const XZ_DEFAULTS: &str = "XZ_DEFAULTS";
+
+/// Environment variable with options passed by scripts and wrappers such as `tar`.
+pub const XZ_OPT: &str = "XZ_OPT";
+
+/// Long options that only tune compression or resource usage and are allowed in
+/// `XZ_DEFAULTS`.
+const SAFE_LONG_OPTIONS: &[&str] = &[
+    "extreme",
+    "threads",
+    "memory",
+    "memlimit-compress",
+    "memlimit-decompress",
+    "memlimit-mt-decompress",
+    "no-adjust",
+    "check",
+    "quiet",
+    "no-warn",
+    "no-sparse",
+];
+
+/// Errors reported for options taken from the environment.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum EnvOptionsError {
+    /// The variable contains something the option parser rejects.
+    #[error("{var}: {message}")]
+    Invalid {
+        /// Name of the environment variable
+        var: &'static str,
+        /// Parser error message
+        message: String,
+    },
+
+    /// The variable contains a file name or another non-option argument.
+    #[error("{var}: Non-option arguments are not allowed")]
+    NonOption {
+        /// Name of the environment variable
+        var: &'static str,
+    },
+
+    /// `XZ_DEFAULTS` contains an option that changes the operation instead of tuning it.
+    #[error("{var}: {option}: Option is not allowed in {var}")]
+    UnsafeOption {
+        /// Name of the environment variable
+        var: &'static str,
+        /// The rejected option as written by the user (e.g. `--decompress`)
+        option: String,
+    },
+}
+
+/// Prepend the options from `XZ_DEFAULTS` and `XZ_OPT` to the process arguments.
+///
+/// # Parameters
+///
+/// * `cmd` - Command definition used to validate the environment options
+///
+/// # Returns
+///
+/// The program name followed by the `XZ_DEFAULTS` options, the `XZ_OPT` options and the
+/// remaining command line arguments.
+///
+/// # Errors
+///
+/// Returns an error if either variable contains invalid options or non-option arguments,
+/// or if `XZ_DEFAULTS` contains an option that isn't allowed there.
+pub fn args_with_env_options(cmd: &Command) -> Result<Vec<OsString>, EnvOptionsError> {
+    merge_env_options(
+        cmd,
+        std::env::args_os(),
+        std::env::var_os(XZ_DEFAULTS).as_deref(),
+        std::env::var_os(XZ_OPT).as_deref(),
+    )
+}
+
+/// Merge explicit `XZ_DEFAULTS` and `XZ_OPT` values into `args`.
+///
+/// # Parameters
+///
+/// * `cmd` - Command definition used to validate the environment options
+/// * `args` - Command line arguments including the program name
+/// * `defaults` - Value of `XZ_DEFAULTS`, if set
+/// * `opt` - Value of `XZ_OPT`, if set
+///
+/// # Returns
+///
+/// The merged argument list in upstream precedence order.
+///
+/// # Errors
+///
+/// See [`args_with_env_options`].
+pub fn merge_env_options<I, T>(
+    cmd: &Command,
+    args: I,
+    defaults: Option<&OsStr>,
+    opt: Option<&OsStr>,
+) -> Result<Vec<OsString>, EnvOptionsError>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut args = args.into_iter().map(Into::into);
+    let program = args
+        .next()
+        .unwrap_or_else(|| OsString::from(cmd.get_name()));
+    let mut merged = vec![program.clone()];
+
+    for (var, value) in [(XZ_DEFAULTS, defaults), (XZ_OPT, opt)] {
+        let Some(value) = value else {
+            continue;
+        };
+        let env_args = split_env_value(value);
+        validate_env_args(cmd, var, &program, &env_args)?;
+        merged.extend(env_args);
+    }
+
+    merged.extend(args);
+    Ok(merged)
+}
+
+/// Split a variable value at whitespace like upstream (no quoting or escaping).
+fn split_env_value(value: &OsStr) -> Vec<OsString> {
+    value
+        .to_string_lossy()
+        .split_whitespace()
+        .map(OsString::from)
+        .collect()
+}
+
+fn validate_env_args(
+    cmd: &Command,
+    var: &'static str,
+    program: &OsStr,
+    env_args: &[OsString],
+) -> Result<(), EnvOptionsError> {
+    let matches = cmd
+        .clone()
+        .try_get_matches_from(
+            std::iter::once(program).chain(env_args.iter().map(OsString::as_os_str)),
+        )
+        .map_err(|err| EnvOptionsError::Invalid {
+            var,
+            message: first_error_line(&err.render().to_string()),
+        })?;
+
+    for id in matches.ids() {
+        if matches.value_source(id.as_str()) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        // Argument groups have ids too but no matching argument.
+        let Some(arg) = cmd.get_arguments().find(|arg| arg.get_id() == id) else {
+            continue;
+        };
+        if arg.is_positional() {
+            return Err(EnvOptionsError::NonOption { var });
+        }
+        if var == XZ_DEFAULTS && !is_safe_default(arg) {
+            return Err(EnvOptionsError::UnsafeOption {
+                var,
+                option: option_name(arg),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn is_safe_default(arg: &Arg) -> bool {
+    arg.get_short().is_some_and(|short| short.is_ascii_digit())
+        || arg
+            .get_long()
+            .is_some_and(|long| SAFE_LONG_OPTIONS.contains(&long))
+}
+
+fn option_name(arg: &Arg) -> String {
+    match (arg.get_long(), arg.get_short()) {
+        (Some(long), _) => format!("--{long}"),
+        (None, Some(short)) => format!("-{short}"),
+        (None, None) => arg.get_id().to_string(),
+    }
+}
+
+/// Reduce a rendered clap error to its message line, without the `error: ` prefix.
+fn first_error_line(rendered: &str) -> String {
+    let line = rendered.lines().next().unwrap_or_default();
+    line.strip_prefix("error: ").unwrap_or(line).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Command {
+        Command::new("xz")
+            .arg(Arg::new("files").num_args(0..))
+            .arg(
+                Arg::new("decompress")
+                    .short('d')
+                    .long("decompress")
+                    .action(clap::ArgAction::SetTrue),
+            )
+            .arg(Arg::new("threads").short('T').long("threads"))
+            .arg(
+                Arg::new("level_9")
+                    .short('9')
+                    .action(clap::ArgAction::SetTrue),
+            )
+    }
+
+    fn merge(defaults: Option<&str>, opt: Option<&str>) -> Result<Vec<String>, EnvOptionsError> {
+        merge_env_options(
+            &command(),
+            ["xz", "-T2", "file"],
+            defaults.map(OsStr::new),
+            opt.map(OsStr::new),
+        )
+        .map(|args| {
+            args.into_iter()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn environment_options_precede_command_line() {
+        assert_eq!(
+            merge(Some("-9  -T4"), Some("\t-d")).unwrap(),
+            ["xz", "-9", "-T4", "-d", "-T2", "file"]
+        );
+        assert_eq!(merge(None, Some("")).unwrap(), ["xz", "-T2", "file"]);
+    }
+
+    #[test]
+    fn environment_rejects_non_option_arguments() {
+        assert_eq!(
+            merge(None, Some("-9 file")),
+            Err(EnvOptionsError::NonOption { var: XZ_OPT })
+        );
+    }
+
+    #[test]
+    fn defaults_reject_unsafe_options() {
+        assert_eq!(
+            merge(Some("-d"), None),
+            Err(EnvOptionsError::UnsafeOption {
+                var: XZ_DEFAULTS,
+                option: "--decompress".into(),
+            })
+        );
+        assert!(merge(None, Some("-d")).is_ok());
+    }
+
+    #[test]
+    fn environment_reports_parse_errors() {
+        let err = merge(None, Some("--bogus")).unwrap_err();
+        assert!(
+            matches!(&err, EnvOptionsError::Invalid { var: XZ_OPT, message } if message.contains("--bogus")),
+            "{err:?}"
+        );
+    }
+}
@@ -14,3 +14,16 @@ pub(crate) fn ratio_fraction(compressed: u64, uncompressed: u64) -> f64 {
     let ratio_micro_u32 = u32::try_from(ratio_micro).unwrap_or(u32::MAX);
     f64::from(ratio_micro_u32) / 1_000_000.0
 }
+
+/// Format a compression ratio like upstream `xz -l`.
+///
+/// Prints `---` when the uncompressed size is zero or the ratio exceeds 9.999, otherwise
+/// the ratio with three decimals.
+pub(crate) fn format_list_ratio(compressed: u64, uncompressed: u64) -> String {
+    let ratio = ratio_fraction(compressed, uncompressed);
+    if uncompressed == 0 || ratio > 9.999 {
+        "---".to_string()
+    } else {
+        format!("{ratio:.3}")
+    }
+}
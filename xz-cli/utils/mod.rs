@@ -4,6 +4,8 @@
 //! entrypoints but don't belong to the higher-level CLI orchestration layers.
 
 pub mod argfiles;
+pub mod env_options;
+pub mod user_config;
 
 pub(crate) mod bytes;
 pub(crate) mod math;
@@ -0,0 +1,309 @@
+//! Support for the user configuration file with default options.
+//!
+//! The file lives at `$XDG_CONFIG_HOME/xz-rs/config.toml` (or `~/.config/xz-rs/config.toml`
+//! when `XDG_CONFIG_HOME` is unset) and `XZ_RS_CONFIG` can point to another file. It uses a
+//! small subset of TOML, one `key = value` pair per line:
+//!
+//! ```toml
+//! # Defaults for xz
+//! level = 9
+//! threads = 0
+//! memlimit = "50%"
+//! suffix = ".txz"
+//! ```
+//!
+//! The settings are turned into options that are placed before `XZ_DEFAULTS`, `XZ_OPT` and
+//! the command line, so each of those overrides the file. Like those variables, the file is
+//! only read by `xz` itself; `unxz`, `xzcat`, `lzma`, `unlzma` and the other tools ignore it.
+//!
+//! A file named by `XZ_RS_CONFIG` has to be readable if it exists. The usual location is
+//! skipped when it can't be reached, such as in a home directory the user can't read.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::process::parse_memory_limit;
+
+/// Environment variable naming the configuration file; an empty value disables it.
+pub const XZ_RS_CONFIG: &str = "XZ_RS_CONFIG";
+
+/// Errors reported for the configuration file.
+#[derive(Debug, Error)]
+pub enum UserConfigError {
+    /// The file exists but can't be read.
+    #[error("{}: {source}", path.display())]
+    Read {
+        /// Path of the configuration file
+        path: PathBuf,
+        /// Underlying I/O error
+        #[source]
+        source: io::Error,
+    },
+
+    /// A line isn't a valid setting.
+    #[error("{}:{line}: {message}", path.display())]
+    Invalid {
+        /// Path of the configuration file
+        path: PathBuf,
+        /// One-based line number
+        line: usize,
+        /// What is wrong with the line
+        message: String,
+    },
+}
+
+/// Path of the configuration file, or `None` if there is no place to look for one.
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(XZ_RS_CONFIG) {
+        return (!path.is_empty()).then(|| PathBuf::from(path));
+    }
+
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| Path::new(dir).is_absolute())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_home.join("xz-rs").join("config.toml"))
+}
+
+/// Read the configuration file and convert its settings to command line options.
+///
+/// # Returns
+///
+/// The options in the order of the settings, or nothing if the file doesn't exist.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or contains an invalid setting.
+pub fn load_default_options() -> Result<Vec<OsString>, UserConfigError> {
+    let Some(path) = config_path() else {
+        return Ok(Vec::new());
+    };
+    let explicit = std::env::var_os(XZ_RS_CONFIG).is_some();
+    load_options_from(path, explicit)
+}
+
+/// Read the configuration file at `path`, which was named by `XZ_RS_CONFIG` if
+/// `explicit` is set.
+fn load_options_from(path: PathBuf, explicit: bool) -> Result<Vec<OsString>, UserConfigError> {
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err)
+            if !explicit
+                && matches!(
+                    err.kind(),
+                    io::ErrorKind::PermissionDenied | io::ErrorKind::NotADirectory
+                ) =>
+        {
+            return Ok(Vec::new());
+        }
+        Err(source) => return Err(UserConfigError::Read { path, source }),
+    };
+
+    parse_config(&text).map_err(|(line, message)| UserConfigError::Invalid {
+        path,
+        line,
+        message,
+    })
+}
+
+/// Convert configuration file contents to command line options.
+///
+/// # Errors
+///
+/// Returns the one-based line number and a message for the first invalid line.
+pub fn parse_config(text: &str) -> Result<Vec<OsString>, (usize, String)> {
+    let mut options = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let option = parse_setting(line).map_err(|message| (index + 1, message))?;
+        options.push(OsString::from(option));
+    }
+
+    Ok(options)
+}
+
+/// Turn one `key = value` line into the matching option.
+fn parse_setting(line: &str) -> Result<String, String> {
+    if line.starts_with('[') {
+        return Err("Tables are not supported".into());
+    }
+    let Some((key, value)) = line.split_once('=') else {
+        return Err("Expected 'key = value'".into());
+    };
+    let key = key.trim();
+    let value = parse_value(value.trim())?;
+
+    match key {
+        "level" => match value {
+            Value::Integer(level @ 0..=9) => Ok(format!("-{level}")),
+            _ => Err("level: Expected an integer from 0 to 9".into()),
+        },
+        "threads" => match value {
+            Value::Integer(threads) => Ok(format!("--threads={threads}")),
+            Value::String(_) => Err("threads: Expected an integer".into()),
+        },
+        "memlimit" => {
+            let limit = match value {
+                Value::Integer(bytes) => bytes.to_string(),
+                Value::String(limit) => limit,
+            };
+            parse_memory_limit(&limit).map_err(|err| format!("memlimit: {err}"))?;
+            Ok(format!("--memory={limit}"))
+        }
+        "suffix" => match value {
+            Value::String(suffix) if !suffix.is_empty() => Ok(format!("--suffix={suffix}")),
+            _ => Err("suffix: Expected a non-empty string".into()),
+        },
+        other => Err(format!("{other}: Unknown setting")),
+    }
+}
+
+/// Value of a setting.
+#[derive(Debug, PartialEq, Eq)]
+enum Value {
+    Integer(u64),
+    String(String),
+}
+
+fn parse_value(raw: &str) -> Result<Value, String> {
+    if let Some(body) = raw.strip_prefix('"') {
+        let body = body.strip_suffix('"').ok_or("Unterminated string")?;
+        return unescape(body).map(Value::String);
+    }
+    if let Some(body) = raw.strip_prefix('\'') {
+        let body = body.strip_suffix('\'').ok_or("Unterminated string")?;
+        return Ok(Value::String(body.to_string()));
+    }
+
+    raw.replace('_', "")
+        .parse()
+        .map(Value::Integer)
+        .map_err(|_| format!("{raw}: Expected a string or a non-negative integer"))
+}
+
+/// Resolve the escapes of a TOML basic string that make sense in option values.
+fn unescape(body: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\\') => out.push('\\'),
+                Some('"') => out.push('"'),
+                Some(other) => return Err(format!("\\{other}: Unsupported escape sequence")),
+                None => return Err("Unterminated string".into()),
+            },
+            '"' => return Err("Unexpected text after string".into()),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// Remove a `#` comment, leaving `#` inside quoted strings alone.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), c) if c == open && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..index],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(text: &str) -> Result<Vec<String>, (usize, String)> {
+        parse_config(text).map(|options| {
+            options
+                .into_iter()
+                .map(|option| option.to_string_lossy().into_owned())
+                .collect()
+        })
+    }
+
+    /// Test that each setting becomes its option, with comments and blank lines skipped.
+    #[test]
+    fn settings_become_options() {
+        let text = "# defaults\n\
+                    level = 9\n\
+                    threads = 0   # all cores\n\
+                    \n\
+                    memlimit = \"50%\"\n\
+                    suffix = '.t#z'\n";
+        assert_eq!(
+            options(text).unwrap(),
+            ["-9", "--threads=0", "--memory=50%", "--suffix=.t#z"]
+        );
+        assert_eq!(
+            options("memlimit = 1_048_576").unwrap(),
+            ["--memory=1048576"]
+        );
+    }
+
+    /// Test that an invalid setting is reported with its line number.
+    #[test]
+    fn invalid_settings_report_the_line() {
+        assert_eq!(
+            options("level = 6\nlevel = 10"),
+            Err((2, "level: Expected an integer from 0 to 9".into()))
+        );
+        assert_eq!(
+            options("compress = 1"),
+            Err((1, "compress: Unknown setting".into()))
+        );
+        assert_eq!(options("[xz]"), Err((1, "Tables are not supported".into())));
+        assert_eq!(
+            options("suffix = \"txz"),
+            Err((1, "Unterminated string".into()))
+        );
+        assert!(options("memlimit = \"lots\"").is_err());
+    }
+
+    /// Test that a `#` inside a quoted string doesn't start a comment.
+    #[test]
+    fn comments_keep_quoted_hashes() {
+        assert_eq!(strip_comment("a = \"x#y\" # c"), "a = \"x#y\" ");
+        assert_eq!(strip_comment("a = \"x\\\"#\" # c"), "a = \"x\\\"#\" ");
+        assert_eq!(strip_comment("# only"), "");
+    }
+
+    /// Test that the usual location is skipped when it can't be reached, but a file named
+    /// by `XZ_RS_CONFIG` has to be readable.
+    #[test]
+    fn unreachable_default_location_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path().join("home");
+        fs::write(&home, "not a directory").unwrap();
+        let path = home.join("xz-rs").join("config.toml");
+
+        assert!(load_options_from(path.clone(), false).unwrap().is_empty());
+        assert!(matches!(
+            load_options_from(path, true),
+            Err(UserConfigError::Read { .. })
+        ));
+        assert!(load_options_from(dir.path().join("missing.toml"), true)
+            .unwrap()
+            .is_empty());
+    }
+}
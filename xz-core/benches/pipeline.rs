@@ -0,0 +1,213 @@
+use std::num::NonZeroUsize;
+
+use criterion::measurement::WallTime;
+use criterion::{
+    black_box, criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion, Throughput,
+};
+use xz_core::config::DecodeMode;
+use xz_core::options::{Compression, CompressionOptions, DecompressionOptions};
+use xz_core::pipeline::{compress, decompress};
+use xz_core::Threading;
+
+const KIB: usize = 1024;
+const MIB: usize = 1024 * KIB;
+
+const INPUT_SIZE: usize = 4 * MIB;
+const PRESETS: &[Compression] = &[
+    Compression::Level1,
+    Compression::Level6,
+    Compression::Level9,
+];
+const THREADS: &[u32] = &[1, 4];
+const BUFFER_SIZES: &[usize] = &[8 * KIB, 64 * KIB, MIB];
+const PROFILES: &[Profile] = &[Profile::Text, Profile::Binary, Profile::Incompressible];
+
+#[derive(Clone, Copy)]
+enum Profile {
+    Text,
+    Binary,
+    Incompressible,
+}
+
+impl Profile {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Binary => "binary",
+            Self::Incompressible => "incompressible",
+        }
+    }
+
+    fn build(self, size: usize) -> Vec<u8> {
+        match self {
+            Self::Text => build_text(size),
+            Self::Binary => build_binary(size),
+            Self::Incompressible => build_random_bytes(size, 0xA076_1D64_78BD_642F),
+        }
+    }
+}
+
+fn build_text(size: usize) -> Vec<u8> {
+    let pattern = b"The quick brown fox jumps over the lazy dog.\n";
+    pattern.iter().copied().cycle().take(size).collect()
+}
+
+/// Structured records with small, slowly changing integers, similar to executable or table data.
+fn build_binary(size: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(size);
+    let mut counter = 0u32;
+    while data.len() < size {
+        data.extend_from_slice(&counter.to_le_bytes());
+        data.extend_from_slice(&(counter.wrapping_mul(31) % 977).to_le_bytes());
+        counter = counter.wrapping_add(1);
+    }
+    data.truncate(size);
+    data
+}
+
+fn build_random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(len);
+    for _ in 0..len {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        data.push(seed.to_le_bytes()[3]);
+    }
+    data
+}
+
+fn bench_ids(profile: Profile, label: &str) -> (BenchmarkId, BenchmarkId) {
+    (
+        BenchmarkId::new(format!("compress/{}", profile.label()), label),
+        BenchmarkId::new(format!("decompress/{}", profile.label()), label),
+    )
+}
+
+fn preset_label(level: Compression) -> String {
+    format!("level-{}", level.to_preset())
+}
+
+fn compress_with(data: &[u8], options: &CompressionOptions) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len() / 2);
+    compress(data, &mut output, options).unwrap();
+    output
+}
+
+fn register_compress(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    id: BenchmarkId,
+    data: &[u8],
+    options: &CompressionOptions,
+) {
+    group.bench_with_input(id, data, |b, input| {
+        b.iter(|| compress_with(black_box(input), options));
+    });
+}
+
+fn register_decompress(
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    id: BenchmarkId,
+    compressed: &[u8],
+    options: &DecompressionOptions,
+) {
+    group.bench_with_input(id, compressed, |b, input| {
+        b.iter(|| {
+            let mut output = Vec::with_capacity(INPUT_SIZE);
+            decompress(black_box(input), &mut output, options).unwrap();
+            output
+        });
+    });
+}
+
+fn bench_presets(c: &mut Criterion) {
+    let mut group = c.benchmark_group("preset");
+    group.throughput(Throughput::Bytes(INPUT_SIZE as u64));
+
+    for &profile in PROFILES {
+        let data = profile.build(INPUT_SIZE);
+        for &level in PRESETS {
+            let options = CompressionOptions::default()
+                .with_level(level)
+                .with_threads(Threading::Exact(1));
+            let label = preset_label(level);
+            let (compress_id, decompress_id) = bench_ids(profile, &label);
+            register_compress(&mut group, compress_id, &data, &options);
+
+            let compressed = compress_with(&data, &options);
+            let options = DecompressionOptions::default().with_threads(Threading::Exact(1));
+            register_decompress(&mut group, decompress_id, &compressed, &options);
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_threads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("threads");
+    group.throughput(Throughput::Bytes(INPUT_SIZE as u64));
+
+    for &profile in PROFILES {
+        let data = profile.build(INPUT_SIZE);
+        for &threads in THREADS {
+            // A small block size gives the multi-threaded coders independent work units.
+            let options = CompressionOptions::default()
+                .with_level(Compression::Level6)
+                .with_threads(Threading::Exact(threads))
+                .with_block_size(std::num::NonZeroU64::new(MIB as u64));
+            let label = format!("threads-{threads}");
+            let (compress_id, decompress_id) = bench_ids(profile, &label);
+            register_compress(&mut group, compress_id, &data, &options);
+
+            let compressed = compress_with(&data, &options);
+            let options = DecompressionOptions::default()
+                .with_mode(DecodeMode::Xz)
+                .with_threads(Threading::Exact(threads));
+            register_decompress(&mut group, decompress_id, &compressed, &options);
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_buffer_sizes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("buffer");
+    group.throughput(Throughput::Bytes(INPUT_SIZE as u64));
+
+    for &profile in PROFILES {
+        let data = profile.build(INPUT_SIZE);
+        let compressed = compress_with(
+            &data,
+            &CompressionOptions::default()
+                .with_level(Compression::Level1)
+                .with_threads(Threading::Exact(1)),
+        );
+
+        for &size in BUFFER_SIZES {
+            let buffer = NonZeroUsize::new(size).unwrap();
+            let label = format!("{}KiB", size / KIB);
+            let (compress_id, decompress_id) = bench_ids(profile, &label);
+
+            let options = CompressionOptions::default()
+                .with_level(Compression::Level1)
+                .with_threads(Threading::Exact(1))
+                .with_input_buffer_size(buffer)
+                .with_output_buffer_size(buffer);
+            register_compress(&mut group, compress_id, &data, &options);
+
+            let options = DecompressionOptions::default()
+                .with_threads(Threading::Exact(1))
+                .with_input_buffer_size(buffer)
+                .with_output_buffer_size(buffer);
+            register_decompress(&mut group, decompress_id, &compressed, &options);
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group! {
+    name = pipeline_benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_presets, bench_threads, bench_buffer_sizes
+}
+criterion_main!(pipeline_benches);
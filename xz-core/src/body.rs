@@ -0,0 +1,351 @@
+//! `AsyncRead` adapters that compress or decompress HTTP bodies as `.xz`.
+//!
+//! [`BodyEncoder`] compresses and [`BodyDecoder`] decompresses any [`AsyncRead`] body on
+//! the fly without buffering whole payloads. Both are [`AsyncRead`]s themselves and also
+//! hand out [`Bytes`] chunks through an inherent `poll_chunk`.
+//!
+//! This module doesn't implement `http_body::Body` or `futures::Stream`, and the crate
+//! doesn't depend on `http-body` or `futures-core`, so neither adapter can be handed to
+//! hyper or axum directly. Callers bridge them with `tokio-util`: `ReaderStream` turns
+//! either adapter into the `Stream` of `Bytes` that `axum::body::Body::from_stream`
+//! takes, and `StreamReader` turns an incoming body stream into the [`AsyncRead`] they
+//! wrap. Other HTTP libraries can wrap `poll_chunk` in their own body type instead.
+//!
+//! ```rust,ignore
+//! use tokio_util::io::{ReaderStream, StreamReader};
+//! use xz_core::body::{BodyDecoder, BodyEncoder, CONTENT_ENCODING};
+//!
+//! // Serving: compress a file as the response body.
+//! let file = tokio::fs::File::open("report.csv").await?;
+//! let body = Body::from_stream(ReaderStream::new(BodyEncoder::new(file, options)));
+//! let response = Response::builder()
+//!     .header(header::CONTENT_ENCODING, CONTENT_ENCODING)
+//!     .body(body)?;
+//!
+//! // Ingesting: decompress a request body as it arrives.
+//! let stream = request.into_body().into_data_stream().map_err(io::Error::other);
+//! let mut decoded = BodyDecoder::new(StreamReader::new(stream), DecompressionOptions::default());
+//! tokio::io::copy(&mut decoded, &mut destination).await?;
+//! ```
+//!
+//! The coding runs in the task that polls the adapter, through the same pipelines as
+//! [`compress_async`] and [`decompress_async`], so the options, format detection and
+//! [metrics](crate::metrics) behave the same. An error is reported after the data that
+//! was decoded before it, like [`decompress_async`] writes that data before returning.
+
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use tokio::io::{AsyncRead, DuplexStream, ReadBuf};
+
+use crate::config::{DecompressionOutcome, StreamSummary};
+use crate::error::{Error, Result};
+use crate::options::{CompressionOptions, DecompressionOptions};
+use crate::pipeline::{compress_async, decompress_async};
+
+/// `Content-Encoding` and `Accept-Encoding` token of `.xz` data
+pub const CONTENT_ENCODING: &str = "xz";
+
+/// Capacity of the pipe between the pipeline and the reader, and the largest chunk
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Pipeline future owned by an adapter
+type Pipeline<T> = Pin<Box<dyn Future<Output = Result<T>> + Send>>;
+
+/// A pipeline writing into a pipe that the adapter reads from.
+struct Pipe<T> {
+    pipeline: Option<Pipeline<T>>,
+    output: DuplexStream,
+    /// What the pipeline returned once it succeeded
+    result: Option<T>,
+    /// What the pipeline returned once it failed, until it's reported
+    error: Option<Error>,
+}
+
+impl<T> Pipe<T> {
+    fn new<F>(start: impl FnOnce(DuplexStream) -> F) -> Self
+    where
+        F: Future<Output = Result<T>> + Send + 'static,
+    {
+        let (input, output) = tokio::io::duplex(CHUNK_SIZE);
+        Self {
+            pipeline: Some(Box::pin(start(input))),
+            output,
+            result: None,
+            error: None,
+        }
+    }
+
+    /// Advances the pipeline, then reads what it wrote so far.
+    ///
+    /// The pipeline drops its end of the pipe when it returns, so the read ends once
+    /// everything it wrote was read. Its error, if any, is returned in place of that end.
+    fn poll_read(&mut self, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        if let Some(pipeline) = self.pipeline.as_mut() {
+            if let Poll::Ready(result) = pipeline.as_mut().poll(cx) {
+                self.pipeline = None;
+                match result {
+                    Ok(result) => self.result = Some(result),
+                    Err(err) => self.error = Some(err),
+                }
+            }
+        }
+
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut self.output).poll_read(cx, buf))?;
+        if buf.filled().len() == filled && buf.remaining() > 0 {
+            if let Some(err) = self.error.take() {
+                return Poll::Ready(Err(err.into()));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Reads the next chunk of at most [`CHUNK_SIZE`] bytes, or `None` at the end.
+    fn poll_chunk(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<Bytes>>> {
+        let mut chunk = BytesMut::zeroed(CHUNK_SIZE);
+        let mut buf = ReadBuf::new(&mut chunk);
+        if let Err(err) = ready!(self.poll_read(cx, &mut buf)) {
+            return Poll::Ready(Some(Err(err)));
+        }
+        let len = buf.filled().len();
+        if len == 0 {
+            return Poll::Ready(None);
+        }
+        chunk.truncate(len);
+        Poll::Ready(Some(Ok(chunk.freeze())))
+    }
+
+    /// What the pipeline returned, once it succeeded
+    fn result(&self) -> Option<&T> {
+        self.result.as_ref()
+    }
+}
+
+/// A body compressed to `.xz` while it's read
+pub struct BodyEncoder {
+    pipe: Pipe<StreamSummary>,
+}
+
+impl BodyEncoder {
+    /// Compresses `body` with `options`.
+    ///
+    /// # Parameters
+    ///
+    /// * `body` - Uncompressed body
+    /// * `options` - Compression configuration options [`CompressionOptions`]
+    pub fn new<R>(body: R, options: CompressionOptions) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        Self {
+            pipe: Pipe::new(|output| async move { compress_async(body, output, &options).await }),
+        }
+    }
+
+    /// Returns the next compressed chunk, or `None` once the body is complete.
+    ///
+    /// It has the shape of `Stream::poll_next` for callers wrapping the encoder in the
+    /// body type of an HTTP library; the trait itself isn't implemented.
+    ///
+    /// # Errors
+    ///
+    /// Yields an error if reading the body or compressing it fails.
+    pub fn poll_chunk(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<io::Result<Bytes>>> {
+        self.pipe.poll_chunk(cx)
+    }
+
+    /// Returns the bytes read and written once compression has finished successfully.
+    pub fn summary(&self) -> Option<StreamSummary> {
+        self.pipe.result().copied()
+    }
+}
+
+impl AsyncRead for BodyEncoder {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.pipe.poll_read(cx, buf)
+    }
+}
+
+impl fmt::Debug for BodyEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyEncoder")
+            .field("finished", &self.pipe.pipeline.is_none())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A `.xz` body decompressed while it's read
+pub struct BodyDecoder {
+    pipe: Pipe<DecompressionOutcome>,
+}
+
+impl BodyDecoder {
+    /// Decompresses `body` with `options`.
+    ///
+    /// # Parameters
+    ///
+    /// * `body` - Compressed body
+    /// * `options` - Decompression configuration options [`DecompressionOptions`]; set a
+    ///   memory limit that suits untrusted input
+    pub fn new<R>(body: R, options: DecompressionOptions) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        Self {
+            pipe: Pipe::new(|output| async move { decompress_async(body, output, &options).await }),
+        }
+    }
+
+    /// Returns the next decompressed chunk, or `None` once the body is complete.
+    ///
+    /// It has the shape of `Stream::poll_next` for callers wrapping the decoder in the
+    /// body type of an HTTP library; the trait itself isn't implemented.
+    ///
+    /// # Errors
+    ///
+    /// Yields an error if reading the body fails or it isn't valid compressed data.
+    pub fn poll_chunk(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<io::Result<Bytes>>> {
+        self.pipe.poll_chunk(cx)
+    }
+
+    /// Returns the outcome of decompression once it has finished successfully.
+    pub fn outcome(&self) -> Option<&DecompressionOutcome> {
+        self.pipe.result()
+    }
+}
+
+impl AsyncRead for BodyDecoder {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.pipe.poll_read(cx, buf)
+    }
+}
+
+impl fmt::Debug for BodyDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyDecoder")
+            .field("finished", &self.pipe.pipeline.is_none())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    use tokio::io::AsyncReadExt;
+
+    use crate::options::{CompressionOptions, DecompressionOptions};
+    use crate::pipeline::{compress, decompress_to_vec};
+
+    use super::{BodyDecoder, BodyEncoder, CHUNK_SIZE};
+
+    /// Input larger than the pipe, so the pipeline has to wait for the reader
+    fn sample() -> Vec<u8> {
+        (0..4 * CHUNK_SIZE as u32)
+            .flat_map(|i| (i / 7).to_le_bytes())
+            .collect()
+    }
+
+    /// The encoder's output is a regular `.xz` stream of the body.
+    #[tokio::test]
+    async fn encoder_compresses_body() {
+        let data = sample();
+        let mut encoder = BodyEncoder::new(
+            std::io::Cursor::new(data.clone()),
+            CompressionOptions::default(),
+        );
+        let mut compressed = Vec::new();
+        encoder.read_to_end(&mut compressed).await.unwrap();
+
+        let summary = encoder.summary().unwrap();
+        assert_eq!(summary.bytes_read, data.len() as u64);
+        assert_eq!(summary.bytes_written, compressed.len() as u64);
+        let (decompressed, _) =
+            decompress_to_vec(&compressed, &DecompressionOptions::default()).unwrap();
+        assert!(decompressed == data);
+    }
+
+    /// Chunks are at most the pipe size and concatenate to the decompressed body.
+    #[tokio::test]
+    async fn decoder_yields_chunks() {
+        let data = sample();
+        let mut compressed = Vec::new();
+        compress(
+            data.as_slice(),
+            &mut compressed,
+            &CompressionOptions::default(),
+        )
+        .unwrap();
+
+        let mut decoder = BodyDecoder::new(
+            std::io::Cursor::new(compressed),
+            DecompressionOptions::default(),
+        );
+        let mut decompressed = Vec::new();
+        while let Some(chunk) = poll_fn(|cx| Pin::new(&mut decoder).poll_chunk(cx)).await {
+            let chunk = chunk.unwrap();
+            assert!(!chunk.is_empty() && chunk.len() <= CHUNK_SIZE);
+            decompressed.extend_from_slice(&chunk);
+        }
+        assert!(decompressed == data);
+        assert_eq!(decoder.outcome().unwrap().bytes_written, data.len() as u64);
+    }
+
+    /// A corrupt body ends with an error instead of a clean end of data.
+    #[tokio::test]
+    async fn decoder_reports_corruption() {
+        let data = sample();
+        let mut compressed = Vec::new();
+        compress(
+            data.as_slice(),
+            &mut compressed,
+            &CompressionOptions::default(),
+        )
+        .unwrap();
+        compressed.truncate(compressed.len() / 2);
+
+        let mut decoder = BodyDecoder::new(
+            std::io::Cursor::new(compressed),
+            DecompressionOptions::default(),
+        );
+        let mut decompressed = Vec::new();
+        let err = decoder.read_to_end(&mut decompressed).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(decoder.outcome().is_none());
+        assert!(data.starts_with(&decompressed));
+    }
+
+    /// The encoder and decoder chain like any other readers.
+    #[tokio::test]
+    async fn round_trip_through_both() {
+        let data = sample();
+        let encoder = BodyEncoder::new(
+            std::io::Cursor::new(data.clone()),
+            CompressionOptions::default(),
+        );
+        let mut decoder = BodyDecoder::new(encoder, DecompressionOptions::default());
+        let mut decompressed = Vec::new();
+        decoder.read_to_end(&mut decompressed).await.unwrap();
+        assert!(decompressed == data);
+    }
+}
@@ -35,6 +35,15 @@ pub enum DecodeMode {
     /// **Use case**: Legacy LZMA stream compatibility
     Lzma,
 
+    /// Process `.lz` (lzip) streams exclusively.
+    ///
+    /// lzip is a foreign format: it can be decoded but not produced.
+    ///
+    /// **Threading**: Single-threaded only
+    /// **Formats**: lzip (.lz) only
+    /// **Use case**: Decoding `.lz` files without auto-detection
+    Lzip,
+
     /// Process raw LZMA1 filter streams with explicitly supplied filter options.
     ///
     /// This mode has no container metadata, so the caller must configure the filter chain
@@ -51,6 +60,26 @@ pub enum UnknownInputPolicy {
     Passthrough,
 }
 
+/// Policy controlling how a mismatch against the expected decompressed size is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeMismatchPolicy {
+    /// Record the mismatch in the [`DecompressionOutcome`] and keep the decoded data.
+    #[default]
+    Warn,
+    /// Fail the operation with [`crate::Error::SizeMismatch`].
+    Error,
+}
+
+/// Difference between the caller-supplied expected size and the real decompressed size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeMismatch {
+    /// Size announced through `DecompressionOptions::with_expected_size`.
+    pub expected: u64,
+
+    /// Size reported by the stream Index or produced by the decoder.
+    pub actual: u64,
+}
+
 /// High-level result status for a decompression operation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DecompressionStatus {
@@ -152,6 +181,9 @@ pub struct DecompressionOutcome {
 
     /// Integrity check ID from the XZ stream header when it isn't supported by liblzma.
     pub unsupported_check_id: Option<u32>,
+
+    /// Mismatch against the expected decompressed size, if one was configured and detected.
+    pub size_mismatch: Option<SizeMismatch>,
 }
 
 impl DecompressionOutcome {
@@ -166,6 +198,7 @@ impl DecompressionOutcome {
             bytes_written: summary.bytes_written,
             status,
             unsupported_check_id,
+            size_mismatch: None,
         }
     }
 
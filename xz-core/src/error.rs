@@ -1,6 +1,7 @@
 //! Error types and result handling for XZ compression and decompression operations.
 
 use std::fmt;
+use std::io;
 
 use crate::config::DecodeMode;
 
@@ -9,6 +10,43 @@ pub use lzma_safe::Error as BackendError;
 /// Result alias using the crate-level [`Error`] type.
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Coarse classification of [`Error`] values.
+///
+/// Categories let callers react to a failure (retry, report corruption, raise a limit)
+/// without matching on every variant or on backend-specific codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// Reading input or writing output failed.
+    Io,
+    /// The input is corrupt, truncated, or not in a recognised format.
+    Corrupt,
+    /// A memory usage limit was reached or an allocation failed.
+    Limit,
+    /// The supplied options or the requested configuration are invalid or unsupported.
+    Config,
+    /// The operation was interrupted before it could complete.
+    Cancelled,
+    /// The liblzma backend failed or cannot be trusted.
+    Backend,
+}
+
+impl ErrorCategory {
+    /// Returns the [`io::ErrorKind`] that best describes this category.
+    ///
+    /// # Returns
+    ///
+    /// The error kind used when an [`Error`] of this category is converted into [`io::Error`].
+    pub const fn io_kind(self) -> io::ErrorKind {
+        match self {
+            ErrorCategory::Io | ErrorCategory::Backend => io::ErrorKind::Other,
+            ErrorCategory::Corrupt => io::ErrorKind::InvalidData,
+            ErrorCategory::Limit => io::ErrorKind::OutOfMemory,
+            ErrorCategory::Config => io::ErrorKind::InvalidInput,
+            ErrorCategory::Cancelled => io::ErrorKind::Interrupted,
+        }
+    }
+}
+
 /// Comprehensive error type covering all failure modes in XZ operations.
 #[derive(Debug)]
 pub enum Error {
@@ -18,11 +56,11 @@ pub enum Error {
     /// I/O failure while reading input or writing output.
     Io(std::io::Error),
 
-    /// The requested thread count exceeds the safe limit for the host.
+    /// The requested thread count exceeds the processor threads of the host.
     InvalidThreadCount {
         /// Number of threads requested by the user
         requested: u32,
-        /// Maximum safe thread count for the current system
+        /// Number of processor threads available on the current system
         maximum: u32,
     },
 
@@ -48,6 +86,61 @@ pub enum Error {
         /// Size in bytes of the buffer that failed to allocate
         capacity: usize,
     },
+
+    /// The encoder needs more memory than the configured limit, even single-threaded.
+    MemoryLimitTooLow {
+        /// Memory needed by the encoder in bytes
+        required: u64,
+        /// Configured memory usage limit in bytes
+        limit: u64,
+    },
+
+    /// The decompressed size differs from the size announced by the caller.
+    SizeMismatch {
+        /// Size announced through the expected-size hint
+        expected: u64,
+        /// Size reported by the stream Index or produced by the decoder
+        actual: u64,
+    },
+}
+
+impl Error {
+    /// Classifies this error into a coarse [`ErrorCategory`].
+    ///
+    /// # Returns
+    ///
+    /// The category describing the failure; I/O errors of kind
+    /// [`io::ErrorKind::Interrupted`] are reported as [`ErrorCategory::Cancelled`].
+    pub fn kind(&self) -> ErrorCategory {
+        match self {
+            Error::Backend(err) => backend_category(*err),
+            Error::Io(err) if err.kind() == io::ErrorKind::Interrupted => ErrorCategory::Cancelled,
+            Error::Io(_) => ErrorCategory::Io,
+            Error::InvalidThreadCount { .. }
+            | Error::ThreadingUnsupported { .. }
+            | Error::InvalidOption(_) => ErrorCategory::Config,
+            Error::CompromisedBackend { .. } => ErrorCategory::Backend,
+            Error::AllocationFailed { .. } | Error::MemoryLimitTooLow { .. } => {
+                ErrorCategory::Limit
+            }
+            Error::SizeMismatch { .. } => ErrorCategory::Corrupt,
+        }
+    }
+}
+
+/// Maps a liblzma error code onto its [`ErrorCategory`].
+fn backend_category(err: BackendError) -> ErrorCategory {
+    match err {
+        BackendError::DataError | BackendError::FormatError | BackendError::BufError => {
+            ErrorCategory::Corrupt
+        }
+        BackendError::MemError | BackendError::MemLimitError => ErrorCategory::Limit,
+        BackendError::OptionsError | BackendError::UnsupportedCheck => ErrorCategory::Config,
+        BackendError::StreamEnd
+        | BackendError::ProgError
+        | BackendError::SeekNeeded
+        | BackendError::Unknown(_) => ErrorCategory::Backend,
+    }
 }
 
 impl fmt::Display for Error {
@@ -57,7 +150,7 @@ impl fmt::Display for Error {
             Error::Io(err) => write!(f, "I/O error: {err}"),
             Error::InvalidThreadCount { requested, maximum } => write!(
                 f,
-                "requested {requested} threads exceeds safe limit of {maximum}",
+                "requested {requested} threads exceeds the {maximum} available processor threads",
             ),
             Error::ThreadingUnsupported { requested, mode } => write!(
                 f,
@@ -71,6 +164,14 @@ impl fmt::Display for Error {
             Error::AllocationFailed { capacity } => {
                 write!(f, "unable to allocate temporary buffer of {capacity} bytes")
             }
+            Error::MemoryLimitTooLow { required, limit } => write!(
+                f,
+                "encoder needs {required} bytes of memory but the limit is {limit} bytes",
+            ),
+            Error::SizeMismatch { expected, actual } => write!(
+                f,
+                "decompressed size {actual} does not match expected size {expected}",
+            ),
         }
     }
 }
@@ -96,3 +197,78 @@ impl From<std::io::Error> for Error {
         Error::Io(err)
     }
 }
+
+impl From<Error> for io::Error {
+    /// Converts into an [`io::Error`] without losing information.
+    ///
+    /// Wrapped I/O errors are returned unchanged. Every other error becomes the payload of a
+    /// new [`io::Error`] whose kind is derived from [`Error::kind`], so the original value can
+    /// be recovered with [`io::Error::into_inner`] and downcasting.
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err,
+            other => io::Error::new(other.kind().io_kind(), other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that backend codes are classified into the expected categories.
+    #[test]
+    fn backend_errors_are_categorised() {
+        let cases = [
+            (BackendError::DataError, ErrorCategory::Corrupt),
+            (BackendError::FormatError, ErrorCategory::Corrupt),
+            (BackendError::BufError, ErrorCategory::Corrupt),
+            (BackendError::MemLimitError, ErrorCategory::Limit),
+            (BackendError::MemError, ErrorCategory::Limit),
+            (BackendError::OptionsError, ErrorCategory::Config),
+            (BackendError::UnsupportedCheck, ErrorCategory::Config),
+            (BackendError::ProgError, ErrorCategory::Backend),
+        ];
+
+        for (backend, expected) in cases {
+            assert_eq!(Error::from(backend).kind(), expected, "{backend:?}");
+        }
+    }
+
+    /// Test that crate-level variants and interrupted I/O map to their categories.
+    #[test]
+    fn crate_errors_are_categorised() {
+        let interrupted = io::Error::from(io::ErrorKind::Interrupted);
+        assert_eq!(Error::Io(interrupted).kind(), ErrorCategory::Cancelled);
+
+        let broken = io::Error::from(io::ErrorKind::BrokenPipe);
+        assert_eq!(Error::Io(broken).kind(), ErrorCategory::Io);
+
+        let invalid = Error::InvalidOption("bad".into());
+        assert_eq!(invalid.kind(), ErrorCategory::Config);
+
+        let alloc = Error::AllocationFailed { capacity: 1 };
+        assert_eq!(alloc.kind(), ErrorCategory::Limit);
+    }
+
+    /// Test that converting into `io::Error` keeps I/O errors intact and wraps the rest.
+    #[test]
+    fn conversion_into_io_error_is_lossless() {
+        let original = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        let converted = io::Error::from(Error::Io(original));
+        assert_eq!(converted.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(converted.to_string(), "denied");
+
+        let converted = io::Error::from(Error::from(BackendError::DataError));
+        assert_eq!(converted.kind(), io::ErrorKind::InvalidData);
+
+        let inner = converted
+            .into_inner()
+            .and_then(|inner| inner.downcast::<Error>().ok())
+            .map(|inner| *inner);
+        assert!(matches!(
+            inner,
+            Some(Error::Backend(BackendError::DataError))
+        ));
+    }
+}
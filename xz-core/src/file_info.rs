@@ -3,10 +3,14 @@
 use std::io::{Read, Seek, SeekFrom};
 use std::num::NonZeroU64;
 
-use lzma_safe::stream::StreamFlags;
-use lzma_safe::{BlockInfo as LzmaBlockInfo, Index, IndexEntry, StreamInfo as LzmaStreamInfo};
+use lzma_safe::encoder::options::IntegrityCheck;
+use lzma_safe::stream::{StreamFlags, BLOCK_HEADER_SIZE_MAX};
+use lzma_safe::{
+    BlockDecoder, BlockHeader, BlockInfo as LzmaBlockInfo, Index, IndexEntry,
+    StreamInfo as LzmaStreamInfo,
+};
 
-use crate::{Error, Result};
+use crate::{BackendError, Error, Result};
 
 /// Size of an XZ Stream Header/Footer in bytes.
 const STREAM_HEADER_SIZE: usize = lzma_safe::stream::HEADER_SIZE;
@@ -103,6 +107,8 @@ pub struct StreamInfo {
     pub uncompressed_size: u64,
     /// Padding size following the stream.
     pub padding: u64,
+    /// Integrity check of the stream, if known.
+    pub check: Option<IntegrityCheck>,
 }
 
 impl StreamInfo {
@@ -115,6 +121,7 @@ impl StreamInfo {
             compressed_size: info.compressed_size,
             uncompressed_size: info.uncompressed_size,
             padding: info.padding,
+            check: info.flags.map(|flags| flags.check),
         }
     }
 }
@@ -152,6 +159,86 @@ impl BlockInfo {
     }
 }
 
+/// Details of a Block read from its Block Header and check field.
+#[derive(Debug, Clone)]
+pub struct BlockDetails {
+    /// Integrity check of the containing stream.
+    pub check: IntegrityCheck,
+    /// Stored check value, as it appears in the file.
+    pub check_value: Vec<u8>,
+    /// Decoded Block Header.
+    pub header: BlockHeader,
+    /// Size of the Compressed Data field.
+    pub compressed_size: u64,
+}
+
+/// Read the Block Header and check value of every Block in the file.
+///
+/// # Parameters
+///
+/// * `reader` - A readable and seekable input containing the XZ file
+/// * `info` - Metadata previously extracted with [`extract_file_info`]
+///
+/// # Returns
+///
+/// Returns one [`BlockDetails`] per Block, in file order.
+///
+/// # Errors
+///
+/// Returns an error if:
+///
+/// - Reading or seeking fails
+/// - A Block Header is corrupt or uses unsupported options
+/// - A stream's integrity check type is unknown
+pub fn read_block_details<R: Read + Seek>(
+    reader: &mut R,
+    info: &FileInfo,
+) -> Result<Vec<BlockDetails>> {
+    let streams = info.streams();
+    let mut details = Vec::new();
+
+    for block in info.blocks() {
+        let check = streams
+            .iter()
+            .find(|stream| {
+                block.compressed_file_offset >= stream.compressed_offset
+                    && block.compressed_file_offset
+                        < stream.compressed_offset + stream.compressed_size
+            })
+            .and_then(|stream| stream.check)
+            .ok_or(Error::Backend(BackendError::UnsupportedCheck))?;
+
+        let mut header = [0u8; BLOCK_HEADER_SIZE_MAX];
+        read_exact_at(reader, block.compressed_file_offset, &mut header[..1])?;
+        let header_size =
+            BlockDecoder::header_size(header[0]).ok_or(Error::Backend(BackendError::DataError))?;
+        reader.read_exact(&mut header[1..header_size])?;
+        let header = BlockHeader::decode(&header[..header_size], check)?;
+
+        let check_size = check.size() as u64;
+        let mut check_value = vec![0u8; check.size()];
+        read_exact_at(
+            reader,
+            block.compressed_file_offset + block.total_size - check_size,
+            &mut check_value,
+        )?;
+
+        // A damaged Index may list a Block smaller than its own header and check.
+        let compressed_size = block
+            .unpadded_size
+            .checked_sub(u64::from(header.header_size) + check_size)
+            .ok_or(Error::Backend(BackendError::DataError))?;
+        details.push(BlockDetails {
+            check,
+            check_value,
+            compressed_size,
+            header,
+        });
+    }
+
+    Ok(details)
+}
+
 /// Read exactly `buf.len()` bytes at an absolute file offset.
 fn read_exact_at<R: Read + Seek>(reader: &mut R, offset: u64, buf: &mut [u8]) -> Result<()> {
     reader.seek(SeekFrom::Start(offset))?;
@@ -661,4 +748,153 @@ mod tests {
             assert!(ratio > 0.0);
         }
     }
+
+    /// Test reading Block Headers and check values for every block.
+    #[test]
+    fn test_read_block_details() {
+        use std::num::NonZeroU64;
+
+        use crate::options::CompressionOptions;
+        use crate::pipeline::compress;
+
+        let test_data = b"Block details ".repeat(10_000);
+        let options = CompressionOptions::default()
+            .with_block_size(NonZeroU64::new(32 * 1024))
+            .with_check(IntegrityCheck::Crc32);
+        let mut compressed = Vec::new();
+        compress(&test_data[..], &mut compressed, &options).unwrap();
+
+        let mut cursor = Cursor::new(compressed);
+        let info = extract_file_info(&mut cursor, None).unwrap();
+        assert_eq!(info.streams()[0].check, Some(IntegrityCheck::Crc32));
+
+        let details = read_block_details(&mut cursor, &info).unwrap();
+        assert_eq!(details.len() as u64, info.block_count());
+        for (detail, block) in details.iter().zip(info.blocks()) {
+            assert_eq!(detail.check, IntegrityCheck::Crc32);
+            assert_eq!(detail.check_value.len(), 4);
+            assert_eq!(detail.header.filter_ids, vec![0x21]);
+            assert_eq!(
+                detail.compressed_size + u64::from(detail.header.header_size) + 4,
+                block.unpadded_size
+            );
+            if let Some(size) = detail.header.compressed_size {
+                assert_eq!(size, detail.compressed_size);
+            }
+        }
+    }
+
+    /// Seekable stand-in for a huge file that is all zeros except for a few ranges.
+    struct SimulatedFile {
+        len: u64,
+        pos: u64,
+        ranges: Vec<(u64, Vec<u8>)>,
+    }
+
+    impl Read for SimulatedFile {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let available = self.len.saturating_sub(self.pos);
+            let n =
+                usize::try_from(available).map_or(buf.len(), |available| available.min(buf.len()));
+            buf[..n].fill(0);
+            for (start, data) in &self.ranges {
+                let end = start + data.len() as u64;
+                let (from, to) = (self.pos.max(*start), (self.pos + n as u64).min(end));
+                if from < to {
+                    let dst = usize::try_from(from - self.pos).unwrap();
+                    let src = usize::try_from(from - start).unwrap();
+                    let len = usize::try_from(to - from).unwrap();
+                    buf[dst..dst + len].copy_from_slice(&data[src..src + len]);
+                }
+            }
+            self.pos += n as u64;
+            Ok(n)
+        }
+    }
+
+    impl Seek for SimulatedFile {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.pos = match pos {
+                SeekFrom::Start(offset) => offset,
+                SeekFrom::End(offset) => self.len.checked_add_signed(offset).unwrap(),
+                SeekFrom::Current(offset) => self.pos.checked_add_signed(offset).unwrap(),
+            };
+            Ok(self.pos)
+        }
+    }
+
+    /// Bitwise CRC32 as used by the `.xz` container.
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &byte in data {
+            crc ^= u32::from(byte);
+            for _ in 0..8 {
+                crc = (crc >> 1) ^ (0xEDB8_8320 & 0u32.wrapping_sub(crc & 1));
+            }
+        }
+        !crc
+    }
+
+    fn encode_vli(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    /// Build a `.xz` file whose only Block is `block_size` bytes of (unread) zeros that
+    /// the Index claims decompress to `uncompressed_size` bytes.
+    fn simulated_huge_file(block_size: u64, uncompressed_size: u64) -> SimulatedFile {
+        let flags = [0u8, 0];
+        let mut header = vec![0xFD, b'7', b'z', b'X', b'Z', 0];
+        header.extend_from_slice(&flags);
+        header.extend_from_slice(&crc32(&flags).to_le_bytes());
+
+        let mut index = vec![0u8];
+        encode_vli(1, &mut index);
+        encode_vli(block_size, &mut index);
+        encode_vli(uncompressed_size, &mut index);
+        while index.len() % 4 != 0 {
+            index.push(0);
+        }
+        index.extend_from_slice(&crc32(&index).to_le_bytes());
+
+        let backward_size = u32::try_from(index.len() / 4 - 1).unwrap();
+        let mut footer_fields = backward_size.to_le_bytes().to_vec();
+        footer_fields.extend_from_slice(&flags);
+        let mut footer = crc32(&footer_fields).to_le_bytes().to_vec();
+        footer.extend_from_slice(&footer_fields);
+        footer.extend_from_slice(b"YZ");
+
+        let index_start = STREAM_HEADER_SIZE_U64 + block_size.next_multiple_of(4);
+        let footer_start = index_start + index.len() as u64;
+        SimulatedFile {
+            len: footer_start + footer.len() as u64,
+            pos: 0,
+            ranges: vec![(0, header), (index_start, index), (footer_start, footer)],
+        }
+    }
+
+    /// Test sizes and offsets beyond 4 GiB are reported without truncation.
+    #[test]
+    fn test_extract_file_info_beyond_4_gib() {
+        const BLOCK_SIZE: u64 = (5 << 30) + 3;
+        const UNCOMPRESSED_SIZE: u64 = 17 << 30;
+
+        let mut file = simulated_huge_file(BLOCK_SIZE, UNCOMPRESSED_SIZE);
+        let info = extract_file_info(&mut file, None).unwrap();
+
+        assert_eq!(info.uncompressed_size(), UNCOMPRESSED_SIZE);
+        assert_eq!(info.file_size(), file.len);
+        assert!(info.file_size() > BLOCK_SIZE);
+        let blocks = info.blocks();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].unpadded_size, BLOCK_SIZE);
+        assert_eq!(blocks[0].uncompressed_size, UNCOMPRESSED_SIZE);
+        assert_eq!(info.streams()[0].compressed_size, file.len);
+
+        // The zero Block Header is rejected as corrupt instead of misread.
+        assert!(read_block_details(&mut file, &info).is_err());
+    }
 }
@@ -2,6 +2,8 @@
 
 use std::io;
 
+use crate::config::DecodeMode;
+
 /// Size of the legacy `LZMA_Alone` header in bytes.
 pub const LZMA_ALONE_HEADER_SIZE: usize = lzma_safe::LZMA_ALONE_HEADER_SIZE;
 
@@ -59,6 +61,19 @@ pub fn is_known_decode_format(prefix: &[u8]) -> bool {
         || is_lzma_alone_header(prefix)
 }
 
+/// Returns `true` when the probe prefix looks like input that `mode` can decode.
+///
+/// Raw streams have no header, so any prefix is accepted in [`DecodeMode::Raw`].
+pub fn is_decode_format(prefix: &[u8], mode: DecodeMode) -> bool {
+    match mode {
+        DecodeMode::Auto => is_known_decode_format(prefix),
+        DecodeMode::Xz => prefix.starts_with(&XZ_STREAM_HEADER_MAGIC),
+        DecodeMode::Lzma => is_lzma_alone_header(prefix),
+        DecodeMode::Lzip => prefix.starts_with(&LZIP_HEADER_MAGIC),
+        DecodeMode::Raw => true,
+    }
+}
+
 /// Returns `true` when the probe prefix looks like a legacy `.lzma` header.
 fn is_lzma_alone_header(prefix: &[u8]) -> bool {
     if prefix.len() < LZMA_ALONE_HEADER_SIZE {
@@ -103,8 +118,8 @@ fn is_picky_lzma_dict_size(dict_size: u32) -> bool {
 #[cfg(test)]
 mod tests {
     use super::{
-        is_known_decode_format, read_decode_format_probe_prefix, LZIP_HEADER_MAGIC,
-        LZMA_ALONE_HEADER_SIZE, XZ_STREAM_HEADER_MAGIC,
+        is_decode_format, is_known_decode_format, read_decode_format_probe_prefix, DecodeMode,
+        LZIP_HEADER_MAGIC, LZMA_ALONE_HEADER_SIZE, XZ_STREAM_HEADER_MAGIC,
     };
 
     /// Detect `.xz` input from the stream header magic.
@@ -141,6 +156,19 @@ mod tests {
         assert!(!is_known_decode_format(b"foo"));
     }
 
+    /// Only the format selected by the decode mode is accepted.
+    #[test]
+    fn decode_format_follows_mode() {
+        let mut prefix = Vec::from(XZ_STREAM_HEADER_MAGIC);
+        prefix.resize(LZMA_ALONE_HEADER_SIZE, 0);
+
+        assert!(is_decode_format(&prefix, DecodeMode::Auto));
+        assert!(is_decode_format(&prefix, DecodeMode::Xz));
+        assert!(!is_decode_format(&prefix, DecodeMode::Lzma));
+        assert!(!is_decode_format(&prefix, DecodeMode::Lzip));
+        assert!(is_decode_format(b"foo", DecodeMode::Raw));
+    }
+
     /// Read at most the auto-detect probe size from the input.
     #[test]
     fn reads_decode_probe_prefix_without_requiring_eof() {
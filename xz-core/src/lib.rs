@@ -153,19 +153,26 @@ mod error;
 mod header;
 mod threading;
 
+#[cfg(feature = "body")]
+pub mod body;
 pub mod config;
 pub mod file_info;
+pub mod metrics;
 pub mod options;
 pub mod pipeline;
+pub mod sparse;
 
-pub use crate::error::{BackendError, Error, Result};
+pub use crate::error::{BackendError, Error, ErrorCategory, Result};
 pub use crate::header::{
-    detect_unsupported_xz_check_id, is_known_decode_format, read_decode_format_probe_prefix,
-    LZMA_ALONE_HEADER_SIZE, XZ_STREAM_HEADER_MAGIC,
+    detect_unsupported_xz_check_id, is_decode_format, is_known_decode_format,
+    read_decode_format_probe_prefix, LZMA_ALONE_HEADER_SIZE, XZ_STREAM_HEADER_MAGIC,
 };
-pub use crate::threading::Threading;
+pub use crate::threading::{available_threads, Threading};
 pub use buffer::{Allocator, Buffer, Deallocator, DeallocatorFn, GlobalAllocator};
-pub use config::{DecompressionOutcome, DecompressionStatus, UnknownInputPolicy};
+pub use config::{
+    DecompressionOutcome, DecompressionStatus, SizeMismatch, SizeMismatchPolicy, UnknownInputPolicy,
+};
+pub use lzma_safe::{Hardware, Version};
 
 /// Calculates the compression/decompression ratio as a percentage.
 ///
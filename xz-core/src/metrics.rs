@@ -0,0 +1,315 @@
+//! Callback hooks for collecting pipeline metrics.
+//!
+//! A [`Metrics`] implementation attached with [`CompressionOptions::with_metrics`] or
+//! [`DecompressionOptions::with_metrics`] receives one [`Measurement`] for every pipeline
+//! call made with those options, successful or not. That is enough to maintain byte
+//! counters, operation and failure counts and latency histograms in Prometheus, StatsD
+//! or any other backend without timing each call by hand:
+//!
+//! ```rust
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use std::sync::Arc;
+//!
+//! use xz_core::metrics::Measurement;
+//! use xz_core::options::CompressionOptions;
+//! use xz_core::pipeline::compress;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let compressed_bytes = Arc::new(AtomicU64::new(0));
+//! let counter = Arc::clone(&compressed_bytes);
+//! let options = CompressionOptions::default().with_metrics(Arc::new(
+//!     move |measurement: &Measurement| {
+//!         counter.fetch_add(measurement.bytes_read, Ordering::Relaxed);
+//!     },
+//! ));
+//!
+//! compress(&b"metrics"[..], Vec::new(), &options)?;
+//! assert_eq!(compressed_bytes.load(Ordering::Relaxed), 7);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The callback runs on the thread that made the pipeline call, after the call finished,
+//! so it should hand the values off quickly rather than block.
+//!
+//! [`CompressionOptions::with_metrics`]: crate::options::CompressionOptions::with_metrics
+//! [`DecompressionOptions::with_metrics`]: crate::options::DecompressionOptions::with_metrics
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::config::{DecompressionOutcome, StreamSummary};
+use crate::error::{ErrorCategory, Result};
+
+/// Receiver of the [`Measurement`] of each pipeline call.
+pub trait Metrics: Send + Sync {
+    /// Records one finished pipeline call.
+    ///
+    /// # Parameters
+    ///
+    /// * `measurement` - Operation, byte counts, duration and failure of the call
+    fn record(&self, measurement: &Measurement);
+}
+
+/// Blanket implementation allowing closures to act as metrics sinks.
+impl<T> Metrics for T
+where
+    T: Fn(&Measurement) + Send + Sync,
+{
+    fn record(&self, measurement: &Measurement) {
+        self(measurement);
+    }
+}
+
+/// Pipeline operation a [`Measurement`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    /// [`compress`](crate::pipeline::compress) or
+    /// [`compress_async`](crate::pipeline::compress_async)
+    Compress,
+    /// [`decompress`](crate::pipeline::decompress),
+    /// [`decompress_async`](crate::pipeline::decompress_async) or
+    /// [`decompress_to_vec`](crate::pipeline::decompress_to_vec)
+    Decompress,
+    /// [`recompress`](crate::pipeline::recompress), recorded with the metrics of its
+    /// compression options only
+    Recompress,
+}
+
+impl Operation {
+    /// Returns a lowercase name suitable as a metric label.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Operation::Compress => "compress",
+            Operation::Decompress => "decompress",
+            Operation::Recompress => "recompress",
+        }
+    }
+}
+
+/// Metrics of one finished pipeline call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Measurement {
+    /// The operation that was run
+    pub operation: Operation,
+    /// Bytes read from the input; zero if the call failed
+    pub bytes_read: u64,
+    /// Bytes written to the output; zero if the call failed
+    pub bytes_written: u64,
+    /// Time from the start of the call until it returned
+    pub duration: Duration,
+    /// Category of the error the call failed with, or `None` if it succeeded
+    pub failure: Option<ErrorCategory>,
+}
+
+impl Measurement {
+    /// Returns `true` if the call succeeded.
+    pub const fn is_success(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// A [`Metrics`] sink stored in the pipeline options.
+#[derive(Clone)]
+pub(crate) struct MetricsHook(Arc<dyn Metrics>);
+
+impl MetricsHook {
+    pub(crate) fn new(metrics: Arc<dyn Metrics>) -> Self {
+        Self(metrics)
+    }
+}
+
+impl fmt::Debug for MetricsHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MetricsHook")
+    }
+}
+
+/// Byte counts of a pipeline result
+pub(crate) trait Totals {
+    /// Returns the bytes read and written.
+    fn totals(&self) -> (u64, u64);
+}
+
+impl Totals for StreamSummary {
+    fn totals(&self) -> (u64, u64) {
+        (self.bytes_read, self.bytes_written)
+    }
+}
+
+impl Totals for DecompressionOutcome {
+    fn totals(&self) -> (u64, u64) {
+        (self.bytes_read, self.bytes_written)
+    }
+}
+
+impl<T: Totals, U> Totals for (U, T) {
+    fn totals(&self) -> (u64, u64) {
+        self.1.totals()
+    }
+}
+
+/// A pipeline call being timed; does nothing without a hook.
+pub(crate) struct Span<'a> {
+    hook: Option<&'a MetricsHook>,
+    operation: Operation,
+    started: Option<Instant>,
+}
+
+impl<'a> Span<'a> {
+    /// Starts timing `operation`.
+    pub(crate) fn start(hook: Option<&'a MetricsHook>, operation: Operation) -> Self {
+        Self {
+            hook,
+            operation,
+            started: hook.map(|_| Instant::now()),
+        }
+    }
+
+    /// Records `result` with the hook and passes it on.
+    pub(crate) fn finish<T: Totals>(self, result: Result<T>) -> Result<T> {
+        if let (Some(MetricsHook(metrics)), Some(started)) = (self.hook, self.started) {
+            let ((bytes_read, bytes_written), failure) = match &result {
+                Ok(value) => (value.totals(), None),
+                Err(err) => ((0, 0), Some(err.kind())),
+            };
+            metrics.record(&Measurement {
+                operation: self.operation,
+                bytes_read,
+                bytes_written,
+                duration: started.elapsed(),
+                failure,
+            });
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::error::ErrorCategory;
+    use crate::options::{CompressionOptions, DecompressionOptions};
+    use crate::pipeline::{compress, decompress, decompress_to_vec, recompress};
+
+    use super::{Measurement, Operation};
+
+    const SAMPLE: &[u8] = b"The quick brown fox jumps over the lazy dog";
+
+    /// Options' metrics sink that keeps every measurement
+    fn recorder() -> (Arc<Mutex<Vec<Measurement>>>, Arc<dyn super::Metrics>) {
+        let measurements = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&measurements);
+        let metrics = Arc::new(move |measurement: &Measurement| {
+            sink.lock().unwrap().push(*measurement);
+        });
+        (measurements, metrics)
+    }
+
+    /// Each call is recorded once, with the byte counts it returned.
+    #[test]
+    fn records_successful_calls() {
+        let (measurements, metrics) = recorder();
+        let compression = CompressionOptions::default().with_metrics(Arc::clone(&metrics));
+        let decompression = DecompressionOptions::default().with_metrics(metrics);
+
+        let mut compressed = Vec::new();
+        let summary = compress(SAMPLE, &mut compressed, &compression).unwrap();
+        let outcome = decompress(compressed.as_slice(), Vec::new(), &decompression).unwrap();
+        decompress_to_vec(&compressed, &decompression).unwrap();
+
+        let measurements = measurements.lock().unwrap();
+        let operations: Vec<Operation> = measurements.iter().map(|m| m.operation).collect();
+        assert_eq!(
+            operations,
+            [
+                Operation::Compress,
+                Operation::Decompress,
+                Operation::Decompress
+            ]
+        );
+        assert_eq!(measurements[0].bytes_read, summary.bytes_read);
+        assert_eq!(measurements[0].bytes_written, summary.bytes_written);
+        assert_eq!(measurements[1].bytes_read, outcome.bytes_read);
+        assert_eq!(measurements[1].bytes_written, SAMPLE.len() as u64);
+        assert!(measurements.iter().all(Measurement::is_success));
+    }
+
+    /// Failures are recorded with the category of their error.
+    #[test]
+    fn records_failures() {
+        let (measurements, metrics) = recorder();
+        let decompression = DecompressionOptions::default().with_metrics(metrics);
+
+        let mut compressed = Vec::new();
+        compress(SAMPLE, &mut compressed, &CompressionOptions::default()).unwrap();
+        compressed.truncate(compressed.len() - 4);
+        let err = decompress(compressed.as_slice(), Vec::new(), &decompression).unwrap_err();
+
+        let measurements = measurements.lock().unwrap();
+        assert_eq!(measurements.len(), 1);
+        assert_eq!(measurements[0].operation, Operation::Decompress);
+        assert_eq!(measurements[0].failure, Some(err.kind()));
+        assert_eq!(measurements[0].failure, Some(ErrorCategory::Corrupt));
+        assert_eq!(
+            (measurements[0].bytes_read, measurements[0].bytes_written),
+            (0, 0)
+        );
+    }
+
+    /// Recompressing is one operation, recorded by the compression sink only.
+    #[test]
+    fn records_recompression() {
+        let (measurements, metrics) = recorder();
+        let compression = CompressionOptions::default().with_metrics(Arc::clone(&metrics));
+        let decompression = DecompressionOptions::default().with_metrics(metrics);
+
+        let mut compressed = Vec::new();
+        compress(SAMPLE, &mut compressed, &CompressionOptions::default()).unwrap();
+        let summary = recompress(
+            compressed.as_slice(),
+            Vec::new(),
+            &decompression,
+            &compression,
+        )
+        .unwrap();
+
+        let measurements = measurements.lock().unwrap();
+        assert_eq!(measurements.len(), 1);
+        assert_eq!(measurements[0].operation, Operation::Recompress);
+        assert_eq!(measurements[0].bytes_read, summary.bytes_read);
+        assert_eq!(measurements[0].bytes_written, summary.bytes_written);
+    }
+
+    /// The async pipeline records the same measurements as the sync one.
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn records_async_calls() {
+        use crate::pipeline::{compress_async, decompress_async};
+
+        let (measurements, metrics) = recorder();
+        let compression = CompressionOptions::default().with_metrics(Arc::clone(&metrics));
+        let decompression = DecompressionOptions::default().with_metrics(metrics);
+
+        let mut compressed = Vec::new();
+        compress_async(SAMPLE, &mut compressed, &compression)
+            .await
+            .unwrap();
+        let mut output = Vec::new();
+        decompress_async(compressed.as_slice(), &mut output, &decompression)
+            .await
+            .unwrap();
+
+        let measurements = measurements.lock().unwrap();
+        assert_eq!(measurements.len(), 2);
+        assert_eq!(measurements[0].operation, Operation::Compress);
+        assert_eq!(measurements[0].bytes_read, SAMPLE.len() as u64);
+        assert_eq!(measurements[0].bytes_written, compressed.len() as u64);
+        assert_eq!(measurements[1].operation, Operation::Decompress);
+        assert_eq!(measurements[1].bytes_written, SAMPLE.len() as u64);
+    }
+}
@@ -1,11 +1,12 @@
 //! High-level configuration builders for XZ compression and decompression operations.
 
 use std::num::{NonZeroU64, NonZeroUsize};
+use std::sync::Arc;
 use std::time::Duration;
 
 use lzma_safe::decoder::options::{Flags as DecoderFlags, Options as DecoderMtOptions};
 use lzma_safe::encoder::options::Options as EncoderMtOptions;
-use lzma_safe::{AloneEncoder, Decoder, Encoder, RawDecoder, RawEncoder, Stream};
+use lzma_safe::{AloneEncoder, Decoder, Encoder, RawDecoder, RawEncoder, Stream, Version};
 
 pub use lzma_safe::decoder::options::Flags;
 pub use lzma_safe::encoder::options::{
@@ -19,26 +20,59 @@ pub mod lzma1 {
 }
 
 use crate::config::DecodeMode;
-use crate::config::{EncodeFormat, UnknownInputPolicy};
+use crate::config::{EncodeFormat, SizeMismatchPolicy, UnknownInputPolicy};
 use crate::error::{Error, Result};
+use crate::metrics::{Metrics, MetricsHook};
 use crate::threading::{sanitize_threads, Threading};
 
+/// Step by which [`CompressionOptions::fit_to_memlimit`] shrinks the dictionary.
+const DICT_SIZE_STEP: u32 = 1 << 20;
+
 const DEFAULT_INPUT_BUFFER: usize = 64 * 1024;
 const DEFAULT_OUTPUT_BUFFER: usize = 64 * 1024;
 
+/// A compression setting that [`CompressionOptions::fit_to_memlimit`] lowered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemlimitAdjustment {
+    /// Fewer worker threads are used than were requested.
+    Threads {
+        /// Threads requested, after clamping to the processor threads.
+        requested: u32,
+        /// Threads that fit into the limit.
+        reduced: u32,
+    },
+    /// The single-threaded encoder is used instead of the multi-threaded one, which
+    /// needs less memory but produces different output.
+    SingleThreaded,
+    /// The LZMA1 or LZMA2 dictionary was made smaller.
+    DictSize {
+        /// [`FilterType::Lzma1`] for `.lzma` output, [`FilterType::Lzma2`] otherwise.
+        filter: FilterType,
+        /// Dictionary size of the preset or filter chain, in bytes.
+        original: u32,
+        /// Dictionary size that fits into the limit, in bytes.
+        reduced: u32,
+    },
+}
+
 /// Configuration builder for XZ compression operations.
 #[derive(Debug, Clone)]
 pub struct CompressionOptions {
     level: Compression,
     check: IntegrityCheck,
     threads: Threading,
+    memlimit: Option<NonZeroU64>,
+    auto_adjust: bool,
     block_size: Option<NonZeroU64>,
+    block_list: Vec<u64>,
+    flush_on_timeout: bool,
     timeout: Option<Duration>,
     filters: Vec<FilterConfig>,
     format: EncodeFormat,
     lzma1: Option<lzma1::Lzma1Options>,
     input_buffer_size: NonZeroUsize,
     output_buffer_size: NonZeroUsize,
+    metrics: Option<MetricsHook>,
 }
 
 impl Default for CompressionOptions {
@@ -47,13 +81,18 @@ impl Default for CompressionOptions {
             level: Compression::Level6,
             check: IntegrityCheck::Crc64,
             threads: Threading::Auto,
+            memlimit: None,
+            auto_adjust: true,
             block_size: None,
+            block_list: Vec::new(),
+            flush_on_timeout: false,
             timeout: None,
             filters: Vec::new(),
             format: EncodeFormat::Xz,
             lzma1: None,
             input_buffer_size: NonZeroUsize::new(DEFAULT_INPUT_BUFFER).unwrap(),
             output_buffer_size: NonZeroUsize::new(DEFAULT_OUTPUT_BUFFER).unwrap(),
+            metrics: None,
         }
     }
 }
@@ -79,6 +118,19 @@ impl BuiltEncoder {
         }
     }
 
+    /// Ends the current `.xz` Block; see [`Encoder::finish_block`].
+    ///
+    /// `.lzma` and raw streams have no Blocks, so there is nothing to finish.
+    pub(crate) fn finish_block(
+        &mut self,
+        output: &mut [u8],
+    ) -> std::result::Result<(usize, bool), lzma_safe::Error> {
+        match self {
+            BuiltEncoder::Xz(enc) => enc.finish_block(output),
+            BuiltEncoder::Lzma(_) | BuiltEncoder::Raw(_) => Ok((0, true)),
+        }
+    }
+
     pub(crate) fn is_finished(&self) -> bool {
         match self {
             BuiltEncoder::Xz(enc) => enc.is_finished(),
@@ -113,6 +165,13 @@ impl BuiltDecoder {
             BuiltDecoder::Raw(dec) => dec.is_finished(),
         }
     }
+
+    pub(crate) fn total_out(&self) -> u64 {
+        match self {
+            BuiltDecoder::Standard(dec) => dec.total_out(),
+            BuiltDecoder::Raw(dec) => dec.total_out(),
+        }
+    }
 }
 
 impl CompressionOptions {
@@ -153,6 +212,31 @@ impl CompressionOptions {
         self
     }
 
+    /// Sets the memory usage limit for compression.
+    ///
+    /// When the multi-threaded encoder would need more memory than `limit`, the number
+    /// of worker threads is reduced until it fits. If even a single thread exceeds the
+    /// limit, building the encoder fails with [`Error::MemoryLimitTooLow`].
+    ///
+    /// By default there is no limit.
+    #[must_use]
+    pub fn with_memlimit(mut self, limit: NonZeroU64) -> Self {
+        self.memlimit = Some(limit);
+        self
+    }
+
+    /// Sets whether [`fit_to_memlimit`](Self::fit_to_memlimit) may change the compressed
+    /// output to fit into the memory limit, like `xz` without `--no-adjust`.
+    ///
+    /// Fewer worker threads leave the output unchanged and are always allowed; switching
+    /// to the single-threaded encoder or shrinking the dictionary is not when disabled.
+    /// Enabled by default.
+    #[must_use]
+    pub fn with_auto_adjust(mut self, enabled: bool) -> Self {
+        self.auto_adjust = enabled;
+        self
+    }
+
     /// Sets a custom block size for multi-threaded compression.
     ///
     /// Block size affects both compression ratio and memory usage:
@@ -168,6 +252,31 @@ impl CompressionOptions {
         self
     }
 
+    /// Sets explicit uncompressed sizes of the `.xz` Blocks, like `xz --block-list`.
+    ///
+    /// Each entry ends a Block after that many input bytes. If the input is longer than
+    /// the sum of the sizes, the last size is repeated until the end of the input; a
+    /// last size of `0` puts the rest of the input into a single Block. Combined with
+    /// [`with_block_size`](Self::with_block_size), Blocks are additionally split at the
+    /// block size. An empty list (default) leaves the Block boundaries to the encoder.
+    #[must_use]
+    pub fn with_block_list(mut self, block_list: Vec<u64>) -> Self {
+        self.block_list = block_list;
+        self
+    }
+
+    /// Flushes all pending output when the reader times out, like `xz --flush-timeout`.
+    ///
+    /// When enabled, a read failing with [`std::io::ErrorKind::TimedOut`] is not an error:
+    /// the current Block is ended, everything compressed so far is written and flushed to
+    /// the writer, and reading continues. This lets a reader that times out when no input
+    /// arrives for a while make the output decodable without closing the stream.
+    #[must_use]
+    pub fn with_flush_on_timeout(mut self, enabled: bool) -> Self {
+        self.flush_on_timeout = enabled;
+        self
+    }
+
     /// Sets a timeout for multi-threaded compression operations.
     ///
     /// This timeout applies to internal synchronization in the multi-threaded
@@ -234,6 +343,14 @@ impl CompressionOptions {
         self
     }
 
+    /// Sets the sink that receives a [`Measurement`](crate::metrics::Measurement) for
+    /// every compression or recompression run with these options.
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(MetricsHook::new(metrics));
+        self
+    }
+
     pub(crate) fn build_encoder(&self) -> Result<BuiltEncoder> {
         match self.format {
             EncodeFormat::Xz => self.build_xz_encoder().map(BuiltEncoder::Xz),
@@ -248,16 +365,23 @@ impl CompressionOptions {
             Err(Error::InvalidThreadCount { maximum, .. }) => maximum.max(1),
             Err(other) => return Err(other),
         };
+        let threads = self.fit_threads_to_memlimit(threads)?;
         let stream = Stream::default();
 
-        if threads <= 1
-            && self.block_size.is_none()
-            && self.timeout.is_none()
-            && self.filters.is_empty()
-        {
-            return Encoder::new(self.level, self.check, stream).map_err(Error::from);
+        if self.is_single_threaded(threads) {
+            return Encoder::new_single_threaded(self.encoder_options(threads), stream)
+                .map_err(Error::from);
         }
 
+        Encoder::new_mt(self.encoder_options(threads), stream).map_err(Error::from)
+    }
+
+    /// Returns `true` when `threads` workers can use the single-threaded encoder.
+    fn is_single_threaded(&self, threads: u32) -> bool {
+        threads <= 1 && self.block_size.is_none() && self.timeout.is_none()
+    }
+
+    fn encoder_options(&self, threads: u32) -> EncoderMtOptions {
         let mut options = EncoderMtOptions::default()
             .with_level(self.level)
             .with_check(self.check)
@@ -275,7 +399,191 @@ impl CompressionOptions {
             options = options.with_filters(self.filters.clone());
         }
 
-        Encoder::new_mt(options, stream).map_err(Error::from)
+        options
+    }
+
+    /// Memory needed by the `.xz` encoder with `threads` workers, or `None` if liblzma
+    /// rejects the settings (the encoder initialization reports that error).
+    fn encoder_memusage(&self, threads: u32) -> Option<u64> {
+        let options = self.encoder_options(threads);
+        if self.is_single_threaded(threads) {
+            options.single_threaded_memusage()
+        } else {
+            options.memusage()
+        }
+    }
+
+    /// Lowers the settings of `.xz` or `.lzma` compression until the encoder fits into the
+    /// memory limit.
+    ///
+    /// Like upstream `xz`, the worker threads of the multi-threaded encoder are reduced
+    /// first. If a single worker still needs too much memory, the single-threaded encoder
+    /// is used instead, and if that doesn't fit either, the LZMA2 dictionary shrinks in
+    /// 1 MiB steps. For `.lzma`, the LZMA1 dictionary shrinks the same way. Only the thread
+    /// count changes without [`with_auto_adjust`](Self::with_auto_adjust). Raw output, and
+    /// options without a limit, are returned unchanged.
+    ///
+    /// # Returns
+    ///
+    /// The adjusted options and what was changed, in the order it was changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MemoryLimitTooLow`] if even a 1 MiB dictionary doesn't fit, or if
+    /// fewer threads don't suffice and the output may not change, and the error of the
+    /// preset if it is invalid.
+    pub fn fit_to_memlimit(mut self) -> Result<(Self, Vec<MemlimitAdjustment>)> {
+        let mut adjustments = Vec::new();
+        let Some(limit) = self.memlimit.map(NonZeroU64::get) else {
+            return Ok((self, adjustments));
+        };
+        match self.format {
+            EncodeFormat::Xz => {}
+            EncodeFormat::Lzma => return self.fit_lzma1_to_memlimit(limit),
+            _ => return Ok((self, adjustments)),
+        }
+
+        let requested = match sanitize_threads(self.threads) {
+            Ok(count) => count.max(1),
+            Err(Error::InvalidThreadCount { maximum, .. }) => maximum.max(1),
+            Err(other) => return Err(other),
+        };
+        let exceeds = |options: &Self, threads| {
+            options
+                .encoder_memusage(threads)
+                .filter(|&required| required > limit)
+        };
+        let mut threads = requested;
+        let mut exceeded = exceeds(&self, threads);
+        let multithreaded = !self.is_single_threaded(requested);
+        if multithreaded {
+            // The multi-threaded encoder is kept down to a single worker, as fewer workers
+            // don't change the output.
+            let exceeds_mt = |threads| {
+                self.encoder_options(threads)
+                    .memusage()
+                    .filter(|&required| required > limit)
+            };
+            while threads > 1 && exceeded.is_some() {
+                threads -= 1;
+                exceeded = exceeds_mt(threads);
+            }
+            if threads < requested {
+                self.threads = Threading::Exact(threads);
+                // Switching to the single-threaded encoder is reported on its own.
+                if exceeded.is_none() || !self.is_single_threaded(1) {
+                    adjustments.push(MemlimitAdjustment::Threads {
+                        requested,
+                        reduced: threads,
+                    });
+                }
+            }
+        }
+
+        let Some(mut required) = exceeded else {
+            return Ok((self, adjustments));
+        };
+        if !self.auto_adjust {
+            return Err(Error::MemoryLimitTooLow { required, limit });
+        }
+        if multithreaded && self.is_single_threaded(1) {
+            adjustments.push(MemlimitAdjustment::SingleThreaded);
+            match exceeds(&self, 1) {
+                Some(needed) => required = needed,
+                None => return Ok((self, adjustments)),
+            }
+        }
+
+        let (chain, mut lzma2) = match self.filters.split_last() {
+            None => {
+                let preset = lzma1::Lzma1Options::from_preset(self.level).map_err(Error::from)?;
+                (Vec::new(), LzmaOptions::from(&preset))
+            }
+            Some((
+                FilterConfig {
+                    filter_type: FilterType::Lzma2,
+                    options: Some(FilterOptions::Lzma(lzma2)),
+                },
+                chain,
+            )) => (chain.to_vec(), lzma2.clone()),
+            // Without LZMA2 options there is no dictionary to shrink.
+            Some(_) => return Err(Error::MemoryLimitTooLow { required, limit }),
+        };
+
+        let original = lzma2.dict_size;
+        let mut candidate = None;
+        let reduced = shrink_dict_size(original, required, limit, |dict_size| {
+            lzma2.dict_size = dict_size;
+            let mut filters = chain.clone();
+            filters.push(FilterConfig {
+                filter_type: FilterType::Lzma2,
+                options: Some(FilterOptions::Lzma(lzma2.clone())),
+            });
+            let options = self.clone().with_filters(filters);
+            let exceeded = exceeds(&options, threads);
+            candidate = Some(options);
+            exceeded
+        })?;
+        adjustments.push(MemlimitAdjustment::DictSize {
+            filter: FilterType::Lzma2,
+            original,
+            reduced,
+        });
+        Ok((candidate.unwrap_or(self), adjustments))
+    }
+
+    /// Shrinks the LZMA1 dictionary of `.lzma` compression until the encoder fits into
+    /// `limit`, the way [`fit_to_memlimit`](Self::fit_to_memlimit) shrinks LZMA2.
+    fn fit_lzma1_to_memlimit(self, limit: u64) -> Result<(Self, Vec<MemlimitAdjustment>)> {
+        let lzma1 = match self.lzma1.clone() {
+            Some(options) => options,
+            None => lzma1::Lzma1Options::from_preset(self.level).map_err(Error::from)?,
+        };
+        let exceeds = |options: &lzma1::Lzma1Options| {
+            options
+                .encoder_memusage()
+                .filter(|&required| required > limit)
+        };
+        let Some(required) = exceeds(&lzma1) else {
+            return Ok((self, Vec::new()));
+        };
+        if !self.auto_adjust {
+            return Err(Error::MemoryLimitTooLow { required, limit });
+        }
+
+        let original = lzma1.dict_size();
+        let reduced = shrink_dict_size(original, required, limit, |dict_size| {
+            exceeds(&lzma1.clone().with_dict_size(dict_size))
+        })?;
+        let adjustments = vec![MemlimitAdjustment::DictSize {
+            filter: FilterType::Lzma1,
+            original,
+            reduced,
+        }];
+        Ok((
+            self.with_lzma1_options(Some(lzma1.with_dict_size(reduced))),
+            adjustments,
+        ))
+    }
+
+    /// Reduces `threads` until the encoder fits into the memory limit, like upstream `xz`.
+    fn fit_threads_to_memlimit(&self, threads: u32) -> Result<u32> {
+        let Some(limit) = self.memlimit.map(NonZeroU64::get) else {
+            return Ok(threads);
+        };
+
+        let mut threads = threads;
+        loop {
+            match self.encoder_memusage(threads) {
+                Some(required) if required > limit => {
+                    if threads <= 1 {
+                        return Err(Error::MemoryLimitTooLow { required, limit });
+                    }
+                    threads -= 1;
+                }
+                _ => return Ok(threads),
+            }
+        }
     }
 
     fn build_lzma_encoder(&self) -> Result<AloneEncoder> {
@@ -292,7 +600,7 @@ impl CompressionOptions {
                 });
             }
         }
-        if self.block_size.is_some() {
+        if self.block_size.is_some() || !self.block_list.is_empty() {
             return Err(Error::InvalidOption(
                 "block size is not supported in .lzma format".into(),
             ));
@@ -328,7 +636,7 @@ impl CompressionOptions {
                 ));
             }
         }
-        if self.block_size.is_some() {
+        if self.block_size.is_some() || !self.block_list.is_empty() {
             return Err(Error::InvalidOption(
                 "block size is not supported in raw format".into(),
             ));
@@ -350,6 +658,14 @@ impl CompressionOptions {
         RawEncoder::new_lzma1(options, Stream::default()).map_err(Error::from)
     }
 
+    pub(crate) fn block_list(&self) -> &[u64] {
+        &self.block_list
+    }
+
+    pub(crate) fn flush_on_timeout(&self) -> bool {
+        self.flush_on_timeout
+    }
+
     pub(crate) fn input_capacity(&self) -> usize {
         self.input_buffer_size.get()
     }
@@ -357,6 +673,10 @@ impl CompressionOptions {
     pub(crate) fn output_capacity(&self) -> usize {
         self.output_buffer_size.get()
     }
+
+    pub(crate) fn metrics(&self) -> Option<&MetricsHook> {
+        self.metrics.as_ref()
+    }
 }
 
 /// Configuration builder for XZ decompression operations with security-focused defaults.
@@ -370,8 +690,11 @@ pub struct DecompressionOptions {
     unknown_input_policy: UnknownInputPolicy,
     raw_lzma1: Option<lzma1::Lzma1Options>,
     timeout: Option<Duration>,
+    expected_size: Option<u64>,
+    size_mismatch_policy: SizeMismatchPolicy,
     input_buffer_size: NonZeroUsize,
     output_buffer_size: NonZeroUsize,
+    metrics: Option<MetricsHook>,
 }
 
 impl Default for DecompressionOptions {
@@ -385,8 +708,11 @@ impl Default for DecompressionOptions {
             unknown_input_policy: UnknownInputPolicy::Error,
             raw_lzma1: None,
             timeout: None,
+            expected_size: None,
+            size_mismatch_policy: SizeMismatchPolicy::Warn,
             input_buffer_size: NonZeroUsize::new(DEFAULT_INPUT_BUFFER).unwrap(),
             output_buffer_size: NonZeroUsize::new(DEFAULT_OUTPUT_BUFFER).unwrap(),
+            metrics: None,
         }
     }
 }
@@ -417,6 +743,7 @@ impl DecompressionOptions {
     ///
     /// If memory usage exceeds this limit, decompression will fail immediately
     /// with an error. This provides a hard boundary against memory exhaustion attacks.
+    /// Single-threaded decoders only use this limit.
     ///
     /// If `None` (default), the hard limit equals the soft limit. The hard limit
     /// must be greater than or equal to the soft limit.
@@ -484,6 +811,30 @@ impl DecompressionOptions {
         self
     }
 
+    /// Announces the expected decompressed size of the input.
+    ///
+    /// The one-shot [`crate::pipeline::decompress_to_vec`] uses the hint to pre-size its
+    /// output `Vec`. When the input carries an XZ Index, the announced size is checked against
+    /// it before decoding starts; every pipeline also compares it with the number of bytes
+    /// actually produced. Mismatches are handled according to
+    /// [`Self::with_size_mismatch_policy`].
+    #[must_use]
+    pub fn with_expected_size(mut self, size: u64) -> Self {
+        self.expected_size = Some(size);
+        self
+    }
+
+    /// Controls whether an expected-size mismatch is reported as a warning or an error.
+    ///
+    /// - `SizeMismatchPolicy::Warn` (default): the mismatch is recorded in
+    ///   [`crate::config::DecompressionOutcome::size_mismatch`]
+    /// - `SizeMismatchPolicy::Error`: the operation fails with [`Error::SizeMismatch`]
+    #[must_use]
+    pub fn with_size_mismatch_policy(mut self, policy: SizeMismatchPolicy) -> Self {
+        self.size_mismatch_policy = policy;
+        self
+    }
+
     /// Sets the input buffer size for reading compressed data.
     ///
     /// Larger buffers can improve performance by reducing the number of read
@@ -504,6 +855,14 @@ impl DecompressionOptions {
         self
     }
 
+    /// Sets the sink that receives a [`Measurement`](crate::metrics::Measurement) for
+    /// every decompression run with these options.
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = Some(MetricsHook::new(metrics));
+        self
+    }
+
     pub(crate) fn build_decoder(&self) -> Result<BuiltDecoder> {
         let memlimit = self.memlimit.get();
         let memlimit_stop = self
@@ -529,11 +888,18 @@ impl DecompressionOptions {
                     }
                 }
 
-                Decoder::new_auto(memlimit, self.flags, stream)
+                Decoder::new_auto(memlimit_stop, self.flags, stream)
                     .map(BuiltDecoder::Standard)
                     .map_err(Error::from)
             }
             DecodeMode::Xz => {
+                // Like upstream `xz -T1`, a single thread uses the plain stream decoder.
+                if self.threads == Threading::Exact(1) || !Version::has_mt_decoder() {
+                    return Decoder::new(memlimit_stop, self.flags, stream)
+                        .map(BuiltDecoder::Standard)
+                        .map_err(Error::from);
+                }
+
                 let threads = match sanitize_threads(self.threads) {
                     Ok(count) => count.max(1),
                     Err(Error::InvalidThreadCount { maximum, .. }) => maximum.max(1),
@@ -562,7 +928,21 @@ impl DecompressionOptions {
                     }
                 }
 
-                Decoder::new_alone(memlimit, stream)
+                Decoder::new_alone(memlimit_stop, stream)
+                    .map(BuiltDecoder::Standard)
+                    .map_err(Error::from)
+            }
+            DecodeMode::Lzip => {
+                if let Threading::Exact(requested) = self.threads {
+                    if requested > 1 {
+                        return Err(Error::ThreadingUnsupported {
+                            requested,
+                            mode: DecodeMode::Lzip,
+                        });
+                    }
+                }
+
+                Decoder::new_lzip(memlimit_stop, self.flags, stream)
                     .map(BuiltDecoder::Standard)
                     .map_err(Error::from)
             }
@@ -581,7 +961,7 @@ impl DecompressionOptions {
                         "raw decode mode requires explicit LZMA1 filter options".into(),
                     )
                 })?;
-                RawDecoder::new_lzma1(memlimit, self.flags, lzma1, stream)
+                RawDecoder::new_lzma1(memlimit_stop, self.flags, lzma1, stream)
                     .map(BuiltDecoder::Raw)
                     .map_err(Error::from)
             }
@@ -604,9 +984,30 @@ impl DecompressionOptions {
         self.mode
     }
 
+    pub(crate) fn threads(&self) -> Threading {
+        self.threads
+    }
+
     pub(crate) fn unknown_input_policy(&self) -> UnknownInputPolicy {
         self.unknown_input_policy
     }
+
+    pub(crate) fn expected_size(&self) -> Option<u64> {
+        self.expected_size
+    }
+
+    pub(crate) fn size_mismatch_policy(&self) -> SizeMismatchPolicy {
+        self.size_mismatch_policy
+    }
+
+    /// Hard memory limit, used as the only limit by single-threaded decoders.
+    pub(crate) fn memlimit(&self) -> NonZeroU64 {
+        self.memlimit_stop.unwrap_or(self.memlimit)
+    }
+
+    pub(crate) fn metrics(&self) -> Option<&MetricsHook> {
+        self.metrics.as_ref()
+    }
 }
 
 /// Converts a `Duration` to a timeout value in milliseconds for the LZMA library.
@@ -622,12 +1023,191 @@ fn duration_to_timeout(duration: Duration) -> u32 {
     duration.as_millis().try_into().unwrap_or(u32::MAX)
 }
 
+/// Shrinks a dictionary of `original` bytes in 1 MiB steps until it fits, like upstream `xz`.
+///
+/// # Parameters
+///
+/// * `original` - Dictionary size of the preset or filter chain
+/// * `required` - Memory needed with the original dictionary
+/// * `limit` - Memory usage limit
+/// * `exceeds` - Returns the memory needed with a dictionary size if it is over the limit
+///
+/// # Returns
+///
+/// The largest dictionary size that fits.
+///
+/// # Errors
+///
+/// Returns [`Error::MemoryLimitTooLow`] if even a 1 MiB dictionary doesn't fit.
+fn shrink_dict_size(
+    original: u32,
+    mut required: u64,
+    limit: u64,
+    mut exceeds: impl FnMut(u32) -> Option<u64>,
+) -> Result<u32> {
+    let mut dict_size = original - original % DICT_SIZE_STEP;
+    while dict_size >= DICT_SIZE_STEP {
+        match exceeds(dict_size) {
+            Some(needed) => required = needed,
+            None => return Ok(dict_size),
+        }
+        dict_size -= DICT_SIZE_STEP;
+    }
+    Err(Error::MemoryLimitTooLow { required, limit })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::error::Error;
 
     use super::*;
 
+    /// Test that a compression memory limit reduces the worker threads until it fits.
+    #[test]
+    fn compression_memlimit_reduces_threads() {
+        let options = CompressionOptions::default().with_level(Compression::Level1);
+        let two_threads = options.encoder_memusage(2).unwrap();
+        let options = options.with_memlimit(NonZeroU64::new(two_threads).unwrap());
+
+        assert!(matches!(options.fit_threads_to_memlimit(4), Ok(2)));
+        assert!(matches!(options.fit_threads_to_memlimit(1), Ok(1)));
+    }
+
+    /// Test that a limit below the single-threaded encoder's needs is an error.
+    #[test]
+    fn compression_memlimit_too_low_fails() {
+        let options = CompressionOptions::default()
+            .with_level(Compression::Level6)
+            .with_memlimit(NonZeroU64::new(1024 * 1024).unwrap());
+
+        assert!(matches!(
+            options.build_encoder(),
+            Err(Error::MemoryLimitTooLow { limit, .. }) if limit == 1024 * 1024
+        ));
+    }
+
+    /// Test that fitting the limit reports the reduced threads.
+    #[test]
+    fn fit_to_memlimit_reports_threads() {
+        let options = CompressionOptions::default()
+            .with_level(Compression::Level1)
+            .with_threads(Threading::Exact(crate::available_threads().min(4)));
+        let one_worker = options.encoder_options(1).memusage().unwrap();
+        let options = options.with_memlimit(NonZeroU64::new(one_worker).unwrap());
+
+        let (fitted, adjustments) = options.fit_to_memlimit().unwrap();
+        assert!(matches!(fitted.threads, Threading::Exact(1)));
+        if crate::available_threads() > 1 {
+            assert!(matches!(
+                adjustments[..],
+                [MemlimitAdjustment::Threads { reduced: 1, .. }]
+            ));
+        } else {
+            assert!(adjustments.is_empty());
+        }
+    }
+
+    /// Test that a single thread over the limit gets a smaller dictionary instead.
+    #[test]
+    fn fit_to_memlimit_shrinks_dictionary() {
+        let options = CompressionOptions::default()
+            .with_level(Compression::Level9)
+            .with_threads(Threading::Exact(1))
+            .with_memlimit(NonZeroU64::new(100 << 20).unwrap());
+
+        let (fitted, adjustments) = options.fit_to_memlimit().unwrap();
+        let [MemlimitAdjustment::DictSize {
+            filter: FilterType::Lzma2,
+            original,
+            reduced,
+        }] = adjustments[..]
+        else {
+            panic!("unexpected adjustments: {adjustments:?}");
+        };
+        assert_eq!((original, reduced), (64 << 20, 8 << 20));
+        assert!(fitted.encoder_memusage(1).unwrap() <= 100 << 20);
+        assert!(fitted.build_encoder().is_ok());
+
+        // Without a limit nothing changes.
+        let (_, adjustments) = CompressionOptions::default().fit_to_memlimit().unwrap();
+        assert!(adjustments.is_empty());
+    }
+
+    /// Test that a single worker over the limit switches to the single-threaded encoder
+    /// before the dictionary shrinks.
+    #[test]
+    fn fit_to_memlimit_switches_to_single_threaded() {
+        if crate::available_threads() < 2 {
+            return;
+        }
+        let options = CompressionOptions::default()
+            .with_level(Compression::Level9)
+            .with_threads(Threading::Exact(2))
+            .with_memlimit(NonZeroU64::new(100 << 20).unwrap());
+
+        let (fitted, adjustments) = options.fit_to_memlimit().unwrap();
+        let [MemlimitAdjustment::SingleThreaded, MemlimitAdjustment::DictSize { reduced, .. }] =
+            adjustments[..]
+        else {
+            panic!("unexpected adjustments: {adjustments:?}");
+        };
+        assert_eq!(reduced, 8 << 20);
+        assert!(matches!(fitted.threads, Threading::Exact(1)));
+    }
+
+    /// Test that the settings aren't lowered beyond the thread count without auto-adjust.
+    #[test]
+    fn fit_to_memlimit_without_auto_adjust_fails() {
+        let options = CompressionOptions::default()
+            .with_level(Compression::Level9)
+            .with_threads(Threading::Exact(1))
+            .with_auto_adjust(false)
+            .with_memlimit(NonZeroU64::new(100 << 20).unwrap());
+        let needed = options.encoder_memusage(1).unwrap();
+
+        assert!(matches!(
+            options.fit_to_memlimit(),
+            Err(Error::MemoryLimitTooLow { required, limit })
+                if required == needed && limit == 100 << 20
+        ));
+    }
+
+    /// Test that `.lzma` compression over the limit gets a smaller LZMA1 dictionary.
+    #[test]
+    fn fit_to_memlimit_shrinks_lzma1_dictionary() {
+        let options = CompressionOptions::default()
+            .with_level(Compression::Level9)
+            .with_check(IntegrityCheck::None)
+            .with_threads(Threading::Exact(1))
+            .with_format(EncodeFormat::Lzma)
+            .with_memlimit(NonZeroU64::new(20 << 20).unwrap());
+
+        let (fitted, adjustments) = options.fit_to_memlimit().unwrap();
+        let [MemlimitAdjustment::DictSize {
+            filter: FilterType::Lzma1,
+            original,
+            reduced,
+        }] = adjustments[..]
+        else {
+            panic!("unexpected adjustments: {adjustments:?}");
+        };
+        assert_eq!((original, reduced), (64 << 20, 1 << 20));
+        assert!(fitted.build_encoder().is_ok());
+    }
+
+    /// Test that a limit no dictionary fits into is still an error.
+    #[test]
+    fn fit_to_memlimit_too_low_fails() {
+        let options = CompressionOptions::default()
+            .with_threads(Threading::Exact(1))
+            .with_memlimit(NonZeroU64::new(1 << 20).unwrap());
+
+        assert!(matches!(
+            options.fit_to_memlimit(),
+            Err(Error::MemoryLimitTooLow { limit, .. }) if limit == 1 << 20
+        ));
+    }
+
     /// Test that [`CompressionOptions`] has expected defaults.
     #[test]
     fn compression_options_defaults() {
@@ -842,6 +1422,18 @@ mod tests {
             .expect("XZ mode should accept multi-threading");
     }
 
+    /// Test that XZ mode with a single thread builds the single-threaded decoder.
+    #[test]
+    fn xz_mode_single_thread_builds_decoder() {
+        let options = DecompressionOptions::default()
+            .with_mode(DecodeMode::Xz)
+            .with_threads(Threading::Exact(1));
+
+        options
+            .build_decoder()
+            .expect("XZ mode should accept a single thread");
+    }
+
     /// Test that timeout conversion handles various durations correctly.
     #[test]
     fn timeout_conversion_handles_normal_durations() {
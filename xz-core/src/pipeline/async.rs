@@ -1,13 +1,17 @@
 //! Asynchronous XZ compression and decompression pipeline.
 
+use std::io::ErrorKind;
+
 use lzma_safe::Action;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use crate::buffer::Buffer;
 use crate::config::{DecompressionOutcome, StreamSummary};
 use crate::error::{BackendError, Result};
+use crate::metrics::{Operation, Span};
 use crate::options::{BuiltDecoder, BuiltEncoder, CompressionOptions, DecompressionOptions};
 
+use super::blocks::BlockBoundaries;
 use super::decode::{
     passthrough_async, probe_async, DecoderSession, PrefixedAsyncReader, ReadAction, RunAction,
 };
@@ -34,6 +38,19 @@ use super::decode::{
 /// - Invalid compression parameters are specified
 /// - Threading limits are exceeded
 pub async fn compress_async<R, W>(
+    reader: R,
+    writer: W,
+    options: &CompressionOptions,
+) -> Result<StreamSummary>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let span = Span::start(options.metrics(), Operation::Compress);
+    span.finish(compress_unmetered(reader, writer, options).await)
+}
+
+async fn compress_unmetered<R, W>(
     mut reader: R,
     mut writer: W,
     options: &CompressionOptions,
@@ -45,11 +62,20 @@ where
     let mut encoder = options.build_encoder()?;
     let mut input = Buffer::new(options.input_capacity())?;
     let mut output = Buffer::new(options.output_capacity())?;
+    let mut blocks = BlockBoundaries::new(options.block_list());
     let mut total_in = 0u64;
     let mut total_out = 0u64;
 
     loop {
-        let read = reader.read(&mut input).await?;
+        let read = match reader.read(&mut input).await {
+            Ok(read) => read,
+            Err(err) if options.flush_on_timeout() && err.kind() == ErrorKind::TimedOut => {
+                finish_block_async(&mut encoder, &mut writer, &mut output, &mut total_out).await?;
+                writer.flush().await?;
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
         if read == 0 {
             finish_encoder_async(&mut encoder, &mut writer, &mut output, &mut total_out).await?;
             return Ok(StreamSummary::new(total_in, total_out));
@@ -57,8 +83,9 @@ where
 
         let mut consumed = 0usize;
         while consumed < read {
+            let end = consumed + blocks.limit(read - consumed);
             let (used, written) =
-                encoder.process(&input[consumed..read], &mut output, Action::Run)?;
+                encoder.process(&input[consumed..end], &mut output, Action::Run)?;
             if written > 0 {
                 writer.write_all(&output[..written]).await?;
                 total_out += written as u64;
@@ -66,6 +93,10 @@ where
             consumed += used;
             total_in += used as u64;
 
+            if blocks.consume(used) {
+                finish_block_async(&mut encoder, &mut writer, &mut output, &mut total_out).await?;
+            }
+
             if encoder.is_finished() {
                 writer.flush().await?;
                 return Ok(StreamSummary::new(total_in, total_out));
@@ -101,6 +132,19 @@ where
 /// - Memory limits are exceeded during decompression
 /// - Threading is requested for unsupported decode modes
 pub async fn decompress_async<R, W>(
+    reader: R,
+    writer: W,
+    options: &DecompressionOptions,
+) -> Result<DecompressionOutcome>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let span = Span::start(options.metrics(), Operation::Decompress);
+    span.finish(decompress_unmetered(reader, writer, options).await)
+}
+
+async fn decompress_unmetered<R, W>(
     mut reader: R,
     mut writer: W,
     options: &DecompressionOptions,
@@ -112,12 +156,13 @@ where
     let probe = probe_async(&mut reader, options).await?;
     if probe.is_passthrough() {
         let summary = passthrough_async(probe.prefix(), &mut reader, &mut writer).await?;
-        return Ok(probe.build_outcome(summary));
+        return probe.build_outcome(summary, options);
     }
 
     let mut reader = PrefixedAsyncReader::new(probe.prefix().to_vec(), reader);
-    let summary = decompress_stream_async(&mut reader, &mut writer, options).await?;
-    Ok(probe.build_outcome(summary))
+    let decoder_options = probe.decoder_options(options);
+    let summary = decompress_stream_async(&mut reader, &mut writer, &decoder_options).await?;
+    probe.build_outcome(summary, options)
 }
 
 async fn decompress_stream_async<R, W>(
@@ -162,6 +207,37 @@ where
     }
 }
 
+/// Ends the current Block asynchronously at a block list boundary or for a flush.
+///
+/// # Parameters
+///
+/// * `encoder` - The encoder whose current Block is ended
+/// * `writer` - Async output writer to receive the rest of the Block
+/// * `output` - Buffer for temporary storage of compressed data
+/// * `total_out` - Running count of total bytes written (updated in-place)
+///
+/// # Returns
+///
+/// * `Ok(())` once the Block has been written
+/// * `Err(_)` if the encoder or the writer fails
+async fn finish_block_async<W: AsyncWrite + Unpin>(
+    encoder: &mut BuiltEncoder,
+    writer: &mut W,
+    output: &mut [u8],
+    total_out: &mut u64,
+) -> Result<()> {
+    loop {
+        let (written, done) = encoder.finish_block(output)?;
+        if written > 0 {
+            writer.write_all(&output[..written]).await?;
+            *total_out += written as u64;
+        }
+        if done {
+            return Ok(());
+        }
+    }
+}
+
 /// Finishes the encoding process asynchronously by flushing any remaining data from the encoder.
 ///
 /// # Parameters
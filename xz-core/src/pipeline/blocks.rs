@@ -0,0 +1,84 @@
+//! Block boundary tracking for [`CompressionOptions::with_block_list`].
+//!
+//! [`CompressionOptions::with_block_list`]: crate::options::CompressionOptions::with_block_list
+
+/// Tracks how much input remains until the next requested Block boundary.
+pub(crate) struct BlockBoundaries<'a> {
+    sizes: &'a [u64],
+    index: usize,
+    remaining: Option<u64>,
+}
+
+impl<'a> BlockBoundaries<'a> {
+    /// Starts tracking the Block sizes in `sizes`; an empty list never ends a Block.
+    pub(crate) fn new(sizes: &'a [u64]) -> Self {
+        Self {
+            sizes,
+            index: 0,
+            remaining: sizes.first().copied().filter(|&size| size != 0),
+        }
+    }
+
+    /// Limits `available` input bytes so that a chunk doesn't cross the next boundary.
+    pub(crate) fn limit(&self, available: usize) -> usize {
+        match self.remaining {
+            Some(remaining) => available.min(usize::try_from(remaining).unwrap_or(usize::MAX)),
+            None => available,
+        }
+    }
+
+    /// Records `consumed` input bytes and returns `true` if a Block boundary was reached.
+    pub(crate) fn consume(&mut self, consumed: usize) -> bool {
+        let Some(remaining) = self.remaining else {
+            return false;
+        };
+
+        let remaining = remaining.saturating_sub(consumed as u64);
+        if remaining > 0 {
+            self.remaining = Some(remaining);
+            return false;
+        }
+
+        // Past the end of the list the last size repeats; a final `0` keeps the rest
+        // of the input in one Block.
+        self.index = (self.index + 1).min(self.sizes.len() - 1);
+        self.remaining = Some(self.sizes[self.index]).filter(|&size| size != 0);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that boundaries follow the list and then repeat the last size.
+    #[test]
+    fn boundaries_repeat_last_size() {
+        let sizes = [3, 2];
+        let mut blocks = BlockBoundaries::new(&sizes);
+
+        assert_eq!(blocks.limit(10), 3);
+        assert!(!blocks.consume(1));
+        assert_eq!(blocks.limit(10), 2);
+        assert!(blocks.consume(2));
+        assert_eq!(blocks.limit(10), 2);
+        assert!(blocks.consume(2));
+        assert_eq!(blocks.limit(1), 1);
+        assert!(!blocks.consume(1));
+        assert!(blocks.consume(1));
+    }
+
+    /// Test that a trailing zero and an empty list never end a Block.
+    #[test]
+    fn boundaries_trailing_zero_and_empty_list() {
+        let sizes = [4, 0];
+        let mut blocks = BlockBoundaries::new(&sizes);
+        assert!(blocks.consume(4));
+        assert_eq!(blocks.limit(100), 100);
+        assert!(!blocks.consume(100));
+
+        let mut blocks = BlockBoundaries::new(&[]);
+        assert_eq!(blocks.limit(100), 100);
+        assert!(!blocks.consume(100));
+    }
+}
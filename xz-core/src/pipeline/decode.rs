@@ -1,19 +1,23 @@
 //! Shared decoder state machine used by sync and async pipelines.
 
+use std::borrow::Cow;
 use std::io::{self, Read};
 
 use lzma_safe::Action;
 
 use crate::buffer::Buffer;
 use crate::config::{
-    DecodeMode, DecompressionOutcome, DecompressionStatus, StreamSummary, UnknownInputPolicy,
+    DecodeMode, DecompressionOutcome, DecompressionStatus, SizeMismatch, SizeMismatchPolicy,
+    StreamSummary, UnknownInputPolicy,
 };
 use crate::error::{BackendError, Error, Result};
+use crate::file_info::extract_file_info;
 use crate::header::{
     detect_unsupported_xz_check_id, is_known_decode_format, read_decode_format_probe_prefix,
-    LZIP_HEADER_MAGIC,
+    LZIP_HEADER_MAGIC, XZ_STREAM_HEADER_MAGIC,
 };
 use crate::options::{BuiltDecoder, DecompressionOptions, Flags};
+use crate::threading::Threading;
 
 /// Size of the I/O buffer used by the decoder during passthrough.
 const IO_BUFFER_SIZE: usize = 8192;
@@ -81,9 +85,41 @@ impl DecompressionProbe {
         &self.prefix
     }
 
+    /// Returns the options used to build the decoder for the probed input.
+    ///
+    /// Auto-detect mode cannot use liblzma's multi-threaded decoder, so when an explicit
+    /// thread count is requested, `.xz` input is decoded in [`DecodeMode::Xz`] mode and
+    /// other formats ignore the request and decode single-threaded.
+    pub fn decoder_options<'a>(
+        &self,
+        options: &'a DecompressionOptions,
+    ) -> Cow<'a, DecompressionOptions> {
+        let threaded = matches!(options.threads(), Threading::Exact(count) if count != 1);
+        if options.mode() != DecodeMode::Auto || !threaded {
+            return Cow::Borrowed(options);
+        }
+
+        if self.prefix.starts_with(&XZ_STREAM_HEADER_MAGIC) {
+            Cow::Owned(options.clone().with_mode(DecodeMode::Xz))
+        } else {
+            Cow::Owned(options.clone().with_threads(Threading::Auto))
+        }
+    }
+
     /// Builds the final decompression outcome from a stream summary.
-    pub fn build_outcome(&self, summary: StreamSummary) -> DecompressionOutcome {
-        DecompressionOutcome::new(summary, self.status, self.unsupported_check_id)
+    ///
+    /// Decoded output is checked against the expected size configured in `options`.
+    pub fn build_outcome(
+        &self,
+        summary: StreamSummary,
+        options: &DecompressionOptions,
+    ) -> Result<DecompressionOutcome> {
+        let mut outcome =
+            DecompressionOutcome::new(summary, self.status, self.unsupported_check_id);
+        if self.status == DecompressionStatus::Decompressed {
+            outcome.size_mismatch = check_expected_size(options, summary.bytes_written)?;
+        }
+        Ok(outcome)
     }
 
     fn decoded(prefix: Vec<u8>, unsupported_check_id: Option<u32>) -> Self {
@@ -113,6 +149,56 @@ impl DecompressionProbe {
     }
 }
 
+/// Compares a decompressed size with the expected size configured in `options`.
+///
+/// Returns the mismatch when the policy is [`SizeMismatchPolicy::Warn`] and fails with
+/// [`Error::SizeMismatch`] when it is [`SizeMismatchPolicy::Error`].
+pub fn check_expected_size(
+    options: &DecompressionOptions,
+    actual: u64,
+) -> Result<Option<SizeMismatch>> {
+    let Some(expected) = options.expected_size() else {
+        return Ok(None);
+    };
+    if expected == actual {
+        return Ok(None);
+    }
+
+    match options.size_mismatch_policy() {
+        SizeMismatchPolicy::Warn => Ok(Some(SizeMismatch { expected, actual })),
+        SizeMismatchPolicy::Error => Err(Error::SizeMismatch { expected, actual }),
+    }
+}
+
+/// Cross-checks the expected size against the Index of an in-memory `.xz` input.
+///
+/// Inputs without a readable Index (other formats, truncated or corrupt files) are skipped;
+/// the decoder reports those problems itself.
+pub fn check_index_size(
+    input: &[u8],
+    options: &DecompressionOptions,
+) -> Result<Option<SizeMismatch>> {
+    if options.expected_size().is_none()
+        || !matches!(options.mode(), DecodeMode::Auto | DecodeMode::Xz)
+        || !input.starts_with(&XZ_STREAM_HEADER_MAGIC)
+    {
+        return Ok(None);
+    }
+
+    let mut cursor = io::Cursor::new(input);
+    let Ok(info) = extract_file_info(&mut cursor, Some(options.memlimit())) else {
+        return Ok(None);
+    };
+
+    // Without `CONCATENATED` only the first stream is decoded, so a multi-stream Index
+    // doesn't describe the output.
+    if info.stream_count() > 1 && !options.flags().is_concatenated() {
+        return Ok(None);
+    }
+
+    check_expected_size(options, info.uncompressed_size())
+}
+
 /// Copy already-read prefix and the remaining reader contents to the output unchanged.
 pub fn passthrough_sync<R: Read, W: io::Write>(
     prefix: &[u8],
@@ -265,6 +351,8 @@ pub struct DecoderSession {
     detected_lzip_input: bool,
     lzip_decoder_options: Option<DecompressionOptions>,
     bootstrapped: bool,
+    /// Error that is reported once the output produced along with it has been written
+    deferred_error: Option<Error>,
 }
 
 impl DecoderSession {
@@ -281,11 +369,20 @@ impl DecoderSession {
             detected_lzip_input: false,
             lzip_decoder_options: None,
             bootstrapped: false,
+            deferred_error: None,
         })
     }
 
     /// Advances the decoder until it produces output, needs more input, or finishes.
+    ///
+    /// liblzma can decode data and detect an error in the same call, e.g. trailing garbage
+    /// after a `.lzma` stream. The decoded data is returned first and the error on the next
+    /// call, so the output matches what upstream xz writes before it reports the error.
     pub fn run(&mut self, options: &DecompressionOptions) -> Result<RunOutcome> {
+        if let Some(err) = self.deferred_error.take() {
+            return Err(err);
+        }
+
         loop {
             if self.pending_len == 0 {
                 self.consumed = 0;
@@ -306,7 +403,19 @@ impl DecoderSession {
                 };
                 let input = &self.input[consumed..pending_len];
                 let output = &mut self.output;
-                decoder.process(input, output, Action::Run)?
+                let total_out = decoder.total_out();
+                match decoder.process(input, output, Action::Run) {
+                    Ok(progress) => progress,
+                    Err(err) => {
+                        let written =
+                            usize::try_from(decoder.total_out() - total_out).unwrap_or_default();
+                        if written == 0 {
+                            return Err(err.into());
+                        }
+                        self.deferred_error = Some(err.into());
+                        return Ok(RunOutcome::new(written, RunAction::Continue));
+                    }
+                }
             };
 
             self.consumed += used;
@@ -488,7 +597,8 @@ impl DecoderBootstrap {
         options: &DecompressionOptions,
         first_chunk: &[u8],
     ) -> Result<Self> {
-        let detected_lzip_input = first_chunk.starts_with(&LZIP_HEADER_MAGIC);
+        let detected_lzip_input =
+            options.mode() == DecodeMode::Lzip || first_chunk.starts_with(&LZIP_HEADER_MAGIC);
         if detected_lzip_input && options.flags().is_concatenated() {
             let mut lzip_flags = options.flags();
             lzip_flags.remove(Flags::CONCATENATED);
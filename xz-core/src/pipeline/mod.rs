@@ -2,12 +2,19 @@
 
 #[cfg(feature = "async")]
 mod r#async;
+mod blocks;
 mod decode;
+#[cfg(test)]
+mod properties;
+mod resume;
 mod sync;
+mod verify;
 
 #[cfg(feature = "async")]
 pub use r#async::{compress_async, decompress_async};
-pub use sync::{compress, decompress};
+pub use resume::resume_decompress;
+pub use sync::{compress, decompress, decompress_to_vec, recompress};
+pub use verify::{verify_blocks, BlockFailure};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,334 @@
+//! Randomized round-trip properties of the sync and async pipelines.
+//!
+//! Every case draws its input data, read chunk sizes, buffer sizes, preset, check,
+//! thread count, Block list and flush points from a seeded generator, so a failure
+//! names the seed that reproduces it. Set `XZ_CORE_PROPERTY_SEED` to rerun only that
+//! case, or `XZ_CORE_PROPERTY_CASES` to run more of them.
+
+use std::collections::VecDeque;
+use std::env;
+use std::io::{self, Read};
+use std::num::NonZeroUsize;
+
+use crate::config::{DecompressionStatus, StreamSummary};
+use crate::options::{Compression, CompressionOptions, DecompressionOptions, IntegrityCheck};
+use crate::pipeline::tests::SAMPLE;
+use crate::threading::{available_threads, Threading};
+
+use super::{compress, decompress};
+
+/// Cases run when `XZ_CORE_PROPERTY_CASES` is unset
+const DEFAULT_CASES: u64 = 24;
+
+/// Upper bound for the generated input size
+const MAX_DATA_LEN: usize = 96 * 1024;
+
+/// Largest compression preset a case uses; higher ones only add encoder memory.
+const MAX_LEVEL: u32 = 6;
+
+/// Checks a case picks from
+const CHECKS: [IntegrityCheck; 4] = [
+    IntegrityCheck::None,
+    IntegrityCheck::Crc32,
+    IntegrityCheck::Crc64,
+    IntegrityCheck::Sha256,
+];
+
+/// One generated round-trip scenario
+struct Case {
+    seed: u64,
+    data: Vec<u8>,
+    /// Read sizes the reader hands out in turn, cycling when exhausted
+    chunks: Vec<usize>,
+    /// Input offsets at which the reader reports a timeout, in increasing order
+    flush_points: Vec<usize>,
+    compression: CompressionOptions,
+    decompression: DecompressionOptions,
+}
+
+impl Case {
+    fn generate(seed: u64) -> Self {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let data = generate_data(&mut rng);
+
+        let chunks = (0..rng.usize(1..8))
+            .map(|_| rng.usize(1..=16 * 1024))
+            .collect();
+        let mut flush_points: Vec<usize> = (0..rng.usize(0..4))
+            .map(|_| rng.usize(0..=data.len()))
+            .collect();
+        flush_points.sort_unstable();
+
+        let max_threads = available_threads().min(4);
+        let block_list = if rng.bool() {
+            (0..rng.usize(1..4))
+                .map(|_| rng.u64(1..=32 * 1024))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let compression = CompressionOptions::default()
+            .with_level(Compression::try_from(rng.u32(0..=MAX_LEVEL)).expect("valid preset"))
+            .with_check(CHECKS[rng.usize(0..CHECKS.len())])
+            .with_threads(Threading::Exact(rng.u32(1..=max_threads)))
+            .with_block_list(block_list)
+            .with_flush_on_timeout(true)
+            .with_input_buffer_size(buffer_size(&mut rng))
+            .with_output_buffer_size(buffer_size(&mut rng));
+        let decompression = DecompressionOptions::default()
+            .with_threads(Threading::Exact(rng.u32(1..=max_threads)))
+            .with_input_buffer_size(buffer_size(&mut rng))
+            .with_output_buffer_size(buffer_size(&mut rng));
+
+        Self {
+            seed,
+            data,
+            chunks,
+            flush_points,
+            compression,
+            decompression,
+        }
+    }
+
+    /// Reader over `input` following this case's chunk sizes and, when `flush` is
+    /// set, its flush points.
+    fn reader<'a>(&self, input: &'a [u8], flush: bool) -> ScriptedReader<'a> {
+        ScriptedReader {
+            data: input,
+            pos: 0,
+            chunks: self.chunks.clone(),
+            next_chunk: 0,
+            flush_points: if flush {
+                self.flush_points.iter().copied().collect()
+            } else {
+                VecDeque::new()
+            },
+        }
+    }
+}
+
+/// Input mixing incompressible, repetitive and text segments, so both literal and
+/// match coding paths are hit
+fn generate_data(rng: &mut fastrand::Rng) -> Vec<u8> {
+    let len = match rng.u8(0..8) {
+        0 => 0,
+        1 => rng.usize(1..64),
+        _ => rng.usize(64..=MAX_DATA_LEN),
+    };
+
+    let mut data = Vec::with_capacity(len);
+    while data.len() < len {
+        let segment = rng.usize(1..=(len - data.len()).min(8 * 1024));
+        match rng.u8(0..4) {
+            0 => data.extend((0..segment).map(|_| rng.u8(..))),
+            1 => data.extend(std::iter::repeat_n(rng.u8(..), segment)),
+            2 => {
+                let pattern: Vec<u8> = (0..rng.usize(2..16)).map(|_| rng.u8(..)).collect();
+                data.extend(pattern.iter().copied().cycle().take(segment));
+            }
+            _ => data.extend(SAMPLE.iter().copied().cycle().take(segment)),
+        }
+    }
+    data
+}
+
+/// Pipeline buffer size, small enough at times to force many encoder calls
+fn buffer_size(rng: &mut fastrand::Rng) -> NonZeroUsize {
+    let size = if rng.bool() {
+        rng.usize(1..=256)
+    } else {
+        rng.usize(256..=64 * 1024)
+    };
+    NonZeroUsize::new(size).expect("non-zero buffer size")
+}
+
+/// Seeds of the cases to run
+fn seeds() -> Vec<u64> {
+    if let Ok(seed) = env::var("XZ_CORE_PROPERTY_SEED") {
+        return vec![seed.parse().expect("XZ_CORE_PROPERTY_SEED is a u64")];
+    }
+    let cases = env::var("XZ_CORE_PROPERTY_CASES").map_or(DEFAULT_CASES, |cases| {
+        cases.parse().expect("XZ_CORE_PROPERTY_CASES is a u64")
+    });
+    (0..cases).collect()
+}
+
+/// Reader handing out data in scripted chunk sizes that fails with
+/// [`io::ErrorKind::TimedOut`] once at every flush point
+struct ScriptedReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    chunks: Vec<usize>,
+    next_chunk: usize,
+    flush_points: VecDeque<usize>,
+}
+
+impl ScriptedReader<'_> {
+    /// Number of bytes the next read may return, or a timeout
+    fn next_len(&mut self, capacity: usize) -> io::Result<usize> {
+        if self.flush_points.front() == Some(&self.pos) {
+            self.flush_points.pop_front();
+            return Err(io::Error::from(io::ErrorKind::TimedOut));
+        }
+
+        let chunk = self.chunks[self.next_chunk % self.chunks.len()];
+        self.next_chunk += 1;
+        let until_flush = self
+            .flush_points
+            .front()
+            .map_or(usize::MAX, |point| point - self.pos);
+        Ok(chunk
+            .min(capacity)
+            .min(until_flush)
+            .min(self.data.len() - self.pos))
+    }
+}
+
+impl Read for ScriptedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.next_len(buf.len())?;
+        buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "async")]
+impl tokio::io::AsyncRead for ScriptedReader<'_> {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        let result = self.next_len(buf.remaining()).map(|len| {
+            let start = self.pos;
+            buf.put_slice(&self.data[start..start + len]);
+            self.pos += len;
+        });
+        std::task::Poll::Ready(result)
+    }
+}
+
+/// Compresses `case.data` with the sync pipeline and checks the summary.
+fn compress_sync(case: &Case) -> (Vec<u8>, StreamSummary) {
+    let mut compressed = Vec::new();
+    let summary = compress(
+        case.reader(&case.data, true),
+        &mut compressed,
+        &case.compression,
+    )
+    .unwrap_or_else(|err| panic!("seed {}: compression failed: {err}", case.seed));
+    assert_eq!(
+        summary.bytes_read,
+        case.data.len() as u64,
+        "seed {}",
+        case.seed
+    );
+    assert_eq!(
+        summary.bytes_written,
+        compressed.len() as u64,
+        "seed {}",
+        case.seed
+    );
+    (compressed, summary)
+}
+
+/// Decompresses `compressed` with the sync pipeline and checks it restores `case.data`.
+fn check_decompress_sync(case: &Case, compressed: &[u8]) {
+    let mut decompressed = Vec::new();
+    let outcome = decompress(
+        case.reader(compressed, false),
+        &mut decompressed,
+        &case.decompression,
+    )
+    .unwrap_or_else(|err| panic!("seed {}: decompression failed: {err}", case.seed));
+    assert!(
+        decompressed == case.data,
+        "seed {}: data differs",
+        case.seed
+    );
+    assert_eq!(
+        outcome.status,
+        DecompressionStatus::Decompressed,
+        "seed {}",
+        case.seed
+    );
+    assert_eq!(
+        outcome.bytes_read,
+        compressed.len() as u64,
+        "seed {}",
+        case.seed
+    );
+    assert_eq!(
+        outcome.bytes_written,
+        case.data.len() as u64,
+        "seed {}",
+        case.seed
+    );
+}
+
+/// Any generated data, chunking, buffer sizes, preset, check, thread count, Block
+/// list and flush points survive a sync round trip byte for byte.
+#[test]
+fn sync_round_trip_property() {
+    for seed in seeds() {
+        let case = Case::generate(seed);
+        let (compressed, _) = compress_sync(&case);
+        check_decompress_sync(&case, &compressed);
+    }
+}
+
+/// The async pipeline produces the same stream and summaries as the sync one, and
+/// each decodes the other's output.
+#[cfg(feature = "async")]
+#[test]
+fn async_matches_sync_property() {
+    use super::{compress_async, decompress_async};
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime");
+
+    for seed in seeds() {
+        let case = Case::generate(seed);
+        let (sync_compressed, sync_summary) = compress_sync(&case);
+
+        let mut async_compressed = Vec::new();
+        let async_summary = runtime
+            .block_on(compress_async(
+                case.reader(&case.data, true),
+                &mut async_compressed,
+                &case.compression,
+            ))
+            .unwrap_or_else(|err| panic!("seed {seed}: async compression failed: {err}"));
+        assert_eq!(async_summary, sync_summary, "seed {seed}");
+        assert!(
+            async_compressed == sync_compressed,
+            "seed {seed}: streams differ"
+        );
+        check_decompress_sync(&case, &async_compressed);
+
+        let mut decompressed = Vec::new();
+        let outcome = runtime
+            .block_on(decompress_async(
+                case.reader(&sync_compressed, false),
+                &mut decompressed,
+                &case.decompression,
+            ))
+            .unwrap_or_else(|err| panic!("seed {seed}: async decompression failed: {err}"));
+        assert!(decompressed == case.data, "seed {seed}: data differs");
+        assert_eq!(
+            outcome.status,
+            DecompressionStatus::Decompressed,
+            "seed {seed}"
+        );
+        assert_eq!(
+            outcome.bytes_read,
+            sync_compressed.len() as u64,
+            "seed {seed}"
+        );
+        assert_eq!(outcome.bytes_written, case.data.len() as u64, "seed {seed}");
+    }
+}
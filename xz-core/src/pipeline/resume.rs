@@ -0,0 +1,322 @@
+//! Resuming XZ decompression from a Block boundary.
+//!
+//! An interrupted extraction does not need to start over from byte zero: the Index records
+//! where every Block begins in both the compressed and the uncompressed data, so decoding can
+//! seek straight to the Block containing the first missing byte.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use lzma_safe::encoder::options::IntegrityCheck;
+use lzma_safe::stream::{StreamFlags, BLOCK_HEADER_SIZE_MAX, HEADER_SIZE};
+use lzma_safe::{Action, BlockDecoder, Stream};
+
+use crate::buffer::Buffer;
+use crate::config::StreamSummary;
+use crate::error::{BackendError, Error, Result};
+use crate::file_info::{BlockInfo, FileInfo, StreamInfo};
+use crate::options::DecompressionOptions;
+
+/// Resumes decompression of an XZ file at an uncompressed offset.
+///
+/// Locates the Block containing `offset` using the Index in `info`, seeks `reader` to that
+/// Block and decodes it and every following Block (across all Streams), writing only the
+/// output from `offset` onwards. Appending the result to the first `offset` bytes of a
+/// previous extraction yields the complete uncompressed data.
+///
+/// Memory limit, buffer sizes and [`DecoderFlags::IGNORE_CHECK`] are taken from `options`;
+/// threading, decode mode and the expected-size hint do not apply.
+///
+/// # Parameters
+///
+/// * `reader` - Seekable source containing the whole `.xz` file described by `info`
+/// * `writer` - Output destination for data starting at `offset`
+/// * `info` - Metadata previously extracted with [`crate::file_info::extract_file_info`]
+/// * `offset` - Number of uncompressed bytes already produced
+/// * `options` - Decompression configuration options [`DecompressionOptions`]
+///
+/// # Returns
+///
+/// Returns a [`StreamSummary`] with the compressed bytes read (starting at the containing
+/// Block) and the uncompressed bytes written.
+///
+/// # Errors
+///
+/// This function will return an error if:
+///
+/// - `offset` is past the end of the uncompressed data
+/// - I/O operations on reader or writer fail
+/// - A Block is corrupt, exceeds the memory limit, or does not match the Index
+///
+/// [`DecoderFlags::IGNORE_CHECK`]: lzma_safe::decoder::options::Flags::IGNORE_CHECK
+pub fn resume_decompress<R, W>(
+    mut reader: R,
+    mut writer: W,
+    info: &FileInfo,
+    offset: u64,
+    options: &DecompressionOptions,
+) -> Result<StreamSummary>
+where
+    R: Read + Seek,
+    W: Write,
+{
+    let total = info.uncompressed_size();
+    if offset > total {
+        return Err(Error::InvalidOption(format!(
+            "resume offset {offset} is past the end of the uncompressed data ({total} bytes)"
+        )));
+    }
+
+    let streams = info.streams();
+    let blocks = info.blocks();
+    let Some(first) = blocks
+        .iter()
+        .position(|block| block.uncompressed_file_offset + block.uncompressed_size > offset)
+    else {
+        writer.flush()?;
+        return Ok(StreamSummary::new(0, 0));
+    };
+
+    let mut input = Buffer::new(options.input_capacity())?;
+    let mut output = Buffer::new(options.output_capacity())?;
+    let mut summary = StreamSummary::new(0, 0);
+    let mut skip = offset - blocks[first].uncompressed_file_offset;
+    let mut current_stream: Option<(u64, IntegrityCheck)> = None;
+
+    for block in &blocks[first..] {
+        let stream = containing_stream(&streams, block)?;
+        let check = match current_stream {
+            Some((number, check)) if number == stream.number => check,
+            _ => {
+                let check = read_stream_check(&mut reader, stream)?;
+                current_stream = Some((stream.number, check));
+                check
+            }
+        };
+
+        let written = decode_block(
+            &mut reader,
+            &mut writer,
+            block,
+            check,
+            options,
+            &mut skip,
+            (&mut input, &mut output),
+        )?;
+        summary.bytes_read += block.total_size;
+        summary.bytes_written += written;
+    }
+
+    writer.flush()?;
+    Ok(summary)
+}
+
+/// Find the Stream whose compressed range contains `block`.
+pub(super) fn containing_stream<'a>(
+    streams: &'a [StreamInfo],
+    block: &BlockInfo,
+) -> Result<&'a StreamInfo> {
+    streams
+        .iter()
+        .find(|stream| {
+            block.compressed_file_offset >= stream.compressed_offset
+                && block.compressed_file_offset < stream.compressed_offset + stream.compressed_size
+        })
+        .ok_or(Error::Backend(BackendError::DataError))
+}
+
+/// Read the integrity check type from a Stream Header.
+pub(super) fn read_stream_check<R: Read + Seek>(
+    reader: &mut R,
+    stream: &StreamInfo,
+) -> Result<IntegrityCheck> {
+    let mut header = [0u8; HEADER_SIZE];
+    reader.seek(SeekFrom::Start(stream.compressed_offset))?;
+    reader.read_exact(&mut header)?;
+    Ok(StreamFlags::decode_header(&header)?.check)
+}
+
+/// Decode one Block, discarding the first `skip` bytes of its output.
+///
+/// Returns the number of bytes written to `writer`.
+pub(super) fn decode_block<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    block: &BlockInfo,
+    check: IntegrityCheck,
+    options: &DecompressionOptions,
+    skip: &mut u64,
+    (input, output): (&mut Buffer, &mut Buffer),
+) -> Result<u64>
+where
+    R: Read + Seek,
+    W: Write,
+{
+    reader.seek(SeekFrom::Start(block.compressed_file_offset))?;
+
+    let mut header = [0u8; BLOCK_HEADER_SIZE_MAX];
+    reader.read_exact(&mut header[..1])?;
+    let header_size =
+        BlockDecoder::header_size(header[0]).ok_or(Error::Backend(BackendError::DataError))?;
+    reader.read_exact(&mut header[1..header_size])?;
+
+    let mut decoder = BlockDecoder::new(
+        &header[..header_size],
+        check,
+        options.flags().is_ignore_check(),
+        options.memlimit().get(),
+        Stream::default(),
+    )?;
+
+    let mut remaining = block.total_size.saturating_sub(header_size as u64);
+    let mut written_total = 0u64;
+
+    while !decoder.is_finished() {
+        let want = usize::try_from(remaining).map_or(input.len(), |r| r.min(input.len()));
+        let read = reader.read(&mut input[..want])?;
+        if read == 0 {
+            // The Index promised more bytes than the file contains.
+            return Err(Error::Backend(BackendError::DataError));
+        }
+        remaining -= read as u64;
+
+        let mut consumed = 0usize;
+        loop {
+            let (used, written) = decoder.process(&input[consumed..read], output, Action::Run)?;
+            consumed += used;
+
+            let mut produced = &output[..written];
+            let skipped = usize::try_from(*skip).map_or(produced.len(), |s| s.min(produced.len()));
+            produced = &produced[skipped..];
+            *skip -= skipped as u64;
+            if !produced.is_empty() {
+                writer.write_all(produced)?;
+                written_total += produced.len() as u64;
+            }
+
+            if decoder.is_finished() || (consumed == read && written < output.len()) {
+                break;
+            }
+            if used == 0 && written == 0 {
+                break;
+            }
+        }
+
+        if !decoder.is_finished() && remaining == 0 {
+            return Err(Error::Backend(BackendError::DataError));
+        }
+    }
+
+    if decoder.total_out() != block.uncompressed_size {
+        return Err(Error::Backend(BackendError::DataError));
+    }
+
+    Ok(written_total)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::num::NonZeroU64;
+
+    use crate::file_info::extract_file_info;
+    use crate::options::CompressionOptions;
+    use crate::pipeline::compress;
+
+    use super::*;
+
+    /// Incompressible enough data to span several 64 KiB Blocks
+    fn sample() -> Vec<u8> {
+        (0..200_000u32).flat_map(u32::to_le_bytes).collect()
+    }
+
+    /// Compresses `data` into 64 KiB Blocks with a CRC64 check
+    fn compress_multi_block(data: &[u8]) -> Vec<u8> {
+        let options = CompressionOptions::default()
+            .with_block_size(NonZeroU64::new(64 * 1024))
+            .with_check(IntegrityCheck::Crc64);
+        let mut compressed = Vec::new();
+        compress(data, &mut compressed, &options).unwrap();
+        compressed
+    }
+
+    /// Test that resuming in the middle of a Block yields exactly the tail of the data
+    /// without reading the whole input.
+    #[test]
+    fn resume_mid_block_matches_tail() {
+        let data = sample();
+        let compressed = compress_multi_block(&data);
+        let info = extract_file_info(&mut Cursor::new(&compressed), None).unwrap();
+        assert!(info.block_count() > 2);
+
+        let offset = 300_001u64;
+        let mut output = Vec::new();
+        let summary = resume_decompress(
+            Cursor::new(&compressed),
+            &mut output,
+            &info,
+            offset,
+            &DecompressionOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(output, &data[offset as usize..]);
+        assert_eq!(summary.bytes_written, output.len() as u64);
+        assert!(summary.bytes_read < compressed.len() as u64);
+    }
+
+    /// Test that resuming near the end of one stream continues into the next one.
+    #[test]
+    fn resume_across_concatenated_streams() {
+        let first = sample();
+        let second = b"second stream payload".repeat(1000);
+        let mut compressed = compress_multi_block(&first);
+        compressed.extend(compress_multi_block(&second));
+        let info = extract_file_info(&mut Cursor::new(&compressed), None).unwrap();
+
+        let offset = first.len() as u64 - 10;
+        let mut output = Vec::new();
+        resume_decompress(
+            Cursor::new(&compressed),
+            &mut output,
+            &info,
+            offset,
+            &DecompressionOptions::default(),
+        )
+        .unwrap();
+
+        let mut expected = first[first.len() - 10..].to_vec();
+        expected.extend_from_slice(&second);
+        assert_eq!(output, expected);
+    }
+
+    /// Test that resuming at the end yields nothing and resuming past it is rejected.
+    #[test]
+    fn resume_at_end_and_past_end() {
+        let data = sample();
+        let compressed = compress_multi_block(&data);
+        let info = extract_file_info(&mut Cursor::new(&compressed), None).unwrap();
+        let options = DecompressionOptions::default();
+
+        let mut output = Vec::new();
+        let summary = resume_decompress(
+            Cursor::new(&compressed),
+            &mut output,
+            &info,
+            data.len() as u64,
+            &options,
+        )
+        .unwrap();
+        assert!(output.is_empty());
+        assert_eq!(summary, StreamSummary::new(0, 0));
+
+        let err = resume_decompress(
+            Cursor::new(&compressed),
+            &mut output,
+            &info,
+            data.len() as u64 + 1,
+            &options,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::InvalidOption(_)));
+    }
+}
@@ -1,15 +1,24 @@
 //! Synchronous XZ compression and decompression pipeline.
 
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
 
 use lzma_safe::Action;
 
 use crate::buffer::Buffer;
 use crate::config::{DecompressionOutcome, StreamSummary};
-use crate::error::{BackendError, Result};
+use crate::error::{BackendError, Error, Result};
+use crate::metrics::{Operation, Span};
 use crate::options::{BuiltDecoder, BuiltEncoder, CompressionOptions, DecompressionOptions};
 
-use super::decode::{passthrough_sync, DecoderSession, DecompressionProbe, ReadAction, RunAction};
+use super::blocks::BlockBoundaries;
+use super::decode::{
+    check_index_size, passthrough_sync, DecoderSession, DecompressionProbe, ReadAction, RunAction,
+};
+
+/// Largest expansion of its input that [`decompress_to_vec`] pre-sizes its output for
+///
+/// The expected size is only a hint; beyond this the output grows as data is decoded.
+const MAX_PRESIZE_RATIO: usize = 64;
 
 /// Compresses data from a reader into a writer using the provided options.
 ///
@@ -32,7 +41,16 @@ use super::decode::{passthrough_sync, DecoderSession, DecompressionProbe, ReadAc
 /// - I/O operations on reader or writer fail
 /// - Invalid compression parameters are specified
 /// - Threading limits are exceeded
-pub fn compress<R, W>(
+pub fn compress<R, W>(reader: R, writer: W, options: &CompressionOptions) -> Result<StreamSummary>
+where
+    R: Read,
+    W: Write,
+{
+    let span = Span::start(options.metrics(), Operation::Compress);
+    span.finish(compress_unmetered(reader, writer, options))
+}
+
+fn compress_unmetered<R, W>(
     mut reader: R,
     mut writer: W,
     options: &CompressionOptions,
@@ -44,11 +62,20 @@ where
     let mut encoder = options.build_encoder()?;
     let mut input = Buffer::new(options.input_capacity())?;
     let mut output = Buffer::new(options.output_capacity())?;
+    let mut blocks = BlockBoundaries::new(options.block_list());
     let mut total_in = 0u64;
     let mut total_out = 0u64;
 
     loop {
-        let read = reader.read(&mut input)?;
+        let read = match reader.read(&mut input) {
+            Ok(read) => read,
+            Err(err) if options.flush_on_timeout() && err.kind() == ErrorKind::TimedOut => {
+                finish_block_sync(&mut encoder, &mut writer, &mut output, &mut total_out)?;
+                writer.flush()?;
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
         if read == 0 {
             finish_encoder_sync(&mut encoder, &mut writer, &mut output, &mut total_out)?;
             return Ok(StreamSummary::new(total_in, total_out));
@@ -56,8 +83,9 @@ where
 
         let mut consumed = 0usize;
         while consumed < read {
+            let end = consumed + blocks.limit(read - consumed);
             let (used, written) =
-                encoder.process(&input[consumed..read], &mut output, Action::Run)?;
+                encoder.process(&input[consumed..end], &mut output, Action::Run)?;
             if written > 0 {
                 writer.write_all(&output[..written])?;
                 total_out += written as u64;
@@ -65,6 +93,10 @@ where
             consumed += used;
             total_in += used as u64;
 
+            if blocks.consume(used) {
+                finish_block_sync(&mut encoder, &mut writer, &mut output, &mut total_out)?;
+            }
+
             if encoder.is_finished() {
                 writer.flush()?;
                 return Ok(StreamSummary::new(total_in, total_out));
@@ -100,6 +132,19 @@ where
 /// - Memory limits are exceeded during decompression
 /// - Threading is requested for unsupported decode modes
 pub fn decompress<R, W>(
+    reader: R,
+    writer: W,
+    options: &DecompressionOptions,
+) -> Result<DecompressionOutcome>
+where
+    R: Read,
+    W: Write,
+{
+    let span = Span::start(options.metrics(), Operation::Decompress);
+    span.finish(decompress_unmetered(reader, writer, options))
+}
+
+fn decompress_unmetered<R, W>(
     mut reader: R,
     mut writer: W,
     options: &DecompressionOptions,
@@ -111,13 +156,203 @@ where
     let probe = DecompressionProbe::read_sync(&mut reader, options)?;
     if probe.is_passthrough() {
         let summary = passthrough_sync(probe.prefix(), &mut reader, &mut writer)?;
-        return Ok(probe.build_outcome(summary));
+        return probe.build_outcome(summary, options);
     }
 
     let prefix = probe.prefix().to_vec();
     let mut reader = std::io::Cursor::new(prefix).chain(reader);
-    let summary = decompress_stream(&mut reader, &mut writer, options)?;
-    Ok(probe.build_outcome(summary))
+    let summary = decompress_stream(&mut reader, &mut writer, &probe.decoder_options(options))?;
+    probe.build_outcome(summary, options)
+}
+
+/// Decompresses an in-memory buffer into a newly allocated `Vec`.
+///
+/// When [`DecompressionOptions::with_expected_size`] is set, the output `Vec` is pre-sized
+/// to the announced size, up to a multiple of the input length, and, for `.xz` input, the
+/// size is cross-checked against the stream Index before decoding starts.
+///
+/// # Parameters
+///
+/// * `input` - Complete compressed input
+/// * `options` - Decompression configuration options [`DecompressionOptions`]
+///
+/// # Returns
+///
+/// Returns the decompressed bytes together with the [`DecompressionOutcome`].
+///
+/// # Errors
+///
+/// This function will return an error if:
+///
+/// - Decompression fails for any reason listed for [`decompress`]
+/// - The output buffer for the expected size cannot be allocated
+/// - The expected size doesn't match and the mismatch policy is `Error`
+pub fn decompress_to_vec(
+    input: &[u8],
+    options: &DecompressionOptions,
+) -> Result<(Vec<u8>, DecompressionOutcome)> {
+    let span = Span::start(options.metrics(), Operation::Decompress);
+    span.finish(decompress_to_vec_unmetered(input, options))
+}
+
+fn decompress_to_vec_unmetered(
+    input: &[u8],
+    options: &DecompressionOptions,
+) -> Result<(Vec<u8>, DecompressionOutcome)> {
+    let index_mismatch = check_index_size(input, options)?;
+
+    let mut output = Vec::new();
+    if let Some(size) = options.expected_size() {
+        // A bogus hint is only a mismatch, so it mustn't make the allocation fail.
+        let capacity = usize::try_from(size)
+            .unwrap_or(usize::MAX)
+            .min(input.len().saturating_mul(MAX_PRESIZE_RATIO));
+        output
+            .try_reserve_exact(capacity)
+            .map_err(|_| Error::AllocationFailed { capacity })?;
+    }
+
+    let mut outcome = decompress_unmetered(input, &mut output, options)?;
+    if outcome.size_mismatch.is_none() {
+        outcome.size_mismatch = index_mismatch;
+    }
+    Ok((output, outcome))
+}
+
+/// Decompresses data from a reader and compresses it again into a writer.
+///
+/// The decoded data is fed straight to the encoder, so no more than the coder buffers is
+/// held in memory whatever the size of the input. This is how a file is rewritten with a
+/// different preset or Block size.
+///
+/// # Parameters
+///
+/// * `reader` - Input source implementing [`Read`] trait
+/// * `writer` - Output destination implementing [`Write`] trait
+/// * `decompression` - Options for decoding the input [`DecompressionOptions`]
+/// * `compression` - Options for encoding the output [`CompressionOptions`]
+///
+/// # Returns
+///
+/// Returns a [`StreamSummary`] with the bytes read from `reader` and written to `writer`.
+///
+/// # Errors
+///
+/// This function will return an error if:
+///
+/// - Decompression fails for any reason listed for [`decompress`]
+/// - Compression fails for any reason listed for [`compress`]
+pub fn recompress<R, W>(
+    reader: R,
+    writer: W,
+    decompression: &DecompressionOptions,
+    compression: &CompressionOptions,
+) -> Result<StreamSummary>
+where
+    R: Read,
+    W: Write,
+{
+    let span = Span::start(compression.metrics(), Operation::Recompress);
+    span.finish(recompress_unmetered(
+        reader,
+        writer,
+        decompression,
+        compression,
+    ))
+}
+
+fn recompress_unmetered<R, W>(
+    reader: R,
+    writer: W,
+    decompression: &DecompressionOptions,
+    compression: &CompressionOptions,
+) -> Result<StreamSummary>
+where
+    R: Read,
+    W: Write,
+{
+    let mut encoder = EncoderWriter {
+        encoder: compression.build_encoder()?,
+        output: Buffer::new(compression.output_capacity())?,
+        blocks: BlockBoundaries::new(compression.block_list()),
+        writer,
+        total_out: 0,
+        error: None,
+    };
+
+    let outcome = decompress_unmetered(reader, &mut encoder, decompression);
+    // The decoder only sees an I/O error; the encoder kept the actual one.
+    if let Some(err) = encoder.error.take() {
+        return Err(err);
+    }
+    let outcome = outcome?;
+
+    let EncoderWriter {
+        encoder: mut built,
+        mut output,
+        mut writer,
+        mut total_out,
+        ..
+    } = encoder;
+    finish_encoder_sync(&mut built, &mut writer, &mut output, &mut total_out)?;
+    Ok(StreamSummary::new(outcome.bytes_read, total_out))
+}
+
+/// Writer that compresses everything written to it, used by [`recompress`].
+struct EncoderWriter<'a, W> {
+    encoder: BuiltEncoder,
+    output: Buffer,
+    blocks: BlockBoundaries<'a>,
+    writer: W,
+    total_out: u64,
+    /// First encoder error, which [`Write`] can only pass on as an I/O error
+    error: Option<Error>,
+}
+
+impl<W: Write> EncoderWriter<'_, W> {
+    fn encode(&mut self, data: &[u8]) -> Result<()> {
+        let mut consumed = 0usize;
+        while consumed < data.len() {
+            let end = consumed + self.blocks.limit(data.len() - consumed);
+            let (used, written) =
+                self.encoder
+                    .process(&data[consumed..end], &mut self.output, Action::Run)?;
+            if written > 0 {
+                self.writer.write_all(&self.output[..written])?;
+                self.total_out += written as u64;
+            }
+            consumed += used;
+
+            if self.blocks.consume(used) {
+                finish_block_sync(
+                    &mut self.encoder,
+                    &mut self.writer,
+                    &mut self.output,
+                    &mut self.total_out,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for EncoderWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.encode(buf) {
+            Ok(()) => Ok(buf.len()),
+            Err(err) => {
+                let message = err.to_string();
+                self.error.get_or_insert(err);
+                Err(std::io::Error::other(message))
+            }
+        }
+    }
+
+    /// Compressed data is written as the encoder produces it; the end of the stream is
+    /// written by [`recompress`].
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 fn decompress_stream<R, W>(
@@ -160,6 +395,37 @@ where
     }
 }
 
+/// Ends the current Block at a block list boundary or for a flush.
+///
+/// # Parameters
+///
+/// * `encoder` - The encoder whose current Block is ended
+/// * `writer` - Output writer to receive the rest of the Block
+/// * `output` - Buffer for temporary storage of compressed data
+/// * `total_out` - Running count of total bytes written (updated in-place)
+///
+/// # Returns
+///
+/// * `Ok(())` once the Block has been written
+/// * `Err(_)` if the encoder or the writer fails
+fn finish_block_sync<W: Write>(
+    encoder: &mut BuiltEncoder,
+    writer: &mut W,
+    output: &mut [u8],
+    total_out: &mut u64,
+) -> Result<()> {
+    loop {
+        let (written, done) = encoder.finish_block(output)?;
+        if written > 0 {
+            writer.write_all(&output[..written])?;
+            *total_out += written as u64;
+        }
+        if done {
+            return Ok(());
+        }
+    }
+}
+
 /// Finishes the encoding process by flushing any remaining data from the encoder.
 ///
 /// # Parameters
@@ -274,7 +540,10 @@ mod tests {
     use std::num::{NonZeroU64, NonZeroUsize};
     use std::time::Duration;
 
-    use crate::config::{DecodeMode, DecompressionStatus, UnknownInputPolicy};
+    use crate::config::{
+        DecodeMode, DecompressionStatus, EncodeFormat, SizeMismatch, SizeMismatchPolicy,
+        UnknownInputPolicy,
+    };
     use crate::options::{
         Compression, CompressionOptions, DecompressionOptions, Flags, IntegrityCheck,
     };
@@ -480,6 +749,150 @@ mod tests {
         }
     }
 
+    /// Test that auto-detect mode honors an explicit thread count for every input format
+    #[test]
+    fn sync_auto_mode_with_threads() {
+        for format in [EncodeFormat::Xz, EncodeFormat::Lzma] {
+            let mut compressed = Vec::new();
+            let check = match format {
+                EncodeFormat::Lzma => IntegrityCheck::None,
+                _ => IntegrityCheck::Crc64,
+            };
+            let options = CompressionOptions::default()
+                .with_format(format)
+                .with_check(check);
+            compress(SAMPLE, &mut compressed, &options).unwrap();
+
+            for threads in [Threading::Exact(0), Threading::Exact(4)] {
+                let options = DecompressionOptions::default().with_threads(threads);
+                let mut decompressed = Vec::new();
+                decompress(compressed.as_slice(), &mut decompressed, &options).unwrap();
+                assert_eq!(decompressed, SAMPLE);
+            }
+        }
+    }
+
+    /// Test that a block list ends Blocks at the requested uncompressed offsets
+    #[test]
+    fn sync_block_list_splits_blocks() {
+        use crate::file_info::extract_file_info;
+
+        for threads in [Threading::Exact(1), Threading::Exact(2)] {
+            let options = CompressionOptions::default()
+                .with_threads(threads)
+                .with_block_list(vec![1000, 300_000])
+                .with_input_buffer_size(NonZeroUsize::new(4096).unwrap());
+            let mut compressed = Vec::new();
+            compress(LARGE_SAMPLE, &mut compressed, &options).unwrap();
+
+            let info = extract_file_info(&mut Cursor::new(&compressed), None).unwrap();
+            // 1000 + 300_000 * 3, then the remaining 47_576 bytes.
+            assert_eq!(info.block_count(), 5);
+
+            let mut decompressed = Vec::new();
+            decompress(
+                compressed.as_slice(),
+                &mut decompressed,
+                &DecompressionOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(decompressed, LARGE_SAMPLE);
+        }
+
+        let options = CompressionOptions::default().with_block_list(vec![1000, 0]);
+        let mut compressed = Vec::new();
+        compress(LARGE_SAMPLE, &mut compressed, &options).unwrap();
+        let info = extract_file_info(&mut Cursor::new(&compressed), None).unwrap();
+        assert_eq!(info.block_count(), 2);
+    }
+
+    /// Test that a block list is rejected for formats without Blocks
+    #[test]
+    fn sync_block_list_requires_xz() {
+        let options = CompressionOptions::default()
+            .with_format(EncodeFormat::Lzma)
+            .with_check(IntegrityCheck::None)
+            .with_block_list(vec![1000]);
+        let mut compressed = Vec::new();
+        assert!(compress(SAMPLE, &mut compressed, &options).is_err());
+    }
+
+    /// Test that a timed-out read flushes the data compressed so far
+    #[test]
+    fn sync_flush_on_timeout() {
+        use std::collections::VecDeque;
+        use std::io::{self, Read, Write};
+
+        struct ScriptedReader(VecDeque<io::Result<&'static [u8]>>);
+
+        impl Read for ScriptedReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.0.pop_front() {
+                    Some(Ok(chunk)) => {
+                        buf[..chunk.len()].copy_from_slice(chunk);
+                        Ok(chunk.len())
+                    }
+                    Some(Err(err)) => Err(err),
+                    None => Ok(0),
+                }
+            }
+        }
+
+        #[derive(Default)]
+        struct FlushRecorder {
+            data: Vec<u8>,
+            flushed: Vec<Vec<u8>>,
+        }
+
+        impl Write for FlushRecorder {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.data.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.flushed.push(self.data.clone());
+                Ok(())
+            }
+        }
+
+        let script = || {
+            ScriptedReader(VecDeque::from([
+                Ok(&b"first line\n"[..]),
+                Err(io::Error::from(io::ErrorKind::TimedOut)),
+                Ok(&b"second line\n"[..]),
+            ]))
+        };
+
+        let options = CompressionOptions::default()
+            .with_threads(Threading::Exact(1))
+            .with_flush_on_timeout(true);
+        let mut writer = FlushRecorder::default();
+        compress(script(), &mut writer, &options).unwrap();
+
+        // The output flushed at the timeout is a truncated stream with the first line.
+        let mut partial = Vec::new();
+        let result = decompress(
+            writer.flushed[0].as_slice(),
+            &mut partial,
+            &DecompressionOptions::default(),
+        );
+        assert!(result.is_err());
+        assert_eq!(partial, b"first line\n");
+
+        let mut decompressed = Vec::new();
+        decompress(
+            writer.data.as_slice(),
+            &mut decompressed,
+            &DecompressionOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(decompressed, b"first line\nsecond line\n");
+
+        let options = CompressionOptions::default().with_threads(Threading::Exact(1));
+        assert!(compress(script(), &mut Vec::new(), &options).is_err());
+    }
+
     /// Test with timeout configuration
     #[test]
     fn sync_with_timeout() {
@@ -683,6 +1096,29 @@ mod tests {
         }
     }
 
+    /// Upstream `good-1-v1.lz` test file, which decodes to `Hello\nWorld!\n`.
+    const LZIP_HELLO: &[u8] = &[
+        0x4c, 0x5a, 0x49, 0x50, 0x01, 0x0c, 0x00, 0x24, 0x19, 0x49, 0x98, 0x6f, 0x05, 0x15, 0x27,
+        0x27, 0x0d, 0x76, 0x78, 0xd0, 0x2a, 0x68, 0x17, 0x15, 0xff, 0xff, 0x75, 0xf8, 0x00, 0x00,
+        0x43, 0xa3, 0xa2, 0x15, 0x0d, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x32, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    /// Test lzip mode decodes `.lz` input and rejects other formats.
+    #[test]
+    fn sync_lzip_mode() {
+        let options = DecompressionOptions::default().with_mode(DecodeMode::Lzip);
+        let mut decompressed = Vec::new();
+        decompress(LZIP_HELLO, &mut decompressed, &options).unwrap();
+        assert_eq!(decompressed, b"Hello\nWorld!\n");
+
+        let mut compressed = Vec::new();
+        compress(SAMPLE, &mut compressed, &CompressionOptions::default()).unwrap();
+        let mut decompressed = Vec::new();
+        let result = decompress(compressed.as_slice(), &mut decompressed, &options);
+        assert!(result.is_err(), "xz input must be rejected in lzip mode");
+    }
+
     /// Test error handling - I/O errors during reading
     #[test]
     fn sync_error_io_failure() {
@@ -861,4 +1297,143 @@ mod tests {
         expected.extend_from_slice(LARGE_SAMPLE);
         assert_eq!(decompressed_all, expected);
     }
+
+    /// Test that data decoded before trailing garbage is written before the error.
+    #[test]
+    fn sync_lzma_trailing_garbage_keeps_decoded_data() {
+        let options = CompressionOptions::default()
+            .with_format(EncodeFormat::Lzma)
+            .with_check(IntegrityCheck::None);
+        let mut compressed = Vec::new();
+        compress(SAMPLE, &mut compressed, &options).unwrap();
+        compressed.extend_from_slice(b"garbage");
+
+        let mut output = Vec::new();
+        let options = DecompressionOptions::default().with_flags(Flags::CONCATENATED);
+        let result = decompress(compressed.as_slice(), &mut output, &options);
+
+        assert!(matches!(
+            result,
+            Err(Error::Backend(BackendError::DataError))
+        ));
+        assert_eq!(output, SAMPLE);
+    }
+
+    /// Test that `decompress_to_vec` pre-sizes the output and accepts a matching size.
+    #[test]
+    fn sync_decompress_to_vec_with_expected_size() {
+        let mut compressed = Vec::new();
+        compress(SAMPLE, &mut compressed, &CompressionOptions::default()).unwrap();
+
+        let options = DecompressionOptions::default().with_expected_size(SAMPLE.len() as u64);
+        let (output, outcome) = decompress_to_vec(&compressed, &options).unwrap();
+
+        assert_eq!(output, SAMPLE);
+        assert!(output.capacity() >= SAMPLE.len());
+        assert_eq!(outcome.size_mismatch, None);
+    }
+
+    /// Test that a huge expected size only pre-sizes the output up to a multiple of the
+    /// input, and is reported as a mismatch under the `Warn` policy.
+    #[test]
+    fn sync_decompress_to_vec_caps_bogus_size() {
+        let mut compressed = Vec::new();
+        compress(SAMPLE, &mut compressed, &CompressionOptions::default()).unwrap();
+
+        let options = DecompressionOptions::default().with_expected_size(u64::MAX);
+        let (output, outcome) = decompress_to_vec(&compressed, &options).unwrap();
+
+        assert_eq!(output, SAMPLE);
+        assert!(output.capacity() <= compressed.len() * MAX_PRESIZE_RATIO);
+        assert_eq!(
+            outcome.size_mismatch,
+            Some(SizeMismatch {
+                expected: u64::MAX,
+                actual: SAMPLE.len() as u64,
+            })
+        );
+    }
+
+    /// Test that recompressing keeps the data and applies the new options.
+    #[test]
+    fn sync_recompress_roundtrip() {
+        let mut original = Vec::new();
+        let options = CompressionOptions::default().with_level(Compression::Level0);
+        compress(LARGE_SAMPLE, &mut original, &options).unwrap();
+
+        let mut recompressed = Vec::new();
+        let compression = CompressionOptions::default()
+            .with_level(Compression::Level9)
+            .with_check(IntegrityCheck::Sha256);
+        let summary = recompress(
+            original.as_slice(),
+            &mut recompressed,
+            &DecompressionOptions::default(),
+            &compression,
+        )
+        .unwrap();
+        assert_eq!(summary.bytes_read, original.len() as u64);
+        assert_eq!(summary.bytes_written, recompressed.len() as u64);
+        // The Stream Flags in the header carry the check ID; 0x0A is SHA-256.
+        assert_eq!(recompressed[7] & 0x0F, 0x0A);
+
+        let mut output = Vec::new();
+        decompress(
+            recompressed.as_slice(),
+            &mut output,
+            &DecompressionOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(output, LARGE_SAMPLE);
+    }
+
+    /// Test that recompressing rejects input that isn't compressed.
+    #[test]
+    fn sync_recompress_rejects_unknown_input() {
+        let mut output = Vec::new();
+        let result = recompress(
+            SAMPLE,
+            &mut output,
+            &DecompressionOptions::default(),
+            &CompressionOptions::default(),
+        );
+        assert!(result.is_err());
+    }
+
+    /// Test that an Index mismatch is rejected before decoding under the `Error` policy.
+    #[test]
+    fn sync_decompress_to_vec_rejects_index_mismatch() {
+        let mut compressed = Vec::new();
+        compress(SAMPLE, &mut compressed, &CompressionOptions::default()).unwrap();
+
+        let options = DecompressionOptions::default()
+            .with_expected_size(1)
+            .with_size_mismatch_policy(SizeMismatchPolicy::Error);
+        let result = decompress_to_vec(&compressed, &options);
+
+        assert!(matches!(
+            result,
+            Err(Error::SizeMismatch { expected: 1, actual }) if actual == SAMPLE.len() as u64
+        ));
+    }
+
+    /// Test that the default `Warn` policy records the mismatch and keeps the output.
+    #[test]
+    fn sync_expected_size_mismatch_is_reported() {
+        let mut compressed = Vec::new();
+        compress(SAMPLE, &mut compressed, &CompressionOptions::default()).unwrap();
+
+        let options = DecompressionOptions::default().with_expected_size(1);
+        let mut output = Vec::new();
+        let outcome = decompress(compressed.as_slice(), &mut output, &options).unwrap();
+
+        assert_eq!(output, SAMPLE);
+        assert_eq!(
+            outcome.size_mismatch,
+            Some(SizeMismatch {
+                expected: 1,
+                actual: SAMPLE.len() as u64,
+            })
+        );
+    }
 }
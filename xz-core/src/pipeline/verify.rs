@@ -0,0 +1,166 @@
+//! Block-by-block verification of XZ files.
+//!
+//! Decoding a file from start to end only tells whether it is intact. Decoding every Block
+//! on its own, at the location recorded in the Index, also tells which Block is damaged and
+//! where in the file it starts.
+
+use std::io::{self, Read, Seek};
+
+use crate::buffer::Buffer;
+use crate::error::{Error, Result};
+use crate::file_info::FileInfo;
+use crate::options::DecompressionOptions;
+
+use super::resume::{containing_stream, decode_block, read_stream_check};
+
+/// A Block that failed verification.
+#[derive(Debug)]
+pub struct BlockFailure {
+    /// Stream containing the Block (1-based).
+    pub stream: u64,
+    /// Block number within the entire file (1-based).
+    pub block: u64,
+    /// Compressed offset of the Block within the file.
+    pub compressed_offset: u64,
+    /// Why the Block could not be decoded.
+    pub error: Error,
+}
+
+/// Decodes every Block of an XZ file and reports the first one that fails.
+///
+/// The decoded data is discarded. Memory limit, buffer sizes and
+/// [`DecoderFlags::IGNORE_CHECK`] are taken from `options`; threading, decode mode and the
+/// expected-size hint do not apply.
+///
+/// Only the Blocks listed in the Index are checked, so damage to the Stream Headers,
+/// Indexes or Stream Footers is found by [`crate::file_info::extract_file_info`] or a
+/// regular decode instead.
+///
+/// # Parameters
+///
+/// * `reader` - Seekable source containing the whole `.xz` file described by `info`
+/// * `info` - Metadata previously extracted with [`crate::file_info::extract_file_info`]
+/// * `options` - Decompression configuration options [`DecompressionOptions`]
+///
+/// # Returns
+///
+/// Returns `Ok(None)` if every Block decodes and matches the Index, or the first
+/// [`BlockFailure`] otherwise.
+///
+/// # Errors
+///
+/// Returns an error if the decoding buffers cannot be allocated.
+///
+/// [`DecoderFlags::IGNORE_CHECK`]: lzma_safe::decoder::options::Flags::IGNORE_CHECK
+pub fn verify_blocks<R>(
+    mut reader: R,
+    info: &FileInfo,
+    options: &DecompressionOptions,
+) -> Result<Option<BlockFailure>>
+where
+    R: Read + Seek,
+{
+    let streams = info.streams();
+    let mut input = Buffer::new(options.input_capacity())?;
+    let mut output = Buffer::new(options.output_capacity())?;
+    let mut current_stream = None;
+
+    for block in &info.blocks() {
+        let mut stream_number = 0;
+        let result = containing_stream(&streams, block).and_then(|stream| {
+            stream_number = stream.number;
+            let check = match current_stream {
+                Some((number, check)) if number == stream.number => check,
+                _ => {
+                    let check = read_stream_check(&mut reader, stream)?;
+                    current_stream = Some((stream.number, check));
+                    check
+                }
+            };
+
+            decode_block(
+                &mut reader,
+                &mut io::sink(),
+                block,
+                check,
+                options,
+                &mut 0,
+                (&mut input, &mut output),
+            )
+        });
+
+        if let Err(error) = result {
+            return Ok(Some(BlockFailure {
+                stream: stream_number,
+                block: block.number_in_file,
+                compressed_offset: block.compressed_file_offset,
+                error,
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::num::NonZeroU64;
+
+    use lzma_safe::encoder::options::IntegrityCheck;
+
+    use crate::file_info::extract_file_info;
+    use crate::options::CompressionOptions;
+    use crate::pipeline::compress;
+
+    use super::*;
+
+    fn compress_multi_block() -> Vec<u8> {
+        let data: Vec<u8> = (0..100_000u32).flat_map(u32::to_le_bytes).collect();
+        let options = CompressionOptions::default()
+            .with_block_size(NonZeroU64::new(64 * 1024))
+            .with_check(IntegrityCheck::Crc32);
+        let mut compressed = Vec::new();
+        compress(data.as_slice(), &mut compressed, &options).unwrap();
+        compressed
+    }
+
+    #[test]
+    fn verify_intact_file() {
+        let compressed = compress_multi_block();
+        let info = extract_file_info(&mut Cursor::new(&compressed), None).unwrap();
+
+        let failure = verify_blocks(
+            Cursor::new(&compressed),
+            &info,
+            &DecompressionOptions::default(),
+        )
+        .unwrap();
+        assert!(failure.is_none());
+    }
+
+    #[test]
+    fn verify_reports_corrupt_block() {
+        let mut compressed = compress_multi_block();
+        let info = extract_file_info(&mut Cursor::new(&compressed), None).unwrap();
+        let blocks = info.blocks();
+        assert!(blocks.len() > 2);
+
+        // Damage the compressed data in the middle of the third Block.
+        let target = &blocks[2];
+        let offset = target.compressed_file_offset + target.total_size / 2;
+        compressed[usize::try_from(offset).unwrap()] ^= 0xFF;
+
+        let failure = verify_blocks(
+            Cursor::new(&compressed),
+            &info,
+            &DecompressionOptions::default(),
+        )
+        .unwrap()
+        .expect("the damaged block should fail");
+        assert_eq!(failure.stream, 1);
+        assert_eq!(failure.block, 3);
+        assert_eq!(failure.compressed_offset, target.compressed_file_offset);
+        assert!(matches!(failure.error, Error::Backend(_)));
+    }
+}
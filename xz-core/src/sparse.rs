@@ -0,0 +1,341 @@
+//! Sparse file output for decompression.
+//!
+//! Decompressed disk and VM images often contain long runs of zero bytes. Upstream `xz`
+//! seeks over such runs when writing to a regular file so that the file system can leave
+//! them unallocated. [`SparseFileWriter`] does the same for any [`File`] opened for
+//! writing and can be passed to [`decompress`](crate::pipeline::decompress) directly.
+//!
+//! ```rust,no_run
+//! use std::fs::File;
+//!
+//! use xz_core::{options::DecompressionOptions, pipeline::decompress, sparse::SparseFileWriter};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let input = File::open("disk.img.xz")?;
+//! let output = SparseFileWriter::new(File::create("disk.img")?);
+//! decompress(input, output, &DecompressionOptions::default())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::File;
+use std::io;
+use std::io::{Seek, SeekFrom, Write};
+
+/// Capacity of the buffered writer in front of the file.
+const BUFFER_SIZE: usize = 512 * 1024;
+
+/// A writer that attempts to create sparse files by turning long runs of zeros into holes.
+///
+/// Holes are created by seeking, so the file must be a seekable regular file that is not
+/// opened in append mode. The file length is set on every [`flush`](Write::flush), which
+/// makes a trailing hole part of the file; [`decompress`](crate::pipeline::decompress)
+/// flushes the writer when it finishes.
+pub struct SparseFileWriter {
+    inner: io::BufWriter<File>,
+    pending_zeros: usize,
+    logical_pos: u64,
+    threshold: usize,
+}
+
+impl SparseFileWriter {
+    /// Creates a sparse writer using a conservative default threshold.
+    ///
+    /// # Parameters
+    ///
+    /// * `file` - Regular file opened for writing at offset zero
+    ///
+    /// # Returns
+    ///
+    /// A writer that turns zero runs of at least 4 KiB into holes.
+    #[must_use]
+    pub fn new(file: File) -> Self {
+        Self::with_threshold(file, Self::default_threshold())
+    }
+
+    /// Creates a sparse writer that will turn zero runs >= `threshold` into holes.
+    ///
+    /// # Parameters
+    ///
+    /// * `file` - Regular file opened for writing at offset zero
+    /// * `threshold` - Minimum length of a zero run that becomes a hole (at least 1)
+    ///
+    /// # Returns
+    ///
+    /// A writer with the given hole threshold.
+    #[must_use]
+    pub fn with_threshold(file: File, threshold: usize) -> Self {
+        Self {
+            inner: io::BufWriter::with_capacity(BUFFER_SIZE, file),
+            pending_zeros: 0,
+            logical_pos: 0,
+            threshold: threshold.max(1),
+        }
+    }
+
+    const fn default_threshold() -> usize {
+        // Upstream `xz` uses sparse output by default when possible. We keep the threshold
+        // conservative to avoid excessive seeks for short zero runs.
+        4096
+    }
+
+    fn write_literal_zeros(&mut self, mut len: usize) -> io::Result<()> {
+        const ZERO_BUF: [u8; 8192] = [0; 8192];
+
+        while len > 0 {
+            let chunk = len.min(ZERO_BUF.len());
+            self.inner.write_all(&ZERO_BUF[..chunk])?;
+            self.logical_pos = self
+                .logical_pos
+                .checked_add(chunk as u64)
+                .ok_or_else(|| io::Error::other("Sparse write overflow"))?;
+            len -= chunk;
+        }
+        Ok(())
+    }
+
+    fn flush_pending_zeros(&mut self) -> io::Result<()> {
+        if self.pending_zeros == 0 {
+            return Ok(());
+        }
+
+        let pending = self.pending_zeros;
+        self.pending_zeros = 0;
+
+        if pending >= self.threshold {
+            // Flush buffered data before seeking.
+            self.inner.flush()?;
+
+            let new_pos = self
+                .logical_pos
+                .checked_add(pending as u64)
+                .ok_or_else(|| io::Error::other("Sparse seek overflow"))?;
+
+            self.inner.get_mut().seek(SeekFrom::Start(new_pos))?;
+            self.logical_pos = new_pos;
+            Ok(())
+        } else {
+            self.write_literal_zeros(pending)
+        }
+    }
+
+    fn finalize_len(&mut self) -> io::Result<()> {
+        // If we have an unmaterialized hole at EOF, we must still ensure the file length matches
+        // the logical length.
+        self.inner.flush()?;
+        self.inner.get_mut().set_len(self.logical_pos)?;
+        Ok(())
+    }
+}
+
+impl io::Write for SparseFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // We always consume the whole buffer or return an error.
+        self.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        let mut i = 0usize;
+        while i < buf.len() {
+            // Collect zero run.
+            if buf[i] == 0 {
+                let start = i;
+                while i < buf.len() && buf[i] == 0 {
+                    i += 1;
+                }
+                self.pending_zeros = self.pending_zeros.saturating_add(i - start);
+                if self.pending_zeros >= self.threshold {
+                    self.flush_pending_zeros()?;
+                }
+                continue;
+            }
+
+            // Before writing non-zero bytes, materialize pending zeros.
+            self.flush_pending_zeros()?;
+
+            let start = i;
+            while i < buf.len() && buf[i] != 0 {
+                i += 1;
+            }
+            self.inner.write_all(&buf[start..i])?;
+            self.logical_pos = self
+                .logical_pos
+                .checked_add((i - start) as u64)
+                .ok_or_else(|| io::Error::other("Sparse write overflow"))?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_pending_zeros()?;
+        self.finalize_len()?;
+        self.inner.flush()
+    }
+}
+
+impl Drop for SparseFileWriter {
+    fn drop(&mut self) {
+        // Best-effort finalize; ignore errors on drop.
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Seek, SeekFrom, Write};
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn temp_file(name: &str) -> io::Result<(TempDir, PathBuf)> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(name);
+        Ok((dir, path))
+    }
+
+    /// Sparse writer preserves correct length for all-zero output.
+    #[test]
+    fn sparse_writer_sets_len_for_trailing_hole() {
+        let (_dir, path) = temp_file("all-zero.tmp").unwrap();
+
+        let file = File::create(&path).unwrap();
+
+        let mut w = SparseFileWriter::new(file);
+
+        let zeros = vec![0u8; 128 * 1024];
+        w.write_all(&zeros).unwrap();
+        w.flush().unwrap();
+
+        let meta = std::fs::metadata(&path).unwrap();
+        assert_eq!(meta.len(), zeros.len() as u64);
+
+        let mut f = File::open(&path).unwrap();
+        let mut head = [1u8; 16];
+        f.read_exact(&mut head).unwrap();
+        assert!(head.iter().all(|&b| b == 0));
+
+        f.seek(SeekFrom::End(-16)).unwrap();
+        let mut tail = [1u8; 16];
+        f.read_exact(&mut tail).unwrap();
+        assert!(tail.iter().all(|&b| b == 0));
+    }
+
+    /// Sparse writer preserves correct contents across a large zero run.
+    #[test]
+    fn sparse_writer_keeps_data_around_hole() {
+        let (_dir, path) = temp_file("mixed.tmp").unwrap();
+
+        let file = File::create(&path).unwrap();
+        let mut w = SparseFileWriter::new(file);
+
+        w.write_all(b"ABC").unwrap();
+        let zeros = vec![0u8; 8192];
+        w.write_all(&zeros).unwrap();
+        w.write_all(b"XYZ").unwrap();
+        w.flush().unwrap();
+
+        let expected_len: u64 = 3 + 8192 + 3;
+        let meta = std::fs::metadata(&path).unwrap();
+        assert_eq!(meta.len(), expected_len);
+
+        let mut f = File::open(&path).unwrap();
+        let mut buf = [0u8; 3];
+        f.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ABC");
+
+        f.seek(SeekFrom::Start(3 + 123)).unwrap();
+        let mut mid = [1u8; 1];
+        f.read_exact(&mut mid).unwrap();
+        assert_eq!(mid[0], 0);
+
+        f.seek(SeekFrom::End(-3)).unwrap();
+        let mut tail = [0u8; 3];
+        f.read_exact(&mut tail).unwrap();
+        assert_eq!(&tail, b"XYZ");
+    }
+
+    /// Small zero runs below the threshold are written literally.
+    #[test]
+    fn sparse_writer_writes_small_zero_runs_literally() {
+        let (_dir, path) = temp_file("small-zero.tmp").unwrap();
+
+        let file = File::create(&path).unwrap();
+        let mut w = SparseFileWriter::with_threshold(file, 1 << 20);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(b"ABC");
+        data.extend(std::iter::repeat_n(0u8, 1024));
+        data.extend_from_slice(b"XYZ");
+
+        w.write_all(&data).unwrap();
+        w.flush().unwrap();
+
+        let mut read_back = Vec::new();
+        File::open(&path)
+            .unwrap()
+            .read_to_end(&mut read_back)
+            .unwrap();
+        assert_eq!(read_back, data);
+    }
+
+    /// Zero runs spanning multiple writes still produce correct output.
+    #[test]
+    fn sparse_writer_handles_zero_runs_split_across_writes() {
+        let (_dir, path) = temp_file("split.tmp").unwrap();
+
+        let file = File::create(&path).unwrap();
+        let mut w = SparseFileWriter::with_threshold(file, 64);
+
+        w.write_all(b"ABC").unwrap();
+        w.write_all(&[0u8; 32]).unwrap();
+        w.write_all(&[0u8; 32]).unwrap();
+        w.write_all(b"XYZ").unwrap();
+        w.flush().unwrap();
+
+        let expected_len: u64 = 3 + 64 + 3;
+        let meta = std::fs::metadata(&path).unwrap();
+        assert_eq!(meta.len(), expected_len);
+
+        let mut f = File::open(&path).unwrap();
+        f.seek(SeekFrom::Start(3)).unwrap();
+        let mut zeros = vec![1u8; 64];
+        f.read_exact(&mut zeros).unwrap();
+        assert!(zeros.iter().all(|&b| b == 0));
+    }
+
+    /// Decompressing into a sparse writer restores the original file.
+    #[test]
+    fn sparse_writer_receives_decompressed_output() {
+        use crate::options::{CompressionOptions, DecompressionOptions};
+        use crate::pipeline::{compress, decompress};
+
+        let (_dir, path) = temp_file("image.tmp").unwrap();
+
+        let mut data = vec![0u8; 256 * 1024];
+        data[..4].copy_from_slice(b"HEAD");
+        data.extend_from_slice(b"TAIL");
+        data.extend(std::iter::repeat_n(0u8, 64 * 1024));
+
+        let mut compressed = Vec::new();
+        compress(
+            data.as_slice(),
+            &mut compressed,
+            &CompressionOptions::default(),
+        )
+        .unwrap();
+
+        let writer = SparseFileWriter::new(File::create(&path).unwrap());
+        decompress(
+            compressed.as_slice(),
+            writer,
+            &DecompressionOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+    }
+}
@@ -13,8 +13,8 @@ pub enum Threading {
     Auto,
     /// Use an explicit number of worker threads.
     ///
-    /// The specified count must not exceed the safe maximum determined by the system.
-    /// If 0 is specified, it will be treated as `Auto`.
+    /// The specified count must not exceed the number of available processor threads.
+    /// If 0 is specified, all available processor threads are used, like `xz -T0`.
     Exact(u32),
 }
 
@@ -27,31 +27,44 @@ pub enum Threading {
 /// # Returns
 ///
 /// * `Ok(u32)` - A safe thread count to use
-/// * `Err(Error::InvalidThreadCount)` - If the requested thread count exceeds system limits
+/// * `Err(Error::InvalidThreadCount)` - If the requested thread count exceeds the number of
+///   available processor threads
 pub(crate) fn sanitize_threads(threads: Threading) -> Result<u32> {
-    let maximum = get_safe_max_threads();
+    let maximum = available_threads();
     match threads {
-        // Zero threads means "auto-detect"
-        Threading::Auto | Threading::Exact(0) => Ok(maximum),
+        Threading::Auto => Ok(get_safe_max_threads()),
+        // Zero threads means "use every available thread"
+        Threading::Exact(0) => Ok(maximum),
         // Valid explicit thread count
         Threading::Exact(requested) if requested <= maximum => Ok(requested),
-        // Thread count exceeds safe limits
+        // Thread count exceeds the hardware
         Threading::Exact(requested) => Err(Error::InvalidThreadCount { requested, maximum }),
     }
 }
 
+/// Returns the number of processor threads available to this process.
+///
+/// # Returns
+///
+/// The available parallelism as a `u32`, or 1 if it cannot be detected or liblzma
+/// was built without threading support (e.g. on wasm32).
+#[must_use]
+pub fn available_threads() -> u32 {
+    if !lzma_safe::Version::has_threads() {
+        return 1;
+    }
+    std::thread::available_parallelism().map_or(1, |n| u32::try_from(n.get()).unwrap_or(u32::MAX))
+}
+
 /// Determines the maximum safe number of threads to use for compression/decompression.
 ///
 /// # Returns
 ///
 /// The maximum safe number of threads as a `u32`. If thread detection fails,
-/// defaults to 1 thread. If the calculated value exceeds `u32::MAX`, returns `u32::MAX`.
+/// defaults to 1 thread.
 pub(crate) fn get_safe_max_threads() -> u32 {
     // Detect available CPU threads, fallback to 1 if detection fails
-    let available_threads_count = match std::thread::available_parallelism() {
-        Ok(n) => n.get(),
-        Err(_) => 1, // Conservative fallback for systems where detection fails
-    };
+    let available_threads_count = available_threads();
 
     // Reserve threads for system processes based on total available threads
     let system_reserve = match available_threads_count {
@@ -62,12 +75,9 @@ pub(crate) fn get_safe_max_threads() -> u32 {
     };
 
     // Calculate safe thread count, ensuring at least 1 thread is available
-    let safe_threads = available_threads_count
+    available_threads_count
         .saturating_sub(system_reserve)
-        .max(1); // Always ensure at least 1 thread for compression work
-
-    // Convert to u32, handling potential overflow on exotic architectures
-    u32::try_from(safe_threads).unwrap_or(u32::MAX)
+        .max(1) // Always ensure at least 1 thread for compression work
 }
 
 #[cfg(test)]
@@ -77,11 +87,12 @@ mod tests {
     #[test]
     /// Test that [`sanitize_threads`] respects system limits and handles edge cases correctly.
     fn sanitize_threads_respects_limits() {
-        let max = get_safe_max_threads();
+        let safe = get_safe_max_threads();
+        let max = available_threads();
 
         // Auto should always return a valid thread count >= 1
         assert!(matches!(sanitize_threads(Threading::Auto), Ok(n) if n >= 1));
-        assert!(matches!(sanitize_threads(Threading::Auto), Ok(n) if n == max));
+        assert!(matches!(sanitize_threads(Threading::Auto), Ok(n) if n == safe));
 
         // Exact with valid count should return that count
         assert!(matches!(sanitize_threads(Threading::Exact(max)), Ok(n) if n == max));
@@ -94,9 +105,9 @@ mod tests {
     }
 
     #[test]
-    /// Test that zero threads is treated as Auto configuration.
-    fn sanitize_threads_zero_means_auto() {
-        let max = get_safe_max_threads();
+    /// Test that zero threads uses every available thread, like `xz -T0`.
+    fn sanitize_threads_zero_means_all_threads() {
+        let max = available_threads();
         let result = sanitize_threads(Threading::Exact(0));
         assert!(matches!(result, Ok(n) if n == max));
     }
@@ -104,7 +115,7 @@ mod tests {
     #[test]
     /// Test various valid thread counts within limits.
     fn sanitize_threads_valid_counts() {
-        let max = get_safe_max_threads();
+        let max = available_threads();
 
         // Test thread count of 1 (should always be valid)
         assert!(matches!(sanitize_threads(Threading::Exact(1)), Ok(1)));
@@ -160,7 +171,7 @@ mod tests {
     #[test]
     /// Test that [`sanitize_threads`] handles edge cases near u32 limits.
     fn sanitize_threads_boundary_conditions() {
-        let max = get_safe_max_threads();
+        let max = available_threads();
 
         // Test maximum valid value
         assert!(matches!(sanitize_threads(Threading::Exact(max)), Ok(n) if n == max));
@@ -182,6 +193,12 @@ mod tests {
         ));
     }
 
+    #[test]
+    /// Test that the safe maximum never exceeds the available threads.
+    fn get_safe_max_threads_within_available() {
+        assert!(get_safe_max_threads() <= available_threads());
+    }
+
     #[test]
     /// Test that [`get_safe_max_threads`] produces consistent results.
     fn get_safe_max_threads_consistency() {